@@ -0,0 +1,254 @@
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::io::{BufRead, Read, Result};
+
+/// How much of the input --analyze looks at
+const SAMPLE_BYTES: u64 = 64 * 1024;
+const SAMPLE_RECORDS: usize = 1000;
+
+/// Delimiters tried by --analyze, from the most to the least likely
+const CANDIDATES: [u8; 6] = [b'\t', b',', b';', b'|', b':', b' '];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum EolStyle {
+    Lf,
+    CrLf,
+    Mixed,
+    Nul,
+}
+
+impl EolStyle {
+    fn name(self) -> &'static str {
+        match self {
+            EolStyle::Lf => "LF",
+            EolStyle::CrLf => "CRLF",
+            EolStyle::Mixed => "mixed LF and CRLF",
+            EolStyle::Nul => "NUL",
+        }
+    }
+}
+
+/// How many records were split in how many fields by a delimiter
+struct Candidate {
+    delimiter: u8,
+    /// fields count => records count
+    distribution: BTreeMap<usize, usize>,
+    /// Did the delimiter appear repeated (e.g. aligned columns)?
+    repeated: bool,
+}
+
+impl Candidate {
+    fn new(delimiter: u8, records: &[&[u8]]) -> Self {
+        let mut distribution = BTreeMap::new();
+        for record in records {
+            let fields = 1 + memchr::memchr_iter(delimiter, record).count();
+            *distribution.entry(fields).or_insert(0) += 1;
+        }
+
+        Candidate {
+            delimiter,
+            distribution,
+            repeated: records
+                .iter()
+                .any(|record| record.windows(2).any(|w| w == [delimiter, delimiter])),
+        }
+    }
+
+    /// Share of the records that have the most common number of fields,
+    /// when that number is greater than one (0 otherwise).
+    fn consistency(&self, records_count: usize) -> f64 {
+        match self.distribution.iter().max_by_key(|(_, &count)| count) {
+            Some((&fields, &count)) if fields > 1 => count as f64 / records_count as f64,
+            _ => 0.0,
+        }
+    }
+
+    fn name(&self) -> String {
+        match self.delimiter {
+            b'\t' => "'\\t'".to_owned(),
+            d => format!("'{}'", d as char),
+        }
+    }
+}
+
+fn plural(count: usize, word: &str) -> String {
+    format!("{} {}{}", count, word, if count == 1 { "" } else { "s" })
+}
+
+/// Read the beginning of the input, as much as --analyze and --sniff look at
+pub fn read_sample<R: Read>(stdin: &mut R) -> Result<Vec<u8>> {
+    let mut sample = Vec::new();
+    stdin.take(SAMPLE_BYTES).read_to_end(&mut sample)?;
+    Ok(sample)
+}
+
+/// The end of line and the records (without it) of a sample
+fn split_records(sample: &[u8]) -> (EolStyle, Vec<&[u8]>) {
+    let data = sample.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(sample);
+
+    let lf_count = memchr::memchr_iter(b'\n', data).count();
+    let crlf_count = memchr::memmem::find_iter(data, b"\r\n").count();
+    let eol_style = if lf_count == 0 && data.contains(&0) {
+        EolStyle::Nul
+    } else if crlf_count == 0 {
+        EolStyle::Lf
+    } else if crlf_count == lf_count {
+        EolStyle::CrLf
+    } else {
+        EolStyle::Mixed
+    };
+
+    let eol = if eol_style == EolStyle::Nul { 0 } else { b'\n' };
+    let mut records: Vec<&[u8]> = data
+        .split(|&b| b == eol)
+        .map(|record| record.strip_suffix(b"\r").unwrap_or(record))
+        .take(SAMPLE_RECORDS)
+        .collect();
+
+    // The last record may have been cut short by the size of the sample
+    if records.len() > 1 && sample.len() as u64 == SAMPLE_BYTES {
+        records.pop();
+    }
+    records.retain(|record| !record.is_empty());
+
+    (eol_style, records)
+}
+
+/// The delimiters found in the records, the most consistent first
+fn rank_candidates(records: &[&[u8]]) -> Vec<Candidate> {
+    let mut candidates: Vec<Candidate> = CANDIDATES
+        .iter()
+        .map(|&delimiter| Candidate::new(delimiter, records))
+        .filter(|candidate| candidate.distribution.keys().any(|&fields| fields > 1))
+        .collect();
+
+    // Stable sort, so that ties keep the order of CANDIDATES
+    candidates.sort_by(|a, b| {
+        b.consistency(records.len())
+            .total_cmp(&a.consistency(records.len()))
+    });
+
+    candidates
+}
+
+/// Pick the delimiter of the records in the sample (see `read_sample`),
+/// the one that splits them most consistently in more than one field.
+/// Also tells if the delimiter appears repeated (e.g. aligned columns).
+pub fn sniff(sample: &[u8]) -> Option<(u8, bool)> {
+    let (_, records) = split_records(sample);
+    rank_candidates(&records)
+        .into_iter()
+        .next()
+        .filter(|best| best.consistency(records.len()) > 0.0)
+        .map(|best| (best.delimiter, best.repeated))
+}
+
+/// Look at the beginning of the input and describe it: end of line,
+/// byte order mark, quotes and, for each candidate delimiter, how many
+/// fields the records would have. Ends with a suggested command line.
+pub fn analyze<R: BufRead>(stdin: &mut R) -> Result<String> {
+    let sample = read_sample(stdin)?;
+    let has_bom = sample.starts_with(b"\xEF\xBB\xBF");
+    let (eol_style, records) = split_records(&sample);
+    let candidates = rank_candidates(&records);
+
+    let yes_no = |value: bool| if value { "yes" } else { "no" };
+    let has_quotes = records.iter().any(|record| record.contains(&b'"'));
+
+    let mut report = String::new();
+    writeln!(report, "records sampled: {}", records.len()).unwrap();
+    writeln!(report, "end of line: {}", eol_style.name()).unwrap();
+    writeln!(report, "byte order mark: {}", yes_no(has_bom)).unwrap();
+    writeln!(report, "double quotes: {}", yes_no(has_quotes)).unwrap();
+    writeln!(report, "delimiters:").unwrap();
+
+    if candidates.is_empty() {
+        writeln!(report, "  none found").unwrap();
+    }
+
+    for candidate in &candidates {
+        let distribution: Vec<String> = candidate
+            .distribution
+            .iter()
+            .rev()
+            .map(|(&fields, &count)| {
+                format!("{} in {}", plural(fields, "field"), plural(count, "record"))
+            })
+            .collect();
+        writeln!(
+            report,
+            "  {:<5} {}",
+            candidate.name(),
+            distribution.join(", ")
+        )
+        .unwrap();
+    }
+
+    let mut command = vec!["tuc".to_owned()];
+    if eol_style == EolStyle::Nul {
+        command.push("-z".into());
+    }
+    if let Some(best) = candidates
+        .first()
+        .filter(|best| best.consistency(records.len()) > 0.0)
+    {
+        // TAB is the default delimiter
+        if best.delimiter != b'\t' {
+            command.extend(["-d".into(), best.name()]);
+        }
+        // Quoted fields may contain the delimiter
+        if has_quotes {
+            command.push("--csv".into());
+        } else if best.repeated {
+            command.push("-g".into());
+        }
+    }
+    command.extend(["-f".into(), "1:".into()]);
+
+    writeln!(report, "suggested: {}", command.join(" ")).unwrap();
+
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_analyzes_the_input() {
+        let report = analyze(&mut &b"\xEF\xBB\xBFa,b;c\r\nd,e,f\r\ng,\"h\",i\r\n"[..]).unwrap();
+        assert_eq!(
+            report,
+            "records sampled: 3\n\
+             end of line: CRLF\n\
+             byte order mark: yes\n\
+             double quotes: yes\n\
+             delimiters:\n  \
+               ','   3 fields in 2 records, 2 fields in 1 record\n  \
+               ';'   2 fields in 1 record, 1 field in 2 records\n\
+             suggested: tuc -d ',' --csv -f 1:\n"
+        );
+    }
+
+    #[test]
+    fn it_sniffs_the_delimiter() {
+        assert_eq!(sniff(b"a;b,c;d\ne;f;g\nh;i,j;k\n"), Some((b';', false)));
+        assert_eq!(sniff(b"a|b\nc|d"), Some((b'|', false)));
+        assert_eq!(sniff(b"a  b c\nd e  f\n"), Some((b' ', true)));
+        assert_eq!(sniff(b"abc\ndef\n"), None);
+    }
+
+    #[test]
+    fn it_suggests_the_flags_for_the_input() {
+        let report = analyze(&mut &b"a\tb\0c\td\0"[..]).unwrap();
+        assert!(report.contains("end of line: NUL\n"));
+        assert!(report.ends_with("suggested: tuc -z -f 1:\n"));
+
+        let report = analyze(&mut &b"a  b\nc d\n"[..]).unwrap();
+        assert!(report.ends_with("suggested: tuc -d ' ' -g -f 1:\n"));
+
+        let report = analyze(&mut &b"abc\n"[..]).unwrap();
+        assert!(report.contains("delimiters:\n  none found\n"));
+        assert!(report.ends_with("suggested: tuc -f 1:\n"));
+    }
+}
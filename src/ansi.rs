@@ -0,0 +1,97 @@
+const ESC: u8 = 0x1b;
+const BEL: u8 = 0x07;
+
+/// Copy `input` into `output` without ANSI escape sequences
+/// (colors, cursor movements, hyperlinks, etc.).
+///
+/// Supported sequences are CSI (`ESC [ ... final`), OSC/DCS/APC/PM/SOS
+/// (terminated by BEL or `ESC \`) and the short `ESC x`/`ESC ( x` forms.
+/// An unterminated sequence is dropped until the end of the input.
+pub fn strip_ansi_escapes(input: &[u8], output: &mut Vec<u8>) {
+    output.clear();
+
+    let mut idx = 0;
+    while let Some(pos) = memchr::memchr(ESC, &input[idx..]) {
+        output.extend_from_slice(&input[idx..idx + pos]);
+        idx += pos + 1;
+
+        idx = match input.get(idx) {
+            // Control Sequence Introducer: parameters and intermediate
+            // bytes, then a single final byte in the range 0x40-0x7E
+            Some(b'[') => input[idx + 1..]
+                .iter()
+                .position(|c| (0x40..=0x7e).contains(c))
+                .map_or(input.len(), |p| idx + 1 + p + 1),
+            // String sequences, terminated by BEL (OSC only) or ESC \
+            Some(b']' | b'P' | b'X' | b'^' | b'_') => {
+                let mut end = input.len();
+                let mut i = idx + 1;
+                while i < input.len() {
+                    if input[i] == BEL {
+                        end = i + 1;
+                        break;
+                    } else if input[i] == ESC && input.get(i + 1) == Some(&b'\\') {
+                        end = i + 2;
+                        break;
+                    }
+                    i += 1;
+                }
+                end
+            }
+            // Intermediate bytes (e.g. charset selection `ESC ( B`), then a final byte
+            Some(0x20..=0x2f) => input[idx..]
+                .iter()
+                .position(|c| !(0x20..=0x2f).contains(c))
+                .map_or(input.len(), |p| idx + p + 1),
+            // Two bytes sequences, e.g. `ESC c` or `ESC 7`
+            Some(_) => idx + 1,
+            None => input.len(),
+        };
+    }
+
+    output.extend_from_slice(&input[idx..]);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strip(input: &[u8]) -> Vec<u8> {
+        let mut output = Vec::new();
+        strip_ansi_escapes(input, &mut output);
+        output
+    }
+
+    #[test]
+    fn it_leaves_plain_text_untouched() {
+        assert_eq!(strip(b""), b"");
+        assert_eq!(strip(b"foo bar"), b"foo bar");
+    }
+
+    #[test]
+    fn it_strips_colors() {
+        assert_eq!(strip(b"\x1b[1;31merror\x1b[0m: oops"), b"error: oops");
+        assert_eq!(strip(b"\x1b[38;5;208mx\x1b[m"), b"x");
+    }
+
+    #[test]
+    fn it_strips_hyperlinks_and_titles() {
+        assert_eq!(
+            strip(b"\x1b]8;;http://example.com\x1b\\link\x1b]8;;\x1b\\"),
+            b"link"
+        );
+        assert_eq!(strip(b"\x1b]0;title\x07text"), b"text");
+    }
+
+    #[test]
+    fn it_strips_short_sequences() {
+        assert_eq!(strip(b"a\x1b(Bb\x1b7c\x1bcd"), b"abcd");
+    }
+
+    #[test]
+    fn it_drops_unterminated_sequences() {
+        assert_eq!(strip(b"a\x1b[31"), b"a");
+        assert_eq!(strip(b"a\x1b]0;title"), b"a");
+        assert_eq!(strip(b"a\x1b"), b"a");
+    }
+}
@@ -0,0 +1,2380 @@
+use anyhow::{anyhow, bail, Result};
+use std::ffi::OsString;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use crate::bounds::userbounds::Align;
+use crate::bounds::{BoundOrFiller, BoundsType, Side, UserBounds, UserBoundsList};
+use crate::config;
+use crate::decompress::Decompress;
+use crate::error::TucError;
+use crate::exec::{split_words, ExecCommand};
+use crate::multi_fixed::MultiFixedFinder;
+use crate::options::{
+    AddedField, ColumnType, ColumnarOutput, ErrorPolicy, FieldsCount, Opt, Pad, Trim, Truncate, EOL,
+};
+use crate::timestamp::TimestampFormat;
+use crate::transform::TransformPipeline;
+use crate::translate::{translate, Translation};
+use crate::wrap::Wrap;
+
+#[cfg(feature = "regex")]
+use crate::bounds::LinePattern;
+#[cfg(feature = "regex")]
+use crate::options::{RegexBag, CHARACTERS_REGEX};
+#[cfg(feature = "regex")]
+use crate::validation::FieldAssertion;
+
+#[cfg(feature = "regex")]
+use regex::bytes::Regex;
+
+/// `--swap A B` and `--insert N VALUE` take two values, which pico_args
+/// doesn't support: we merge them into a single one (`--swap A,B`).
+fn merge_pair_values(mut args: Vec<OsString>) -> Vec<OsString> {
+    for flag in ["--swap", "--insert"] {
+        if let Some(idx) = args.iter().position(|arg| arg == flag) {
+            if idx + 2 < args.len() && !args[idx + 1].to_string_lossy().contains(',') {
+                let second_value = args.remove(idx + 2);
+                args[idx + 1].push(",");
+                args[idx + 1].push(second_value);
+            }
+        }
+    }
+
+    args
+}
+
+/// Like sed, `-i` takes its suffix attached (`-i.bak` or `-i=.bak`), which
+/// pico_args would split as combined flags: we turn it into `--in-place=.bak`.
+fn merge_in_place_suffix(mut args: Vec<OsString>) -> Vec<OsString> {
+    for arg in args.iter_mut() {
+        if arg == "--" {
+            break;
+        }
+
+        let text = arg.to_string_lossy();
+        if let Some(suffix) = text.strip_prefix("-i").filter(|suffix| !suffix.is_empty()) {
+            let suffix = suffix.strip_prefix('=').unwrap_or(suffix);
+            *arg = format!("--in-place={}", suffix).into();
+        }
+    }
+
+    args
+}
+
+fn is_single_field(b: &UserBounds) -> bool {
+    b.l == b.r
+        && b.l != Side::Continue
+        && b.fallback_oob.is_none()
+        && b.sub.is_none()
+        && b.bytes.is_none()
+}
+
+fn parse_swap(value: &str) -> Option<(UserBounds, UserBounds)> {
+    let (a, b) = value.split_once(',')?;
+    let a = UserBounds::from_str(a).ok().filter(is_single_field)?;
+    let b = UserBounds::from_str(b).ok().filter(is_single_field)?;
+
+    Some((a, b))
+}
+
+/// Check if a --fields value can select a value of a JSON object, either by key
+/// (e.g. `msg` or `user.id`) or by JSON Pointer (e.g. `/user/id`).
+fn is_json_key(s: &str) -> bool {
+    s.starts_with('/')
+        || (s.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+            && !s.contains(['{', '}', ':', '=']))
+}
+
+/// Replace the JSON keys found in the placeholders of a format string with
+/// field numbers (e.g. `{/user/id}: {msg:upper}` becomes `{1}: {2:upper}`),
+/// appending the keys to `keys`.
+fn number_json_placeholders(value: &str, keys: &mut Vec<String>) -> String {
+    let mut output = String::with_capacity(value.len());
+    let mut rest = value;
+
+    while let Some(idx) = rest.find('{') {
+        output.push_str(&rest[..=idx]);
+        rest = &rest[idx + 1..];
+
+        if let Some(escaped) = rest.strip_prefix('{') {
+            output.push('{');
+            rest = escaped;
+            continue;
+        }
+
+        // A JSON Pointer ends only with the placeholder
+        let end = if rest.starts_with('/') {
+            rest.find('}')
+        } else {
+            rest.find([':', '|', '=', '?', '}'])
+        }
+        .unwrap_or(rest.len());
+
+        let key = &rest[..end];
+        if is_json_key(key) {
+            let position = match keys.iter().position(|k| k == key) {
+                Some(position) => position,
+                None => {
+                    keys.push(key.to_owned());
+                    keys.len() - 1
+                }
+            };
+            output.push_str(&(position + 1).to_string());
+            rest = &rest[end..];
+        }
+    }
+
+    output.push_str(rest);
+    output
+}
+
+/// Parse --fields when reading JSON input: either bounds (e.g. `2,1`), or the keys
+/// of the objects (e.g. `user.id,msg` or `/user/id,/msg`, also within a format string
+/// like `{/user/id}: {msg}`), that become the fields 1, 2...
+fn parse_json_fields(value: &str) -> Result<(Option<Vec<String>>, UserBoundsList)> {
+    if value.contains('{') {
+        let mut keys = Vec::new();
+        let format = number_json_placeholders(value, &mut keys);
+        return Ok(if keys.is_empty() {
+            (None, UserBoundsList::from_str(value)?)
+        } else {
+            (Some(keys), UserBoundsList::from_str(&format)?)
+        });
+    }
+
+    let e = match UserBoundsList::from_str(value) {
+        Ok(fields) => return Ok((None, fields)),
+        Err(e) => e,
+    };
+
+    let mut keys = Vec::new();
+    let mut fields = Vec::new();
+    for part in value.split(',') {
+        // A key can have a fallback (e.g. msg=none), a JSON Pointer ends only with the field
+        let (key, fallback) = match part.split_once('=') {
+            Some((key, fallback)) if !key.starts_with('/') => (key, Some(fallback)),
+            _ => (part, None),
+        };
+        if !is_json_key(key) {
+            return Err(e.into());
+        }
+
+        keys.push(key.to_owned());
+        fields.push(match fallback {
+            Some(fallback) => format!("{}={}", keys.len(), fallback),
+            None => keys.len().to_string(),
+        });
+    }
+
+    Ok((Some(keys), UserBoundsList::from_str(&fields.join(","))?))
+}
+
+fn parse_set(value: &str) -> Result<(UserBounds, UserBoundsList)> {
+    let (field, template) = value
+        .split_once('=')
+        .and_then(|(field, template)| {
+            let field = UserBounds::from_str(field).ok().filter(is_single_field)?;
+            Some((field, template))
+        })
+        .ok_or_else(|| {
+            anyhow!("runtime error. --set requires a field and a value, e.g. --set 3=redacted")
+        })?;
+
+    let template = UserBoundsList::from_template(template)
+        .map_err(|e| anyhow!("runtime error. --set {}: {}", value, e))?;
+
+    Ok((field, template))
+}
+
+fn parse_insert(value: &str) -> Result<(i32, AddedField)> {
+    let (position, value) = value.split_once(',').unwrap_or((value, ""));
+    let position = match position.parse::<i32>() {
+        Ok(position) if position != 0 => position,
+        _ => bail!(
+            "runtime error. --insert requires a field position and a value, e.g. --insert 2 text"
+        ),
+    };
+    let value = AddedField::from_str(value).map_err(|e| anyhow!("runtime error. {}", e))?;
+
+    Ok((position, value))
+}
+
+fn parse_column_types(value: &str) -> Result<Vec<ColumnType>> {
+    value
+        .split(',')
+        .map(|t| ColumnType::from_str(t).map_err(|e| anyhow!("{}", e)))
+        .collect()
+}
+
+/// Value of --lines: the bounds, or a range of lines between regex matches
+/// (e.g. `/^BEGIN/:/^END/`), found while reading: its bounds keep every line.
+#[cfg(feature = "regex")]
+fn parse_lines(value: &str) -> Result<(UserBoundsList, Option<LinePattern>)> {
+    if LinePattern::is_pattern(value) {
+        let pattern = LinePattern::from_str(value)?;
+        Ok((UserBoundsList::from_str("1:")?, Some(pattern)))
+    } else {
+        Ok((UserBoundsList::from_str(value)?, None))
+    }
+}
+
+#[cfg(not(feature = "regex"))]
+fn parse_lines(value: &str) -> Result<(UserBoundsList, Option<()>)> {
+    if value.starts_with('/') || value.starts_with(":/") {
+        bail!("The use of /regex/ in --lines requires `tuc` to be compiled with `regex` support");
+    }
+    Ok((UserBoundsList::from_str(value)?, None))
+}
+
+/// Value of --fallback-oob: the content of a file (`@path`, without its
+/// trailing newline), of an environment variable (`env:VAR`) or the text itself.
+pub(crate) fn resolve_fallback(value: String) -> Result<Vec<u8>> {
+    if let Some(path) = value.strip_prefix('@') {
+        let mut content = std::fs::read(path).map_err(|e| {
+            anyhow!(
+                "runtime error. Cannot read --fallback-oob file {}: {}",
+                path,
+                e
+            )
+        })?;
+        if content.last() == Some(&b'\n') {
+            content.pop();
+        }
+        Ok(content)
+    } else if let Some(name) = value.strip_prefix("env:") {
+        std::env::var_os(name)
+            .map(|v| v.to_string_lossy().into_owned().into_bytes())
+            .ok_or_else(|| {
+                anyhow!(
+                    "runtime error. --fallback-oob environment variable not set: {}",
+                    name
+                )
+            })
+    } else {
+        Ok(value.into_bytes())
+    }
+}
+
+/// The options of TUC_OPTS, split as a shell would, without their values and
+/// those that the command line sets again or unsets (it wins), or that
+/// don't fit what is cut (as for the config, e.g. --delimiter with --bytes).
+fn tuc_opts_args(opts: &str, args: &[OsString]) -> Result<Vec<OsString>> {
+    let words = split_words(opts).map_err(|e| anyhow!("runtime error. TUC_OPTS: {}", e))?;
+    let words_args: Vec<OsString> = words.iter().map(Into::into).collect();
+    let mode = config::mode_of(args)
+        .or_else(|| config::mode_of(&words_args))
+        .unwrap_or(config::Mode::Fields);
+    let mut tuc_opts_args = Vec::new();
+    let mut skip_value = false;
+
+    for word in words {
+        let is_option = word.len() > 1 && word.starts_with('-');
+        if !is_option && std::mem::take(&mut skip_value) {
+            continue;
+        }
+
+        if is_option {
+            let flag = match word.split_once('=') {
+                _ if !word.starts_with("--") => &word[..2],
+                Some((flag, _)) => flag,
+                None => &word,
+            };
+            skip_value = config::is_overridden(args, flag) || !config::flag_applies_to(flag, mode);
+            if skip_value {
+                continue;
+            }
+        }
+
+        tuc_opts_args.push(word.into());
+    }
+
+    Ok(tuc_opts_args)
+}
+
+impl Opt {
+    /// Like `try_from_args`, but with the defaults of a config file: the one
+    /// given with `--config PATH` or else, if it exists,
+    /// `$XDG_CONFIG_HOME/tuc/config.toml` (or `~/.config/tuc/config.toml`).
+    /// `--no-config` ignores them. `--preset NAME` adds the options of a preset
+    /// of the config. The command line overrides the preset, which overrides
+    /// the defaults of the config.
+    ///
+    /// The options in the `TUC_OPTS` environment variable come before
+    /// those of the command line (which win) and after those of the config.
+    pub fn try_from_args_with_config<I: IntoIterator<Item = OsString>>(args: I) -> Result<Opt> {
+        let mut args: Vec<OsString> = args.into_iter().collect();
+        if let Some(opts) = std::env::var_os("TUC_OPTS") {
+            let mut env_args = tuc_opts_args(&opts.to_string_lossy(), &args)?;
+            env_args.append(&mut args);
+            args = env_args;
+        }
+        let mut path: Option<PathBuf> = None;
+        let mut preset: Option<String> = None;
+        let mut no_config = false;
+
+        let mut idx = 0;
+        while idx < args.len() {
+            let arg = args[idx].to_string_lossy().into_owned();
+            if arg == "--" {
+                break;
+            } else if arg == "--no-config" {
+                no_config = true;
+                args.remove(idx);
+            } else if let Some(value) = arg.strip_prefix("--config=") {
+                path = Some(value.into());
+                args.remove(idx);
+            } else if arg == "--config" {
+                if idx + 1 == args.len() {
+                    bail!("runtime error. --config requires a path");
+                }
+                args.remove(idx);
+                path = Some(args.remove(idx).into());
+            } else if let Some(value) = arg.strip_prefix("--preset=") {
+                preset = Some(value.to_owned());
+                args.remove(idx);
+            } else if arg == "--preset" {
+                if idx + 1 == args.len() {
+                    bail!("runtime error. --preset requires a name");
+                }
+                args.remove(idx);
+                preset = Some(args.remove(idx).to_string_lossy().into_owned());
+            } else {
+                idx += 1;
+            }
+        }
+
+        if no_config && (path.is_some() || preset.is_some()) {
+            bail!("runtime error. --config and --preset cannot be used with --no-config");
+        }
+
+        let path = path.or_else(|| config::default_path().filter(|path| path.exists()));
+        if preset.is_some() && path.is_none() {
+            bail!("runtime error. --preset requires a config file (see --config)");
+        }
+
+        if let Some(path) = path.filter(|_| !no_config) {
+            let mut config_args = config::config_args(&path, &args, preset.as_deref())?;
+            config_args.append(&mut args);
+            args = config_args;
+        }
+
+        Opt::try_from_args(args)
+    }
+
+    /// Build the options the same way the `tuc` command line does.
+    ///
+    /// `args` must not contain the program name (e.g. use `std::env::args_os().skip(1)`).
+    /// Help flags (`-h`/`--help`) are not handled: it's up to the caller
+    /// to display the help, if needed.
+    ///
+    /// ```rust
+    /// # use tuc::options::Opt;
+    /// # use tuc::bounds::BoundsType;
+    /// let opt = Opt::try_from_args(["-d", ",", "-f", "2:"].map(Into::into)).unwrap();
+    /// assert_eq!(opt.delimiter, b",");
+    /// assert_eq!(opt.bounds_type, BoundsType::Fields);
+    ///
+    /// assert!(Opt::try_from_args(["--lines", "1", "--json"].map(Into::into)).is_err());
+    /// ```
+    pub fn try_from_args<I: IntoIterator<Item = OsString>>(args: I) -> Result<Opt> {
+        let mut pargs = pico_args::Arguments::from_vec(merge_in_place_suffix(merge_pair_values(
+            args.into_iter().collect(),
+        )));
+
+        let excel_columns = pargs.contains("--excel-columns");
+        let parse_fields: fn(&str) -> Result<UserBoundsList, TucError> = if excel_columns {
+            UserBoundsList::from_excel_columns
+        } else {
+            UserBoundsList::from_str
+        };
+
+        let json_input = pargs.contains("--json-input");
+        let mut keys_from_fields: Option<Vec<String>> = None;
+        let mut maybe_fields: Option<UserBoundsList> = if json_input && !excel_columns {
+            pargs
+                .opt_value_from_fn(["-f", "--fields"], parse_json_fields)?
+                .map(|(keys, fields)| {
+                    keys_from_fields = keys;
+                    fields
+                })
+        } else {
+            pargs.opt_value_from_fn(["-f", "--fields"], parse_fields)?
+        };
+        let maybe_characters: Option<UserBoundsList> =
+            pargs.opt_value_from_str(["-c", "--characters"])?;
+        let maybe_bytes: Option<UserBoundsList> = pargs.opt_value_from_str(["-b", "--bytes"])?;
+        let (maybe_lines, line_pattern) =
+            match pargs.opt_value_from_fn(["-l", "--lines"], parse_lines)? {
+                Some((bounds, line_pattern)) => (Some(bounds), line_pattern),
+                None => (None, None),
+            };
+        let maybe_width: Option<UserBoundsList> = pargs.opt_value_from_str("--width")?;
+
+        if excel_columns && maybe_fields.is_none() {
+            bail!("runtime error. --excel-columns requires --fields");
+        }
+
+        // With both --fields and --bytes, the bytes are cut from each field
+        if let (Some(fields), Some(bytes)) = (maybe_fields.as_mut(), &maybe_bytes) {
+            match bytes.list.as_slice() {
+                [BoundOrFiller::Bound(b)] if !b.has_modifiers() && b.fallback_oob.is_none() => {
+                    fields.set_byte_range(b)
+                }
+                _ => bail!("runtime error. With --fields, --bytes must be a single range (e.g. -f 2 --bytes 1:8)"),
+            }
+        }
+
+        let has_explicit_bounds = maybe_fields.is_some()
+            || maybe_characters.is_some()
+            || maybe_bytes.is_some()
+            || maybe_lines.is_some()
+            || maybe_width.is_some();
+
+        let bounds_type = if maybe_fields.is_some() {
+            BoundsType::Fields
+        } else if maybe_bytes.is_some() {
+            BoundsType::Bytes
+        } else if maybe_characters.is_some() {
+            BoundsType::Characters
+        } else if maybe_lines.is_some() {
+            BoundsType::Lines
+        } else if maybe_width.is_some() {
+            BoundsType::Width
+        } else {
+            maybe_fields = Some(UserBoundsList::from_str("1:").unwrap());
+            BoundsType::Fields
+        };
+
+        if bounds_type == BoundsType::Fields
+            && (maybe_fields.is_none() || maybe_fields.as_ref().unwrap().is_empty())
+        {
+            bail!("invariant error. At this point we expected to find at least 1 field bound");
+        }
+
+        let swap = pargs
+            .opt_value_from_str::<_, String>("--swap")?
+            .map(|value| {
+                parse_swap(&value).ok_or_else(|| {
+                    anyhow!("runtime error. --swap requires two fields, e.g. --swap 1 3")
+                })
+            })
+            .transpose()?;
+
+        if swap.is_some() && has_explicit_bounds {
+            bail!("runtime error. --swap cannot be used with --fields, --characters, --bytes or --lines");
+        }
+
+        let insert = pargs
+            .opt_value_from_str::<_, String>("--insert")?
+            .map(|value| parse_insert(&value))
+            .transpose()?;
+
+        if insert.is_some() && (has_explicit_bounds || swap.is_some()) {
+            bail!("runtime error. --insert cannot be used with --swap, --fields, --characters, --bytes or --lines");
+        }
+
+        let set = pargs
+            .values_from_str::<_, String>("--set")?
+            .iter()
+            .map(|value| parse_set(value))
+            .collect::<Result<Vec<_>>>()?;
+
+        if !set.is_empty() && (has_explicit_bounds || swap.is_some() || insert.is_some()) {
+            bail!("runtime error. --set cannot be used with --swap, --insert, --fields, --characters, --bytes or --lines");
+        }
+
+        let csv = pargs.contains("--csv");
+
+        if csv && bounds_type != BoundsType::Fields {
+            bail!("runtime error. --csv is available only for --fields");
+        }
+
+        let mut delimiters: Vec<Vec<u8>> = match bounds_type {
+            BoundsType::Fields => pargs
+                .values_from_str(["-d", "--delimiter"])?
+                .into_iter()
+                .map(|x: String| x.into())
+                .collect(),
+            _ => Vec::new(),
+        };
+
+        let delimiter: Vec<u8> = match bounds_type {
+            BoundsType::Fields if delimiters.is_empty() => if csv { "," } else { "\t" }.into(),
+            BoundsType::Fields => delimiters[0].clone(),
+            BoundsType::Lines => "\n".into(),
+            _ => Vec::new(),
+        };
+
+        let has_delimiter = !delimiters.is_empty();
+
+        // With a single delimiter there's nothing more to find
+        if delimiters.len() == 1 {
+            delimiters.clear();
+        }
+
+        let greedy_delimiter = pargs.contains(["-g", "--greedy-delimiter"]);
+        let tmp_replace_delimiter: Option<String> =
+            pargs.opt_value_from_str(["-r", "--replace-delimiter"])?;
+        let mut replace_delimiter: Option<Vec<u8>> = tmp_replace_delimiter.map(|x| x.into());
+        let output_delimiter: Option<Vec<u8>> = pargs
+            .opt_value_from_str::<_, String>("--output-delimiter")?
+            .map(|x| x.into());
+
+        let json_pretty = pargs.contains("--json-pretty");
+        let msgpack = pargs.contains("--msgpack");
+        let json_object = pargs.contains("--json-object");
+        let has_json = pargs.contains("--json") || json_pretty || msgpack || json_object;
+
+        if msgpack && json_pretty {
+            bail!("runtime error. --msgpack and --json-pretty cannot be used together");
+        }
+
+        if msgpack && json_object {
+            bail!("runtime error. --msgpack and --json-object cannot be used together");
+        }
+        let has_join = pargs.contains(["-j", "--join"]);
+        let has_no_join = pargs.contains("--no-join");
+
+        if has_json && pargs.contains("--no-json") {
+            bail!("runtime error. It's not possible to use --json and --no-json simultaneously");
+        }
+
+        if has_join && has_no_join {
+            bail!("runtime error. It's not possible to use --join and --no-join simultaneously");
+        }
+
+        if has_json && has_no_join {
+            bail!("runtime error. Using both --json and --no-join is not permitted");
+        }
+
+        if replace_delimiter.is_some() {
+            if has_no_join {
+                bail!("runtime error. You can't pass --no-join when using --replace, which implies --join");
+            } else if has_json {
+                bail!("runtime error. The use of --replace with --json is not supported");
+            }
+        }
+
+        if output_delimiter.is_some() {
+            if has_no_join {
+                bail!("runtime error. You can't pass --no-join when using --output-delimiter, which implies --join");
+            } else if has_json {
+                bail!("runtime error. The use of --output-delimiter with --json is not supported");
+            } else if bounds_type != BoundsType::Fields && bounds_type != BoundsType::Characters {
+                bail!("runtime error. --output-delimiter is available only for --fields and --characters");
+            }
+        }
+
+        if bounds_type == BoundsType::Bytes && has_join && replace_delimiter.is_none() && !has_json
+        {
+            bail!("runtime error. --bytes has no delimiter to join with, use --replace-delimiter instead of --join");
+        }
+
+        if bounds_type == BoundsType::Characters && has_no_join {
+            bail!("runtime error. Since --characters implies --join, you can't pass --no-join");
+        }
+
+        if bounds_type == BoundsType::Characters {
+            replace_delimiter = Some("".into());
+        }
+
+        if has_json {
+            replace_delimiter = Some(if json_pretty { ",\n  " } else { "," }.into());
+        }
+
+        let join = has_join
+            || has_json
+            || replace_delimiter.is_some()
+            || output_delimiter.is_some()
+            || (bounds_type == BoundsType::Lines && !has_no_join)
+            || (bounds_type == BoundsType::Characters);
+
+        #[cfg(not(feature = "regex"))]
+        let regex_bag = None;
+
+        #[cfg(feature = "regex")]
+        let regex_bag: Option<RegexBag> = (if bounds_type == BoundsType::Characters {
+            Some(CHARACTERS_REGEX.to_owned())
+        } else {
+            pargs.opt_value_from_str::<_, String>(["-e", "--regex"])?
+        })
+        .map(|regex_text| RegexBag::new(&regex_text))
+        .transpose()?;
+
+        if regex_bag.is_some() && cfg!(not(feature = "regex")) {
+            bail!("invariant error. There should not be any regex when compiled without regex support");
+        }
+
+        let bounds = maybe_fields
+            .or(maybe_characters)
+            .or(maybe_bytes)
+            .or(maybe_lines)
+            .or(maybe_width)
+            .unwrap();
+
+        let strip_ansi = pargs.contains("--strip-ansi");
+
+        if strip_ansi && bounds_type != BoundsType::Fields && bounds_type != BoundsType::Characters
+        {
+            bail!("runtime error. --strip-ansi is available only for --fields and --characters");
+        }
+
+        let show_nonprinting = pargs.contains(["-v", "--show-nonprinting"]);
+
+        if show_nonprinting
+            && bounds_type != BoundsType::Fields
+            && bounds_type != BoundsType::Characters
+        {
+            bail!(
+                "runtime error. --show-nonprinting is available only for --fields and --characters"
+            );
+        }
+
+        let explain = match pargs.opt_value_from_str::<_, String>("--explain")? {
+            Some(format) if format == "json" => true,
+            Some(_) => {
+                bail!("runtime error. --explain supports only the json format (--explain=json)")
+            }
+            None => false,
+        };
+
+        if explain && cfg!(not(feature = "json")) {
+            bail!("runtime error. The use of --explain requires `tuc` to be compiled with `json` support");
+        }
+
+        let analyze = pargs.contains("--analyze");
+        let sniff = pargs.contains("--sniff");
+
+        if sniff {
+            if bounds_type != BoundsType::Fields {
+                bail!("runtime error. --sniff is available only for --fields");
+            } else if has_delimiter || regex_bag.is_some() || csv || json_input || analyze {
+                bail!("runtime error. --sniff cannot be used with --delimiter, --regex, --csv, --json-input or --analyze");
+            }
+        }
+
+        let translation = match (pargs.contains("--to-awk"), pargs.contains("--to-cut")) {
+            (true, true) => bail!("runtime error. --to-awk and --to-cut cannot be used together"),
+            (true, false) => Some(Translation::Awk),
+            (false, true) => Some(Translation::Cut),
+            (false, false) => None,
+        };
+
+        let truncate: Option<Truncate> = pargs.opt_value_from_str("--truncate")?;
+        let map: Option<TransformPipeline> = pargs.opt_value_from_str("--map")?;
+        let mut pad: Option<Pad> = pargs.opt_value_from_str("--pad")?;
+        let pad_align: Option<Align> = pargs.opt_value_from_str("--pad-align")?;
+        let whole_stream = pargs.contains("--whole-stream");
+
+        if whole_stream && bounds_type != BoundsType::Bytes {
+            bail!("runtime error. --whole-stream is available only for --bytes");
+        }
+
+        let print_delimiters = pargs.contains("--print-delimiters");
+
+        if print_delimiters && bounds_type != BoundsType::Fields {
+            bail!("runtime error. --print-delimiters is available only for --fields");
+        }
+
+        let pairs = pargs.contains("--pairs");
+
+        if pairs {
+            if bounds_type != BoundsType::Fields {
+                bail!("runtime error. --pairs is available only for --fields");
+            } else if print_delimiters {
+                bail!("runtime error. --pairs cannot be used with --print-delimiters");
+            }
+        }
+
+        let add_fields: Vec<AddedField> = pargs.values_from_str("--add-field")?;
+
+        if !add_fields.is_empty() {
+            if bounds_type != BoundsType::Fields {
+                bail!("runtime error. --add-field is available only for --fields");
+            } else if print_delimiters {
+                bail!("runtime error. --add-field cannot be used with --print-delimiters");
+            }
+        }
+
+        let assert_fields: Option<FieldsCount> = pargs.opt_value_from_str("--assert-fields")?;
+        let relaxed = pargs.contains("--relaxed");
+        let lenient = pargs.contains("--lenient");
+        let report = pargs.contains("--report");
+
+        #[cfg(feature = "regex")]
+        let assertions: Vec<FieldAssertion> = pargs.values_from_str("--assert")?;
+
+        #[cfg(not(feature = "regex"))]
+        let assertions: Vec<()> = if pargs.opt_value_from_str::<_, String>("--assert")?.is_some() {
+            bail!("runtime error. The use of --assert requires `tuc` to be compiled with `regex` support");
+        } else {
+            Vec::new()
+        };
+
+        if (assert_fields.is_some() || !assertions.is_empty() || report)
+            && bounds_type != BoundsType::Fields
+        {
+            bail!("runtime error. --assert-fields, --assert and --report are available only for --fields");
+        }
+
+        if relaxed && assert_fields.is_none() && assertions.is_empty() {
+            bail!("runtime error. --relaxed requires --assert-fields or --assert");
+        }
+
+        let errors: ErrorPolicy = pargs
+            .opt_value_from_str("--errors")?
+            .unwrap_or(ErrorPolicy::FailFast);
+
+        if lenient {
+            if bounds_type != BoundsType::Fields && bounds_type != BoundsType::Characters {
+                bail!("runtime error. --lenient is available only for --fields and --characters");
+            } else if errors != ErrorPolicy::FailFast {
+                bail!("runtime error. --lenient cannot be used with --errors collect or ignore");
+            }
+        }
+
+        if errors != ErrorPolicy::FailFast {
+            if bounds_type != BoundsType::Fields && bounds_type != BoundsType::Characters {
+                bail!("runtime error. --errors is available only for --fields and --characters");
+            } else if relaxed {
+                bail!("runtime error. --relaxed cannot be used with --errors collect or ignore");
+            }
+        }
+
+        let json_keys: Option<Vec<String>> = match pargs
+            .opt_value_from_str::<_, String>("--json-keys")?
+            .map(|keys| keys.split(',').map(str::to_owned).collect())
+        {
+            Some(_) if keys_from_fields.is_some() => {
+                bail!("runtime error. --json-keys cannot be used when --fields names the keys")
+            }
+            json_keys => json_keys.or(keys_from_fields),
+        };
+        let json_input = json_input || json_keys.is_some();
+
+        if json_input && cfg!(not(feature = "json")) {
+            bail!("runtime error. The use of --json-input requires `tuc` to be compiled with `json` support");
+        }
+
+        if json_input && bounds_type != BoundsType::Fields {
+            bail!("runtime error. --json-input is available only for --fields");
+        }
+
+        let only_delimited = pargs.contains(["-s", "--only-delimited"]);
+        let compress_delimiter = pargs.contains(["-p", "--compress-delimiter"]);
+        let trim: Option<Trim> = pargs.opt_value_from_str(["-t", "--trim"])?;
+
+        let delimiters: Option<MultiFixedFinder> = if delimiters.is_empty() {
+            None
+        } else if regex_bag.is_some() || csv || json_input || compress_delimiter || trim.is_some() {
+            bail!("runtime error. Multiple --delimiter cannot be used with --regex, --csv, --json-input, --compress-delimiter or --trim");
+        } else {
+            Some(
+                MultiFixedFinder::new(delimiters)
+                    .map_err(|e| anyhow!("runtime error. Invalid --delimiter: {}", e))?,
+            )
+        };
+
+        let quote = match pargs.opt_value_from_str::<_, String>("--quote")? {
+            Some(quote) if quote.len() != 1 => {
+                bail!("runtime error. --quote must be a single character");
+            }
+            Some(_) if bounds_type != BoundsType::Fields => {
+                bail!("runtime error. --quote is available only for --fields");
+            }
+            Some(_)
+                if regex_bag.is_some()
+                    || delimiters.is_some()
+                    || json_input
+                    || greedy_delimiter
+                    || compress_delimiter =>
+            {
+                bail!("runtime error. --quote cannot be used with --regex, multiple --delimiter, --json-input, --greedy-delimiter or --compress-delimiter");
+            }
+            Some(quote) if delimiter.contains(&quote.as_bytes()[0]) => {
+                bail!("runtime error. --quote cannot be part of the delimiter");
+            }
+            Some(quote) => Some(quote.as_bytes()[0]),
+            None => None,
+        };
+
+        // The character must be attached (--escape=CHAR), or it would be
+        // mistaken for the first input file
+        let escape: Option<String> = if pargs.contains("--escape") {
+            Some("\\".into())
+        } else {
+            pargs.opt_value_from_str("--escape")?
+        };
+        let unescape = pargs.contains("--unescape");
+
+        let escape = match escape {
+            Some(escape) if escape.len() != 1 => {
+                bail!("runtime error. --escape must be a single character");
+            }
+            Some(_) if bounds_type != BoundsType::Fields => {
+                bail!("runtime error. --escape is available only for --fields");
+            }
+            Some(_)
+                if regex_bag.is_some()
+                    || delimiters.is_some()
+                    || csv
+                    || quote.is_some()
+                    || json_input
+                    || greedy_delimiter
+                    || compress_delimiter =>
+            {
+                bail!("runtime error. --escape cannot be used with --regex, multiple --delimiter, --csv, --quote, --json-input, --greedy-delimiter or --compress-delimiter");
+            }
+            Some(escape) if delimiter.contains(&escape.as_bytes()[0]) => {
+                bail!("runtime error. --escape cannot be part of the delimiter");
+            }
+            Some(escape) => Some(escape.as_bytes()[0]),
+            None if unescape => bail!("runtime error. --unescape requires --escape"),
+            None => None,
+        };
+
+        let respect_brackets = pargs.contains("--respect-brackets");
+
+        if respect_brackets && bounds_type != BoundsType::Fields {
+            bail!("runtime error. --respect-brackets is available only for --fields");
+        }
+
+        if respect_brackets
+            && (regex_bag.is_some()
+                || delimiters.is_some()
+                || csv
+                || quote.is_some()
+                || escape.is_some()
+                || json_input
+                || greedy_delimiter
+                || compress_delimiter)
+        {
+            bail!("runtime error. --respect-brackets cannot be used with --regex, multiple --delimiter, --csv, --quote, --escape, --json-input, --greedy-delimiter or --compress-delimiter");
+        }
+
+        let skip_empty = pargs.contains("--skip-empty");
+
+        if skip_empty && bounds_type != BoundsType::Lines {
+            bail!("runtime error. --skip-empty is available only for --lines");
+        }
+
+        let no_split_codepoints = pargs.contains("--no-split-codepoints");
+
+        if no_split_codepoints && bounds_type != BoundsType::Bytes {
+            bail!("runtime error. --no-split-codepoints is available only for --bytes");
+        }
+
+        if truncate.is_some() && bounds_type != BoundsType::Fields {
+            bail!("runtime error. --truncate is available only for --fields");
+        }
+
+        if map.is_some() && bounds_type != BoundsType::Fields {
+            bail!("runtime error. --map is available only for --fields");
+        }
+
+        if pad.is_some() && bounds_type != BoundsType::Fields {
+            bail!("runtime error. --pad is available only for --fields");
+        }
+
+        if let Some(align) = pad_align {
+            match &mut pad {
+                Some(pad) => pad.align = align,
+                None => bail!("runtime error. --pad-align requires --pad"),
+            }
+        }
+
+        let complement = pargs.contains(["-m", "--complement"]);
+
+        let keep_original_delimiters = pargs.contains("--keep-original-delimiters");
+
+        if keep_original_delimiters {
+            if !complement {
+                bail!("runtime error. --keep-original-delimiters requires --complement");
+            } else if bounds_type != BoundsType::Fields {
+                bail!("runtime error. --keep-original-delimiters is available only for --fields");
+            } else if has_json
+                || replace_delimiter.is_some()
+                || output_delimiter.is_some()
+                || bounds.has_modifiers()
+                || bounds.iter().any(|s| !matches!(s, BoundOrFiller::Bound(_)))
+            {
+                bail!("runtime error. --keep-original-delimiters cannot be used with --json, --replace-delimiter, --output-delimiter or formatted fields");
+            }
+        }
+
+        if swap.is_some() && (has_json || complement) {
+            bail!("runtime error. --swap cannot be used with --json or --complement");
+        }
+
+        let sub_delimiter: Option<Vec<u8>> = pargs
+            .opt_value_from_str::<_, String>(["-D", "--sub-delimiter"])?
+            .map(|x| x.into());
+
+        #[cfg(feature = "regex")]
+        let assertions_have_sub_fields = assertions.iter().any(|a| a.bounds.sub.is_some());
+        #[cfg(not(feature = "regex"))]
+        let assertions_have_sub_fields = false;
+
+        let has_sub_fields = bounds.has_sub_fields()
+            || set.iter().any(|(_, template)| template.has_sub_fields())
+            || assertions_have_sub_fields;
+
+        if has_sub_fields && sub_delimiter.is_none() {
+            bail!("runtime error. Sub-fields (e.g. 2.1) require a secondary delimiter (-D)");
+        } else if !has_sub_fields && sub_delimiter.is_some() {
+            bail!("runtime error. -D is used only by sub-fields (e.g. -f 2.1)");
+        } else if sub_delimiter.as_ref().is_some_and(|d| d.is_empty()) {
+            bail!("runtime error. The secondary delimiter cannot be empty");
+        } else if has_sub_fields && complement {
+            bail!("runtime error. Sub-fields (e.g. 2.1) cannot be used with --complement");
+        } else if bounds.has_byte_ranges() && complement {
+            bail!("runtime error. Byte ranges (e.g. 2[1:8]) cannot be used with --complement");
+        }
+
+        if pairs
+            && (has_json
+                || complement
+                || swap.is_some()
+                || insert.is_some()
+                || !set.is_empty()
+                || !add_fields.is_empty())
+        {
+            bail!("runtime error. --pairs cannot be used with --json, --complement, --swap, --insert, --set or --add-field");
+        }
+
+        if !set.is_empty() && (has_json || complement || print_delimiters) {
+            bail!("runtime error. --set cannot be used with --json, --complement or --print-delimiters");
+        }
+
+        if insert.is_some() {
+            if has_json || complement || print_delimiters {
+                bail!("runtime error. --insert cannot be used with --json, --complement or --print-delimiters");
+            } else if regex_bag.is_some()
+                && replace_delimiter.is_none()
+                && output_delimiter.is_none()
+            {
+                bail!("runtime error. --insert with --regex requires --replace-delimiter or --output-delimiter");
+            }
+        }
+
+        let parquet: Option<String> = pargs.opt_value_from_str("--parquet")?;
+        let columnar = match (parquet, pargs.contains("--arrow")) {
+            (Some(_), true) => {
+                bail!("runtime error. --parquet and --arrow cannot be used together")
+            }
+            (Some(path), false) => Some(ColumnarOutput::Parquet(path.into())),
+            (None, true) => Some(ColumnarOutput::Arrow),
+            (None, false) => None,
+        };
+        let column_types = pargs
+            .opt_value_from_fn("--types", parse_column_types)?
+            .unwrap_or_default();
+
+        if columnar.is_some() {
+            if cfg!(not(feature = "columnar")) {
+                bail!("runtime error. The use of --parquet and --arrow requires `tuc` to be compiled with `columnar` support");
+            } else if bounds_type != BoundsType::Fields {
+                bail!("runtime error. --parquet and --arrow are available only for --fields");
+            } else if !bounds.iter().all(|b| {
+                matches!(b, BoundOrFiller::Bound(b) if b.l == b.r && b.l != Side::Continue && !b.has_modifiers())
+            }) {
+                bail!("runtime error. --parquet and --arrow require a list of single fields (e.g. -f 1,3,-1)");
+            } else if has_json
+                || complement
+                || replace_delimiter.is_some()
+                || output_delimiter.is_some()
+                || keep_original_delimiters
+                || swap.is_some()
+                || insert.is_some()
+                || !set.is_empty()
+                || !add_fields.is_empty()
+                || print_delimiters
+                || pairs
+            {
+                bail!("runtime error. --parquet and --arrow cannot be used with options that change the printed fields or their delimiters");
+            } else if !column_types.is_empty() && column_types.len() != bounds.len() {
+                bail!(
+                    "runtime error. --types requires a type for each of the {} fields",
+                    bounds.len()
+                );
+            }
+        } else if !column_types.is_empty() {
+            bail!("runtime error. --types requires --parquet or --arrow");
+        }
+
+        let exec: Option<ExecCommand> = pargs
+            .opt_value_from_str::<_, String>("--exec")?
+            .map(|command| ExecCommand::from_str(&command))
+            .transpose()
+            .map_err(|e| anyhow!("runtime error. {}", e))?;
+        let max_procs: Option<usize> = pargs.opt_value_from_str("--max-procs")?;
+
+        if exec.is_some() {
+            if bounds_type != BoundsType::Fields {
+                bail!("runtime error. --exec is available only for --fields");
+            } else if bounds.has_modifiers()
+                || bounds.iter().any(|b| !matches!(b, BoundOrFiller::Bound(_)))
+            {
+                bail!("runtime error. --exec formats the fields itself, use -f to select them (e.g. -f 1,3)");
+            } else if has_json
+                || columnar.is_some()
+                || swap.is_some()
+                || insert.is_some()
+                || !set.is_empty()
+                || print_delimiters
+                || pairs
+            {
+                bail!("runtime error. --exec cannot be used with options that change the printed fields or their format");
+            }
+        }
+
+        match max_procs {
+            Some(_) if exec.is_none() => bail!("runtime error. --max-procs requires --exec"),
+            Some(0) => bail!("runtime error. --max-procs must be at least 1"),
+            _ => (),
+        }
+
+        let tee_cmd: Option<String> = pargs.opt_value_from_str("--tee-cmd")?;
+
+        if let Some(tee_cmd) = &tee_cmd {
+            if split_words(tee_cmd).map_or(true, |words| words.is_empty()) {
+                bail!("runtime error. --tee-cmd requires a command, e.g. --tee-cmd 'sort -u'");
+            } else if exec.is_some() || columnar.is_some() {
+                bail!("runtime error. --tee-cmd cannot be used with --exec, --parquet or --arrow");
+            }
+        }
+
+        let timestamp: Option<TimestampFormat> = if pargs.contains("--timestamp") {
+            Some(TimestampFormat::default())
+        } else {
+            pargs.opt_value_from_str("--timestamp")?
+        };
+
+        if timestamp.is_some() && (exec.is_some() || columnar.is_some() || msgpack) {
+            bail!("runtime error. --timestamp cannot be used with --exec, --msgpack, --parquet or --arrow");
+        }
+
+        #[cfg(feature = "regex")]
+        let grep_fields: Option<Regex> = pargs
+            .opt_value_from_str::<_, String>("--grep-fields")?
+            .map(|regex_text| {
+                Regex::new(&regex_text)
+                    .map_err(|e| anyhow!("runtime error. The regular expression is malformed. {e}"))
+            })
+            .transpose()?;
+
+        #[cfg(not(feature = "regex"))]
+        let grep_fields: Option<()> = if pargs
+            .opt_value_from_str::<_, String>("--grep-fields")?
+            .is_some()
+        {
+            bail!("runtime error. The use of --grep-fields requires `tuc` to be compiled with `regex` support");
+        } else {
+            None
+        };
+
+        if grep_fields.is_some() {
+            if bounds_type != BoundsType::Fields {
+                bail!("runtime error. --grep-fields is available only for --fields");
+            } else if has_json
+                || complement
+                || pairs
+                || print_delimiters
+                || !set.is_empty()
+                || !add_fields.is_empty()
+                || exec.is_some()
+                || columnar.is_some()
+            {
+                bail!("runtime error. --grep-fields prints the whole record and cannot be used with --json, --complement, --pairs, --print-delimiters, --set, --add-field, --exec, --parquet or --arrow");
+            }
+        }
+
+        #[cfg(feature = "regex")]
+        let field_match: Option<Regex> = pargs
+            .opt_value_from_str::<_, String>("--field-match")?
+            .map(|regex_text| {
+                Regex::new(&regex_text)
+                    .map_err(|e| anyhow!("runtime error. The regular expression is malformed. {e}"))
+            })
+            .transpose()?;
+
+        #[cfg(not(feature = "regex"))]
+        let field_match: Option<()> = if pargs
+            .opt_value_from_str::<_, String>("--field-match")?
+            .is_some()
+        {
+            bail!("runtime error. The use of --field-match requires `tuc` to be compiled with `regex` support");
+        } else {
+            None
+        };
+
+        let field_drop = pargs.contains("--field-drop");
+
+        if field_drop && field_match.is_none() {
+            bail!("runtime error. --field-drop requires --field-match");
+        }
+
+        if field_match.is_some() {
+            if has_explicit_bounds || swap.is_some() || insert.is_some() || !set.is_empty() {
+                bail!("runtime error. --field-match cannot be used with --fields, --characters, --bytes, --lines, --width, --swap, --insert or --set");
+            } else if has_json || complement || pairs || print_delimiters || grep_fields.is_some() {
+                bail!("runtime error. --field-match cannot be used with --json, --complement, --pairs, --print-delimiters or --grep-fields");
+            }
+        }
+
+        let count = pargs.contains("--count");
+
+        if count {
+            if has_explicit_bounds || swap.is_some() || insert.is_some() || !set.is_empty() {
+                bail!("runtime error. --count cannot be used with --fields, --characters, --bytes, --lines, --width, --swap, --insert or --set");
+            } else if has_json
+                || complement
+                || pairs
+                || print_delimiters
+                || grep_fields.is_some()
+                || field_match.is_some()
+                || !add_fields.is_empty()
+                || exec.is_some()
+                || columnar.is_some()
+            {
+                bail!("runtime error. --count prints only the number of fields and cannot be used with --json, --complement, --pairs, --print-delimiters, --grep-fields, --field-match, --add-field, --exec, --parquet or --arrow");
+            }
+        }
+
+        let with_index = pargs.contains("--with-index");
+
+        if with_index {
+            if bounds_type != BoundsType::Fields {
+                bail!("runtime error. --with-index is available only for --fields");
+            } else if json_object
+                || msgpack
+                || swap.is_some()
+                || insert.is_some()
+                || !set.is_empty()
+                || keep_original_delimiters
+                || pairs
+                || print_delimiters
+                || count
+                || grep_fields.is_some()
+                || field_match.is_some()
+                || exec.is_some()
+                || columnar.is_some()
+            {
+                bail!("runtime error. --with-index cannot be used with --json-object, --msgpack, --swap, --insert, --set, --keep-original-delimiters, --pairs, --print-delimiters, --count, --grep-fields, --field-match, --exec, --parquet or --arrow");
+            }
+        }
+
+        let seek: u64 = pargs.opt_value_from_str("--seek")?.unwrap_or(0);
+        let print_offset = pargs.contains("--print-offset");
+
+        if print_offset && (exec.is_some() || columnar.is_some() || msgpack) {
+            bail!("runtime error. --print-offset cannot be used with --exec, --msgpack, --parquet or --arrow");
+        }
+
+        let paste: Vec<PathBuf> = pargs
+            .values_from_str::<_, String>("--paste")?
+            .into_iter()
+            .map(PathBuf::from)
+            .collect();
+
+        if !paste.is_empty() {
+            if bounds_type != BoundsType::Fields {
+                bail!("runtime error. --paste is available only for --fields");
+            } else if regex_bag.is_some() || json_input || print_offset {
+                bail!("runtime error. --paste cannot be used with --regex, --json-input or --print-offset");
+            }
+        }
+
+        if json_object && (!add_fields.is_empty() || exec.is_some() || columnar.is_some()) {
+            bail!("runtime error. --json-object cannot be used with --add-field, --exec, --parquet or --arrow");
+        }
+
+        let wrap: Option<Wrap> = pargs.opt_value_from_str("--wrap")?;
+
+        if wrap.is_some() && (has_json || exec.is_some() || columnar.is_some()) {
+            bail!("runtime error. --wrap cannot be used with --json, --exec, --parquet or --arrow");
+        }
+
+        let version = pargs.contains(["-V", "--version"]);
+        let eol = if pargs.contains(["-z", "--zero-terminated"]) {
+            EOL::Zero
+        } else {
+            EOL::Newline
+        };
+        let fallback_oob = pargs
+            .opt_value_from_str("--fallback-oob")
+            .or_else(|e| match e {
+                pico_args::Error::OptionWithoutAValue(_) => {
+                    // We must consume the arg ourselves (it's not done on error)
+                    pargs.contains("--fallback-oob=");
+
+                    Ok(Some("".into()))
+                }
+                _ => Err(e),
+            })?
+            .map(resolve_fallback)
+            .transpose()?;
+        let output_buffer_size = pargs
+            .opt_value_from_str("--output-buffer-size")?
+            .unwrap_or(64 * 1024);
+        let timing = pargs.contains("--timing");
+        let copy = pargs.contains("--copy");
+
+        let decompress: Option<Decompress> = pargs.opt_value_from_str("--decompress")?;
+        // The suffix must be attached (--in-place=SUFFIX or -iSUFFIX), or it
+        // would be mistaken for the first input file
+        let in_place: Option<String> = if pargs.contains(["-i", "--in-place"]) {
+            Some(String::new())
+        } else {
+            pargs.opt_value_from_str("--in-place")?
+        };
+        let has_number = pargs.contains("--number");
+        let number_separator: Option<String> = pargs.opt_value_from_str("--number-separator")?;
+        let skip: usize = pargs.opt_value_from_str("--skip")?.unwrap_or(0);
+        let skip_print = pargs.contains("--skip-print");
+        let keep_header = pargs.contains("--keep-header");
+        let cut_header = pargs.contains("--cut-header");
+        let remaining = pargs.finish();
+
+        // Whatever is left is an input file, unless it looks like a flag
+        let is_flag = |arg: &OsString| arg.to_string_lossy().starts_with('-') && arg != "-";
+        if !version && remaining.iter().any(is_flag) {
+            bail!("unexpected arguments {remaining:?}\nTry 'tuc --help' for more information.");
+        }
+
+        let files: Vec<PathBuf> = remaining.into_iter().map(PathBuf::from).collect();
+
+        if !files.is_empty() && (seek != 0 || print_offset) {
+            bail!("runtime error. --seek and --print-offset cannot be used with input files");
+        }
+
+        if let Some(Decompress::Format(compression)) = decompress {
+            if !compression.is_supported() {
+                bail!("runtime error. The use of --decompress {} requires `tuc` to be compiled with `{}` support", compression, compression);
+            }
+        }
+
+        if decompress.is_some() && (seek != 0 || print_offset) {
+            bail!("runtime error. --decompress cannot be used with --seek or --print-offset");
+        }
+
+        if number_separator.is_some() && !has_number {
+            bail!("runtime error. --number-separator requires --number");
+        }
+
+        if has_number {
+            if bounds_type != BoundsType::Fields && bounds_type != BoundsType::Characters {
+                bail!("runtime error. --number is available only for --fields and --characters");
+            } else if has_json || analyze || exec.is_some() || columnar.is_some() {
+                bail!("runtime error. --number cannot be used with --json, --msgpack, --analyze, --exec, --parquet or --arrow");
+            }
+        }
+
+        let number: Option<Vec<u8>> =
+            has_number.then(|| number_separator.unwrap_or_else(|| "\t".into()).into());
+
+        if skip_print && skip == 0 {
+            bail!("runtime error. --skip-print requires --skip");
+        }
+
+        if skip > 0 && (analyze || exec.is_some() || msgpack || columnar.is_some()) {
+            bail!("runtime error. --skip cannot be used with --analyze, --exec, --msgpack, --parquet or --arrow");
+        }
+
+        if skip_print && has_json {
+            bail!("runtime error. --skip-print cannot be used with --json");
+        }
+
+        if keep_header && cut_header {
+            bail!("runtime error. --keep-header and --cut-header cannot be used together");
+        }
+
+        // the header of --keep-header gives its keys to --json-object
+        let has_json_without_keys = has_json && !(keep_header && json_object);
+        if (keep_header || cut_header)
+            && (has_json_without_keys || analyze || exec.is_some() || columnar.is_some())
+        {
+            bail!("runtime error. --keep-header and --cut-header cannot be used with --json, --msgpack, --analyze, --exec, --parquet or --arrow (except --keep-header with --json-object)");
+        }
+
+        if in_place.is_some() {
+            if files.is_empty() || files.iter().any(|path| path.as_os_str() == "-") {
+                bail!("runtime error. --in-place requires input files, and none can be -");
+            }
+
+            if !paste.is_empty()
+                || decompress.is_some()
+                || tee_cmd.is_some()
+                || copy
+                || timing
+                || analyze
+                || sniff
+                || exec.is_some()
+                || msgpack
+                || columnar.is_some()
+            {
+                bail!("runtime error. --in-place cannot be used with --paste, --decompress, --tee-cmd, --copy, --timing, --analyze, --sniff, --exec, --msgpack, --parquet or --arrow");
+            }
+        }
+
+        let args = Opt {
+            complement,
+            only_delimited,
+            greedy_delimiter,
+            compress_delimiter,
+            version,
+            eol,
+            join,
+            json: has_json,
+            json_pretty,
+            json_object,
+            field_names: Vec::new(),
+            msgpack,
+            delimiter,
+            sub_delimiter,
+            bounds_type,
+            bounds,
+            replace_delimiter,
+            output_delimiter,
+            trim,
+            fallback_oob,
+            strip_ansi,
+            show_nonprinting,
+            swap,
+            insert,
+            set,
+            truncate,
+            map,
+            pad,
+            output_buffer_size,
+            whole_stream,
+            no_split_codepoints,
+            print_delimiters,
+            pairs,
+            explain,
+            translate: translation,
+            analyze,
+            sniff,
+            timing,
+            copy,
+            columnar,
+            column_types,
+            exec,
+            max_procs: max_procs.unwrap_or(1),
+            tee_cmd,
+            timestamp,
+            wrap,
+            seek,
+            print_offset,
+            paste,
+            files,
+            decompress,
+            in_place,
+            skip,
+            number,
+            skip_print,
+            keep_header,
+            cut_header,
+            keep_original_delimiters,
+            skip_empty,
+            json_input,
+            csv,
+            quote,
+            escape,
+            unescape,
+            respect_brackets,
+            json_keys,
+            add_fields,
+            assert_fields,
+            relaxed,
+            lenient,
+            assertions,
+            report,
+            errors,
+            delimiters,
+            regex_bag,
+            grep_fields,
+            line_pattern,
+            field_match,
+            field_drop,
+            count,
+            with_index,
+        };
+
+        args.validate()?;
+
+        if let Some(translation) = args.translate {
+            translate(&args, translation).map_err(|e| anyhow!("runtime error. {}", e))?;
+        }
+
+        Ok(args)
+    }
+
+    /// Check the combinations of options that cannot be used together.
+    /// Shared by `try_from_args` and `OptBuilder::build`, so that the
+    /// options are validated the same way however they are built.
+    pub(crate) fn validate(&self) -> Result<()> {
+        let bounds_type = self.bounds_type;
+        let bounds = &self.bounds;
+        let is_fields_or_characters =
+            bounds_type == BoundsType::Fields || bounds_type == BoundsType::Characters;
+
+        if self.json && cfg!(not(feature = "json")) {
+            bail!("runtime error. The use of --json requires `tuc` to be compiled with `json` support");
+        }
+
+        if bounds_type == BoundsType::Characters && cfg!(not(feature = "regex")) {
+            bail!("runtime error. The use of --characters requires `tuc` to be compiled with `regex` support");
+        }
+
+        if self.json && !is_fields_or_characters {
+            bail!("runtime error. --json support is available only for --fields and --characters");
+        }
+
+        if bounds.has_conditionals() && !is_fields_or_characters {
+            bail!("runtime error. Conditional formatting is available only for --fields and --characters");
+        }
+
+        if bounds.has_modifiers() && bounds_type != BoundsType::Fields {
+            bail!("runtime error. Placeholder modifiers (e.g. {{1:<10}}) are available only for --fields");
+        }
+
+        if bounds_type == BoundsType::Lines && bounds.contains(&BoundOrFiller::WholeLine) {
+            bail!("runtime error. The placeholder {{0}} is not available for --lines");
+        }
+
+        if bounds.get_capture_names().next().is_some() && bounds_type != BoundsType::Fields {
+            bail!("runtime error. Named placeholders are available only for --fields");
+        }
+
+        #[cfg(feature = "regex")]
+        let has_capture_group = |name: &str| {
+            self.regex_bag
+                .as_ref()
+                .is_some_and(|re_bag| re_bag.normal.capture_names().any(|n| n == Some(name)))
+        };
+
+        #[cfg(not(feature = "regex"))]
+        let has_capture_group = |_: &str| false;
+
+        if let Some(name) = bounds
+            .get_capture_names()
+            .find(|name| !has_capture_group(name))
+        {
+            bail!("runtime error. The placeholder {{{name}}} requires --regex with a capture group named `{name}`");
+        }
+
+        if self.json
+            && (bounds.has_modifiers()
+                || bounds.iter().any(|s| !matches!(s, BoundOrFiller::Bound(_))))
+        {
+            bail!("runtime error. Cannot format fields when using --json");
+        }
+
+        if self.json_input
+            && (self.regex_bag.is_some()
+                || self.greedy_delimiter
+                || self.compress_delimiter
+                || self.only_delimited
+                || self.trim.is_some())
+        {
+            bail!("runtime error. --json-input cannot be used with --regex, --greedy-delimiter, --compress-delimiter, --only-delimited or --trim");
+        }
+
+        if self.csv
+            && (self.regex_bag.is_some()
+                || self.json_input
+                || self.greedy_delimiter
+                || self.compress_delimiter)
+        {
+            bail!("runtime error. --csv cannot be used with --regex, --json-input, --greedy-delimiter or --compress-delimiter");
+        }
+
+        if self.complement && bounds_type == BoundsType::Width {
+            bail!("runtime error. --complement is not available for --width");
+        }
+
+        if self.line_pattern.is_some()
+            && (self.complement || self.trim.is_some() || self.compress_delimiter || self.json)
+        {
+            bail!("runtime error. --lines /regex/ cannot be used with --complement, --trim, --compress-delimiter or --json");
+        }
+
+        if bounds.has_steps() {
+            if !is_fields_or_characters {
+                bail!("runtime error. Steps (e.g. 1:10:2) are available only for --fields and --characters");
+            } else if self.complement {
+                bail!("runtime error. Steps (e.g. 1:10:2) cannot be used with --complement");
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Build a command line that, given to `try_from_args`, produces equivalent options.
+    ///
+    /// Values that are implied by other options (e.g. `--join` when using `--replace`)
+    /// are omitted. Non UTF-8 delimiters and fillers are converted lossily.
+    ///
+    /// ```rust
+    /// # use tuc::options::Opt;
+    /// let opt = Opt::try_from_args(["-d", ",", "-f", "2:", "-j"].map(Into::into)).unwrap();
+    /// assert_eq!(opt.to_args(), ["--fields", "2:", "--delimiter", ",", "--join"]);
+    ///
+    /// let same_opt = Opt::try_from_args(opt.to_args().into_iter().map(Into::into)).unwrap();
+    /// assert_eq!(same_opt.to_args(), opt.to_args());
+    /// ```
+    pub fn to_args(&self) -> Vec<String> {
+        let mut args: Vec<String> = Vec::new();
+        let lossy = |bytes: &[u8]| String::from_utf8_lossy(bytes).into_owned();
+
+        if self.version {
+            args.push("--version".into());
+        }
+
+        if let Some((a, b)) = &self.swap {
+            args.extend(["--swap".into(), a.to_string(), b.to_string()]);
+        } else if let Some((position, value)) = &self.insert {
+            args.extend(["--insert".into(), position.to_string(), value.to_string()]);
+        } else if self.field_match.is_some() {
+            #[cfg(feature = "regex")]
+            if let Some(field_match) = &self.field_match {
+                args.extend(["--field-match".into(), field_match.as_str().into()]);
+            }
+
+            if self.field_drop {
+                args.push("--field-drop".into());
+            }
+        } else if !self.set.is_empty() {
+            for (field, template) in &self.set {
+                args.extend([
+                    "--set".into(),
+                    format!("{}={}", field, template.to_template()),
+                ]);
+            }
+        } else {
+            let flag = match self.bounds_type {
+                BoundsType::Fields => "--fields",
+                BoundsType::Characters => "--characters",
+                BoundsType::Bytes => "--bytes",
+                BoundsType::Lines => "--lines",
+                BoundsType::Width => "--width",
+            };
+            #[cfg(feature = "regex")]
+            let bounds = match &self.line_pattern {
+                Some(line_pattern) => line_pattern.to_string(),
+                None => self.bounds.to_string(),
+            };
+            #[cfg(not(feature = "regex"))]
+            let bounds = self.bounds.to_string();
+            args.extend([flag.into(), bounds]);
+        }
+
+        if self.bounds_type == BoundsType::Fields {
+            args.extend(["--delimiter".into(), lossy(&self.delimiter)]);
+        }
+
+        if let Some(delimiters) = &self.delimiters {
+            for delimiter in &delimiters.delimiters()[1..] {
+                args.extend(["--delimiter".into(), lossy(delimiter)]);
+            }
+        }
+
+        if let Some(sub_delimiter) = &self.sub_delimiter {
+            args.extend(["--sub-delimiter".into(), lossy(sub_delimiter)]);
+        }
+
+        #[cfg(feature = "regex")]
+        if let Some(regex_bag) = &self.regex_bag {
+            if self.bounds_type != BoundsType::Characters {
+                args.extend(["--regex".into(), regex_bag.normal.as_str().into()]);
+            }
+        }
+
+        #[cfg(feature = "regex")]
+        if let Some(grep_fields) = &self.grep_fields {
+            args.extend(["--grep-fields".into(), grep_fields.as_str().into()]);
+        }
+
+        if self.greedy_delimiter {
+            args.push("--greedy-delimiter".into());
+        }
+
+        if self.compress_delimiter {
+            args.push("--compress-delimiter".into());
+        }
+
+        if self.only_delimited {
+            args.push("--only-delimited".into());
+        }
+
+        if self.complement {
+            args.push("--complement".into());
+        }
+
+        if self.keep_original_delimiters {
+            args.push("--keep-original-delimiters".into());
+        }
+
+        if self.msgpack {
+            args.push("--msgpack".into());
+        } else if self.json_pretty {
+            args.push("--json-pretty".into());
+        } else if self.json {
+            if !self.json_object {
+                args.push("--json".into());
+            }
+        } else if self.bounds_type != BoundsType::Characters {
+            if let Some(replace_delimiter) = &self.replace_delimiter {
+                args.extend(["--replace-delimiter".into(), lossy(replace_delimiter)]);
+            } else if self.bounds_type == BoundsType::Lines && !self.join {
+                args.push("--no-join".into());
+            } else if self.bounds_type != BoundsType::Lines && self.join {
+                args.push("--join".into());
+            }
+        }
+
+        if let Some(output_delimiter) = &self.output_delimiter {
+            args.extend(["--output-delimiter".into(), lossy(output_delimiter)]);
+        }
+
+        if self.json_object {
+            args.push("--json-object".into());
+        }
+
+        if let Some(trim) = self.trim {
+            let trim = match trim {
+                Trim::Left => "l",
+                Trim::Right => "r",
+                Trim::Both => "b",
+            };
+            args.extend(["--trim".into(), trim.into()]);
+        }
+
+        if let Some(fallback_oob) = &self.fallback_oob {
+            args.push(format!("--fallback-oob={}", lossy(fallback_oob)));
+        }
+
+        if let EOL::Zero = self.eol {
+            args.push("--zero-terminated".into());
+        }
+
+        if self.strip_ansi {
+            args.push("--strip-ansi".into());
+        }
+
+        if self.show_nonprinting {
+            args.push("--show-nonprinting".into());
+        }
+
+        if let Some(map) = &self.map {
+            args.extend(["--map".into(), map.to_string()]);
+        }
+
+        if let Some(truncate) = &self.truncate {
+            args.extend([
+                "--truncate".into(),
+                format!("{}={}", truncate.width, lossy(&truncate.ellipsis)),
+            ]);
+        }
+
+        if let Some(pad) = &self.pad {
+            args.extend(["--pad".into(), format!("{}={}", pad.width, pad.fill)]);
+            if pad.align != Align::Left {
+                args.extend(["--pad-align".into(), pad.align.to_string()]);
+            }
+        }
+
+        if self.output_buffer_size != Opt::default().output_buffer_size {
+            args.extend([
+                "--output-buffer-size".into(),
+                self.output_buffer_size.to_string(),
+            ]);
+        }
+
+        if self.whole_stream {
+            args.push("--whole-stream".into());
+        }
+
+        if let Some(json_keys) = &self.json_keys {
+            args.extend(["--json-keys".into(), json_keys.join(",")]);
+        } else if self.json_input {
+            args.push("--json-input".into());
+        }
+
+        if self.csv {
+            args.push("--csv".into());
+        }
+
+        if let Some(quote) = self.quote {
+            args.extend(["--quote".into(), (quote as char).to_string()]);
+        }
+
+        match self.escape {
+            Some(b'\\') => args.push("--escape".into()),
+            Some(escape) => args.push(format!("--escape={}", escape as char)),
+            None => (),
+        }
+
+        if self.unescape {
+            args.push("--unescape".into());
+        }
+
+        if self.respect_brackets {
+            args.push("--respect-brackets".into());
+        }
+
+        for field in &self.add_fields {
+            args.extend(["--add-field".into(), field.to_string()]);
+        }
+
+        if let Some(assert_fields) = &self.assert_fields {
+            args.extend(["--assert-fields".into(), assert_fields.to_string()]);
+        }
+
+        #[cfg(feature = "regex")]
+        for assertion in &self.assertions {
+            args.extend(["--assert".into(), assertion.to_string()]);
+        }
+
+        if self.relaxed {
+            args.push("--relaxed".into());
+        }
+
+        if self.lenient {
+            args.push("--lenient".into());
+        }
+
+        if self.report {
+            args.push("--report".into());
+        }
+
+        if self.errors != ErrorPolicy::FailFast {
+            args.extend(["--errors".into(), self.errors.to_string()]);
+        }
+
+        if self.skip_empty {
+            args.push("--skip-empty".into());
+        }
+
+        if self.no_split_codepoints {
+            args.push("--no-split-codepoints".into());
+        }
+
+        if self.print_delimiters {
+            args.push("--print-delimiters".into());
+        }
+
+        if self.count {
+            args.push("--count".into());
+        }
+
+        if self.with_index {
+            args.push("--with-index".into());
+        }
+
+        if self.pairs {
+            args.push("--pairs".into());
+        }
+
+        if self.explain {
+            args.push("--explain=json".into());
+        }
+
+        if let Some(translation) = self.translate {
+            args.push(format!("--to-{}", translation));
+        }
+
+        if self.analyze {
+            args.push("--analyze".into());
+        }
+
+        if self.sniff {
+            args.push("--sniff".into());
+        }
+
+        if self.timing {
+            args.push("--timing".into());
+        }
+
+        if self.copy {
+            args.push("--copy".into());
+        }
+
+        match &self.columnar {
+            Some(ColumnarOutput::Parquet(path)) => {
+                args.extend(["--parquet".into(), path.to_string_lossy().into_owned()]);
+            }
+            Some(ColumnarOutput::Arrow) => args.push("--arrow".into()),
+            None => (),
+        }
+
+        if let Some(exec) = &self.exec {
+            args.extend(["--exec".into(), exec.to_string()]);
+        }
+
+        if self.max_procs != 1 {
+            args.extend(["--max-procs".into(), self.max_procs.to_string()]);
+        }
+
+        if let Some(timestamp) = &self.timestamp {
+            args.push(format!("--timestamp={}", timestamp));
+        }
+
+        if self.seek != 0 {
+            args.extend(["--seek".into(), self.seek.to_string()]);
+        }
+
+        if self.print_offset {
+            args.push("--print-offset".into());
+        }
+
+        if let Some(decompress) = self.decompress {
+            args.extend(["--decompress".into(), decompress.to_string()]);
+        }
+
+        if let Some(separator) = &self.number {
+            args.push("--number".into());
+            if separator != b"\t" {
+                args.extend(["--number-separator".into(), lossy(separator)]);
+            }
+        }
+
+        if self.skip > 0 {
+            args.extend(["--skip".into(), self.skip.to_string()]);
+        }
+
+        if self.skip_print {
+            args.push("--skip-print".into());
+        }
+
+        if self.keep_header {
+            args.push("--keep-header".into());
+        }
+
+        if self.cut_header {
+            args.push("--cut-header".into());
+        }
+
+        match self.in_place.as_deref() {
+            Some("") => args.push("--in-place".into()),
+            Some(suffix) => args.push(format!("--in-place={}", suffix)),
+            None => (),
+        }
+
+        for path in &self.paste {
+            args.extend(["--paste".into(), path.display().to_string()]);
+        }
+
+        if let Some(wrap) = &self.wrap {
+            args.extend(["--wrap".into(), wrap.to_string()]);
+        }
+
+        if let Some(tee_cmd) = &self.tee_cmd {
+            args.extend(["--tee-cmd".into(), tee_cmd.clone()]);
+        }
+
+        if !self.column_types.is_empty() {
+            let types: Vec<String> = self.column_types.iter().map(|t| t.to_string()).collect();
+            args.extend(["--types".into(), types.join(",")]);
+        }
+
+        args.extend(self.files.iter().map(|path| path.display().to_string()));
+
+        args
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn try_from_args(args: &[&str]) -> Result<Opt> {
+        Opt::try_from_args(args.iter().map(OsString::from))
+    }
+
+    #[test]
+    fn it_uses_the_default_options() {
+        let opt = try_from_args(&[]).unwrap();
+        assert_eq!(opt.delimiter, b"\t");
+        assert_eq!(opt.bounds_type, BoundsType::Fields);
+        assert_eq!(
+            opt.bounds.list,
+            UserBoundsList::from_str("1:").unwrap().list
+        );
+        assert!(!opt.join);
+    }
+
+    #[test]
+    fn it_merges_the_values_of_swap() {
+        let opt = try_from_args(&["--swap", "1", "-1", "-d", " "]).unwrap();
+        let (a, b) = opt.swap.unwrap();
+        assert_eq!((a.l, b.l), (Side::Some(1), Side::Some(-1)));
+        assert_eq!(opt.delimiter, b" ");
+
+        let opt = try_from_args(&["--swap", "2,3"]).unwrap();
+        assert!(opt.swap.is_some());
+
+        let opt = try_from_args(&["--insert", "-1", "a,b", "--swap", "1", "2"]);
+        assert!(opt.is_err());
+
+        let opt = try_from_args(&["--insert", "-1", "a,b"]).unwrap();
+        let (position, value) = opt.insert.unwrap();
+        assert_eq!((position, value.to_string()), (-1, "a,b".to_owned()));
+
+        assert!(try_from_args(&["--insert", "0", "a"]).is_err());
+    }
+
+    #[test]
+    fn it_parses_the_fields_to_set() {
+        let opt = try_from_args(&["--set", "2=x", "--set", "-1={1}={2}"]).unwrap();
+        let set: Vec<_> = opt
+            .set
+            .iter()
+            .map(|(field, template)| format!("{}={}", field, template.to_template()))
+            .collect();
+        assert_eq!(set, ["2=x", "-1={1}={2}"]);
+        assert_eq!(
+            opt.to_args(),
+            ["--set", "2=x", "--set", "-1={1}={2}", "--delimiter", "\t"]
+        );
+
+        assert!(try_from_args(&["--set", "2:3=x"]).is_err());
+        assert!(try_from_args(&["--set", "x"]).is_err());
+        assert!(try_from_args(&["--set", "2={"]).is_err());
+        assert!(try_from_args(&["--set", "2=x", "-f", "1"]).is_err());
+    }
+
+    #[test]
+    fn it_accepts_multiple_delimiters() {
+        let opt = try_from_args(&["-d", ",", "-d", ";", "-f", "1"]).unwrap();
+        assert_eq!(opt.delimiter, b",");
+        assert_eq!(opt.delimiters.unwrap().delimiters(), [b",", b";"]);
+        assert!(try_from_args(&["-d", ",", "-f", "1"])
+            .unwrap()
+            .delimiters
+            .is_none());
+
+        assert!(try_from_args(&["-d", ",", "-d", ";", "--trim", "b"]).is_err());
+        assert!(try_from_args(&["-d", ",", "-d", ""]).is_err());
+    }
+
+    #[test]
+    fn it_lets_the_command_line_override_tuc_opts() {
+        let args: Vec<OsString> = ["-d", ":", "--trim=l"].map(Into::into).to_vec();
+        let tuc_opts = tuc_opts_args("-d ',' -j --trim b --replace-delimiter ' | '", &args);
+        assert_eq!(tuc_opts.unwrap(), ["-j", "--replace-delimiter", " | "]);
+
+        let args: Vec<OsString> = ["--no-join", "--no-json"].map(Into::into).to_vec();
+        let tuc_opts = tuc_opts_args("-j --json -s", &args);
+        assert_eq!(tuc_opts.unwrap(), ["-s"]);
+
+        let args: Vec<OsString> = ["-b", "1"].map(Into::into).to_vec();
+        let tuc_opts = tuc_opts_args("-d , --json -z", &args);
+        assert_eq!(tuc_opts.unwrap(), ["-z"]);
+
+        let tuc_opts = tuc_opts_args("-l 1 -d ,", &[]);
+        assert_eq!(tuc_opts.unwrap(), ["-l", "1"]);
+
+        assert!(tuc_opts_args("-d '", &[]).is_err());
+    }
+
+    #[test]
+    fn it_reads_the_fallback_from_a_file_or_the_environment() {
+        let path = std::env::temp_dir().join(format!("tuc-fallback-{}", std::process::id()));
+        std::fs::write(&path, "{\n  \"a\": null\n}\n").unwrap();
+        let opt = try_from_args(&["--fallback-oob", &format!("@{}", path.display())]);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(opt.unwrap().fallback_oob.unwrap(), b"{\n  \"a\": null\n}");
+
+        std::env::set_var("TUC_TEST_FALLBACK_OOB", "n/a");
+        let opt = try_from_args(&["--fallback-oob", "env:TUC_TEST_FALLBACK_OOB"]).unwrap();
+        assert_eq!(opt.fallback_oob.unwrap(), b"n/a");
+
+        assert!(try_from_args(&["--fallback-oob", "env:TUC_TEST_UNSET_VARIABLE"]).is_err());
+        assert!(try_from_args(&["--fallback-oob", "@/nonexistent/tuc"]).is_err());
+    }
+
+    #[test]
+    fn it_fails_on_invalid_combinations() {
+        assert_eq!(
+            try_from_args(&["-j", "--no-join"]).unwrap_err().to_string(),
+            "runtime error. It's not possible to use --join and --no-join simultaneously"
+        );
+        assert_eq!(
+            try_from_args(&["--pairs", "-b", "1"])
+                .unwrap_err()
+                .to_string(),
+            "runtime error. --pairs is available only for --fields"
+        );
+        assert!(try_from_args(&["--pairs", "--json"]).is_err());
+        assert!(try_from_args(&["--pairs", "--print-delimiters"]).is_err());
+        assert_eq!(
+            try_from_args(&["-b", "1:2,4:5", "-j"])
+                .unwrap_err()
+                .to_string(),
+            "runtime error. --bytes has no delimiter to join with, use --replace-delimiter instead of --join"
+        );
+        assert!(try_from_args(&["-b", "1:2,4:5", "-j", "-r", "-"]).is_ok());
+    }
+
+    #[test]
+    fn it_fails_on_unexpected_arguments() {
+        assert_eq!(
+            try_from_args(&["-f", "1", "foo", "--bar"])
+                .unwrap_err()
+                .to_string(),
+            "unexpected arguments [\"foo\", \"--bar\"]\nTry 'tuc --help' for more information."
+        );
+
+        // anything else is an input file
+        let opt = try_from_args(&["-f", "1", "foo", "-"]).unwrap();
+        assert_eq!(opt.files, [PathBuf::from("foo"), PathBuf::from("-")]);
+
+        // parsing errors are the ones from pico_args
+        assert!(try_from_args(&["--output-buffer-size", "lots"])
+            .unwrap_err()
+            .is::<pico_args::Error>());
+    }
+
+    #[test]
+    fn it_parses_width() {
+        let opt = try_from_args(&["--width", "1:80"]).unwrap();
+        assert_eq!(opt.bounds_type, BoundsType::Width);
+        assert_eq!(opt.to_args(), ["--width", "1:80"]);
+        assert!(try_from_args(&["--width", "1:80", "-m"]).is_err());
+    }
+
+    #[test]
+    fn it_parses_number() {
+        let opt = try_from_args(&["-f", "1", "--number"]).unwrap();
+        assert_eq!(opt.number.as_deref(), Some(&b"\t"[..]));
+        assert!(opt.to_args().ends_with(&["--number".into()]));
+
+        let opt = try_from_args(&["-f", "1", "--number", "--number-separator", ":"]).unwrap();
+        assert_eq!(opt.number.as_deref(), Some(&b":"[..]));
+
+        assert!(try_from_args(&["-f", "1", "--number-separator", ":"]).is_err());
+        assert!(try_from_args(&["-l", "1", "--number"]).is_err());
+        assert!(try_from_args(&["-f", "1", "--number", "--json"]).is_err());
+    }
+
+    #[test]
+    fn it_parses_skip() {
+        let opt = try_from_args(&["-f", "1", "--skip", "3", "--skip-print"]).unwrap();
+        assert_eq!((opt.skip, opt.skip_print), (3, true));
+        assert!(opt
+            .to_args()
+            .ends_with(&["--skip".into(), "3".into(), "--skip-print".into()]));
+
+        assert!(try_from_args(&["-f", "1", "--skip-print"]).is_err());
+        assert!(try_from_args(&["-f", "1", "--skip", "-1"]).is_err());
+    }
+
+    #[test]
+    fn it_parses_quote() {
+        let opt = try_from_args(&["-d", " ", "-f", "1", "--quote", "'"]).unwrap();
+        assert_eq!(opt.quote, Some(b'\''));
+        assert!(opt.to_args().ends_with(&["--quote".into(), "'".into()]));
+
+        assert!(try_from_args(&["-f", "1", "--quote", "ab"]).is_err());
+        assert!(try_from_args(&["-c", "1", "--quote", "'"]).is_err());
+        assert!(try_from_args(&["-f", "1", "-g", "--quote", "'"]).is_err());
+        assert!(try_from_args(&["-d", "'", "-f", "1", "--quote", "'"]).is_err());
+    }
+
+    #[test]
+    fn it_parses_escape() {
+        let opt = try_from_args(&["-d", ",", "-f", "1", "--escape", "--unescape"]).unwrap();
+        assert_eq!((opt.escape, opt.unescape), (Some(b'\\'), true));
+        assert!(opt
+            .to_args()
+            .ends_with(&["--escape".into(), "--unescape".into()]));
+
+        let opt = try_from_args(&["-d", ",", "-f", "1", "--escape=^"]).unwrap();
+        assert_eq!(opt.escape, Some(b'^'));
+        assert!(opt.to_args().ends_with(&["--escape=^".into()]));
+
+        assert!(try_from_args(&["-f", "1", "--unescape"]).is_err());
+        assert!(try_from_args(&["-f", "1", "--escape", "--csv"]).is_err());
+        assert!(try_from_args(&["-l", "1", "--escape"]).is_err());
+    }
+
+    #[test]
+    fn it_parses_respect_brackets() {
+        let opt = try_from_args(&["-d", ",", "-f", "1", "--respect-brackets"]).unwrap();
+        assert!(opt.respect_brackets);
+        assert!(opt.to_args().ends_with(&["--respect-brackets".into()]));
+
+        assert!(try_from_args(&["-c", "1", "--respect-brackets"]).is_err());
+        assert!(try_from_args(&["-f", "1", "--csv", "--respect-brackets"]).is_err());
+    }
+
+    #[cfg(feature = "regex")]
+    #[test]
+    fn it_parses_line_patterns() {
+        let opt = try_from_args(&["-l", "/^BEGIN/:/^END/"]).unwrap();
+        assert_eq!(opt.bounds_type, BoundsType::Lines);
+        assert_eq!(
+            opt.line_pattern.as_ref().unwrap().to_string(),
+            "/^BEGIN/:/^END/"
+        );
+        assert!(opt
+            .to_args()
+            .starts_with(&["--lines".into(), "/^BEGIN/:/^END/".into()]));
+
+        assert!(try_from_args(&["-l", "/^BEGIN/"]).is_err());
+        assert!(try_from_args(&["-l", "/^BEGIN/:", "-m"]).is_err());
+    }
+
+    #[cfg(feature = "regex")]
+    #[test]
+    fn it_parses_field_match() {
+        let opt = try_from_args(&["-d", " ", "--field-match", "^\\d+$", "--field-drop"]).unwrap();
+        assert_eq!(opt.field_match.as_ref().unwrap().as_str(), "^\\d+$");
+        assert!(opt.field_drop);
+        assert_eq!(
+            opt.to_args(),
+            [
+                "--field-match",
+                "^\\d+$",
+                "--field-drop",
+                "--delimiter",
+                " "
+            ]
+        );
+
+        assert!(try_from_args(&["--field-drop"]).is_err());
+        assert!(try_from_args(&["-f", "1", "--field-match", "a"]).is_err());
+        assert!(try_from_args(&["--field-match", "a", "--json"]).is_err());
+    }
+
+    #[test]
+    fn it_parses_count() {
+        let opt = try_from_args(&["-d", ",", "-g", "--count"]).unwrap();
+        assert!(opt.count);
+        assert!(opt.to_args().ends_with(&["--count".into()]));
+
+        assert!(try_from_args(&["-f", "1", "--count"]).is_err());
+        assert!(try_from_args(&["--count", "--json"]).is_err());
+    }
+
+    #[test]
+    fn it_parses_with_index() {
+        let opt = try_from_args(&["-f", "1,3", "--with-index"]).unwrap();
+        assert!(opt.with_index);
+        assert!(opt.to_args().ends_with(&["--with-index".into()]));
+
+        assert!(try_from_args(&["-c", "1", "--with-index"]).is_err());
+        assert!(try_from_args(&["-f", "1", "--with-index", "--pairs"]).is_err());
+    }
+
+    #[test]
+    fn it_parses_the_header_options() {
+        assert!(
+            try_from_args(&["-f", "1", "--keep-header"])
+                .unwrap()
+                .keep_header
+        );
+        assert!(
+            try_from_args(&["-f", "1", "--cut-header"])
+                .unwrap()
+                .cut_header
+        );
+        assert!(try_from_args(&["-f", "1", "--keep-header", "--cut-header"]).is_err());
+        assert!(try_from_args(&["-f", "1", "--keep-header", "--analyze"]).is_err());
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn it_parses_the_header_options_with_json() {
+        assert!(try_from_args(&["-f", "1", "--keep-header", "--json"]).is_err());
+        assert!(try_from_args(&["-f", "1", "--cut-header", "--json-object"]).is_err());
+        assert!(try_from_args(&["-f", "1", "--keep-header", "--json-object"]).is_ok());
+    }
+
+    #[test]
+    fn it_parses_steps() {
+        assert!(try_from_args(&["-f", "1:10:2"]).unwrap().bounds.has_steps());
+        assert!(try_from_args(&["-l", "1:10:2"]).is_err());
+        assert!(try_from_args(&["-f", "1:10:2", "-m"]).is_err());
+    }
+
+    #[test]
+    fn it_parses_output_delimiter() {
+        let opt = try_from_args(&["-f", "1,2", "--output-delimiter", ","]).unwrap();
+        assert_eq!(opt.output_delimiter.as_deref(), Some(&b","[..]));
+        assert!(opt.join);
+
+        assert!(try_from_args(&["-f", "1", "--output-delimiter", ",", "--no-join"]).is_err());
+        assert!(try_from_args(&["-f", "1", "--output-delimiter", ",", "--json"]).is_err());
+        assert!(try_from_args(&["-l", "1", "--output-delimiter", ","]).is_err());
+    }
+
+    #[test]
+    fn it_parses_in_place() {
+        let opt = try_from_args(&["-f", "1", "-i", "foo"]).unwrap();
+        assert_eq!(opt.in_place.as_deref(), Some(""));
+        assert_eq!(opt.files, [PathBuf::from("foo")]);
+
+        let opt = try_from_args(&["-f", "1", "--in-place=.bak", "foo"]).unwrap();
+        assert_eq!(opt.in_place.as_deref(), Some(".bak"));
+        assert!(opt
+            .to_args()
+            .ends_with(&["--in-place=.bak".into(), "foo".into()]));
+
+        for short in ["-i.bak", "-i=.bak"] {
+            let opt = try_from_args(&["-f", "1", short, "foo"]).unwrap();
+            assert_eq!(opt.in_place.as_deref(), Some(".bak"));
+            assert_eq!(opt.files, [PathBuf::from("foo")]);
+        }
+
+        assert_eq!(
+            try_from_args(&["-f", "1", "-i"]).unwrap_err().to_string(),
+            "runtime error. --in-place requires input files, and none can be -"
+        );
+        assert!(try_from_args(&["-f", "1", "-i", "foo", "-"]).is_err());
+        assert!(try_from_args(&["-f", "1", "-i", "--copy", "foo"]).is_err());
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn it_parses_json_keys_as_fields() {
+        let opt = try_from_args(&["--json-input", "-f", "user.id,msg"]).unwrap();
+        assert!(opt.json_input);
+        assert_eq!(opt.json_keys, Some(vec!["user.id".into(), "msg".into()]));
+        assert_eq!(opt.bounds.to_string(), "1,2");
+
+        let opt = try_from_args(&["--json-input", "-f", "2,1"]).unwrap();
+        assert_eq!(opt.json_keys, None);
+        assert_eq!(opt.bounds.to_string(), "2,1");
+
+        let opt = try_from_args(&["--json-input", "-f", "/a/0,/b"]).unwrap();
+        assert_eq!(opt.json_keys, Some(vec!["/a/0".into(), "/b".into()]));
+        assert_eq!(opt.bounds.to_string(), "1,2");
+
+        let opt = try_from_args(&["--json-input", "-f", "id,msg=none"]).unwrap();
+        assert_eq!(opt.json_keys, Some(vec!["id".into(), "msg".into()]));
+        assert_eq!(opt.bounds.to_string(), "1,2=none");
+
+        let opt =
+            try_from_args(&["--json-input", "-f", "{{ {/a/b} }} {msg:upper}|{/a/b}"]).unwrap();
+        assert_eq!(opt.json_keys, Some(vec!["/a/b".into(), "msg".into()]));
+        assert_eq!(opt.bounds.to_string(), "{{ {1} }} {2:upper}|{1}");
+
+        assert!(try_from_args(&["-f", "user.id,msg"]).is_err());
+        assert!(try_from_args(&["--json-input", "-f", "msg,-1"]).is_err());
+        assert_eq!(
+            try_from_args(&["--json-input", "--json-keys", "a", "-f", "msg"])
+                .unwrap_err()
+                .to_string(),
+            "runtime error. --json-keys cannot be used when --fields names the keys"
+        );
+    }
+
+    #[test]
+    fn it_converts_back_to_args() {
+        let roundtrip = |args: &[&str]| {
+            let to_args = try_from_args(args).unwrap().to_args();
+            let to_args_again = Opt::try_from_args(to_args.iter().map(OsString::from))
+                .unwrap()
+                .to_args();
+            assert_eq!(to_args, to_args_again);
+            to_args
+        };
+
+        assert_eq!(roundtrip(&[]), ["--fields", "1:", "--delimiter", "\t"]);
+        assert_eq!(
+            roundtrip(&[
+                "-d",
+                "-",
+                "-f",
+                "{-1,2|human}",
+                "-t",
+                "l",
+                "--fallback-oob="
+            ]),
+            [
+                "--fields",
+                "{-1}{2|human}",
+                "--delimiter",
+                "-",
+                "--trim",
+                "l",
+                "--fallback-oob="
+            ]
+        );
+        assert_eq!(
+            roundtrip(&["-l", "2:", "--no-join"]),
+            ["--lines", "2:", "--no-join"]
+        );
+        assert_eq!(
+            roundtrip(&["-b", "1:3", "-r", "x"]),
+            ["--bytes", "1:3", "--replace-delimiter", "x"]
+        );
+        assert_eq!(
+            roundtrip(&["--swap", "1", "3", "-z", "--output-buffer-size", "10"]),
+            [
+                "--swap",
+                "1",
+                "3",
+                "--delimiter",
+                "\t",
+                "--zero-terminated",
+                "--output-buffer-size",
+                "10"
+            ]
+        );
+        assert_eq!(
+            roundtrip(&["--truncate", "5", "-m", "-s", "-p", "-g"]),
+            [
+                "--fields",
+                "1:",
+                "--delimiter",
+                "\t",
+                "--greedy-delimiter",
+                "--compress-delimiter",
+                "--only-delimited",
+                "--complement",
+                "--truncate",
+                "5=…"
+            ]
+        );
+        assert_eq!(
+            roundtrip(&["-f", "2", "--pad", "4=0", "--pad-align", "right"]),
+            [
+                "--fields",
+                "2",
+                "--delimiter",
+                "\t",
+                "--pad",
+                "4=0",
+                "--pad-align",
+                "right"
+            ]
+        );
+        assert_eq!(
+            roundtrip(&["-f", "2", "--add-field", "x", "--add-field", "{NR}}}"]),
+            [
+                "--fields",
+                "2",
+                "--delimiter",
+                "\t",
+                "--add-field",
+                "x",
+                "--add-field",
+                "{NR}}}"
+            ]
+        );
+
+        #[cfg(feature = "json")]
+        assert_eq!(
+            roundtrip(&["-f", "2", "--json-pretty"]),
+            ["--fields", "2", "--delimiter", "\t", "--json-pretty"]
+        );
+
+        #[cfg(feature = "regex")]
+        assert_eq!(roundtrip(&["-c", "2:"]), ["--characters", "2:"]);
+    }
+}
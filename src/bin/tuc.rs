@@ -1,24 +1,31 @@
 use anyhow::Result;
 use std::convert::TryFrom;
-use std::env::args;
-use std::io::Write;
-use std::str::FromStr;
-use tuc::bounds::{BoundOrFiller, BoundsType, UserBoundsList};
+use std::env::args_os;
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+use tuc::bounds::BoundsType;
+use tuc::clipboard::{osc52, CopyWriter};
 use tuc::cut_bytes::read_and_cut_bytes;
 use tuc::cut_lines::read_and_cut_lines;
-use tuc::cut_str::read_and_cut_str;
+use tuc::cut_str::read_and_cut_str_with_diagnostics;
+use tuc::cut_width::read_and_cut_width;
+use tuc::decompress::decompress;
+use tuc::error::{Diagnostic, TucError};
+use tuc::exec::{spawn_output_command, wait_output_command};
 use tuc::help::{get_help, get_short_help};
-use tuc::options::{Opt, EOL};
+use tuc::input_files::{FilesReader, LineIndex};
+use tuc::options::Opt;
+use tuc::paste::PasteReader;
+use tuc::seek::{seek_stdin, OffsetReader};
+use tuc::timestamp::TimestampWriter;
+use tuc::timing::{report, TimedReader, TimedWriter};
+use tuc::wrap::WrapWriter;
 
 #[cfg(feature = "fast-lane")]
 use tuc::fast_lane::{read_and_cut_text_as_bytes, FastOpt};
 
-#[cfg(feature = "regex")]
-use tuc::options::RegexBag;
-
-#[cfg(feature = "regex")]
-use regex::bytes::Regex;
-
 #[cfg(not(feature = "fast-lane"))]
 struct FastOpt {}
 
@@ -42,222 +49,427 @@ fn read_and_cut_text_as_bytes<R: std::io::BufRead, W: Write>(
     ))
 }
 
-fn parse_args() -> Result<Opt, pico_args::Error> {
-    let mut pargs = pico_args::Arguments::from_env();
+fn main() {
+    if let Err(e) = run() {
+        eprintln!("Error: {e:?}");
+        std::process::exit(exit_code(&e));
+    }
+}
 
-    if args().len() == 1 {
+/// Exit code of a failure, so that scripts can tell bad arguments (2) from
+/// bounds not found in the input (3) and I/O errors (4). Anything else is 1.
+fn exit_code(e: &anyhow::Error) -> i32 {
+    for cause in e.chain() {
+        // Errors of the records reach us wrapped in an I/O error
+        let tuc_error = cause.downcast_ref::<TucError>().or_else(|| {
+            cause
+                .downcast_ref::<std::io::Error>()?
+                .get_ref()?
+                .downcast_ref::<TucError>()
+        });
+
+        match tuc_error.map(TucError::inner) {
+            Some(TucError::ParseBounds { .. }) => return 2,
+            Some(TucError::OutOfBounds { .. } | TucError::InvertedRange) => return 3,
+            Some(TucError::Io(_)) => return 4,
+            Some(_) => return 1,
+            None if cause.is::<pico_args::Error>() => return 2,
+            // e.g. a compressed input, without the feature to read it
+            None if cause
+                .downcast_ref::<std::io::Error>()
+                .is_some_and(|e| e.kind() == std::io::ErrorKind::Unsupported) =>
+            {
+                return 2
+            }
+            None if cause.is::<std::io::Error>() => return 4,
+            None => (),
+        }
+    }
+
+    1
+}
+
+fn run() -> Result<()> {
+    if args_os().len() == 1 {
         print!("{}", get_short_help());
         std::process::exit(0);
     }
 
-    if pargs.contains(["-h", "--help"]) {
+    if args_os().skip(1).any(|arg| arg == "-h" || arg == "--help") {
         print!("{}", get_help());
         std::process::exit(0);
     }
 
-    let mut maybe_fields: Option<UserBoundsList> = pargs.opt_value_from_str(["-f", "--fields"])?;
-    let maybe_characters: Option<UserBoundsList> =
-        pargs.opt_value_from_str(["-c", "--characters"])?;
-    let maybe_bytes: Option<UserBoundsList> = pargs.opt_value_from_str(["-b", "--bytes"])?;
-    let maybe_lines: Option<UserBoundsList> = pargs.opt_value_from_str(["-l", "--lines"])?;
-
-    let bounds_type = if maybe_fields.is_some() {
-        BoundsType::Fields
-    } else if maybe_bytes.is_some() {
-        BoundsType::Bytes
-    } else if maybe_characters.is_some() {
-        BoundsType::Characters
-    } else if maybe_lines.is_some() {
-        BoundsType::Lines
-    } else {
-        maybe_fields = Some(UserBoundsList::from_str("1:").unwrap());
-        BoundsType::Fields
+    let mut opt = match Opt::try_from_args_with_config(args_os().skip(1)) {
+        Ok(opt) => opt,
+        Err(e) if e.is::<pico_args::Error>() => return Err(e),
+        Err(e) => {
+            eprintln!("tuc: {e}");
+            std::process::exit(2);
+        }
     };
 
-    if bounds_type == BoundsType::Fields
-        && (maybe_fields.is_none() || maybe_fields.as_ref().unwrap().is_empty())
-    {
-        eprintln!("tuc: invariant error. At this point we expected to find at least 1 field bound");
-        std::process::exit(1);
+    if opt.version {
+        println!("tuc {}", env!("CARGO_PKG_VERSION"));
+        std::process::exit(0);
     }
 
-    let delimiter: Vec<u8> = match bounds_type {
-        BoundsType::Fields => pargs
-            .opt_value_from_str(["-d", "--delimiter"])?
-            .map(|x: String| x.into())
-            .unwrap_or_else(|| "\t".into()),
-        BoundsType::Lines => "\n".into(),
-        _ => Vec::new(),
-    };
+    #[cfg(feature = "json")]
+    if opt.explain {
+        println!("{}", tuc::explain::explain(&opt));
+        std::process::exit(0);
+    }
 
-    let greedy_delimiter = pargs.contains(["-g", "--greedy-delimiter"]);
-    let tmp_replace_delimiter: Option<String> =
-        pargs.opt_value_from_str(["-r", "--replace-delimiter"])?;
-    let mut replace_delimiter: Option<Vec<u8>> = tmp_replace_delimiter.map(|x| x.into());
+    if let Some(translation) = opt.translate {
+        println!("{}", tuc::translate::translate(&opt, translation)?);
+        std::process::exit(0);
+    }
 
-    let has_json = pargs.contains("--json");
-    let has_join = pargs.contains(["-j", "--join"]);
-    let has_no_join = pargs.contains("--no-join");
+    opt.expand_env()?;
 
-    if has_join && has_no_join {
-        eprintln!(
-            "tuc: runtime error. It's not possible to use --join and --no-join simultaneously"
-        );
-        std::process::exit(1);
+    if opt.in_place.is_some() {
+        return edit_in_place(opt);
     }
 
-    if has_json && has_no_join {
-        eprintln!("tuc: runtime error. Using both --json and --no-join is not permitted");
-        std::process::exit(1);
-    }
+    let start_offset = seek_stdin(opt.seek, opt.eol.into())?;
+    let (mut input, line_index) = open_input(&opt)?;
 
-    if replace_delimiter.is_some() {
-        if has_no_join {
-            eprintln!("tuc: runtime error. You can't pass --no-join when using --replace, which implies --join");
-            std::process::exit(1);
-        } else if has_json {
-            eprintln!("tuc: runtime error. The use of --replace with --json is not supported");
-            std::process::exit(1);
+    if opt.sniff {
+        let sample = tuc::analyze::read_sample(&mut input)?;
+        if let Some((delimiter, repeated)) = tuc::analyze::sniff(&sample) {
+            opt.delimiter = vec![delimiter];
+            opt.greedy_delimiter |= repeated;
         }
+        input = Box::new(std::io::Cursor::new(sample).chain(input));
     }
 
-    if bounds_type == BoundsType::Characters && has_no_join {
-        eprintln!(
-            "tuc: runtime error. Since --characters implies --join, you can't pass --no-join"
-        );
-        std::process::exit(1);
+    if opt.analyze {
+        let mut stdin = std::io::BufReader::new(input);
+        print!("{}", tuc::analyze::analyze(&mut stdin)?);
+        std::process::exit(0);
     }
 
-    if bounds_type == BoundsType::Characters && cfg!(not(feature = "regex")) {
-        eprintln!(
-            "tuc: runtime error. The use of --characters requires `tuc` to be compiled with `regex` support"
-        );
-        std::process::exit(1);
+    #[cfg(feature = "columnar")]
+    if opt.columnar.is_some() {
+        let mut stdin = std::io::BufReader::with_capacity(64 * 1024, input);
+        return tuc::columnar::read_and_write_columns(&mut stdin, opt, print_diagnostic);
     }
 
-    if bounds_type == BoundsType::Characters {
-        replace_delimiter = Some("".into());
+    let output_buffer_size = opt.output_buffer_size;
+
+    if opt.exec.is_some() {
+        let mut stdin = std::io::BufReader::with_capacity(64 * 1024, input);
+        return tuc::exec::read_and_exec(&mut stdin, opt, print_diagnostic);
     }
 
-    if has_json {
-        replace_delimiter = Some(",".into());
+    let mut output_command = opt
+        .tee_cmd
+        .as_deref()
+        .map(spawn_output_command)
+        .transpose()?;
+    let output: Box<dyn Write> = match output_command.as_mut().and_then(|c| c.stdin.take()) {
+        Some(command_stdin) => Box::new(command_stdin),
+        None => Box::new(std::io::stdout().lock()),
+    };
+
+    #[cfg(feature = "json")]
+    if opt.msgpack {
+        let mut stdin = std::io::BufReader::with_capacity(64 * 1024, input);
+        let mut stdout = std::io::BufWriter::with_capacity(output_buffer_size, output);
+        tuc::msgpack::read_and_write_msgpack(&mut stdin, &mut stdout, opt, print_diagnostic)?;
+        stdout.flush()?;
+        drop(stdout);
+        return output_command.map_or(Ok(()), wait_output_command);
     }
 
-    let join = has_join
-        || has_json
-        || replace_delimiter.is_some()
-        || (bounds_type == BoundsType::Lines && !has_no_join)
-        || (bounds_type == BoundsType::Characters);
+    let copy = opt.copy;
+    let timestamp = opt.timestamp.clone();
+    let wrap = opt.wrap.clone();
+    let print_offset = opt.print_offset;
+    let eol: u8 = opt.eol.into();
+
+    if opt.timing {
+        let start = Instant::now();
+        let mut stdin =
+            std::io::BufReader::with_capacity(64 * 1024, TimedReader::new(input, opt.eol.into()));
+        let mut stdout = TimestampWriter::new(
+            std::io::BufWriter::with_capacity(
+                output_buffer_size,
+                WrapWriter::new(CopyWriter::new(TimedWriter::new(output), copy), wrap, eol),
+            ),
+            timestamp,
+            eol,
+        );
 
-    if has_json && bounds_type != BoundsType::Characters && bounds_type != BoundsType::Fields {
+        cut_preamble(&mut stdin, &mut stdout, &mut opt)
+            .and_then(|_| cut(&mut stdin, &mut stdout, opt))
+            .map_err(|e| locate_error(e, |line| line_index.as_ref()?.locate(line)))?;
+        stdout.flush()?;
+        let copy_writer = stdout.get_ref().get_ref().get_ref();
+        copy_to_clipboard(copy_writer.copied())?;
         eprintln!(
-            "tuc: runtime error. --json support is available only for --fields and --characters"
+            "{}",
+            report(start.elapsed(), stdin.get_ref(), copy_writer.get_ref())
         );
-        std::process::exit(1);
+
+        if print_offset {
+            eprintln!("{}", start_offset + stdin.get_ref().bytes);
+        }
+    } else {
+        let mut stdin =
+            std::io::BufReader::with_capacity(64 * 1024, OffsetReader::new(input, start_offset));
+        let mut stdout = TimestampWriter::new(
+            std::io::BufWriter::with_capacity(
+                output_buffer_size,
+                WrapWriter::new(CopyWriter::new(output, copy), wrap, eol),
+            ),
+            timestamp,
+            eol,
+        );
+
+        cut_preamble(&mut stdin, &mut stdout, &mut opt)
+            .and_then(|_| cut(&mut stdin, &mut stdout, opt))
+            .map_err(|e| locate_error(e, |line| line_index.as_ref()?.locate(line)))?;
+        stdout.flush()?;
+        copy_to_clipboard(stdout.get_ref().get_ref().get_ref().copied())?;
+
+        if print_offset {
+            eprintln!("{}", stdin.get_ref().offset);
+        }
     }
 
-    #[cfg(not(feature = "regex"))]
-    let regex_bag = None;
+    // The output has been dropped, so the command sees the end of its input
+    output_command.map_or(Ok(()), wait_output_command)
+}
 
-    #[cfg(feature = "regex")]
-    let regex_bag: Option<RegexBag> = (if bounds_type == BoundsType::Characters {
-        Some("\\b|\\B".to_owned())
+/// The input files (or standard input, if there are none),
+/// merged with the files of --paste, if any.
+fn open_input(opt: &Opt) -> Result<(Box<dyn Read>, Option<LineIndex>)> {
+    let mut line_index = None;
+    let input: Box<dyn Read> = if !opt.files.is_empty() {
+        let files = FilesReader::new(opt.files.clone(), opt.eol.into(), opt.decompress);
+        line_index = Some(files.line_index());
+        Box::new(files)
+    } else if let Some(format) = opt.decompress {
+        decompress(Box::new(std::io::stdin().lock()), format)?
     } else {
-        pargs.opt_value_from_str::<_, String>(["-e", "--regex"])?
-    })
-    .map(|regex_text| RegexBag {
-        normal: Regex::new(&regex_text).unwrap_or_else(|e| {
-            eprintln!("tuc: runtime error. The regular expression is malformed. {e}");
-            std::process::exit(1);
-        }),
-        greedy: Regex::new(&format!("({})+", &regex_text)).unwrap_or_else(|e| {
-            eprintln!("tuc: runtime error. The regular expression is malformed. {e}");
-            std::process::exit(1);
-        }),
-    });
-
-    if regex_bag.is_some() && cfg!(not(feature = "regex")) {
-        eprintln!("tuc: invariant error. There should not be any regex when compiled without regex support");
-        std::process::exit(1);
-    }
+        Box::new(std::io::stdin().lock())
+    };
 
-    let bounds = maybe_fields
-        .or(maybe_characters)
-        .or(maybe_bytes)
-        .or(maybe_lines)
-        .unwrap();
+    if opt.paste.is_empty() {
+        return Ok((input, line_index));
+    }
 
-    if has_json && bounds.iter().any(|s| matches!(s, BoundOrFiller::Filler(_))) {
-        eprintln!("tuc: runtime error. Cannot format fields when using --json");
-        std::process::exit(1);
+    let mut inputs: Vec<Box<dyn BufRead>> = vec![Box::new(std::io::BufReader::new(input))];
+    for path in &opt.paste {
+        let file = File::open(path).map_err(|e| {
+            std::io::Error::new(e.kind(), format!("Cannot open {}: {}", path.display(), e))
+        })?;
+        inputs.push(Box::new(std::io::BufReader::new(file)));
     }
 
-    let args = Opt {
-        complement: pargs.contains(["-m", "--complement"]),
-        only_delimited: pargs.contains(["-s", "--only-delimited"]),
-        greedy_delimiter,
-        compress_delimiter: pargs.contains(["-p", "--compress-delimiter"]),
-        version: pargs.contains(["-V", "--version"]),
-        eol: if pargs.contains(["-z", "--zero-terminated"]) {
-            EOL::Zero
-        } else {
-            EOL::Newline
-        },
-        join,
-        json: has_json,
-        delimiter,
-        bounds_type,
-        bounds,
-        replace_delimiter,
-        trim: pargs.opt_value_from_str(["-t", "--trim"])?,
-        fallback_oob: pargs
-            .opt_value_from_str("--fallback-oob")
-            .or_else(|e| match e {
-                pico_args::Error::OptionWithoutAValue(_) => {
-                    // We must consume the arg ourselves (it's not done on error)
-                    pargs.contains("--fallback-oob=");
-
-                    Ok(Some("".into()))
-                }
-                _ => Err(e),
-            })?
-            .map(|x: String| x.into()),
-        regex_bag,
+    let input = PasteReader::new(inputs, opt.delimiter.clone(), opt.eol.into());
+    Ok((Box::new(input), line_index))
+}
+
+/// Tell which input file (and which of its lines) a failing line comes from.
+fn locate_error(
+    e: anyhow::Error,
+    locate: impl Fn(usize) -> Option<(PathBuf, usize)>,
+) -> anyhow::Error {
+    // Errors of the records reach us wrapped in an I/O error
+    let io_error = match e.downcast::<std::io::Error>() {
+        Ok(io_error) if io_error.get_ref().is_some_and(|e| e.is::<TucError>()) => io_error,
+        Ok(io_error) => return io_error.into(),
+        Err(e) => return e,
     };
 
-    let remaining = pargs.finish();
+    match io_error.into_inner().map(|e| e.downcast::<TucError>()) {
+        Some(Ok(tuc_error)) => match *tuc_error {
+            TucError::AtLine {
+                path: None,
+                line,
+                error,
+            } => match locate(line) {
+                Some((path, line)) => TucError::AtLine {
+                    path: Some(path),
+                    line,
+                    error,
+                },
+                None => TucError::AtLine {
+                    path: None,
+                    line,
+                    error,
+                },
+            }
+            .into(),
+            tuc_error => tuc_error.into(),
+        },
+        _ => unreachable!("the error was checked to be a TucError"),
+    }
+}
 
-    if args.version {
-        println!("tuc {}", env!("CARGO_PKG_VERSION"));
-        std::process::exit(0);
+/// Replace every input file with its cut output (--in-place), keeping a
+/// backup of the original when a suffix is given. The output is written
+/// to a temporary file next to the original, that is then renamed over it,
+/// so a file is left untouched if cutting it fails.
+fn edit_in_place(opt: Opt) -> Result<()> {
+    let suffix = opt.in_place.clone().unwrap_or_default();
+
+    for path in &opt.files {
+        let mut temp_name = std::ffi::OsString::from(".");
+        temp_name.push(path.file_name().unwrap_or_default());
+        temp_name.push(format!(".tuc-{}.tmp", std::process::id()));
+        let temp_path = path.with_file_name(temp_name);
+
+        if let Err(e) = cut_file(path, &temp_path, opt.clone()) {
+            let _ = std::fs::remove_file(&temp_path);
+            return Err(e);
+        }
+
+        if !suffix.is_empty() {
+            let mut backup_path = path.clone().into_os_string();
+            backup_path.push(&suffix);
+            std::fs::copy(path, &backup_path).map_err(|e| {
+                anyhow::anyhow!("Cannot create the backup of {}: {}", path.display(), e)
+            })?;
+        }
+
+        std::fs::rename(&temp_path, path)
+            .map_err(|e| anyhow::anyhow!("Cannot replace {}: {}", path.display(), e))?;
+    }
+
+    Ok(())
+}
+
+/// Cut the file at `path` into a new file at `output_path`,
+/// with the same permissions.
+fn cut_file(path: &Path, output_path: &Path, mut opt: Opt) -> Result<()> {
+    let input =
+        File::open(path).map_err(|e| anyhow::anyhow!("Cannot open {}: {}", path.display(), e))?;
+    let output = OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(output_path)
+        .map_err(|e| anyhow::anyhow!("Cannot create {}: {}", output_path.display(), e))?;
+    output.set_permissions(input.metadata()?.permissions())?;
+
+    let eol: u8 = opt.eol.into();
+    let mut stdin = std::io::BufReader::with_capacity(64 * 1024, input);
+    let mut stdout = TimestampWriter::new(
+        std::io::BufWriter::with_capacity(
+            opt.output_buffer_size,
+            WrapWriter::new(output, opt.wrap.clone(), eol),
+        ),
+        opt.timestamp.clone(),
+        eol,
+    );
+
+    cut_preamble(&mut stdin, &mut stdout, &mut opt)
+        .and_then(|_| cut(&mut stdin, &mut stdout, opt))
+        .map_err(|e| locate_error(e, |line| Some((path.to_owned(), line))))?;
+    stdout.flush()?;
+    stdout.get_ref().get_ref().get_ref().sync_all()?;
+
+    Ok(())
+}
+
+/// Send the output to the terminal clipboard (--copy). The escape
+/// sequence goes to stderr, so that stdout can still be redirected.
+fn copy_to_clipboard(output: Option<&[u8]>) -> Result<()> {
+    if let Some(output) = output {
+        let mut stderr = std::io::stderr().lock();
+        stderr.write_all(&osc52(output))?;
+        stderr.flush()?;
     }
 
-    if !remaining.is_empty() {
-        eprintln!("tuc: unexpected arguments {remaining:?}");
-        eprintln!("Try 'tuc --help' for more information.");
-        std::process::exit(1);
+    Ok(())
+}
+
+/// Handle the records that come before the data: the ones to skip
+/// (--skip, printed as they are with --skip-print) and the header.
+fn cut_preamble<R: BufRead, W: Write>(stdin: &mut R, stdout: &mut W, opt: &mut Opt) -> Result<()> {
+    let eol: u8 = opt.eol.into();
+    let mut record = Vec::new();
+
+    for _ in 0..opt.skip {
+        record.clear();
+        if stdin.read_until(eol, &mut record)? == 0 {
+            return Ok(());
+        }
+
+        if opt.skip_print {
+            stdout.write_all(&record)?;
+            if !record.ends_with(&[eol]) {
+                stdout.write_all(&[eol])?;
+            }
+        }
     }
 
-    Ok(args)
+    cut_header(stdin, stdout, opt)
 }
 
-fn main() -> Result<()> {
-    let opt: Opt = parse_args()?;
+/// Handle the first record on its own, when it's a header: --keep-header
+/// prints it as it is (with --json-object its fields become the keys
+/// instead), while --cut-header cuts it with the same bounds,
+/// but without the checks and filters meant for the data
+/// (e.g. --grep-fields or --assert).
+fn cut_header<R: BufRead, W: Write>(stdin: &mut R, stdout: &mut W, opt: &mut Opt) -> Result<()> {
+    if !opt.keep_header && !opt.cut_header {
+        return Ok(());
+    }
 
-    let mut stdin = std::io::BufReader::with_capacity(64 * 1024, std::io::stdin().lock());
-    let mut stdout = std::io::BufWriter::with_capacity(64 * 1024, std::io::stdout().lock());
+    let eol: u8 = opt.eol.into();
+    let mut header = Vec::new();
+    if stdin.read_until(eol, &mut header)? == 0 {
+        return Ok(());
+    }
+
+    if opt.keep_header && opt.json_object {
+        let header = header.strip_suffix(&[eol]).unwrap_or(&header);
+        opt.field_names = tuc::fields(header, opt)
+            .map(|name| String::from_utf8_lossy(name).into_owned())
+            .collect();
+        return Ok(());
+    }
 
+    if opt.keep_header {
+        stdout.write_all(&header)?;
+        if !header.ends_with(&[eol]) {
+            stdout.write_all(&[eol])?;
+        }
+        return Ok(());
+    }
+
+    let mut header_opt = opt.clone();
+    // the header is the only line after the skipped ones
+    header_opt.keep_header = false;
+    header_opt.cut_header = false;
+    header_opt.grep_fields = None;
+    header_opt.assertions = Vec::new();
+    header_opt.assert_fields = None;
+    header_opt.report = false;
+
+    cut(&mut &header[..], stdout, header_opt)
+}
+
+/// Warnings and errors of the skipped records, and the summary of --report
+fn print_diagnostic(diagnostic: Diagnostic) {
+    eprintln!("{}", diagnostic);
+}
+
+fn cut<R: BufRead, W: Write>(stdin: &mut R, stdout: &mut W, opt: Opt) -> Result<()> {
     if opt.bounds_type == BoundsType::Bytes {
-        read_and_cut_bytes(&mut stdin, &mut stdout, &opt)?;
+        read_and_cut_bytes(stdin, stdout, &opt)?;
     } else if opt.bounds_type == BoundsType::Lines {
-        read_and_cut_lines(&mut stdin, &mut stdout, &opt)?;
+        read_and_cut_lines(stdin, stdout, &opt)?;
+    } else if opt.bounds_type == BoundsType::Width {
+        read_and_cut_width(stdin, stdout, &opt)?;
     } else if let Ok(fast_opt) = FastOpt::try_from(&opt) {
-        read_and_cut_text_as_bytes(&mut stdin, &mut stdout, &fast_opt)?;
+        read_and_cut_text_as_bytes(stdin, stdout, &fast_opt)?;
     } else {
-        read_and_cut_str(&mut stdin, &mut stdout, opt)?;
+        read_and_cut_str_with_diagnostics(stdin, stdout, opt, print_diagnostic)?;
     }
 
-    stdout.flush()?;
-
     Ok(())
 }
@@ -0,0 +1,153 @@
+use regex::bytes::Regex;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::error::TucError;
+
+/// Lines from one that matches `start` to the next one that matches `end`,
+/// both included (e.g. `/^BEGIN/:/^END/`), as many times as they're found.
+/// Without `start` the lines begin at the first one (`:/^END/`), without
+/// `end` they last until the end of the input (`/^BEGIN/:`).
+#[derive(Debug, Clone)]
+pub struct LinePattern {
+    pub start: Option<Regex>,
+    pub end: Option<Regex>,
+}
+
+impl LinePattern {
+    /// Whether the bounds of --lines are regexes rather than indexes
+    pub fn is_pattern(s: &str) -> bool {
+        s.starts_with('/') || s.starts_with(":/")
+    }
+}
+
+/// Parse `/regex/` at the start of the text, returning the text after it.
+/// A slash within the regex must be escaped (`\/`).
+fn parse_regex(text: &str) -> Result<(Regex, &str), TucError> {
+    let text = text
+        .strip_prefix('/')
+        .ok_or_else(|| TucError::parse_bounds("Line patterns must start with /"))?;
+
+    let mut pattern = String::with_capacity(text.len());
+    let mut chars = text.char_indices();
+
+    while let Some((idx, c)) = chars.next() {
+        match c {
+            '/' => {
+                let regex = Regex::new(&pattern).map_err(|e| {
+                    TucError::parse_bounds(format!("The regular expression is malformed. {e}"))
+                })?;
+                return Ok((regex, &text[idx + 1..]));
+            }
+            '\\' => match chars.next() {
+                Some((_, '/')) => pattern.push('/'),
+                Some((_, c)) => {
+                    pattern.push('\\');
+                    pattern.push(c);
+                }
+                None => pattern.push('\\'),
+            },
+            c => pattern.push(c),
+        }
+    }
+
+    Err(TucError::parse_bounds(format!(
+        "Line pattern not terminated by /: /{text}"
+    )))
+}
+
+impl FromStr for LinePattern {
+    type Err = TucError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (start, rest) = match s.strip_prefix(':') {
+            Some(rest) => (None, rest),
+            None => {
+                let (start, rest) = parse_regex(s)?;
+                let rest = rest.strip_prefix(':').ok_or_else(|| {
+                    TucError::parse_bounds(format!(
+                        "Line patterns must be /regex/:/regex/, /regex/: or :/regex/, got {s}"
+                    ))
+                })?;
+                (Some(start), rest)
+            }
+        };
+
+        let end = match rest {
+            "" => None,
+            rest => match parse_regex(rest)? {
+                (end, "") => Some(end),
+                (_, extra) => {
+                    return Err(TucError::parse_bounds(format!(
+                        "Unexpected text after the line pattern: {extra}"
+                    )))
+                }
+            },
+        };
+
+        if start.is_none() && end.is_none() {
+            return Err(TucError::parse_bounds(
+                "Line patterns need at least one regex",
+            ));
+        }
+
+        Ok(LinePattern { start, end })
+    }
+}
+
+impl fmt::Display for LinePattern {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let escaped = |regex: &Regex| regex.as_str().replace('/', "\\/");
+
+        if let Some(start) = &self.start {
+            write!(f, "/{}/", escaped(start))?;
+        }
+        f.write_str(":")?;
+        if let Some(end) = &self.end {
+            write!(f, "/{}/", escaped(end))?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_parses_the_line_patterns() {
+        let pattern = LinePattern::from_str("/^BEGIN/:/^END/").unwrap();
+        assert_eq!(pattern.start.unwrap().as_str(), "^BEGIN");
+        assert_eq!(pattern.end.unwrap().as_str(), "^END");
+
+        let pattern = LinePattern::from_str(r"/a\/b\d/:").unwrap();
+        assert_eq!(pattern.start.as_ref().unwrap().as_str(), r"a/b\d");
+        assert!(pattern.end.is_none());
+        assert_eq!(pattern.to_string(), r"/a\/b\d/:");
+
+        let pattern = LinePattern::from_str(":/END/").unwrap();
+        assert!(pattern.start.is_none());
+        assert_eq!(pattern.to_string(), ":/END/");
+
+        assert!(LinePattern::is_pattern("/a/:"));
+        assert!(!LinePattern::is_pattern("1:/a/"));
+    }
+
+    #[test]
+    fn it_rejects_invalid_line_patterns() {
+        let error = |s: &str| LinePattern::from_str(s).unwrap_err().to_string();
+
+        assert_eq!(error("/a"), "Line pattern not terminated by /: /a");
+        assert_eq!(
+            error("/a/"),
+            "Line patterns must be /regex/:/regex/, /regex/: or :/regex/, got /a/"
+        );
+        assert_eq!(
+            error("/a/:/b/c"),
+            "Unexpected text after the line pattern: c"
+        );
+        assert_eq!(error(":"), "Line patterns need at least one regex");
+        assert!(error("/(/:").starts_with("The regular expression is malformed."));
+    }
+}
@@ -1,15 +1,21 @@
+#[cfg(feature = "regex")]
+pub mod linepattern;
 pub mod side;
 pub mod userbounds;
 pub mod userboundslist;
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum BoundsType {
     Bytes,
     Characters,
     Fields,
     Lines,
+    /// Terminal display columns (wide characters take two)
+    Width,
 }
 
+#[cfg(feature = "regex")]
+pub use linepattern::LinePattern;
 pub use side::Side;
 pub use userbounds::{BoundOrFiller, UserBounds, UserBoundsTrait};
 pub use userboundslist::UserBoundsList;
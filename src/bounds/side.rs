@@ -1,4 +1,5 @@
-use anyhow::{bail, Result};
+use crate::error::TucError;
+use anyhow::Result;
 use std::cmp::Ordering;
 use std::fmt;
 use std::str::FromStr;
@@ -10,14 +11,14 @@ pub enum Side {
 }
 
 impl FromStr for Side {
-    type Err = anyhow::Error;
+    type Err = TucError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         Ok(match s {
             "" => Side::Continue,
             _ => Side::Some(
                 s.parse::<i32>()
-                    .or_else(|_| bail!("Not a number `{}`", s))?,
+                    .map_err(|_| TucError::parse_bounds(format!("Not a number `{}`", s)))?,
             ),
         })
     }
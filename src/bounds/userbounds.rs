@@ -2,10 +2,15 @@ use anyhow::{bail, Result};
 use std::cmp::Ordering;
 use std::convert::TryInto;
 use std::fmt;
+use std::io::Write;
 use std::ops::Range;
 use std::str::FromStr;
 
 use crate::bounds::Side;
+use crate::cut_width::char_width;
+use crate::error::TucError;
+use crate::humanize::Humanize;
+use crate::transform::TransformPipeline;
 
 #[derive(Debug, Eq, Clone)]
 pub struct UserBounds {
@@ -13,28 +18,174 @@ pub struct UserBounds {
     pub r: Side,
     pub is_last: bool,
     pub fallback_oob: Option<Vec<u8>>,
+    pub pad: Option<Padding>,
+    pub humanize: Option<Humanize>,
+    /// Transforms to apply to the field (e.g. `upper` in `{1:upper}`)
+    pub transform: Option<TransformPipeline>,
+    /// Part of the field to keep, once split by the secondary delimiter
+    /// (e.g. `1` in `2.1`)
+    pub sub: Option<Box<UserBounds>>,
+    /// Bytes of the field to keep (e.g. `1:8` in `2[1:8]`)
+    pub bytes: Option<Box<UserBounds>>,
+    /// Keep one field every `step` fields of the range (e.g. `2` in `1:10:2`)
+    pub step: Option<usize>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Align {
+    Left,
+    Right,
+    Center,
+}
+
+impl FromStr for Align {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "left" => Ok(Align::Left),
+            "right" => Ok(Align::Right),
+            "center" => Ok(Align::Center),
+            _ => bail!("Valid alignments are left, right and center"),
+        }
+    }
+}
+
+impl fmt::Display for Align {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Align::Left => "left",
+            Align::Right => "right",
+            Align::Center => "center",
+        })
+    }
+}
+
+/// Width and alignment of a field, e.g. `<10`, `>8` or `^12`
+/// as in `{1:<10}`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Padding {
+    pub align: Align,
+    pub width: usize,
+}
+
+impl FromStr for Padding {
+    type Err = TucError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let align = match s.as_bytes().first() {
+            Some(b'<') => Align::Left,
+            Some(b'>') => Align::Right,
+            Some(b'^') => Align::Center,
+            _ => {
+                return Err(TucError::parse_bounds(format!(
+                    "Field format error: unknown alignment in `{}`",
+                    s
+                )))
+            }
+        };
+
+        let width = s[1..].parse::<usize>().map_err(|_| {
+            TucError::parse_bounds(format!("Field format error: invalid width `{}`", &s[1..]))
+        })?;
+
+        Ok(Padding { align, width })
+    }
+}
+
+impl fmt::Display for Padding {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let align = match self.align {
+            Align::Left => '<',
+            Align::Right => '>',
+            Align::Center => '^',
+        };
+        write!(f, "{align}{}", self.width)
+    }
+}
+
+impl Padding {
+    /// Write `text` surrounded by enough spaces to fill the width.
+    /// Width is measured in terminal columns (2 for East Asian wide
+    /// characters, 0 for combining marks), longer text is not truncated.
+    pub fn write_padded<W: Write>(&self, output: &mut W, text: &[u8]) -> std::io::Result<()> {
+        let len: usize = bstr::ByteSlice::chars(text).map(char_width).sum();
+        let missing = self.width.saturating_sub(len);
+        let (left, right) = match self.align {
+            Align::Left => (0, missing),
+            Align::Right => (missing, 0),
+            Align::Center => (missing / 2, missing - missing / 2),
+        };
+
+        write!(output, "{:left$}", "")?;
+        output.write_all(text)?;
+        write!(output, "{:right$}", "")
+    }
 }
 
 #[derive(Clone, Debug, PartialEq)]
 pub enum BoundOrFiller {
     Bound(UserBounds),
     Filler(Vec<u8>),
+    /// Named capture group of the delimiter regex, e.g. `{name}`
+    Capture(String),
+    /// The whole record as it was read, e.g. `{0}` or `{LINE}`
+    WholeLine,
+    /// Value of an environment variable, e.g. `{$HOME}` or `{env:HOME}`
+    /// (see `UserBoundsList::expand_env`)
+    Env(String),
+    /// Text displayed only if `condition` is found and it's not empty
+    /// (otherwise display `otherwise`), e.g. `{2?yes:no}` or `{3?, {3}}`
+    Conditional {
+        condition: Box<BoundOrFiller>,
+        then: Vec<BoundOrFiller>,
+        otherwise: Vec<BoundOrFiller>,
+    },
 }
 
 impl fmt::Display for UserBounds {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        match (self.l, self.r) {
-            (Side::Continue, Side::Continue) => write!(f, "1:-1"),
-            (l, r) if l == r => write!(f, "{l}"),
-            (l, r) => write!(f, "{l}:{r}"),
+        match (self.l, self.r, self.step) {
+            (l, r, Some(step)) => write!(f, "{l}:{r}:{step}")?,
+            (Side::Continue, Side::Continue, None) => write!(f, "1:-1")?,
+            (l, r, None) if l == r => write!(f, "{l}")?,
+            (l, r, None) => write!(f, "{l}:{r}")?,
+        }
+
+        if let Some(sub) = &self.sub {
+            write!(f, ".{sub}")?;
         }
+
+        if let Some(bytes) = &self.bytes {
+            write!(f, "[{bytes}]")?;
+        }
+
+        Ok(())
+    }
+}
+
+impl UserBounds {
+    /// Check if the field must be modified before being printed
+    /// (e.g. `{1:<10}`, `{1|human}`, `{1:upper}`, `2.1` or `2[1:8]`).
+    pub fn has_modifiers(&self) -> bool {
+        self.pad.is_some()
+            || self.humanize.is_some()
+            || self.transform.is_some()
+            || self.sub.is_some()
+            || self.bytes.is_some()
     }
 }
 
 impl FromStr for UserBounds {
-    type Err = anyhow::Error;
+    type Err = TucError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
+        UserBounds::parse(s).map_err(TucError::parse_bounds)
+    }
+}
+
+impl UserBounds {
+    fn parse(s: &str) -> Result<Self> {
         if s.is_empty() {
             bail!("Field format error: empty field");
         } else if s == ":" {
@@ -48,7 +199,56 @@ impl FromStr for UserBounds {
             s = range_part;
         }
 
+        let mut bytes: Option<Box<UserBounds>> = None;
+        if let Some((field_part, bytes_part)) = s.strip_suffix(']').and_then(|s| s.split_once('['))
+        {
+            let bytes_bounds = UserBounds::from_str(bytes_part)?;
+            if bytes_bounds.has_modifiers() || bytes_bounds.fallback_oob.is_some() {
+                bail!("Field format error: a byte range must be a plain range (e.g. 2[1:8])");
+            }
+            bytes = Some(Box::new(bytes_bounds));
+            s = field_part;
+        }
+
+        let mut sub: Option<Box<UserBounds>> = None;
+        if let Some((field_part, sub_part)) = s.split_once('.') {
+            let sub_bounds = UserBounds::from_str(sub_part)?;
+            if sub_bounds.sub.is_some() {
+                bail!("Field format error: only one level of sub-fields is allowed");
+            } else if sub_bounds.step.is_some() {
+                bail!("Field format error: a sub-field cannot have a step");
+            }
+            sub = Some(Box::new(sub_bounds));
+            s = field_part;
+        }
+
+        if bytes.as_ref().is_some_and(|bytes| bytes.step.is_some()) {
+            bail!("Field format error: a byte range cannot have a step");
+        }
+
+        let mut step: Option<usize> = None;
+        if s.matches(':').count() == 2 {
+            let (range_part, step_part) = s.rsplit_once(':').unwrap();
+            step = Some(
+                step_part
+                    .parse::<usize>()
+                    .ok()
+                    .filter(|&step| step > 0)
+                    .ok_or_else(|| {
+                        anyhow::anyhow!(
+                            "Field format error: the step must be a positive number (e.g. 1:10:2)"
+                        )
+                    })?,
+            );
+            s = range_part;
+        }
+
         let (l, r) = match s.find(':') {
+            // `::2` selects every other field of the whole record
+            Some(_) if s == ":" => (Side::Continue, Side::Continue),
+            None if step.is_some() => {
+                bail!("Field format error: a step requires a range (e.g. 1:10:2)");
+            }
             None => {
                 let side = Side::from_str(s)?;
                 (side, side)
@@ -80,8 +280,15 @@ impl FromStr for UserBounds {
             _ => (),
         }
 
+        if sub.is_some() && (l != r || l == Side::Continue) {
+            bail!("Field format error: sub-fields are available only for single fields (e.g. 2.1)");
+        }
+
         let mut b = UserBounds::new(l, r);
         b.fallback_oob = fallback_oob;
+        b.sub = sub;
+        b.bytes = bytes;
+        b.step = step;
         Ok(b)
     }
 }
@@ -114,7 +321,8 @@ impl PartialOrd for UserBounds {
 
 impl PartialEq for UserBounds {
     fn eq(&self, other: &Self) -> bool {
-        (self.l, self.r) == (other.l, other.r)
+        (self.l, self.r, &self.sub, &self.bytes, self.step)
+            == (other.l, other.r, &other.sub, &other.bytes, other.step)
     }
 }
 
@@ -127,7 +335,7 @@ impl Default for UserBounds {
 pub trait UserBoundsTrait<T> {
     fn new(l: Side, r: Side) -> Self;
     fn with_fallback(l: Side, r: Side, fallback_oob: Option<Vec<u8>>) -> Self;
-    fn try_into_range(&self, parts_length: usize) -> Result<Range<usize>>;
+    fn try_into_range(&self, parts_length: usize) -> Result<Range<usize>, TucError>;
     fn matches(&self, idx: T) -> Result<bool>;
     fn unpack(&self, num_fields: usize) -> Vec<UserBounds>;
     fn complement(&self, num_fields: usize) -> Result<Vec<UserBounds>>;
@@ -140,6 +348,12 @@ impl UserBoundsTrait<i32> for UserBounds {
             r,
             is_last: false,
             fallback_oob: None,
+            pad: None,
+            humanize: None,
+            transform: None,
+            sub: None,
+            bytes: None,
+            step: None,
         }
     }
 
@@ -149,6 +363,12 @@ impl UserBoundsTrait<i32> for UserBounds {
             r,
             is_last: false,
             fallback_oob,
+            pad: None,
+            humanize: None,
+            transform: None,
+            sub: None,
+            bytes: None,
+            step: None,
         }
     }
 
@@ -212,14 +432,14 @@ impl UserBoundsTrait<i32> for UserBounds {
     ///   Range { start: 0, end: 5}
     /// );
     /// ```
-    fn try_into_range(&self, parts_length: usize) -> Result<Range<usize>> {
+    fn try_into_range(&self, parts_length: usize) -> Result<Range<usize>, TucError> {
         let parts_length = parts_length as i32;
 
         let start: i32 = match self.l {
             Side::Continue => 0,
             Side::Some(v) => {
                 if v > parts_length || v < -parts_length {
-                    bail!("Out of bounds: {}", v);
+                    return Err(TucError::OutOfBounds { index: v });
                 }
                 if v < 0 {
                     parts_length + v
@@ -233,7 +453,7 @@ impl UserBoundsTrait<i32> for UserBounds {
             Side::Continue => parts_length,
             Side::Some(v) => {
                 if v > parts_length || v < -parts_length {
-                    bail!("Out of bounds: {}", v);
+                    return Err(TucError::OutOfBounds { index: v });
                 }
                 if v < 0 {
                     parts_length + v + 1
@@ -245,7 +465,7 @@ impl UserBoundsTrait<i32> for UserBounds {
 
         if end <= start {
             // `end` must always be 1 or more greater than start
-            bail!("Field left value cannot be greater than right value");
+            return Err(TucError::InvertedRange);
         }
 
         Ok(Range {
@@ -255,8 +475,14 @@ impl UserBoundsTrait<i32> for UserBounds {
     }
 
     /// Transform a ranged bound into a list of one or more
-    /// slot bound
+    /// slot bound (one every `step` fields, if there's a step)
     fn unpack(&self, num_fields: usize) -> Vec<UserBounds> {
+        // An inverted range (e.g. -1:1:2) is kept as it is,
+        // so that it fails as it would without a step
+        if let Err(TucError::InvertedRange) = self.try_into_range(num_fields) {
+            return vec![self.clone()];
+        }
+
         let mut bounds = Vec::new();
         let n: i32 = num_fields
             .try_into()
@@ -274,8 +500,10 @@ impl UserBoundsTrait<i32> for UserBounds {
             (Side::Some(left), Side::Continue) => (if left > 0 { left } else { n + 1 + left }, n),
         };
 
-        for i in start..=end {
-            bounds.push(UserBounds::new(Side::Some(i), Side::Some(i)))
+        for i in (start..=end).step_by(self.step.unwrap_or(1)) {
+            let mut b = UserBounds::new(Side::Some(i), Side::Some(i));
+            b.transform = self.transform.clone();
+            bounds.push(b)
         }
 
         bounds
@@ -465,6 +693,127 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_user_bounds_with_sub_fields() {
+        let b = UserBounds::from_str("2.1:-2=none").unwrap();
+        assert_eq!((b.l, b.r), (Side::Some(2), Side::Some(2)));
+        assert_eq!(b.fallback_oob, Some(b"none".to_vec()));
+        let sub = b.sub.as_ref().unwrap();
+        assert_eq!((sub.l, sub.r), (Side::Some(1), Side::Some(-2)));
+        assert_eq!(b.to_string(), "2.1:-2");
+        assert!(b.has_modifiers());
+
+        assert_ne!(b, UserBounds::from_str("2").unwrap());
+        assert!(UserBounds::from_str("2:3.1").is_err());
+        assert!(UserBounds::from_str("2.").is_err());
+        assert!(UserBounds::from_str("2.1.1").is_err());
+        assert!(UserBounds::from_str("2.0").is_err());
+    }
+
+    #[test]
+    fn test_user_bounds_with_byte_ranges() {
+        let b = UserBounds::from_str("-1[1:8]=none").unwrap();
+        assert_eq!((b.l, b.r), (Side::Some(-1), Side::Some(-1)));
+        assert_eq!(b.fallback_oob, Some(b"none".to_vec()));
+        let bytes = b.bytes.as_ref().unwrap();
+        assert_eq!((bytes.l, bytes.r), (Side::Some(1), Side::Some(8)));
+        assert_eq!(b.to_string(), "-1[1:8]");
+        assert!(b.has_modifiers());
+
+        let b = UserBounds::from_str("2.1[-3:]").unwrap();
+        assert!(b.sub.is_some() && b.bytes.is_some());
+        assert_eq!(b.to_string(), "2.1[-3:]");
+
+        assert_ne!(b, UserBounds::from_str("2.1").unwrap());
+        assert!(UserBounds::from_str("2[]").is_err());
+        assert!(UserBounds::from_str("2[1:0]").is_err());
+        assert!(UserBounds::from_str("2[1.1]").is_err());
+    }
+
+    #[test]
+    fn test_user_bounds_with_step() {
+        let b = UserBounds::from_str("1:10:2").unwrap();
+        assert_eq!((b.l, b.r, b.step), (Side::Some(1), Side::Some(10), Some(2)));
+        assert_eq!(b.to_string(), "1:10:2");
+        assert_ne!(b, UserBounds::from_str("1:10").unwrap());
+
+        let b = UserBounds::from_str("::2").unwrap();
+        assert_eq!(
+            (b.l, b.r, b.step),
+            (Side::Continue, Side::Continue, Some(2))
+        );
+        assert_eq!(b.to_string(), "::2");
+
+        let b = UserBounds::from_str("-4::3=none").unwrap();
+        assert_eq!(
+            (b.l, b.r, b.step),
+            (Side::Some(-4), Side::Continue, Some(3))
+        );
+        assert_eq!(b.fallback_oob, Some(b"none".to_vec()));
+
+        assert!(UserBounds::from_str("1:10:0").is_err());
+        assert!(UserBounds::from_str("1:10:x").is_err());
+        assert!(UserBounds::from_str("1::").is_err());
+        assert!(UserBounds::from_str("1:2:3:4").is_err());
+        assert!(UserBounds::from_str("2.1:4:2").is_err());
+        assert!(UserBounds::from_str("2[1:4:2]").is_err());
+    }
+
+    #[test]
+    fn test_try_into_range_errors() {
+        let range = |s: &str, parts_length| {
+            UserBounds::from_str(s)
+                .unwrap()
+                .try_into_range(parts_length)
+        };
+
+        assert_eq!(range("2:-1", 3).unwrap(), Range { start: 1, end: 3 });
+        assert!(matches!(
+            range("4", 3),
+            Err(TucError::OutOfBounds { index: 4 })
+        ));
+        assert!(matches!(
+            range("-4:", 3),
+            Err(TucError::OutOfBounds { index: -4 })
+        ));
+        assert!(matches!(range("3:-3", 3), Err(TucError::InvertedRange)));
+        assert!(matches!(
+            UserBounds::from_str("1:x"),
+            Err(TucError::ParseBounds { message }) if message == "Not a number `x`"
+        ));
+    }
+
+    #[test]
+    fn test_padding() {
+        assert_eq!(
+            Padding::from_str("^12").unwrap(),
+            Padding {
+                align: Align::Center,
+                width: 12
+            }
+        );
+        assert!(Padding::from_str("<").is_err());
+        assert!(Padding::from_str("=3").is_err());
+
+        let pad = |s: &str, text: &str| {
+            let mut output = Vec::new();
+            Padding::from_str(s)
+                .unwrap()
+                .write_padded(&mut output, text.as_bytes())
+                .unwrap();
+            String::from_utf8(output).unwrap()
+        };
+
+        assert_eq!(pad("<5", "ab"), "ab   ");
+        assert_eq!(pad(">5", "ab"), "   ab");
+        assert_eq!(pad("^5", "ab"), " ab  ");
+        assert_eq!(pad("<3", "àèì"), "àèì");
+        assert_eq!(pad("<5", "日本"), "日本 ");
+        assert_eq!(pad(">4", "e\u{301}"), "   e\u{301}");
+        assert_eq!(pad("^4", "日"), " 日 ");
+        assert_eq!(pad(">2", "abc"), "abc");
+    }
+
     #[test]
     fn test_unpack_bound() {
         assert_eq!(
@@ -516,6 +865,17 @@ mod tests {
                 UserBounds::new(Side::Some(2), Side::Some(2))
             ],
         );
+
+        assert_eq!(
+            UserBounds::from_str("2::2").unwrap().unpack(5),
+            vec![
+                UserBounds::new(Side::Some(2), Side::Some(2)),
+                UserBounds::new(Side::Some(4), Side::Some(4))
+            ],
+        );
+
+        let inverted = UserBounds::from_str("-1:1:2").unwrap();
+        assert_eq!(inverted.unpack(3), vec![inverted.clone()]);
     }
 
     #[test]
@@ -1,5 +1,11 @@
+use crate::bounds::userbounds::Padding;
 use crate::bounds::{BoundOrFiller, Side, UserBounds, UserBoundsTrait};
-use anyhow::{bail, Result};
+use crate::error::TucError;
+use crate::humanize::Humanize;
+use crate::transform::{Transform, TransformPipeline};
+use anyhow::{anyhow, bail, Result};
+use std::borrow::Cow;
+use std::fmt;
 use std::ops::Deref;
 use std::str::FromStr;
 
@@ -32,12 +38,14 @@ impl From<Vec<BoundOrFiller>> for UserBoundsList {
 
         let is_sortable = ubl.is_sortable();
 
+        ubl.get_userbounds_only().for_each(|b| {
+            if rightmost_bound.is_none() || b.r > rightmost_bound.unwrap() {
+                rightmost_bound = Some(b.r);
+            }
+        });
+
         ubl.list.iter_mut().for_each(|bof| {
             if let BoundOrFiller::Bound(b) = bof {
-                if rightmost_bound.is_none() || b.r > rightmost_bound.unwrap() {
-                    rightmost_bound = Some(b.r);
-                }
-
                 last_bound = Some(b);
             }
         });
@@ -46,26 +54,266 @@ impl From<Vec<BoundOrFiller>> for UserBoundsList {
             rightmost_bound = None;
         }
 
-        last_bound
-            .expect("UserBoundsList must contain at least one UserBounds")
-            .is_last = true;
+        if let Some(b) = last_bound {
+            b.is_last = true;
+        }
 
         ubl.last_interesting_field = rightmost_bound.unwrap_or(Side::Continue);
         ubl
     }
 }
 
+/// Collect every placeholder, including the ones nested in conditionals.
+fn collect_placeholders<'a>(list: &'a [BoundOrFiller], output: &mut Vec<&'a BoundOrFiller>) {
+    for bof in list {
+        match bof {
+            BoundOrFiller::Conditional {
+                condition,
+                then,
+                otherwise,
+            } => {
+                output.push(condition);
+                collect_placeholders(then, output);
+                collect_placeholders(otherwise, output);
+            }
+            BoundOrFiller::Filler(_) => (),
+            _ => output.push(bof),
+        }
+    }
+}
+
+/// Value of the environment variable of a `{$VAR}` placeholder.
+pub(crate) fn env_value(name: &str) -> Result<Vec<u8>, TucError> {
+    std::env::var(name).map(String::into_bytes).map_err(|_| {
+        TucError::parse_bounds(format!(
+            "Field format error: environment variable not set: {name}"
+        ))
+    })
+}
+
+/// Replace every `{$VAR}` placeholder with the value of the variable,
+/// including the ones nested in conditionals.
+fn expand_env(list: &mut [BoundOrFiller]) -> Result<(), TucError> {
+    for bof in list {
+        match bof {
+            BoundOrFiller::Env(name) => *bof = BoundOrFiller::Filler(env_value(name)?),
+            BoundOrFiller::Conditional {
+                then, otherwise, ..
+            } => {
+                expand_env(then)?;
+                expand_env(otherwise)?;
+            }
+            _ => (),
+        }
+    }
+
+    Ok(())
+}
+
+/// Set the byte range of every bound, including the ones nested in conditionals.
+fn set_bytes(list: &mut [BoundOrFiller], bytes: &UserBounds) {
+    for bof in list {
+        match bof {
+            BoundOrFiller::Bound(b) => b.bytes = Some(Box::new(bytes.clone())),
+            BoundOrFiller::Conditional {
+                condition,
+                then,
+                otherwise,
+            } => {
+                set_bytes(std::slice::from_mut(condition.as_mut()), bytes);
+                set_bytes(then, bytes);
+                set_bytes(otherwise, bytes);
+            }
+            _ => (),
+        }
+    }
+}
+
+/// Build the list, as long as it contains something more than fillers.
+fn try_from_parsed(list: Vec<BoundOrFiller>) -> Result<UserBoundsList> {
+    if !list
+        .iter()
+        .any(|bof| !matches!(bof, BoundOrFiller::Filler(_)))
+    {
+        bail!("UserBoundsList must contain at least one UserBounds");
+    }
+    Ok(list.into())
+}
+
 impl FromStr for UserBoundsList {
-    type Err = anyhow::Error;
+    type Err = TucError;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        if s.trim().is_empty() {
-            bail!("UserBoundsList must contain at least one UserBounds");
+        parse_bounds_list_with_columns(s, false)
+            .and_then(try_from_parsed)
+            .map_err(TucError::parse_bounds)
+    }
+}
+
+/// Write a bound the way it's written inside {}, e.g. `1:3:trim|human:<10=none`.
+fn write_placeholder(f: &mut fmt::Formatter, b: &UserBounds) -> fmt::Result {
+    write!(f, "{b}")?;
+
+    if let Some(TransformPipeline(transforms)) = &b.transform {
+        for transform in transforms {
+            write!(f, ":{transform}")?;
         }
-        Ok(parse_bounds_list(s)?.into())
+    }
+
+    if let Some(humanize) = b.humanize {
+        write!(f, "|{humanize}")?;
+    }
+
+    if let Some(pad) = b.pad {
+        write!(f, ":{pad}")?;
+    }
+
+    if let Some(fallback) = &b.fallback_oob {
+        write!(f, "={}", String::from_utf8_lossy(fallback))?;
+    }
+
+    Ok(())
+}
+
+/// Write a list of bounds and fillers as a format string (the opposite of `parse_format`).
+fn write_format(f: &mut fmt::Formatter, list: &[BoundOrFiller]) -> fmt::Result {
+    for bof in list {
+        match bof {
+            BoundOrFiller::Filler(text) => write!(
+                f,
+                "{}",
+                String::from_utf8_lossy(text)
+                    .replace('{', "{{")
+                    .replace('}', "}}")
+                    .replace('\n', "\\n")
+                    .replace('\t', "\\t")
+            )?,
+            BoundOrFiller::Bound(b) => {
+                write!(f, "{{")?;
+                write_placeholder(f, b)?;
+                write!(f, "}}")?;
+            }
+            BoundOrFiller::Capture(name) => write!(f, "{{{name}}}")?,
+            BoundOrFiller::WholeLine => write!(f, "{{0}}")?,
+            BoundOrFiller::Env(name) => write!(f, "{{${name}}}")?,
+            BoundOrFiller::Conditional {
+                condition,
+                then,
+                otherwise,
+            } => {
+                write!(f, "{{")?;
+                match condition.as_ref() {
+                    BoundOrFiller::Bound(b) => write_placeholder(f, b)?,
+                    BoundOrFiller::Capture(name) => write!(f, "{name}")?,
+                    _ => write!(f, "0")?,
+                }
+                write!(f, "?")?;
+                write_format(f, then)?;
+                if !otherwise.is_empty() {
+                    write!(f, ":")?;
+                    write_format(f, otherwise)?;
+                }
+                write!(f, "}}")?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// A list displayed as a format string, even when it could be
+/// displayed as plain bounds (see `UserBoundsList::to_template`).
+struct Template<'a>(&'a [BoundOrFiller]);
+
+impl fmt::Display for Template<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write_format(f, self.0)
+    }
+}
+
+/// Display the list so that it can be parsed back into an equivalent list,
+/// e.g. `1:3,-1` or `Hello {1}, {2?found:missing}`.
+impl fmt::Display for UserBoundsList {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let is_plain = self
+            .list
+            .iter()
+            .all(|bof| matches!(bof, BoundOrFiller::Bound(b) if !b.has_modifiers()));
+
+        if !is_plain {
+            return write_format(f, &self.list);
+        }
+
+        for (idx, bof) in self.list.iter().enumerate() {
+            if let BoundOrFiller::Bound(b) = bof {
+                if idx > 0 {
+                    write!(f, ",")?;
+                }
+                write!(f, "{b}")?;
+                if let Some(fallback) = &b.fallback_oob {
+                    write!(f, "={}", String::from_utf8_lossy(fallback))?;
+                }
+            }
+        }
+
+        Ok(())
     }
 }
 
 impl UserBoundsList {
+    /// Parse bounds where fields can also be spreadsheet column
+    /// letters, e.g. `A,C:F` or `{AA} {B}` (same as `1,3:6` and `{27} {2}`).
+    ///
+    /// ```rust
+    /// # use tuc::bounds::UserBoundsList;
+    /// # use std::str::FromStr;
+    /// assert_eq!(
+    ///   UserBoundsList::from_excel_columns("A,C:F,AA").unwrap().list,
+    ///   UserBoundsList::from_str("1,3:6,27").unwrap().list,
+    /// );
+    /// ```
+    pub fn from_excel_columns(s: &str) -> Result<Self, TucError> {
+        parse_bounds_list_with_columns(s, true)
+            .and_then(try_from_parsed)
+            .map_err(TucError::parse_bounds)
+    }
+
+    /// Parse a template, that is a format string where the text alone is
+    /// valid too (e.g. `redacted` or `{1}/{2}`), as used by `--set`.
+    ///
+    /// ```rust
+    /// # use tuc::bounds::UserBoundsList;
+    /// let template = UserBoundsList::from_template("{1}").unwrap();
+    /// assert_eq!(template.to_template(), "{1}");
+    /// assert_eq!(template.to_string(), "1");
+    /// ```
+    pub fn from_template(s: &str) -> Result<Self, TucError> {
+        parse_format(s, false)
+            .map(Into::into)
+            .map_err(TucError::parse_bounds)
+    }
+
+    /// Replace the `{$VAR}` (and `{env:VAR}`) placeholders with the value
+    /// of the environment variables, so that they are not read for every
+    /// record. The variables are read when cutting otherwise.
+    ///
+    /// ```rust
+    /// # use tuc::bounds::UserBoundsList;
+    /// # use std::str::FromStr;
+    /// std::env::set_var("TUC_DOC_REGION", "eu-1");
+    /// let mut list = UserBoundsList::from_str("{1}@{$TUC_DOC_REGION}").unwrap();
+    /// assert_eq!(list.to_string(), "{1}@{$TUC_DOC_REGION}");
+    /// list.expand_env().unwrap();
+    /// assert_eq!(list.to_string(), "{1}@eu-1");
+    /// ```
+    pub fn expand_env(&mut self) -> Result<(), TucError> {
+        expand_env(&mut self.list)
+    }
+
+    /// Display the list as a template (see `from_template`).
+    pub fn to_template(&self) -> String {
+        Template(&self.list).to_string()
+    }
+
     /// Detect whether the list can be sorted.
     /// It can be sorted only if every bound
     /// has the same sign (all positive or all negative).
@@ -93,8 +341,54 @@ impl UserBoundsList {
         !(has_negative_idx && has_positive_idx)
     }
 
+    /// Iterate over the names used by `{name}` placeholders
+    /// (including the ones inside conditionals).
+    pub fn get_capture_names(&self) -> impl Iterator<Item = &str> + '_ {
+        let mut placeholders = Vec::new();
+        collect_placeholders(&self.list, &mut placeholders);
+        placeholders.into_iter().flat_map(|b| match b {
+            BoundOrFiller::Capture(name) => Some(name.as_str()),
+            _ => None,
+        })
+    }
+
+    /// Check if there are conditional placeholders, e.g. `{2?yes:no}`.
+    pub fn has_conditionals(&self) -> bool {
+        self.list
+            .iter()
+            .any(|b| matches!(b, BoundOrFiller::Conditional { .. }))
+    }
+
+    /// Check if any bound has a modifier, e.g. `{1:<10}` or `{1|human}`.
+    pub fn has_modifiers(&self) -> bool {
+        self.get_userbounds_only().any(|b| b.has_modifiers())
+    }
+
+    /// Check if any bound selects a sub-field, e.g. `2.1` or `{2.-1}`.
+    pub fn has_sub_fields(&self) -> bool {
+        self.get_userbounds_only().any(|b| b.sub.is_some())
+    }
+
+    /// Check if any bound keeps only some bytes of the field, e.g. `2[1:8]`.
+    pub fn has_byte_ranges(&self) -> bool {
+        self.get_userbounds_only().any(|b| b.bytes.is_some())
+    }
+
+    /// Check if any range has a step, e.g. `1:10:2`.
+    pub fn has_steps(&self) -> bool {
+        self.get_userbounds_only().any(|b| b.step.is_some())
+    }
+
+    /// Keep only the bytes in `bytes` of every field (`-f 2 --bytes 1:8`
+    /// is the same as `-f '2[1:8]'`).
+    pub fn set_byte_range(&mut self, bytes: &UserBounds) {
+        set_bytes(&mut self.list, bytes);
+    }
+
     fn get_userbounds_only(&self) -> impl Iterator<Item = &UserBounds> + '_ {
-        self.list.iter().flat_map(|b| match b {
+        let mut placeholders = Vec::new();
+        collect_placeholders(&self.list, &mut placeholders);
+        placeholders.into_iter().flat_map(|b| match b {
             BoundOrFiller::Bound(x) => Some(x),
             _ => None,
         })
@@ -164,7 +458,7 @@ impl UserBoundsList {
                     .into_iter()
                     .map(BoundOrFiller::Bound)
                     .collect(),
-                BoundOrFiller::Filler(f) => vec![BoundOrFiller::Filler(f.clone())],
+                _ => vec![bof.clone()],
             })
             .collect();
 
@@ -173,6 +467,18 @@ impl UserBoundsList {
 
     /// Create a new UserBoundsList with every range complemented (inverted).
     pub fn complement(&self, num_fields: usize) -> Result<UserBoundsList> {
+        let list = self.complement_or_empty(num_fields)?;
+
+        if list.is_empty() {
+            bail!("the complement is empty");
+        }
+
+        Ok(list)
+    }
+
+    /// Like `complement`, but the bounds can match every part
+    /// (e.g. `-c 1 -m` on a line of one character).
+    pub fn complement_or_empty(&self, num_fields: usize) -> Result<UserBoundsList> {
         let list: Vec<BoundOrFiller> = self
             .list
             .iter()
@@ -184,19 +490,262 @@ impl UserBoundsList {
                         .map(BoundOrFiller::Bound)
                         .collect(),
                 ),
-                BoundOrFiller::Filler(f) => Ok(vec![BoundOrFiller::Filler(f.clone())]),
+                _ => Ok(vec![bof.clone()]),
             })
             .flatten()
             .collect();
 
-        if list.is_empty() {
-            bail!("the complement is empty");
+        Ok(list.into())
+    }
+}
+
+/// Check if the text inside {} is the name of a capture group
+/// (same rules as an identifier) rather than a bound.
+fn is_capture_name(s: &str) -> bool {
+    let mut chars = s.chars();
+    matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Split the width/alignment specifier (e.g. `:<10`) from a placeholder.
+/// The specifier goes after the bound and before any fallback, e.g. `{1:3:>8=none}`.
+fn split_padding(s: &str) -> Result<(Cow<'_, str>, Option<Padding>)> {
+    let range_part_end = s.find('=').unwrap_or(s.len());
+
+    match s[..range_part_end].rfind(':') {
+        Some(idx) if matches!(s.as_bytes().get(idx + 1), Some(b'<' | b'>' | b'^')) => {
+            let pad = Padding::from_str(&s[idx + 1..range_part_end])?;
+            let rest = format!("{}{}", &s[..idx], &s[range_part_end..]);
+            Ok((Cow::Owned(rest), Some(pad)))
         }
+        _ => Ok((Cow::Borrowed(s), None)),
+    }
+}
 
-        Ok(list.into())
+/// Split the pipe (e.g. `|human`) from a placeholder, after the width has been removed.
+/// The pipe goes after the bound and before any fallback, e.g. `{2|size=0}`.
+fn split_humanize(s: &str) -> Result<(Cow<'_, str>, Option<Humanize>)> {
+    let range_part_end = s.find('=').unwrap_or(s.len());
+
+    match s[..range_part_end].find('|') {
+        Some(idx) => {
+            let humanize = Humanize::from_str(&s[idx + 1..range_part_end])?;
+            let rest = format!("{}{}", &s[..idx], &s[range_part_end..]);
+            Ok((Cow::Owned(rest), Some(humanize)))
+        }
+        None => Ok((Cow::Borrowed(s), None)),
+    }
+}
+
+/// Split the transforms (e.g. `:upper` or `:trim:lower`) from a placeholder,
+/// after the width and the pipe have been removed, e.g. `{1:upper=none}`.
+fn split_transforms(s: &str) -> Result<(Cow<'_, str>, Option<TransformPipeline>)> {
+    let range_part_end = s.find('=').unwrap_or(s.len());
+    let mut range_part = &s[..range_part_end];
+    let mut transforms = Vec::new();
+
+    while let Some(idx) = range_part.rfind(':') {
+        let name = &range_part[idx + 1..];
+        if !name.starts_with(|c: char| c.is_ascii_lowercase()) {
+            break;
+        }
+        let transform =
+            Transform::from_str(name).map_err(|e| anyhow!("Field format error: {}", e))?;
+        transforms.push(transform);
+        range_part = &range_part[..idx];
+    }
+
+    if transforms.is_empty() {
+        return Ok((Cow::Borrowed(s), None));
+    }
+
+    transforms.reverse();
+    let rest = format!("{}{}", range_part, &s[range_part_end..]);
+    Ok((Cow::Owned(rest), Some(TransformPipeline(transforms))))
+}
+
+/// Number of a spreadsheet column, e.g. `A` is 1, `Z` is 26 and `AA` is 27.
+fn excel_column_number(s: &str) -> Option<i32> {
+    if s.is_empty() || !s.bytes().all(|c| c.is_ascii_uppercase()) {
+        return None;
+    }
+
+    s.bytes().try_fold(0i32, |acc, c| {
+        acc.checked_mul(26)?.checked_add(i32::from(c - b'A' + 1))
+    })
+}
+
+/// Replace the column letters of a bound with field numbers (e.g. `C:F=x` becomes `3:6=x`).
+fn translate_excel_columns(s: &str) -> Cow<'_, str> {
+    let (range_part, fallback) = s.split_at(s.find('=').unwrap_or(s.len()));
+
+    if !range_part.bytes().any(|c| c.is_ascii_uppercase()) {
+        return Cow::Borrowed(s);
+    }
+
+    let sides: Vec<String> = range_part
+        .split(':')
+        .map(|side| excel_column_number(side).map_or_else(|| side.to_owned(), |n| n.to_string()))
+        .collect();
+
+    Cow::Owned(format!("{}{}", sides.join(":"), fallback))
+}
+
+/// Parse a single element found inside {} (e.g. `2`, `1:3=foo`, `2:<10`, `2|human`, `2:upper`,
+/// `0`, `user`).
+fn parse_placeholder(s: &str, excel_columns: bool) -> Result<BoundOrFiller> {
+    let (s, pad) = split_padding(s)?;
+    let (s, humanize) = split_humanize(&s)?;
+    let (s, transform) = split_transforms(&s)?;
+    let s = if excel_columns && s != "LINE" {
+        translate_excel_columns(&s).into_owned()
+    } else {
+        s.into_owned()
+    };
+    let s = s.as_str();
+
+    if (pad.is_some() || humanize.is_some() || transform.is_some())
+        && (s == "0" || s == "LINE" || is_capture_name(s))
+    {
+        bail!("Field format error: modifiers can be set only on fields (e.g. {{1:<10}})");
+    }
+
+    Ok(if s == "0" || s == "LINE" {
+        BoundOrFiller::WholeLine
+    } else if is_capture_name(s) {
+        BoundOrFiller::Capture(s.to_owned())
+    } else {
+        let mut b = UserBounds::from_str(s)?;
+        b.pad = pad;
+        b.humanize = humanize;
+        b.transform = transform;
+        BoundOrFiller::Bound(b)
+    })
+}
+
+fn push_filler(bof: &mut Vec<BoundOrFiller>, s: &str) {
+    if !s.is_empty() {
+        bof.push(BoundOrFiller::Filler(
+            s.replace("{{", "{")
+                .replace("}}", "}")
+                .replace("\\n", "\n")
+                .replace("\\t", "\t")
+                .into_bytes(),
+        ));
     }
 }
 
+/// Find where the conditional part of a placeholder starts (the index of `?`),
+/// as long as it's not part of a fallback (e.g. `{1=what?}`).
+fn find_condition_end(s: &str) -> Option<usize> {
+    s.find(['?', '=']).filter(|idx| s.as_bytes()[*idx] == b'?')
+}
+
+/// Find the index of the `}` that closes the placeholder whose content
+/// starts at `start`. The text of a conditional can contain other placeholders.
+fn find_placeholder_end(s: &str, start: usize) -> Result<usize> {
+    let bytes = s.as_bytes();
+    let mut idx = start;
+    let mut is_conditional = false;
+    let mut depth = 0;
+
+    while idx < bytes.len() {
+        match bytes[idx] {
+            b'?' if !is_conditional => {
+                is_conditional = find_condition_end(&s[start..]) == Some(idx - start);
+                idx += 1;
+            }
+            // escaped bracket, we will replace it later
+            b'{' | b'}' if depth == 0 && bytes.get(idx + 1) == Some(&bytes[idx]) => idx += 2,
+            b'{' if is_conditional => {
+                depth += 1;
+                idx += 1;
+            }
+            b'}' if depth == 0 => return Ok(idx),
+            b'}' => {
+                depth -= 1;
+                idx += 1;
+            }
+            _ => idx += 1,
+        }
+    }
+
+    bail!("Field format error: missing closing parenthesis");
+}
+
+/// Split the text of a conditional (what follows `?`) into the text
+/// to use when the placeholder is found and the text to use when it's not.
+fn split_conditional_text(s: &str) -> (&str, &str) {
+    let bytes = s.as_bytes();
+    let mut depth = 0;
+
+    for (idx, c) in bytes.iter().enumerate() {
+        match c {
+            b'{' => depth += 1,
+            b'}' => depth -= 1,
+            b':' if depth == 0 => return (&s[..idx], &s[idx + 1..]),
+            _ => (),
+        }
+    }
+
+    (s, "")
+}
+
+/// Parse a format string, where everything inside {} is a placeholder
+/// and the rest is text to display.
+fn parse_format(s: &str, excel_columns: bool) -> Result<Vec<BoundOrFiller>> {
+    let mut bof: Vec<BoundOrFiller> = Vec::new();
+    let bytes = s.as_bytes();
+    let mut part_start = 0;
+    let mut idx = 0;
+
+    while idx < bytes.len() {
+        match bytes[idx] {
+            // escaped bracket, we will replace it later
+            b'{' | b'}' if bytes.get(idx + 1) == Some(&bytes[idx]) => idx += 2,
+            b'}' => bail!("Field format error: missing opening parenthesis"),
+            b'{' => {
+                push_filler(&mut bof, &s[part_start..idx]);
+
+                let end = find_placeholder_end(s, idx + 1)?;
+                let placeholder = &s[idx + 1..end];
+
+                if let Some(q_idx) = find_condition_end(placeholder) {
+                    let condition = &placeholder[..q_idx];
+                    if condition.contains(',') {
+                        bail!("Field format error: a condition must be a single field");
+                    }
+
+                    let (then, otherwise) = split_conditional_text(&placeholder[q_idx + 1..]);
+                    bof.push(BoundOrFiller::Conditional {
+                        condition: Box::new(parse_placeholder(condition, excel_columns)?),
+                        then: parse_format(then, excel_columns)?,
+                        otherwise: parse_format(otherwise, excel_columns)?,
+                    });
+                } else if let Some(name) = placeholder
+                    .strip_prefix('$')
+                    .or_else(|| placeholder.strip_prefix("env:"))
+                {
+                    bof.push(BoundOrFiller::Env(name.to_owned()));
+                } else {
+                    // consider also comma separated bounds
+                    for maybe_bounds in placeholder.split(',') {
+                        bof.push(parse_placeholder(maybe_bounds, excel_columns)?);
+                    }
+                }
+
+                idx = end + 1;
+                part_start = idx;
+            }
+            _ => idx += 1,
+        }
+    }
+
+    push_filler(&mut bof, &s[part_start..]);
+
+    Ok(bof)
+}
+
 /**
  * Parse bound string. It can contain formatting elements or not.
  *
@@ -205,73 +754,36 @@ impl UserBoundsList {
  * in that case everything inside {} is considered a bound, and the rest
  * just some text to display when the bounds are found.
  * e.g. "Hello {1}, found {1:3} and {2,4}"
+ *
+ * A name inside {} (e.g. {user}) refers to a named capture group
+ * of the delimiter regex, while {0} (or {LINE}) is the whole line.
+ * {$VAR} (or {env:VAR}) is the value of the environment variable VAR.
+ *
+ * A placeholder can display some text only when a field is found
+ * and it's not empty, e.g. {2?yes:no} or {3?, {3}}
  */
-pub fn parse_bounds_list(s: &str) -> Result<Vec<BoundOrFiller>> {
+pub fn parse_bounds_list(s: &str) -> Result<Vec<BoundOrFiller>, TucError> {
+    parse_bounds_list_with_columns(s, false).map_err(TucError::parse_bounds)
+}
+
+fn parse_bounds_list_with_columns(s: &str, excel_columns: bool) -> Result<Vec<BoundOrFiller>> {
     if s.is_empty() {
         return Ok(Vec::new());
     }
 
     if s.contains(['{', '}']) {
-        let mut bof: Vec<BoundOrFiller> = Vec::new();
-        let mut inside_bound = false;
-        let mut part_start = 0;
-
-        let mut iter = s.char_indices().peekable();
-        while let Some((idx, w0)) = iter.next() {
-            let w1 = iter.peek().unwrap_or(&(0, 'x')).1;
-
-            if w0 == w1 && (w0 == '{' || w0 == '}') {
-                // escaped bracket, ignore it, we will replace it later
-                iter.next();
-            } else if w0 == '}' && !inside_bound {
-                bail!("Field format error: missing opening parenthesis",);
-            } else if w0 == '{' {
-                // starting a new bound
-                inside_bound = true;
-
-                if idx - part_start > 0 {
-                    bof.push(BoundOrFiller::Filler(
-                        s[part_start..idx]
-                            .replace("{{", "{")
-                            .replace("}}", "}")
-                            .replace("\\n", "\n")
-                            .replace("\\t", "\t")
-                            .into_bytes(),
-                    ));
-                }
-
-                part_start = idx + 1;
-            } else if w0 == '}' {
-                // ending a bound
-                inside_bound = false;
-
-                // consider also comma separated bounds
-                for maybe_bounds in s[part_start..idx].split(',') {
-                    bof.push(BoundOrFiller::Bound(UserBounds::from_str(maybe_bounds)?));
-                }
-
-                part_start = idx + 1;
-            }
-        }
-
-        if inside_bound {
-            bail!("Field format error: missing closing parenthesis");
-        } else if s.len() - part_start > 0 {
-            bof.push(BoundOrFiller::Filler(
-                s[part_start..]
-                    .replace("{{", "{")
-                    .replace("}}", "}")
-                    .replace("\\n", "\n")
-                    .replace("\\t", "\t")
-                    .into_bytes(),
-            ));
-        }
-
-        Ok(bof)
+        parse_format(s, excel_columns)
     } else {
         let k: Result<Vec<BoundOrFiller>, _> = s
             .split(',')
-            .map(|x| UserBounds::from_str(x).map(BoundOrFiller::Bound))
+            .map(|x| {
+                let x = if excel_columns {
+                    translate_excel_columns(x)
+                } else {
+                    Cow::Borrowed(x)
+                };
+                UserBounds::from_str(&x).map(BoundOrFiller::Bound)
+            })
             .collect();
         Ok(k?)
     }
@@ -401,6 +913,222 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_parse_format_with_environment_variables() {
+        assert_eq!(
+            parse_bounds_list("{1}@{$TUC_TEST_FORMAT_REGION}/{env:TUC_TEST_FORMAT_REGION}")
+                .unwrap(),
+            vec![
+                BoundOrFiller::Bound(UserBounds::new(Side::Some(1), Side::Some(1))),
+                BoundOrFiller::Filler("@".into()),
+                BoundOrFiller::Env("TUC_TEST_FORMAT_REGION".into()),
+                BoundOrFiller::Filler("/".into()),
+                BoundOrFiller::Env("TUC_TEST_FORMAT_REGION".into()),
+            ],
+        );
+
+        // the placeholders are kept until they are expanded
+        let mut list = UserBoundsList::from_str("{1}@{env:TUC_TEST_FORMAT_REGION}").unwrap();
+        assert_eq!(list.to_string(), "{1}@{$TUC_TEST_FORMAT_REGION}");
+
+        std::env::set_var("TUC_TEST_FORMAT_REGION", "eu-1");
+        list.expand_env().unwrap();
+        assert_eq!(list.to_string(), "{1}@eu-1");
+
+        let mut list = UserBoundsList::from_str("{1?{$TUC_TEST_FORMAT_MISSING}}").unwrap();
+        assert_eq!(
+            list.expand_env().unwrap_err().to_string(),
+            "Field format error: environment variable not set: TUC_TEST_FORMAT_MISSING"
+        );
+    }
+
+    #[test]
+    fn test_parse_bounds_list_with_named_placeholders() {
+        assert_eq!(
+            parse_bounds_list("{user}@{2,host_1}").unwrap(),
+            vec![
+                BoundOrFiller::Capture("user".into()),
+                BoundOrFiller::Filler("@".into()),
+                BoundOrFiller::Bound(UserBounds::new(Side::Some(2), Side::Some(2))),
+                BoundOrFiller::Capture("host_1".into()),
+            ],
+        );
+
+        assert_eq!(
+            &parse_bounds_list("{1a}").unwrap_err().to_string(),
+            "Not a number `1a`"
+        );
+
+        assert_eq!(
+            parse_bounds_list("{2}: {0}{LINE}").unwrap(),
+            vec![
+                BoundOrFiller::Bound(UserBounds::new(Side::Some(2), Side::Some(2))),
+                BoundOrFiller::Filler(": ".into()),
+                BoundOrFiller::WholeLine,
+                BoundOrFiller::WholeLine,
+            ],
+        );
+
+        assert!(UserBoundsList::from_str("{user}").is_ok());
+        assert!(UserBoundsList::from_str("{{user}}").is_err());
+    }
+
+    #[test]
+    fn test_parse_bounds_list_with_conditionals() {
+        assert_eq!(
+            parse_bounds_list("{2?yes:no}").unwrap(),
+            vec![BoundOrFiller::Conditional {
+                condition: Box::new(BoundOrFiller::Bound(UserBounds::new(
+                    Side::Some(2),
+                    Side::Some(2)
+                ))),
+                then: vec![BoundOrFiller::Filler("yes".into())],
+                otherwise: vec![BoundOrFiller::Filler("no".into())],
+            }],
+        );
+
+        assert_eq!(
+            parse_bounds_list("{1}{3? ({3})}").unwrap(),
+            vec![
+                BoundOrFiller::Bound(UserBounds::new(Side::Some(1), Side::Some(1))),
+                BoundOrFiller::Conditional {
+                    condition: Box::new(BoundOrFiller::Bound(UserBounds::new(
+                        Side::Some(3),
+                        Side::Some(3)
+                    ))),
+                    then: vec![
+                        BoundOrFiller::Filler(" (".into()),
+                        BoundOrFiller::Bound(UserBounds::new(Side::Some(3), Side::Some(3))),
+                        BoundOrFiller::Filler(")".into()),
+                    ],
+                    otherwise: vec![],
+                },
+            ],
+        );
+
+        // a question mark after the fallback is part of the fallback
+        assert_eq!(
+            parse_bounds_list("{2=?}").unwrap(),
+            vec![BoundOrFiller::Bound(UserBounds::from_str("2=?").unwrap())],
+        );
+
+        assert_eq!(
+            &parse_bounds_list("{1,2?yes}").unwrap_err().to_string(),
+            "Field format error: a condition must be a single field"
+        );
+
+        assert!(UserBoundsList::from_str("{2?yes:no}")
+            .unwrap()
+            .has_conditionals());
+        assert!(!UserBoundsList::from_str("{2}").unwrap().has_conditionals());
+    }
+
+    #[test]
+    fn test_parse_bounds_list_with_padding() {
+        let pad = |s: &str| match &parse_bounds_list(s).unwrap()[0] {
+            BoundOrFiller::Bound(b) => (b.to_string(), b.pad, b.fallback_oob.clone()),
+            _ => unreachable!(),
+        };
+
+        assert_eq!(
+            pad("{1:<10}"),
+            ("1".into(), Some(Padding::from_str("<10").unwrap()), None)
+        );
+        assert_eq!(
+            pad("{2:3:^4=none}"),
+            (
+                "2:3".into(),
+                Some(Padding::from_str("^4").unwrap()),
+                Some("none".into())
+            )
+        );
+        assert_eq!(pad("{1:-1}"), ("1:-1".into(), None, None));
+        assert_eq!(pad("{1=a:<3}"), ("1".into(), None, Some("a:<3".into())));
+
+        assert_eq!(
+            &parse_bounds_list("{1:>x}").unwrap_err().to_string(),
+            "Field format error: invalid width `x`"
+        );
+        assert_eq!(
+            &parse_bounds_list("{0:>3}").unwrap_err().to_string(),
+            "Field format error: modifiers can be set only on fields (e.g. {1:<10})"
+        );
+    }
+
+    #[test]
+    fn test_parse_bounds_list_with_humanize_pipes() {
+        let humanize = |s: &str| match &parse_bounds_list(s).unwrap()[0] {
+            BoundOrFiller::Bound(b) => (b.to_string(), b.humanize, b.pad, b.fallback_oob.clone()),
+            _ => unreachable!(),
+        };
+
+        assert_eq!(
+            humanize("{2|human}"),
+            ("2".into(), Some(Humanize::Thousands), None, None)
+        );
+        assert_eq!(
+            humanize("{1:2|size:>8=a|b}"),
+            (
+                "1:2".into(),
+                Some(Humanize::Size),
+                Some(Padding::from_str(">8").unwrap()),
+                Some("a|b".into())
+            )
+        );
+
+        assert_eq!(
+            &parse_bounds_list("{1|nope}").unwrap_err().to_string(),
+            "Field format error: unknown pipe `nope` (use human or size)"
+        );
+        assert_eq!(
+            &parse_bounds_list("{0|human}").unwrap_err().to_string(),
+            "Field format error: modifiers can be set only on fields (e.g. {1:<10})"
+        );
+    }
+
+    #[test]
+    fn test_parse_bounds_list_with_transforms() {
+        let transform = |s: &str| match &parse_bounds_list(s).unwrap()[0] {
+            BoundOrFiller::Bound(b) => (b.to_string(), b.transform.clone(), b.fallback_oob.clone()),
+            _ => unreachable!(),
+        };
+
+        assert_eq!(
+            transform("{1:upper}"),
+            (
+                "1".into(),
+                Some(TransformPipeline(vec![Transform::Upper])),
+                None
+            )
+        );
+        assert_eq!(
+            transform("{2:4:trim:lower=a:b}"),
+            (
+                "2:4".into(),
+                Some(TransformPipeline(vec![Transform::Trim, Transform::Lower])),
+                Some("a:b".into())
+            )
+        );
+
+        match &parse_bounds_list("{-1:trim|human:<8}").unwrap()[0] {
+            BoundOrFiller::Bound(b) => {
+                assert_eq!(b.transform, Some(TransformPipeline(vec![Transform::Trim])));
+                assert_eq!(b.humanize, Some(Humanize::Thousands));
+                assert_eq!(b.pad, Some(Padding::from_str("<8").unwrap()));
+            }
+            _ => unreachable!(),
+        }
+
+        assert_eq!(
+            &parse_bounds_list("{1:shout}").unwrap_err().to_string(),
+            "Field format error: unknown transform `shout` (use trim, lower, upper or squeeze-spaces)"
+        );
+        assert_eq!(
+            &parse_bounds_list("{0:upper}").unwrap_err().to_string(),
+            "Field format error: modifiers can be set only on fields (e.g. {1:<10})"
+        );
+    }
+
     #[test]
     fn test_user_bounds_cannot_be_empty() {
         assert!(UserBoundsList::from_str("").is_err());
@@ -503,5 +1231,61 @@ mod tests {
                 .map(|x| x.to_string()),
             Some("the complement is empty".to_owned())
         );
+        assert!(UserBoundsList::from_str("1:")
+            .unwrap()
+            .complement_or_empty(6)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_vec_of_bounds_can_be_displayed() {
+        let display = |s: &str| UserBoundsList::from_str(s).unwrap().to_string();
+
+        assert_eq!(display("1"), "1");
+        assert_eq!(display("1:3,-1,:2,4:"), "1:3,-1,:2,4:");
+        assert_eq!(display("1=foo,2"), "1=foo,2");
+        assert_eq!(display("{1,2}"), "1,2");
+        assert_eq!(display("{1=a b}"), "1=a b");
+        assert_eq!(
+            display("Hello {1} {{world}}\\n\\t{2}"),
+            "Hello {1} {{world}}\\n\\t{2}"
+        );
+        assert_eq!(display("{0} {LINE}"), "{0} {0}");
+        assert_eq!(display("{2|human:>8=none}"), "{2|human:>8=none}");
+        assert_eq!(display("{1:3:<10}"), "{1:3:<10}");
+        assert_eq!(display("{1:trim:upper|human=x}"), "{1:trim:upper|human=x}");
+        assert_eq!(display("{1?yes:no}{2?, {2}}"), "{1?yes:no}{2?, {2}}");
+        assert_eq!(display("{3?:missing}"), "{3?:missing}");
+
+        for s in [
+            "1:3,4=none",
+            "a{1}b{{c}}",
+            "{1|size:^6}",
+            "{1?{2?both:one}:none}",
+        ] {
+            assert_eq!(display(&display(s)), display(s));
+        }
+    }
+
+    #[test]
+    fn test_parse_bounds_list_with_excel_columns() {
+        let columns = |s: &str| UserBoundsList::from_excel_columns(s).unwrap().to_string();
+
+        assert_eq!(excel_column_number("A"), Some(1));
+        assert_eq!(excel_column_number("Z"), Some(26));
+        assert_eq!(excel_column_number("AA"), Some(27));
+        assert_eq!(excel_column_number("AZ"), Some(52));
+        assert_eq!(excel_column_number("XFD"), Some(16384));
+        assert_eq!(excel_column_number("a"), None);
+
+        assert_eq!(columns("A,C:F,AA:,:B,-1"), "1,3:6,27:,:2,-1");
+        assert_eq!(columns("B=none"), "2=none");
+        assert_eq!(
+            columns("{A} {B:<5} {C|human} {D?yes:no} {LINE} {host}"),
+            "{1} {2:<5} {3|human} {4?yes:no} {0} {host}"
+        );
+        assert!(UserBoundsList::from_excel_columns("A1").is_err());
+        assert!(UserBoundsList::from_str("A").is_err());
     }
 }
@@ -0,0 +1,106 @@
+use std::io::{Result, Write};
+
+const BASE64_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+fn base64(data: &[u8]) -> Vec<u8> {
+    let mut encoded = Vec::with_capacity(data.len().div_ceil(3) * 4);
+
+    for chunk in data.chunks(3) {
+        let b = [
+            chunk[0],
+            *chunk.get(1).unwrap_or(&0),
+            *chunk.get(2).unwrap_or(&0),
+        ];
+        let n = (b[0] as usize) << 16 | (b[1] as usize) << 8 | b[2] as usize;
+
+        encoded.push(BASE64_ALPHABET[n >> 18 & 63]);
+        encoded.push(BASE64_ALPHABET[n >> 12 & 63]);
+        encoded.push(if chunk.len() > 1 {
+            BASE64_ALPHABET[n >> 6 & 63]
+        } else {
+            b'='
+        });
+        encoded.push(if chunk.len() > 2 {
+            BASE64_ALPHABET[n & 63]
+        } else {
+            b'='
+        });
+    }
+
+    encoded
+}
+
+/// OSC 52 escape sequence that asks the terminal to put `data` on the
+/// clipboard. It works over SSH too, since the terminal does the copy.
+pub fn osc52(data: &[u8]) -> Vec<u8> {
+    let mut sequence = b"\x1b]52;c;".to_vec();
+    sequence.extend_from_slice(&base64(data));
+    sequence.push(b'\x07');
+    sequence
+}
+
+/// Writer that keeps a copy of everything written through it,
+/// when `copy` is enabled (--copy).
+pub struct CopyWriter<W> {
+    inner: W,
+    copy: Option<Vec<u8>>,
+}
+
+impl<W: Write> CopyWriter<W> {
+    pub fn new(inner: W, copy: bool) -> Self {
+        CopyWriter {
+            inner,
+            copy: copy.then(Vec::new),
+        }
+    }
+
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    /// What was written so far, if copying was enabled.
+    pub fn copied(&self) -> Option<&[u8]> {
+        self.copy.as_deref()
+    }
+}
+
+impl<W: Write> Write for CopyWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> Result<usize> {
+        let n = self.inner.write(buf)?;
+        if let Some(copy) = self.copy.as_mut() {
+            copy.extend_from_slice(&buf[..n]);
+        }
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        self.inner.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_encodes_in_base64() {
+        assert_eq!(base64(b""), b"");
+        assert_eq!(base64(b"a"), b"YQ==");
+        assert_eq!(base64(b"ab"), b"YWI=");
+        assert_eq!(base64(b"abc"), b"YWJj");
+        assert_eq!(base64(b"foo\nbar\xff"), b"Zm9vCmJhcv8=");
+    }
+
+    #[test]
+    fn it_copies_what_is_written() {
+        let mut writer = CopyWriter::new(Vec::new(), true);
+        writer.write_all(b"a\n").unwrap();
+        assert_eq!(writer.copied(), Some(b"a\n".as_slice()));
+        assert_eq!(osc52(writer.copied().unwrap()), b"\x1b]52;c;YQo=\x07");
+
+        let mut writer = CopyWriter::new(Vec::new(), false);
+        writer.write_all(b"a\n").unwrap();
+        assert_eq!(writer.copied(), None);
+    }
+}
@@ -0,0 +1,330 @@
+use crate::bounds::{BoundOrFiller, Side};
+use crate::error::Diagnostic;
+use crate::options::{ColumnType, ColumnarOutput, Opt};
+use crate::record_sink::{read_and_cut_into, RecordSink};
+use anyhow::{bail, Result};
+use arrow_array::builder::{BooleanBuilder, Float64Builder, Int64Builder, StringBuilder};
+use arrow_array::{ArrayRef, RecordBatch};
+use arrow_ipc::writer::StreamWriter;
+use arrow_schema::{DataType, Field, Schema, SchemaRef};
+use bstr::ByteSlice;
+use parquet::arrow::ArrowWriter;
+use std::io::{BufRead, Write};
+use std::sync::Arc;
+
+/// Rows kept in memory before being written as a batch
+const BATCH_SIZE: usize = 8192;
+
+enum ColumnBuilder {
+    Str(StringBuilder),
+    Int(Int64Builder),
+    Float(Float64Builder),
+    Bool(BooleanBuilder),
+}
+
+impl ColumnBuilder {
+    fn new(column_type: ColumnType) -> Self {
+        match column_type {
+            ColumnType::Str => ColumnBuilder::Str(StringBuilder::new()),
+            ColumnType::Int => ColumnBuilder::Int(Int64Builder::new()),
+            ColumnType::Float => ColumnBuilder::Float(Float64Builder::new()),
+            ColumnType::Bool => ColumnBuilder::Bool(BooleanBuilder::new()),
+        }
+    }
+
+    /// Append `value`, converted to the type of the column.
+    /// Empty values become nulls, except for string columns.
+    /// Invalid UTF-8 is replaced in string columns.
+    fn append(&mut self, value: &[u8]) -> std::result::Result<(), ColumnType> {
+        let text = std::str::from_utf8(value).ok();
+
+        match self {
+            ColumnBuilder::Str(b) => b.append_value(String::from_utf8_lossy(value)),
+            ColumnBuilder::Int(b) if value.is_empty() => b.append_null(),
+            ColumnBuilder::Int(b) => {
+                b.append_value(text.and_then(|t| t.parse().ok()).ok_or(ColumnType::Int)?)
+            }
+            ColumnBuilder::Float(b) if value.is_empty() => b.append_null(),
+            ColumnBuilder::Float(b) => {
+                b.append_value(text.and_then(|t| t.parse().ok()).ok_or(ColumnType::Float)?)
+            }
+            ColumnBuilder::Bool(b) => match value {
+                b"" => b.append_null(),
+                b"true" | b"1" => b.append_value(true),
+                b"false" | b"0" => b.append_value(false),
+                _ => return Err(ColumnType::Bool),
+            },
+        }
+
+        Ok(())
+    }
+
+    fn finish(&mut self) -> ArrayRef {
+        match self {
+            ColumnBuilder::Str(b) => Arc::new(b.finish()),
+            ColumnBuilder::Int(b) => Arc::new(b.finish()),
+            ColumnBuilder::Float(b) => Arc::new(b.finish()),
+            ColumnBuilder::Bool(b) => Arc::new(b.finish()),
+        }
+    }
+}
+
+enum Sink<W: Write + Send> {
+    Parquet(ArrowWriter<W>),
+    Arrow(StreamWriter<W>),
+}
+
+/// Store the fields of every record as the columns
+/// of a Parquet file or of an Arrow IPC stream.
+pub struct ColumnarWriter<W: Write + Send> {
+    sink: Sink<W>,
+    schema: SchemaRef,
+    builders: Vec<ColumnBuilder>,
+    rows_in_batch: usize,
+    rows: usize,
+}
+
+impl<W: Write + Send> ColumnarWriter<W> {
+    pub fn try_new(output: W, parquet: bool, columns: &[(String, ColumnType)]) -> Result<Self> {
+        let schema: SchemaRef = Arc::new(Schema::new(
+            columns
+                .iter()
+                .map(|(name, column_type)| {
+                    let data_type = match column_type {
+                        ColumnType::Str => DataType::Utf8,
+                        ColumnType::Int => DataType::Int64,
+                        ColumnType::Float => DataType::Float64,
+                        ColumnType::Bool => DataType::Boolean,
+                    };
+                    Field::new(name, data_type, true)
+                })
+                .collect::<Vec<_>>(),
+        ));
+
+        let sink = if parquet {
+            Sink::Parquet(ArrowWriter::try_new(output, schema.clone(), None)?)
+        } else {
+            Sink::Arrow(StreamWriter::try_new(output, &schema)?)
+        };
+
+        Ok(ColumnarWriter {
+            sink,
+            schema,
+            builders: columns
+                .iter()
+                .map(|(_, t)| ColumnBuilder::new(*t))
+                .collect(),
+            rows_in_batch: 0,
+            rows: 0,
+        })
+    }
+
+    fn add_row(&mut self, values: &[&[u8]]) -> Result<()> {
+        self.rows += 1;
+
+        // An empty line has no fields to print
+        let empty_row;
+        let values = if values.is_empty() {
+            empty_row = vec![&b""[..]; self.builders.len()];
+            &empty_row
+        } else {
+            values
+        };
+
+        if values.len() != self.builders.len() {
+            bail!(
+                "row {}: expected {} columns, found {}",
+                self.rows,
+                self.builders.len(),
+                values.len()
+            );
+        }
+
+        for (idx, (builder, value)) in self.builders.iter_mut().zip(values).enumerate() {
+            if let Err(column_type) = builder.append(value) {
+                bail!(
+                    "row {}, column {}: {:?} is not a valid {}",
+                    self.rows,
+                    idx + 1,
+                    value.as_bstr(),
+                    column_type
+                );
+            }
+        }
+
+        self.rows_in_batch += 1;
+        if self.rows_in_batch == BATCH_SIZE {
+            self.write_batch()?;
+        }
+
+        Ok(())
+    }
+
+    fn write_batch(&mut self) -> Result<()> {
+        if self.rows_in_batch == 0 {
+            return Ok(());
+        }
+
+        let columns = self.builders.iter_mut().map(|b| b.finish()).collect();
+        let batch = RecordBatch::try_new(self.schema.clone(), columns)?;
+
+        match &mut self.sink {
+            Sink::Parquet(writer) => writer.write(&batch)?,
+            Sink::Arrow(writer) => writer.write(&batch)?,
+        }
+
+        self.rows_in_batch = 0;
+        Ok(())
+    }
+
+    /// Write the rows still in memory and complete the output.
+    pub fn finish(mut self) -> Result<()> {
+        self.write_batch()?;
+
+        match self.sink {
+            Sink::Parquet(writer) => {
+                writer.close()?;
+            }
+            Sink::Arrow(mut writer) => {
+                writer.finish()?;
+                writer.into_inner()?.flush()?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl<W: Write + Send> RecordSink for ColumnarWriter<W> {
+    fn add_record(&mut self, values: &[&[u8]]) -> Result<()> {
+        self.add_row(values)
+    }
+}
+
+/// Name and type of each column, one for every field in `opt.bounds`.
+fn columns(opt: &Opt) -> Vec<(String, ColumnType)> {
+    opt.bounds
+        .iter()
+        .enumerate()
+        .map(|(idx, bof)| {
+            let name = match bof {
+                BoundOrFiller::Bound(b) => match b.l {
+                    Side::Some(field) => field.to_string(),
+                    Side::Continue => (idx + 1).to_string(),
+                },
+                _ => (idx + 1).to_string(),
+            };
+            let column_type = opt
+                .column_types
+                .get(idx)
+                .copied()
+                .unwrap_or(ColumnType::Str);
+            (name, column_type)
+        })
+        .collect()
+}
+
+/// Cut the fields of every record and write them as columns,
+/// in the format requested by --parquet or --arrow.
+pub fn read_and_write_columns<B: BufRead>(
+    stdin: &mut B,
+    opt: Opt,
+    on_diagnostic: impl FnMut(Diagnostic),
+) -> Result<()> {
+    let columns = columns(&opt);
+
+    match opt.columnar.clone() {
+        Some(ColumnarOutput::Parquet(path)) => {
+            let file = std::fs::File::create(&path)
+                .map_err(|e| anyhow::anyhow!("Cannot create {}: {}", path.to_string_lossy(), e))?;
+            let writer = ColumnarWriter::try_new(file, true, &columns)?;
+            read_and_cut_into(stdin, opt, writer, on_diagnostic)?.finish()
+        }
+        Some(ColumnarOutput::Arrow) => {
+            let stdout = std::io::BufWriter::new(std::io::stdout());
+            let writer = ColumnarWriter::try_new(stdout, false, &columns)?;
+            read_and_cut_into(stdin, opt, writer, on_diagnostic)?.finish()
+        }
+        None => bail!("invariant error. --parquet or --arrow is required"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow_array::cast::AsArray;
+    use arrow_array::types::{Float64Type, Int64Type};
+    use arrow_ipc::reader::StreamReader;
+    use std::io::Cursor;
+
+    fn columns_of(types: &[ColumnType]) -> Vec<(String, ColumnType)> {
+        types
+            .iter()
+            .enumerate()
+            .map(|(idx, t)| ((idx + 1).to_string(), *t))
+            .collect()
+    }
+
+    fn arrow_stream(types: &[ColumnType], rows: &[&[&str]]) -> Result<Vec<u8>> {
+        let mut output = Vec::new();
+        let mut writer = ColumnarWriter::try_new(&mut output, false, &columns_of(types))?;
+        for row in rows {
+            let row: Vec<&[u8]> = row.iter().map(|v| v.as_bytes()).collect();
+            writer.add_record(&row)?;
+        }
+        writer.finish()?;
+        Ok(output)
+    }
+
+    #[test]
+    fn it_writes_typed_columns() {
+        let types = [
+            ColumnType::Str,
+            ColumnType::Int,
+            ColumnType::Float,
+            ColumnType::Bool,
+        ];
+        let output =
+            arrow_stream(&types, &[&["a", "1", "1.5", "true"], &["b", "", "2", "0"]]).unwrap();
+
+        let batches: Vec<RecordBatch> = StreamReader::try_new(Cursor::new(output), None)
+            .unwrap()
+            .collect::<std::result::Result<_, _>>()
+            .unwrap();
+        assert_eq!(batches.len(), 1);
+
+        let batch = &batches[0];
+        assert_eq!(batch.num_rows(), 2);
+        assert_eq!(batch.schema().field(1).name(), "2");
+        assert_eq!(batch.column(0).as_string::<i32>().value(1), "b");
+        assert_eq!(batch.column(1).as_primitive::<Int64Type>().value(0), 1);
+        assert!(batch.column(1).is_null(1));
+        assert_eq!(batch.column(2).as_primitive::<Float64Type>().value(0), 1.5);
+        assert!(!batch.column(3).as_boolean().value(1));
+    }
+
+    #[test]
+    fn it_fails_on_values_of_the_wrong_type() {
+        assert_eq!(
+            arrow_stream(
+                &[ColumnType::Str, ColumnType::Int],
+                &[&["a", "1"], &["b", "x"]]
+            )
+            .unwrap_err()
+            .to_string(),
+            "row 2, column 2: \"x\" is not a valid int"
+        );
+    }
+
+    #[test]
+    fn it_writes_parquet_files() {
+        let mut output = Vec::new();
+        let mut writer =
+            ColumnarWriter::try_new(&mut output, true, &columns_of(&[ColumnType::Str])).unwrap();
+        writer.add_record(&[&b"a"[..]]).unwrap();
+        writer.finish().unwrap();
+
+        assert!(output.starts_with(b"PAR1"));
+        assert!(output.ends_with(b"PAR1"));
+    }
+}
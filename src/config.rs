@@ -0,0 +1,460 @@
+use anyhow::{anyhow, bail, Result};
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+
+/// What is cut (--fields, --bytes, ...). Most defaults of the config
+/// make sense only for some of them (e.g. --delimiter for --fields).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum Mode {
+    Fields,
+    Characters,
+    Bytes,
+    Lines,
+    Width,
+}
+
+/// The option that selects each mode, in the order in which
+/// `Opt::try_from_args` picks them when more than one is given
+const MODES: &[(&str, Mode)] = &[
+    ("fields", Mode::Fields),
+    ("bytes", Mode::Bytes),
+    ("characters", Mode::Characters),
+    ("lines", Mode::Lines),
+    ("width", Mode::Width),
+];
+
+const ALL_MODES: &[Mode] = &[
+    Mode::Fields,
+    Mode::Characters,
+    Mode::Bytes,
+    Mode::Lines,
+    Mode::Width,
+];
+
+/// Options that can have a default in the config file: the key, the
+/// command line arguments that set (or unset) it, whether it's a flag
+/// and the modes that accept it.
+const CONFIG_KEYS: &[(&str, &[&str], bool, &[Mode])] = &[
+    ("delimiter", &["-d", "--delimiter"], false, &[Mode::Fields]),
+    (
+        "replace-delimiter",
+        &["-r", "--replace-delimiter"],
+        false,
+        &[Mode::Fields],
+    ),
+    ("trim", &["-t", "--trim"], false, &[Mode::Fields]),
+    (
+        "greedy-delimiter",
+        &["-g", "--greedy-delimiter"],
+        true,
+        &[Mode::Fields],
+    ),
+    (
+        "compress-delimiter",
+        &["-p", "--compress-delimiter"],
+        true,
+        &[Mode::Fields],
+    ),
+    (
+        "join",
+        &["-j", "--join", "--no-join"],
+        true,
+        &[Mode::Fields, Mode::Characters, Mode::Lines],
+    ),
+    (
+        "json",
+        &["--json", "--no-json"],
+        true,
+        &[Mode::Fields, Mode::Characters],
+    ),
+    ("eol", &["-z", "--zero-terminated"], false, ALL_MODES),
+];
+
+#[derive(Debug, PartialEq)]
+enum Value {
+    String(String),
+    Bool(bool),
+}
+
+/// Where the config file is looked for when --config is not given:
+/// `$XDG_CONFIG_HOME/tuc/config.toml`, or `~/.config/tuc/config.toml`.
+pub fn default_path() -> Option<PathBuf> {
+    let config_dir = match std::env::var_os("XDG_CONFIG_HOME").filter(|dir| !dir.is_empty()) {
+        Some(dir) => PathBuf::from(dir),
+        None => PathBuf::from(std::env::var_os("HOME")?).join(".config"),
+    };
+
+    Some(config_dir.join("tuc").join("config.toml"))
+}
+
+/// Short aliases of the options that a preset may set, to tell
+/// whether the command line overrides them
+const SHORT_FLAGS: &[(&str, &str)] = &[
+    ("fields", "-f"),
+    ("characters", "-c"),
+    ("bytes", "-b"),
+    ("lines", "-l"),
+    ("sub-delimiter", "-D"),
+    ("regex", "-e"),
+    ("only-delimited", "-s"),
+    ("complement", "-m"),
+    ("show-nonprinting", "-v"),
+];
+
+type Entries = Vec<(String, Value)>;
+
+/// The options at the top of the config file (defaults for every run)
+/// and those of each `[presets.NAME]` table.
+#[derive(Debug, Default)]
+struct Config {
+    defaults: Entries,
+    presets: Vec<(String, Entries)>,
+}
+
+/// Read the config file and turn it into command line arguments: the
+/// defaults, then the options of the preset (which win over the defaults),
+/// skipping those that `args` already has (they win over both).
+pub fn config_args(path: &Path, args: &[OsString], preset: Option<&str>) -> Result<Vec<OsString>> {
+    let content = std::fs::read_to_string(path).map_err(|e| {
+        anyhow!(
+            "runtime error. Cannot read the config file {}: {}",
+            path.display(),
+            e
+        )
+    })?;
+
+    let Config {
+        mut defaults,
+        mut presets,
+    } = parse_config(&content)
+        .map_err(|e| anyhow!("runtime error. Config file {}, {}", path.display(), e))?;
+
+    let mut entries = Vec::new();
+    if let Some(name) = preset {
+        let idx = presets.iter().position(|(n, _)| n == name).ok_or_else(|| {
+            anyhow!(
+                "runtime error. Preset {} not found in {}",
+                name,
+                path.display()
+            )
+        })?;
+        entries = presets.swap_remove(idx).1;
+        defaults.retain(|(key, _)| entries.iter().all(|(k, _)| k != key));
+    }
+    entries.splice(0..0, defaults);
+
+    let mode = mode_of(args).unwrap_or_else(|| {
+        MODES
+            .iter()
+            .find(|(key, _)| entries.iter().any(|(k, _)| k == key))
+            .map_or(Mode::Fields, |&(_, mode)| mode)
+    });
+
+    let mut config_args = Vec::new();
+    for (key, value) in entries {
+        if !flags_of(&key).iter().any(|flag| has_flag(args, flag)) && applies_to(&key, mode) {
+            config_args.extend(to_args(&key, value));
+        }
+    }
+
+    Ok(config_args)
+}
+
+fn parse_config(content: &str) -> Result<Config> {
+    let mut config = Config::default();
+
+    for (idx, line) in content.lines().enumerate() {
+        let line = line.trim();
+        let in_preset = !config.presets.is_empty();
+        let error = |e: anyhow::Error| anyhow!("line {}: {}", idx + 1, e);
+
+        if let Some(table) = line.strip_prefix('[') {
+            let name = table
+                .split('#')
+                .next()
+                .and_then(|table| table.trim_end().strip_suffix(']'))
+                .and_then(|table| table.trim().strip_prefix("presets."))
+                .filter(|name| !name.is_empty())
+                .ok_or_else(|| error(anyhow!("expected [presets.NAME]")))?;
+            config.presets.push((name.to_owned(), Vec::new()));
+        } else if let Some((key, value)) = parse_line(line).map_err(error)? {
+            check_entry(&key, &value, in_preset).map_err(error)?;
+            match config.presets.last_mut() {
+                Some((_, entries)) => entries.push((key, value)),
+                None => config.defaults.push((key, value)),
+            }
+        }
+    }
+
+    Ok(config)
+}
+
+/// The mode selected by the command line arguments, if any
+pub(crate) fn mode_of(args: &[OsString]) -> Option<Mode> {
+    MODES
+        .iter()
+        .find(|(key, _)| flags_of(key).iter().any(|flag| has_flag(args, flag)))
+        .map(|&(_, mode)| mode)
+}
+
+/// Whether the option of the config can be used in `mode`.
+/// Those that the config cannot have as defaults are left to the user.
+fn applies_to(key: &str, mode: Mode) -> bool {
+    match CONFIG_KEYS.iter().find(|(k, ..)| *k == key) {
+        Some((.., modes)) => modes.contains(&mode),
+        None => true,
+    }
+}
+
+/// The key of the config that a command line argument sets or unsets
+/// (e.g. `join` for `--no-join`)
+fn key_of_flag(flag: &str) -> Option<&'static str> {
+    CONFIG_KEYS
+        .iter()
+        .find(|(_, flags, ..)| flags.contains(&flag))
+        .map(|(key, ..)| *key)
+}
+
+/// Whether the command line sets the option of `flag` again, or unsets it
+/// (e.g. `--no-join` for `-j`)
+pub(crate) fn is_overridden(args: &[OsString], flag: &str) -> bool {
+    match key_of_flag(flag) {
+        Some(key) => flags_of(key).iter().any(|flag| has_flag(args, flag)),
+        None => has_flag(args, flag),
+    }
+}
+
+/// Whether the option of `flag` can be used in `mode`, as for the config
+pub(crate) fn flag_applies_to(flag: &str, mode: Mode) -> bool {
+    match key_of_flag(flag) {
+        Some(key) => applies_to(key, mode),
+        None => true,
+    }
+}
+
+/// The command line arguments that set the option
+fn flags_of(key: &str) -> Vec<String> {
+    match CONFIG_KEYS.iter().find(|(k, ..)| *k == key) {
+        Some((_, flags, ..)) => flags.iter().map(|flag| flag.to_string()).collect(),
+        None => SHORT_FLAGS
+            .iter()
+            .filter(|(k, _)| *k == key)
+            .map(|(_, flag)| flag.to_string())
+            .chain([format!("--{}", key)])
+            .collect(),
+    }
+}
+
+fn to_args(key: &str, value: Value) -> Vec<OsString> {
+    let flag = OsString::from(format!("--{}", key));
+    match (key, value) {
+        ("eol", Value::String(eol)) if eol == "zero" => vec!["--zero-terminated".into()],
+        ("eol", _) => Vec::new(),
+        (_, Value::Bool(true)) => vec![flag],
+        (_, Value::String(value)) => vec![flag, value.into()],
+        _ => Vec::new(),
+    }
+}
+
+/// Whether the argument is on the command line, either alone, with its
+/// value attached (`--delimiter=,` or `-d,`) or among other short flags (`-jz`)
+pub(crate) fn has_flag(args: &[OsString], flag: &str) -> bool {
+    args.iter()
+        .map(|arg| arg.to_string_lossy())
+        .take_while(|arg| arg != "--")
+        .any(|arg| {
+            if flag.starts_with("--") {
+                arg == flag || arg.starts_with(&format!("{}=", flag))
+            } else {
+                arg.starts_with(flag)
+                    || (!arg.starts_with("--")
+                        && arg.starts_with('-')
+                        && arg[1..].chars().all(|c| c.is_ascii_alphabetic())
+                        && arg.contains(&flag[1..]))
+            }
+        })
+}
+
+/// Parse a line of the config file, a (small) subset of TOML:
+/// `key = "string"`, `key = 'literal string'`, `key = 123` or `key = true|false`
+fn parse_line(line: &str) -> Result<Option<(String, Value)>> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+        return Ok(None);
+    }
+
+    let (key, value) = line
+        .split_once('=')
+        .ok_or_else(|| anyhow!("expected key = value"))?;
+    let key = key.trim().replace('_', "-");
+    let value = value.trim();
+
+    let (value, rest) = if let Some(text) = value.strip_prefix('"') {
+        parse_basic_string(text)?
+    } else if let Some(text) = value.strip_prefix('\'') {
+        let end = text
+            .find('\'')
+            .ok_or_else(|| anyhow!("unterminated string"))?;
+        (Value::String(text[..end].to_owned()), &text[end + 1..])
+    } else {
+        let end = value.find('#').unwrap_or(value.len());
+        match value[..end].trim_end() {
+            "true" => (Value::Bool(true), ""),
+            "false" => (Value::Bool(false), ""),
+            number if number.parse::<i64>().is_ok() => (Value::String(number.to_owned()), ""),
+            other => bail!("unsupported value {}", other),
+        }
+    };
+
+    let rest = rest.trim_start();
+    if !rest.is_empty() && !rest.starts_with('#') {
+        bail!("unexpected text after the value: {}", rest);
+    }
+
+    Ok(Some((key, value)))
+}
+
+/// The defaults are limited to a few options, presets can have any
+fn check_entry(key: &str, value: &Value, in_preset: bool) -> Result<()> {
+    let is_flag = match CONFIG_KEYS.iter().find(|(k, ..)| *k == key) {
+        Some(&(_, _, is_flag, _)) => is_flag,
+        None if in_preset => return Ok(()),
+        None => bail!("unknown key {}", key),
+    };
+
+    match (value, is_flag) {
+        (Value::Bool(_), true) | (Value::String(_), false) => (),
+        (_, true) => bail!("{} must be true or false", key),
+        (_, false) => bail!("{} must be a string", key),
+    }
+
+    if let Value::String(eol) = value {
+        if key == "eol" && eol != "newline" && eol != "zero" {
+            bail!("eol must be \"newline\" or \"zero\"");
+        }
+    }
+
+    Ok(())
+}
+
+/// Parse the rest of a double quoted string, returning the text after it
+fn parse_basic_string(text: &str) -> Result<(Value, &str)> {
+    let mut value = String::new();
+    let mut chars = text.char_indices();
+
+    while let Some((idx, c)) = chars.next() {
+        match c {
+            '"' => return Ok((Value::String(value), &text[idx + 1..])),
+            '\\' => value.push(match chars.next().map(|(_, c)| c) {
+                Some('t') => '\t',
+                Some('n') => '\n',
+                Some('0') => '\0',
+                Some('"') => '"',
+                Some('\\') => '\\',
+                _ => bail!("unsupported escape sequence"),
+            }),
+            c => value.push(c),
+        }
+    }
+
+    bail!("unterminated string")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_parses_the_config_lines() {
+        assert_eq!(parse_line("  # comment").unwrap(), None);
+        assert_eq!(
+            parse_line(r#"delimiter = "\t" # tabs"#).unwrap(),
+            Some(("delimiter".into(), Value::String("\t".into())))
+        );
+        assert_eq!(
+            parse_line("replace_delimiter = '\\t'").unwrap(),
+            Some(("replace-delimiter".into(), Value::String("\\t".into())))
+        );
+        assert_eq!(
+            parse_line("json = true").unwrap(),
+            Some(("json".into(), Value::Bool(true)))
+        );
+        assert_eq!(
+            parse_line("skip = 1").unwrap(),
+            Some(("skip".into(), Value::String("1".into())))
+        );
+
+        assert!(parse_line("json = yes").is_err());
+        assert!(parse_line("delimiter = \",").is_err());
+        assert!(parse_line("delimiter = \",\" ,").is_err());
+    }
+
+    #[test]
+    fn it_parses_the_defaults_and_the_presets() {
+        let config = parse_config(
+            "delimiter = ','\n\n[presets.passwd] # users\ndelimiter = ':'\nfields = '1,7'\n",
+        )
+        .unwrap();
+        assert_eq!(
+            config.defaults,
+            [("delimiter".into(), Value::String(",".into()))]
+        );
+        assert_eq!(config.presets.len(), 1);
+        assert_eq!(config.presets[0].0, "passwd");
+        assert_eq!(config.presets[0].1.len(), 2);
+
+        let error = |content: &str| parse_config(content).unwrap_err().to_string();
+        assert_eq!(error("json = 1"), "line 1: json must be true or false");
+        assert_eq!(
+            error("delimiter = true"),
+            "line 1: delimiter must be a string"
+        );
+        assert_eq!(error("fields = \"1\""), "line 1: unknown key fields");
+        assert_eq!(
+            error("eol = \"crlf\""),
+            "line 1: eol must be \"newline\" or \"zero\""
+        );
+        assert_eq!(error("\n[section]"), "line 2: expected [presets.NAME]");
+    }
+
+    #[test]
+    fn it_lets_the_command_line_win() {
+        let args = |args: &[&str]| args.iter().map(OsString::from).collect::<Vec<_>>();
+
+        assert!(has_flag(&args(&["-d", ","]), "-d"));
+        assert!(has_flag(&args(&["-d,"]), "-d"));
+        assert!(has_flag(&args(&["--delimiter=,"]), "--delimiter"));
+        assert!(has_flag(&args(&["-jz"]), "-z"));
+        assert!(!has_flag(&args(&["-f", "1", "--", "-d"]), "-d"));
+        assert!(!has_flag(&args(&["--json-input"]), "--json"));
+        assert!(!has_flag(&args(&["--delimiters"]), "--delimiter"));
+
+        assert_eq!(flags_of("fields"), ["-f", "--fields"]);
+        assert_eq!(flags_of("skip"), ["--skip"]);
+
+        assert_eq!(
+            to_args("eol", Value::String("zero".into())),
+            ["--zero-terminated"]
+        );
+        assert!(to_args("eol", Value::String("newline".into())).is_empty());
+        assert!(to_args("json", Value::Bool(false)).is_empty());
+        assert_eq!(to_args("skip", Value::String("1".into())), ["--skip", "1"]);
+    }
+
+    #[test]
+    fn it_applies_the_defaults_of_the_mode() {
+        let args = |args: &[&str]| args.iter().map(OsString::from).collect::<Vec<_>>();
+
+        assert_eq!(mode_of(&args(&["-d", ","])), None);
+        assert_eq!(mode_of(&args(&["-b", "1:2"])), Some(Mode::Bytes));
+        assert_eq!(mode_of(&args(&["--width=1"])), Some(Mode::Width));
+        assert_eq!(mode_of(&args(&["-c", "1", "-f", "2"])), Some(Mode::Fields));
+
+        assert!(applies_to("delimiter", Mode::Fields));
+        assert!(!applies_to("delimiter", Mode::Bytes));
+        assert!(!applies_to("json", Mode::Lines));
+        assert!(applies_to("join", Mode::Lines));
+        assert!(applies_to("eol", Mode::Width));
+        assert!(applies_to("skip", Mode::Bytes));
+    }
+}
@@ -1,22 +1,78 @@
-use anyhow::Result;
-use std::io::{Read, Write};
+use anyhow::{bail, Result};
+use bstr::io::BufReadExt;
+use std::io::{BufRead, Write};
+use std::ops::Range;
 
+use crate::bounds::userboundslist::env_value;
 use crate::bounds::{BoundOrFiller, UserBoundsTrait};
-use crate::options::Opt;
+use crate::error::TucError;
+use crate::options::{Opt, EOL};
 use crate::read_utils::read_bytes_to_end;
 
+fn is_utf8_continuation_byte(b: u8) -> bool {
+    b & 0b1100_0000 == 0b1000_0000
+}
+
+/// Widen a range so that it doesn't split UTF-8 characters: the start moves
+/// back to the beginning of its character and the end moves forward to the
+/// end of its character, so that a character partly in the range is printed whole.
+fn snap_to_char_boundaries(data: &[u8], r: Range<usize>) -> Range<usize> {
+    let mut start = r.start;
+    while start > 0 && is_utf8_continuation_byte(data[start]) {
+        start -= 1;
+    }
+
+    let mut end = r.end;
+    while end < data.len() && is_utf8_continuation_byte(data[end]) {
+        end += 1;
+    }
+
+    start..end
+}
+
 fn cut_bytes<W: Write>(data: &[u8], opt: &Opt, stdout: &mut W) -> Result<()> {
     if data.is_empty() {
         return Ok(());
     }
 
     opt.bounds.iter().try_for_each(|bof| -> Result<()> {
+        let value;
         let output = match bof {
             BoundOrFiller::Bound(b) => {
-                let r = b.try_into_range(data.len())?;
-                &data[r.start..r.end]
+                match b.try_into_range(data.len()) {
+                    Ok(r) => {
+                        let r = if opt.no_split_codepoints {
+                            snap_to_char_boundaries(data, r)
+                        } else {
+                            r
+                        };
+                        stdout.write_all(&data[r.start..r.end])?;
+                    }
+                    Err(e) => match (&b.fallback_oob, &opt.fallback_oob) {
+                        (Some(fallback), _) => stdout.write_all(fallback)?,
+                        (None, Some(generic_fallback)) => stdout.write_all(generic_fallback)?,
+                        (None, None) => return Err(e.into()),
+                    },
+                }
+
+                if opt.join && !b.is_last {
+                    stdout.write_all(opt.replace_delimiter.as_ref().unwrap_or(&opt.delimiter))?;
+                }
+
+                return Ok(());
             }
             BoundOrFiller::Filler(f) => f,
+            BoundOrFiller::WholeLine => data,
+            BoundOrFiller::Env(name) => {
+                value = env_value(name)?;
+                &value
+            }
+            BoundOrFiller::Capture(_) => {
+                bail!("Named placeholders are not supported when cutting bytes")
+            }
+            BoundOrFiller::Conditional { .. } => {
+                bail!("Conditional formatting is not supported when cutting bytes")
+            }
         };
 
         stdout.write_all(output)?;
@@ -27,13 +83,161 @@ fn cut_bytes<W: Write>(data: &[u8], opt: &Opt, stdout: &mut W) -> Result<()> {
     Ok(())
 }
 
-pub fn read_and_cut_bytes<R: Read, W: Write>(
+fn cut_bytes_line<W: Write>(
+    line: &[u8],
+    line_number: usize,
+    opt: &Opt,
+    stdout: &mut W,
+) -> std::io::Result<bool> {
+    cut_bytes(line, opt, stdout)
+        .map_err(|x| std::io::Error::other(TucError::from(x).at_line(line_number)))?;
+    stdout.write_all(&[opt.eol.into()])?;
+    Ok(true)
+}
+
+/// Cut the bytes of every line, or of the whole input when `opt.whole_stream`
+/// is set (in that case no end of line is added to the output).
+pub fn read_and_cut_bytes<R: BufRead, W: Write>(
     stdin: &mut R,
     stdout: &mut W,
     opt: &Opt,
 ) -> Result<()> {
-    let mut buffer: Vec<u8> = Vec::with_capacity(32 * 1024);
-    read_bytes_to_end(stdin, &mut buffer);
-    cut_bytes(&buffer, opt, stdout)?;
+    if opt.whole_stream {
+        let mut buffer: Vec<u8> = Vec::with_capacity(32 * 1024);
+        read_bytes_to_end(stdin, &mut buffer);
+        cut_bytes(&buffer, opt, stdout)?;
+        return Ok(());
+    }
+
+    let mut line_number = opt.preamble_lines();
+    let mut cut_line = |line: &[u8]| {
+        line_number += 1;
+        cut_bytes_line(line, line_number, opt, stdout)
+    };
+
+    match opt.eol {
+        EOL::Newline => stdin.for_byte_line(&mut cut_line)?,
+        EOL::Zero => stdin.for_byte_record(opt.eol.into(), &mut cut_line)?,
+    }
+
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use crate::bounds::{BoundsType, UserBoundsList};
+
+    use super::*;
+
+    fn make_bytes_opt(bounds: &str) -> Opt {
+        Opt {
+            bounds_type: BoundsType::Bytes,
+            bounds: UserBoundsList::from_str(bounds).unwrap(),
+            ..Opt::default()
+        }
+    }
+
+    #[test]
+    fn it_cut_bytes_of_every_line() {
+        let opt = make_bytes_opt("1:2");
+
+        let mut input = b"abc\n\ndef".as_slice();
+        let mut output = Vec::new();
+        read_and_cut_bytes(&mut input, &mut output, &opt).unwrap();
+        assert_eq!(output, b"ab\n\nde\n");
+    }
+
+    #[test]
+    fn it_cut_bytes_of_zero_terminated_lines() {
+        let mut opt = make_bytes_opt("-1");
+        opt.eol = EOL::Zero;
+
+        let mut input = b"abc\0def".as_slice();
+        let mut output = Vec::new();
+        read_and_cut_bytes(&mut input, &mut output, &opt).unwrap();
+        assert_eq!(output, b"c\0f\0");
+    }
+
+    #[test]
+    fn it_does_not_split_codepoints_when_requested() {
+        let data = "über".as_bytes();
+        assert_eq!(snap_to_char_boundaries(data, 0..1), 0..2);
+        assert_eq!(snap_to_char_boundaries(data, 1..3), 0..3);
+        assert_eq!(snap_to_char_boundaries(data, 2..5), 2..5);
+
+        // Both ends take the whole character they fall in
+        let data = "aé".as_bytes();
+        assert_eq!(snap_to_char_boundaries(data, 1..2), 1..3);
+        assert_eq!(snap_to_char_boundaries(data, 2..3), 1..3);
+
+        let mut opt = make_bytes_opt("2:4,1");
+        opt.no_split_codepoints = true;
+
+        let mut input = "über\n😁".as_bytes();
+        let mut output = Vec::new();
+        read_and_cut_bytes(&mut input, &mut output, &opt).unwrap();
+        assert_eq!(output, "übeü\n😁😁\n".as_bytes());
+    }
+
+    #[test]
+    fn it_join_bytes_ranges() {
+        let mut opt = make_bytes_opt("1:2,5,-1");
+        opt.join = true;
+        opt.replace_delimiter = Some(b", ".to_vec());
+
+        let mut input = b"abcdefg\n1234567".as_slice();
+        let mut output = Vec::new();
+        read_and_cut_bytes(&mut input, &mut output, &opt).unwrap();
+        assert_eq!(output, b"ab, e, g\n12, 5, 7\n");
+    }
+
+    #[test]
+    fn it_cut_bytes_from_the_end() {
+        let mut input = b"abcdefg\n1234567".as_slice();
+        let mut output = Vec::new();
+        read_and_cut_bytes(&mut input, &mut output, &make_bytes_opt("-5:,-3:-2")).unwrap();
+        assert_eq!(output, b"cdefgef\n3456756\n");
+
+        let mut opt = make_bytes_opt("-3:");
+        opt.whole_stream = true;
+
+        let mut input = b"abc\ndef\n".as_slice();
+        let mut output = Vec::new();
+        read_and_cut_bytes(&mut input, &mut output, &opt).unwrap();
+        assert_eq!(output, b"ef\n");
+    }
+
+    #[test]
+    fn it_prints_the_fallback_when_out_of_bounds() {
+        let mut opt = make_bytes_opt("1,100,5=none");
+        opt.fallback_oob = Some(b"-".to_vec());
+
+        let mut input = b"abcdef\nab".as_slice();
+        let mut output = Vec::new();
+        read_and_cut_bytes(&mut input, &mut output, &opt).unwrap();
+        assert_eq!(output, b"a-e\na-none\n");
+    }
+
+    #[test]
+    fn it_formats_the_bytes() {
+        let opt = make_bytes_opt("{1:4}-{9:12}");
+
+        let mut input = b"abcdefghijkl\n123456789012".as_slice();
+        let mut output = Vec::new();
+        read_and_cut_bytes(&mut input, &mut output, &opt).unwrap();
+        assert_eq!(output, b"abcd-ijkl\n1234-9012\n");
+    }
+
+    #[test]
+    fn it_cut_bytes_of_the_whole_stream() {
+        let mut opt = make_bytes_opt("3:5");
+        opt.whole_stream = true;
+
+        let mut input = b"abc\ndef".as_slice();
+        let mut output = Vec::new();
+        read_and_cut_bytes(&mut input, &mut output, &opt).unwrap();
+        assert_eq!(output, b"c\nd");
+    }
+}
@@ -1,7 +1,11 @@
 use anyhow::{bail, Result};
+use std::collections::VecDeque;
 use std::io::{BufRead, Write};
 use std::ops::Range;
 
+use crate::bounds::userboundslist::env_value;
+#[cfg(feature = "regex")]
+use crate::bounds::LinePattern;
 use crate::bounds::{BoundOrFiller, Side, UserBoundsTrait};
 use crate::cut_str::cut_str;
 use crate::options::Opt;
@@ -17,12 +21,16 @@ fn cut_lines_forward_only<A: BufRead, B: Write>(
     let mut bounds_idx = 0; // keep track of which bounds have been used
     let mut add_newline_next = false;
     while let Some(line) = read_line_with_eol(stdin, &mut line_buf, opt.eol) {
-        line_idx += 1;
-
         let line = line?;
         let line: &str = line.as_ref();
         let line = line.strip_suffix(opt.eol as u8 as char).unwrap_or(line);
 
+        if opt.skip_empty && line.is_empty() {
+            continue;
+        }
+
+        line_idx += 1;
+
         // Print the matching fields. Fields are ordered but can still be
         // duplicated, e.g. 1-2,2,3 , so we may have to print the same
         // line multiple times
@@ -40,7 +48,23 @@ fn cut_lines_forward_only<A: BufRead, B: Write>(
 
                     continue;
                 }
+                BoundOrFiller::Env(name) => {
+                    stdout.write_all(&env_value(name)?)?;
+                    bounds_idx += 1;
+
+                    if opt.join && bounds_idx != opt.bounds.len() {
+                        stdout.write_all(&[opt.eol as u8])?;
+                    }
+
+                    continue;
+                }
                 BoundOrFiller::Bound(b) => b,
+                BoundOrFiller::Capture(_) | BoundOrFiller::WholeLine => {
+                    bail!("Named placeholders are not supported when cutting lines")
+                }
+                BoundOrFiller::Conditional { .. } => {
+                    bail!("Conditional formatting is not supported when cutting lines")
+                }
             };
 
             if b.matches(line_idx).unwrap_or(false) {
@@ -91,6 +115,54 @@ fn cut_lines<A: BufRead, B: Write>(stdin: &mut A, stdout: &mut B, opt: &Opt) ->
     let mut buffer: Vec<u8> = Vec::with_capacity(32 * 1024);
     stdin.read_to_end(&mut buffer)?;
     let buffer_as_str = std::str::from_utf8(&buffer)?;
+
+    cut_buffered_lines(buffer_as_str, stdout, opt)
+}
+
+/// Keep in memory only the last `count` lines (like `tail -n`), enough
+/// to cut bounds that use solely negative indices (e.g. -100:).
+///
+/// Memory is bounded by the lines kept, not by the size of the input:
+/// at most `count` lines while reading (their allocations are reused),
+/// then about twice as much once they're joined to be cut. A line is
+/// still read whole, so a single long line is held entirely.
+fn cut_lines_from_the_end<A: BufRead, B: Write>(
+    stdin: &mut A,
+    stdout: &mut B,
+    opt: &Opt,
+    count: usize,
+) -> Result<()> {
+    let mut line_buf = String::with_capacity(1024);
+    // not preallocated, count comes from the bounds (e.g. -2000000000:)
+    let mut tail: VecDeque<String> = VecDeque::new();
+
+    while let Some(line) = read_line_with_eol(stdin, &mut line_buf, opt.eol) {
+        let line = line?;
+        let line = line.strip_suffix(opt.eol as u8 as char).unwrap_or(line);
+
+        if opt.skip_empty && line.is_empty() {
+            continue;
+        }
+
+        // reuse the allocation of the line that falls out of the tail
+        let mut kept = if tail.len() == count {
+            tail.pop_front().unwrap_or_default()
+        } else {
+            String::new()
+        };
+        kept.clear();
+        kept.push_str(line);
+        tail.push_back(kept);
+    }
+
+    // every line had its end of line, as far as cut_buffered_lines knows
+    let eol = (opt.eol as u8 as char).to_string();
+    let buffer = Vec::from(tail).join(&eol) + &eol;
+
+    cut_buffered_lines(&buffer, stdout, opt)
+}
+
+fn cut_buffered_lines<B: Write>(buffer_as_str: &str, stdout: &mut B, opt: &Opt) -> Result<()> {
     let mut bounds_as_ranges: Vec<Range<usize>> = Vec::with_capacity(100);
     let mut compressed_line_buf = Vec::new();
 
@@ -98,6 +170,19 @@ fn cut_lines<A: BufRead, B: Write>(stdin: &mut A, stdout: &mut B, opt: &Opt) ->
         .strip_suffix(opt.eol as u8 as char)
         .unwrap_or(buffer_as_str);
 
+    let non_empty_lines: String;
+    let buffer_as_str = if opt.skip_empty {
+        let eol = opt.eol as u8 as char;
+        non_empty_lines = buffer_as_str
+            .split(eol)
+            .filter(|line| !line.is_empty())
+            .collect::<Vec<_>>()
+            .join(&eol.to_string());
+        &non_empty_lines
+    } else {
+        buffer_as_str
+    };
+
     // Just use cut_str, we're cutting a (big) string whose delimiter is newline
     cut_str(
         buffer_as_str.as_bytes(),
@@ -106,7 +191,80 @@ fn cut_lines<A: BufRead, B: Write>(stdin: &mut A, stdout: &mut B, opt: &Opt) ->
         &mut bounds_as_ranges,
         &mut compressed_line_buf,
         &[opt.eol as u8],
-    )
+        1,
+    )?;
+
+    Ok(())
+}
+
+/// How many lines from the end are enough to cut the bounds, if they all
+/// use negative indices (fillers aside).
+fn tail_length(opt: &Opt) -> Option<usize> {
+    let mut count = 0;
+
+    for bof in opt.bounds.iter() {
+        match bof {
+            BoundOrFiller::Filler(_) | BoundOrFiller::Env(_) => (),
+            BoundOrFiller::Bound(b) => {
+                let farthest = match (b.l, b.r) {
+                    (Side::Some(l), Side::Some(r)) if l < 0 && r < 0 => l.min(r),
+                    (Side::Some(l), Side::Continue) if l < 0 => l,
+                    _ => return None,
+                };
+                count = count.max(farthest.unsigned_abs() as usize);
+            }
+            _ => return None,
+        }
+    }
+
+    Some(count).filter(|&count| count > 0)
+}
+
+/// Print the lines between those that match the patterns (both included),
+/// checking the lines as they're read.
+#[cfg(feature = "regex")]
+fn cut_lines_between_patterns<A: BufRead, B: Write>(
+    stdin: &mut A,
+    stdout: &mut B,
+    opt: &Opt,
+    pattern: &LinePattern,
+) -> Result<()> {
+    let mut line_buf = String::with_capacity(1024);
+    let mut within = pattern.start.is_none();
+
+    while let Some(line) = read_line_with_eol(stdin, &mut line_buf, opt.eol) {
+        let line = line?;
+        let line = line.strip_suffix(opt.eol as u8 as char).unwrap_or(line);
+
+        if opt.skip_empty && line.is_empty() {
+            continue;
+        }
+
+        let is_start = !within
+            && pattern
+                .start
+                .as_ref()
+                .is_some_and(|start| start.is_match(line.as_bytes()));
+
+        if within || is_start {
+            stdout.write_all(line.as_bytes())?;
+            stdout.write_all(&[opt.eol as u8])?;
+        }
+
+        // The end is searched after the line that started the range
+        if within
+            && pattern
+                .end
+                .as_ref()
+                .is_some_and(|end| end.is_match(line.as_bytes()))
+        {
+            within = false;
+        } else if is_start {
+            within = true;
+        }
+    }
+
+    Ok(())
 }
 
 pub fn read_and_cut_lines<A: BufRead, B: Write>(
@@ -116,12 +274,31 @@ pub fn read_and_cut_lines<A: BufRead, B: Write>(
 ) -> Result<()> {
     // If bounds cut from left to right and do not internally overlap
     // (e.g. 1:2,2,4:5,8) then we can use a streaming algorithm and avoid
-    // allocating everything in memory.
-    let can_be_streamed =
-        { !opt.complement && !opt.compress_delimiter && opt.bounds.is_forward_only() };
+    // allocating everything in memory. Trimming the empty lines at the end
+    // of the input requires to read it all.
+    let can_be_streamed = {
+        !opt.complement
+            && !opt.compress_delimiter
+            && opt.trim.is_none()
+            && opt.bounds.is_forward_only()
+    };
+
+    // Bounds counting from the end (e.g. -100:) need only the last lines
+    let tail_length = if opt.complement || opt.compress_delimiter || opt.trim.is_some() {
+        None
+    } else {
+        tail_length(opt)
+    };
+
+    #[cfg(feature = "regex")]
+    if let Some(pattern) = &opt.line_pattern {
+        return cut_lines_between_patterns(stdin, stdout, opt, pattern);
+    }
 
     if can_be_streamed {
         cut_lines_forward_only(stdin, stdout, opt)?;
+    } else if let Some(count) = tail_length {
+        cut_lines_from_the_end(stdin, stdout, opt, count)?;
     } else {
         cut_lines(stdin, stdout, opt)?;
     }
@@ -135,7 +312,7 @@ mod tests {
 
     use crate::{
         bounds::{BoundsType, UserBoundsList},
-        options::EOL,
+        options::{Trim, EOL},
     };
 
     use super::*;
@@ -307,4 +484,123 @@ mod tests {
         cut_lines(&mut input, &mut output, &opt).unwrap();
         assert_eq!(output, b"a\0");
     }
+
+    #[test]
+    fn it_trims_empty_lines() {
+        let mut opt = make_lines_opt();
+        opt.bounds = UserBoundsList::from_str("1,-1").unwrap();
+
+        opt.trim = Some(Trim::Both);
+        let mut input = b"\n\na\nb\n\nc\n\n\n".as_slice();
+        let mut output = Vec::with_capacity(100);
+        read_and_cut_lines(&mut input, &mut output, &opt).unwrap();
+        assert_eq!(output, b"a\nc\n");
+
+        opt.trim = Some(Trim::Left);
+        let mut input = b"\n\na\nb\n\n".as_slice();
+        let mut output = Vec::with_capacity(100);
+        read_and_cut_lines(&mut input, &mut output, &opt).unwrap();
+        assert_eq!(output, b"a\n\n");
+
+        opt.trim = Some(Trim::Right);
+        opt.bounds = UserBoundsList::from_str("1").unwrap();
+        let mut input = b"\na\n\n".as_slice();
+        let mut output = Vec::with_capacity(100);
+        read_and_cut_lines(&mut input, &mut output, &opt).unwrap();
+        assert_eq!(output, b"\n");
+    }
+
+    #[test]
+    fn it_skips_empty_lines() {
+        let mut opt = make_lines_opt();
+        opt.skip_empty = true;
+
+        // streaming
+        opt.bounds = UserBoundsList::from_str("1,3").unwrap();
+        let mut input = b"\na\n\nb\nc\n".as_slice();
+        let mut output = Vec::with_capacity(100);
+        read_and_cut_lines(&mut input, &mut output, &opt).unwrap();
+        assert_eq!(output, b"a\nc\n");
+
+        // whole input in memory
+        opt.bounds = UserBoundsList::from_str("-1,1").unwrap();
+        let mut input = b"\na\n\nb\nc\n\n".as_slice();
+        let mut output = Vec::with_capacity(100);
+        read_and_cut_lines(&mut input, &mut output, &opt).unwrap();
+        assert_eq!(output, b"c\na\n");
+    }
+
+    #[test]
+    fn it_stops_reading_after_the_last_wanted_line() {
+        struct Endless;
+
+        impl std::io::Read for Endless {
+            fn read(&mut self, _buf: &mut [u8]) -> std::io::Result<usize> {
+                Err(std::io::Error::other("read past the last wanted line"))
+            }
+        }
+
+        let mut opt = make_lines_opt();
+        opt.bounds = UserBoundsList::from_str("2:3").unwrap();
+
+        let mut input = std::io::BufReader::new(std::io::Read::chain(&b"a\nb\nc\n"[..], Endless));
+        let mut output = Vec::with_capacity(100);
+        read_and_cut_lines(&mut input, &mut output, &opt).unwrap();
+        assert_eq!(output, b"b\nc\n");
+    }
+
+    #[test]
+    fn it_keeps_only_the_last_lines_for_negative_bounds() {
+        let mut opt = make_lines_opt();
+        opt.bounds = UserBoundsList::from_str("-2:,-4").unwrap();
+        assert_eq!(tail_length(&opt), Some(4));
+
+        for input in ["a\nb\nc\nd\ne\n", "a\nb\nc\nd\ne", "a\nb\nc\nd\n\n"] {
+            let mut tail_output = Vec::new();
+            cut_lines_from_the_end(&mut input.as_bytes(), &mut tail_output, &opt, 4).unwrap();
+            let mut output = Vec::new();
+            cut_lines(&mut input.as_bytes(), &mut output, &opt).unwrap();
+            assert_eq!(tail_output, output);
+        }
+
+        let mut input = b"a\nb\n".as_slice();
+        let mut output = Vec::new();
+        assert!(cut_lines_from_the_end(&mut input, &mut output, &opt, 4).is_err());
+
+        // a huge bound is out of bounds, without allocating the whole tail first
+        opt.bounds = UserBoundsList::from_str("-2000000000:").unwrap();
+        let mut input = b"a\nb\nc\n".as_slice();
+        let mut output = Vec::new();
+        assert_eq!(
+            read_and_cut_lines(&mut input, &mut output, &opt)
+                .unwrap_err()
+                .to_string(),
+            "Out of bounds: -2000000000"
+        );
+
+        opt.bounds = UserBoundsList::from_str("-2:,1").unwrap();
+        assert_eq!(tail_length(&opt), None);
+    }
+
+    #[cfg(feature = "regex")]
+    #[test]
+    fn it_cuts_the_lines_between_patterns() {
+        let cut = |pattern: &str, input: &[u8]| {
+            let mut opt = make_lines_opt();
+            opt.line_pattern = Some(LinePattern::from_str(pattern).unwrap());
+            let mut output = Vec::new();
+            read_and_cut_lines(&mut &input[..], &mut output, &opt).unwrap();
+            output
+        };
+
+        let input = b"a\nBEGIN 1\nb\nEND\nc\nBEGIN 2\nEND\nd\n";
+        assert_eq!(
+            cut("/^BEGIN/:/^END/", input),
+            b"BEGIN 1\nb\nEND\nBEGIN 2\nEND\n"
+        );
+        assert_eq!(cut("/2$/:", input), b"BEGIN 2\nEND\nd\n");
+        assert_eq!(cut(":/^b/", input), b"a\nBEGIN 1\nb\n");
+        // the line that starts the range cannot end it
+        assert_eq!(cut("/^c/:/c/", b"c\nc\nd\n"), b"c\nc\n");
+    }
 }
@@ -0,0 +1,159 @@
+use anyhow::{bail, Result};
+use std::io::{BufRead, Read};
+
+use crate::bounds::BoundsType;
+use crate::cut_str::RecordCutter;
+use crate::error::{Diagnostic, TucError};
+use crate::options::{Opt, EOL};
+
+/// Reader that yields the cut output of another reader, one record at a
+/// time, so that tuc can be used inside a streaming pipeline.
+/// Only fields and characters are supported.
+///
+/// ```rust
+/// # use std::io::Read;
+/// # use tuc::cut_reader::CutReader;
+/// # use tuc::options::Opt;
+/// let opt = Opt::try_from_args(["-d", ",", "-f", "2"].map(Into::into)).unwrap();
+/// let mut reader = CutReader::new(&b"a,b\nc,d\n"[..], opt).unwrap();
+///
+/// let mut output = String::new();
+/// reader.read_to_string(&mut output).unwrap();
+/// assert_eq!(output, "b\nd\n");
+/// ```
+pub struct CutReader<R> {
+    inner: R,
+    eol: EOL,
+    cutter: RecordCutter,
+    record: Vec<u8>,
+    /// Output of the last record, served from `pos` onward
+    output: Vec<u8>,
+    pos: usize,
+    finished: bool,
+}
+
+impl<R: BufRead> CutReader<R> {
+    pub fn new(inner: R, opt: Opt) -> Result<Self> {
+        if !matches!(opt.bounds_type, BoundsType::Fields | BoundsType::Characters) {
+            bail!("CutReader supports only fields and characters");
+        }
+
+        Ok(CutReader {
+            inner,
+            eol: opt.eol,
+            cutter: RecordCutter::new(opt),
+            record: Vec::new(),
+            output: Vec::new(),
+            pos: 0,
+            finished: false,
+        })
+    }
+
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    /// The warnings and errors of the records skipped so far (e.g. by
+    /// --lenient) and, at the end of the input, the summary of --report.
+    pub fn diagnostics(&mut self) -> impl Iterator<Item = Diagnostic> + '_ {
+        self.cutter.take_diagnostics()
+    }
+
+    /// Cut the next record into the output buffer.
+    /// Returns false once the input is over.
+    fn cut_next_record(&mut self) -> Result<bool> {
+        self.record.clear();
+        self.output.clear();
+        self.pos = 0;
+
+        if self.inner.read_until(self.eol as u8, &mut self.record)? == 0 {
+            // The last record of --csv may still be waiting for its end
+            self.cutter.finish(&mut self.output)?;
+            if !self.output.is_empty() {
+                return Ok(true);
+            }
+
+            self.cutter.finish_report();
+            self.cutter.check_errors()?;
+            return Ok(false);
+        }
+
+        // Same as reading stdin: "\r\n" ends a line too
+        let mut record = &self.record[..];
+        if let EOL::Newline = self.eol {
+            if let Some(line) = record.strip_suffix(b"\n") {
+                record = line.strip_suffix(b"\r").unwrap_or(line);
+            }
+        }
+
+        self.cutter.cut_record(record, &mut self.output)?;
+        Ok(true)
+    }
+}
+
+impl<R: BufRead> Read for CutReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        while self.pos == self.output.len() {
+            if self.finished {
+                return Ok(0);
+            }
+
+            self.finished = !self
+                .cut_next_record()
+                .map_err(|e| std::io::Error::other(TucError::from(e)))?;
+        }
+
+        let pending = &self.output[self.pos..];
+        let n = pending.len().min(buf.len());
+        buf[..n].copy_from_slice(&pending[..n]);
+        self.pos += n;
+        Ok(n)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_opt(args: &[&str]) -> Opt {
+        Opt::try_from_args(args.iter().map(Into::into)).unwrap()
+    }
+
+    #[test]
+    fn it_reads_the_cut_output() {
+        let opt = make_opt(&["-d", ",", "-f", "2,1", "--join"]);
+        let mut reader = CutReader::new(&b"a,b\r\nc,d\n\ne,f"[..], opt).unwrap();
+
+        // A tiny buffer, to read a record across several calls
+        let mut output = Vec::new();
+        let mut buf = [0; 2];
+        loop {
+            let n = reader.read(&mut buf).unwrap();
+            if n == 0 {
+                break;
+            }
+            output.extend_from_slice(&buf[..n]);
+        }
+
+        assert_eq!(output, b"b,a\nd,c\n\nf,e\n");
+    }
+
+    #[test]
+    fn it_reads_records_ending_with_zero() {
+        let opt = make_opt(&["-z", "-d", ",", "-f", "1"]);
+        let mut reader = CutReader::new(&b"a,b\0c,d\0"[..], opt).unwrap();
+
+        let mut output = Vec::new();
+        reader.read_to_end(&mut output).unwrap();
+        assert_eq!(output, b"a\0c\0");
+    }
+
+    #[test]
+    fn it_fails_like_the_command_line() {
+        let opt = make_opt(&["-d", ",", "-f", "3"]);
+        let mut reader = CutReader::new(&b"a,b\n"[..], opt).unwrap();
+        assert!(reader.read_to_end(&mut Vec::new()).is_err());
+
+        assert!(CutReader::new(&b""[..], make_opt(&["-l", "1"])).is_err());
+    }
+}
@@ -1,11 +1,21 @@
-use anyhow::{bail, Result};
+use anyhow::{anyhow, bail, Result};
 use bstr::io::BufReadExt;
 use bstr::ByteSlice;
 use std::io::{BufRead, Write};
 use std::ops::Range;
 
+use crate::ansi::strip_ansi_escapes;
+use crate::bounds::userboundslist::env_value;
 use crate::bounds::{BoundOrFiller, BoundsType, Side, UserBounds, UserBoundsList, UserBoundsTrait};
-use crate::options::{Opt, Trim, EOL};
+use crate::error::{Diagnostic, TucError};
+use crate::nonprinting::show_nonprinting;
+use crate::options::{ErrorPolicy, Opt, Trim, EOL};
+use crate::record_sink::RecordSink;
+use crate::transform::FieldTransform;
+use crate::validation::{validate, Report};
+
+#[cfg(feature = "json")]
+use crate::json_input::fill_with_json_elements;
 
 #[cfg(feature = "regex")]
 use regex::bytes::Regex;
@@ -83,6 +93,205 @@ fn fill_with_fields_locations_greedy(
     });
 }
 
+/// The character that quotes the delimiters, if any (--csv quotes with `"`
+/// unless --quote says otherwise)
+fn quote_of(opt: &Opt) -> Option<u8> {
+    match opt.quote {
+        Some(quote) => Some(quote),
+        None if opt.csv => Some(b'"'),
+        None => None,
+    }
+}
+
+/// Split a quoted (e.g. CSV) record into parts and fill a buffer with ranges
+/// that match those parts. Delimiters within quotes are part of the
+/// field (RFC 4180). Quotes are kept in the ranges.
+///
+/// - `buffer` - vector that will be filled with ranges
+/// - `line` - the string to split
+/// - `delimiter` - what to search to split the string, outside quotes
+/// - `quote` - the quote character (`"` for CSV)
+fn fill_with_quoted_fields_locations(
+    buffer: &mut Vec<Range<usize>>,
+    line: &[u8],
+    delimiter: &[u8],
+    quote: u8,
+) {
+    buffer.clear();
+
+    if line.is_empty() {
+        return;
+    }
+
+    let mut prev_part_start = 0;
+    let mut in_quotes = false;
+    let mut idx = 0;
+
+    while idx < line.len() {
+        if line[idx] == quote {
+            // An escaped quote ("") closes and reopens the quoted text
+            in_quotes = !in_quotes;
+        } else if !in_quotes && line[idx..].starts_with(delimiter) {
+            buffer.push(Range {
+                start: prev_part_start,
+                end: idx,
+            });
+
+            prev_part_start = idx + delimiter.len();
+            idx = prev_part_start;
+            continue;
+        }
+
+        idx += 1;
+    }
+
+    buffer.push(Range {
+        start: prev_part_start,
+        end: line.len(),
+    });
+}
+
+/// Remove the quotes around the fields of a --csv record and turn the
+/// doubled quotes within them into single ones (RFC 4180), writing the
+/// record into `buffer` and updating the ranges to match it.
+/// Return false, leaving `buffer` untouched, if there are no quotes.
+fn unquote_fields(
+    fields: &mut [Range<usize>],
+    line: &[u8],
+    quote: u8,
+    buffer: &mut Vec<u8>,
+) -> bool {
+    if memchr::memchr(quote, line).is_none() {
+        return false;
+    }
+
+    buffer.clear();
+    let mut prev_field_end = 0;
+
+    for field in fields.iter_mut() {
+        // the delimiter
+        buffer.extend_from_slice(&line[prev_field_end..field.start]);
+        prev_field_end = field.end;
+
+        let start = buffer.len();
+        match &line[field.clone()] {
+            [first, inner @ .., last] if *first == quote && *last == quote => {
+                let mut rest = inner;
+                while let Some(idx) = memchr::memchr(quote, rest) {
+                    buffer.extend_from_slice(&rest[..=idx]);
+                    rest = &rest[idx + 1..];
+                    if rest.first() == Some(&quote) {
+                        rest = &rest[1..];
+                    }
+                }
+                buffer.extend_from_slice(rest);
+            }
+            value => buffer.extend_from_slice(value),
+        }
+
+        *field = start..buffer.len();
+    }
+
+    buffer.extend_from_slice(&line[prev_field_end..]);
+    true
+}
+
+/// Split a string into parts and fill a buffer with ranges
+/// that match those parts, skipping the delimiters preceded by an odd
+/// number of `escape` characters (`\,` is escaped, `\\,` is not).
+///
+/// - `buffer` - vector that will be filled with ranges
+/// - `line` - the string to split
+/// - `delimiter` - what to search to split the string
+/// - `escape` - the escape character (e.g. `\`)
+fn fill_with_escaped_fields_locations(
+    buffer: &mut Vec<Range<usize>>,
+    line: &[u8],
+    delimiter: &[u8],
+    escape: u8,
+) {
+    buffer.clear();
+
+    if line.is_empty() {
+        return;
+    }
+
+    let mut prev_part_start = 0;
+
+    for idx in line.find_iter(&delimiter) {
+        let escapes = line[..idx]
+            .iter()
+            .rev()
+            .take_while(|&&c| c == escape)
+            .count();
+
+        if escapes % 2 == 1 {
+            continue;
+        }
+
+        buffer.push(Range {
+            start: prev_part_start,
+            end: idx,
+        });
+
+        prev_part_start = idx + delimiter.len();
+    }
+
+    buffer.push(Range {
+        start: prev_part_start,
+        end: line.len(),
+    });
+}
+
+/// Split a string into parts and fill a buffer with ranges
+/// that match those parts, skipping the delimiters within `()`, `[]`
+/// or `{}` (at any depth, e.g. `f(a, g(b, c))`). Unbalanced closing
+/// brackets are ignored.
+///
+/// - `buffer` - vector that will be filled with ranges
+/// - `line` - the string to split
+/// - `delimiter` - what to search to split the string, outside brackets
+fn fill_with_bracketed_fields_locations(
+    buffer: &mut Vec<Range<usize>>,
+    line: &[u8],
+    delimiter: &[u8],
+) {
+    buffer.clear();
+
+    if line.is_empty() {
+        return;
+    }
+
+    let mut prev_part_start = 0;
+    let mut depth: usize = 0;
+    let mut idx = 0;
+
+    while idx < line.len() {
+        match line[idx] {
+            b'(' | b'[' | b'{' => depth += 1,
+            b')' | b']' | b'}' => depth = depth.saturating_sub(1),
+            _ if depth == 0 && line[idx..].starts_with(delimiter) => {
+                buffer.push(Range {
+                    start: prev_part_start,
+                    end: idx,
+                });
+
+                prev_part_start = idx + delimiter.len();
+                idx = prev_part_start;
+                continue;
+            }
+            _ => (),
+        }
+
+        idx += 1;
+    }
+
+    buffer.push(Range {
+        start: prev_part_start,
+        end: line.len(),
+    });
+}
+
 #[cfg(feature = "regex")]
 fn fill_with_fields_locations_using_regex(buffer: &mut Vec<Range<usize>>, line: &[u8], re: &Regex) {
     buffer.clear();
@@ -165,6 +374,40 @@ fn maybe_replace_delimiter<'a>(text: &'a [u8], opt: &Opt) -> std::borrow::Cow<'a
     }
 }
 
+/// Remove the escape character in front of the delimiters and of
+/// itself, with --unescape
+fn maybe_unescape<'a>(text: &'a [u8], opt: &Opt) -> std::borrow::Cow<'a, [u8]> {
+    let escape = match opt.escape {
+        Some(escape) if opt.unescape && text.contains(&escape) => escape,
+        _ => return std::borrow::Cow::Borrowed(text),
+    };
+
+    let mut output = Vec::with_capacity(text.len());
+    let mut idx = 0;
+    while idx < text.len() {
+        let rest = &text[idx + 1..];
+        if text[idx] == escape
+            && (rest.starts_with(&opt.delimiter) || rest.first() == Some(&escape))
+        {
+            idx += 1;
+        }
+        if let Some(&c) = text.get(idx) {
+            output.push(c);
+        }
+        idx += 1;
+    }
+
+    std::borrow::Cow::Owned(output)
+}
+
+fn maybe_show_nonprinting<'a>(text: &'a [u8], opt: &Opt) -> std::borrow::Cow<'a, [u8]> {
+    if opt.show_nonprinting {
+        show_nonprinting(text)
+    } else {
+        std::borrow::Cow::Borrowed(text)
+    }
+}
+
 fn trim<'a>(buffer: &'a [u8], trim_kind: &Trim, delimiter: &[u8]) -> &'a [u8] {
     match trim_kind {
         Trim::Both => {
@@ -227,6 +470,7 @@ fn trim_regex<'a>(line: &'a [u8], trim_kind: &Trim, re: &Regex) -> &'a [u8] {
     &line[idx_start..idx_end]
 }
 
+#[cfg(feature = "json")]
 macro_rules! write_maybe_as_json {
     ($writer:ident, $to_print:ident, $as_json:expr) => {{
         if $as_json {
@@ -241,468 +485,2653 @@ macro_rules! write_maybe_as_json {
     }};
 }
 
-pub fn cut_str<W: Write>(
-    line: &[u8],
-    opt: &Opt,
-    stdout: &mut W,
-    fields: &mut Vec<Range<usize>>,
-    compressed_line_buf: &mut Vec<u8>,
-    eol: &[u8],
-) -> Result<()> {
-    if opt.regex_bag.is_some() {
-        if opt.compress_delimiter && opt.replace_delimiter.is_none() {
-            // TODO return a proper error; do not tie cli options to errors at this level
-            bail!("Cannot use --regex and --compress-delimiter without --replace-delimiter");
-        }
+#[cfg(not(feature = "json"))]
+macro_rules! write_maybe_as_json {
+    ($writer:ident, $to_print:ident, $as_json:expr) => {{
+        // --json is rejected while parsing the arguments
+        debug_assert!(!$as_json);
+        $writer.write_all(&$to_print)?;
+    }};
+}
 
-        if opt.join && opt.replace_delimiter.is_none() {
-            // TODO return a proper error; do not tie cli options to errors at this level
-            bail!("Cannot use --regex and --join without --replace-delimiter");
-        }
-    }
+/// Split `field` using the secondary delimiter and return the parts
+/// selected by `sub` (e.g. `user` from `user@host` using `@` and `1`).
+fn get_sub_field<'a>(field: &'a [u8], sub: &UserBounds, sub_delimiter: &[u8]) -> Result<&'a [u8]> {
+    let mut sub_fields = Vec::new();
+    fill_with_fields_locations(&mut sub_fields, field, sub_delimiter);
 
-    let mut line = line;
+    let r = sub.try_into_range(sub_fields.len())?;
+    Ok(&field[sub_fields[r.start].start..sub_fields[r.end - 1].end])
+}
 
-    if let Some(trim_kind) = opt.trim {
-        if opt.regex_bag.is_some() {
-            #[cfg(feature = "regex")]
-            {
-                line = trim_regex(line, &trim_kind, &opt.regex_bag.as_ref().unwrap().greedy);
-            }
-        } else {
-            line = trim(line, &trim_kind, &opt.delimiter);
-        }
-    }
+/// Apply the parts of `b` that select within a field (the sub-field
+/// of `2.1` and the bytes of `2[1:8]`) to `field`.
+pub(crate) fn select_within_field<'a>(
+    field: &'a [u8],
+    b: &UserBounds,
+    sub_delimiter: Option<&[u8]>,
+) -> Result<&'a [u8]> {
+    let field = match (&b.sub, sub_delimiter) {
+        (Some(sub), Some(sub_delimiter)) => get_sub_field(field, sub, sub_delimiter)?,
+        _ => field,
+    };
 
-    if line.is_empty() {
-        if !opt.only_delimited {
-            stdout.write_all(eol)?;
-        }
-        return Ok(());
+    match &b.bytes {
+        Some(bytes) => Ok(&field[bytes.try_into_range(field.len())?]),
+        None => Ok(field),
     }
+}
 
-    #[allow(unused_variables)]
-    let line_holder: std::borrow::Cow<[u8]>;
-    #[allow(unused_mut)]
-    let mut should_build_ranges_using_regex = opt.regex_bag.is_some() && cfg!(feature = "regex");
-    #[allow(unused_mut)]
-    let mut delimiter = &opt.delimiter;
-    let should_compress_delimiter = opt.compress_delimiter
-        && (opt.bounds_type == BoundsType::Fields || opt.bounds_type == BoundsType::Lines);
+/// What a field read by --json-input was in its JSON record
+#[cfg_attr(not(feature = "json"), allow(dead_code))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum JsonField {
+    /// A string, without its quotes
+    String,
+    /// Any other value (e.g. a number), that --json can print as it is
+    Value,
+    /// A key missing from the object, that is out of bounds
+    Missing,
+}
 
-    if should_compress_delimiter {
-        if opt.regex_bag.is_some() && cfg!(feature = "regex") {
-            #[cfg(feature = "regex")]
-            {
-                delimiter = opt.replace_delimiter.as_ref().unwrap(); // we checked earlier the invariant
-                line_holder = compress_delimiter_with_regex(
-                    line,
-                    &opt.regex_bag.as_ref().unwrap().greedy,
-                    delimiter,
-                );
-                line = &line_holder;
-                should_build_ranges_using_regex = false;
-            }
-        } else {
-            compress_delimiter(line, &opt.delimiter, compressed_line_buf);
-            line = compressed_line_buf;
+/// The line being cut, with what's needed to print its bounds.
+struct Record<'a> {
+    /// The line after trimming and compressing delimiters
+    line: &'a [u8],
+    /// The line as it was read
+    original_line: &'a [u8],
+    fields: &'a [Range<usize>],
+    /// What the fields were in the JSON record (with --json-input)
+    json_values: &'a [JsonField],
+    /// Delimiter of the sub-fields (e.g. `2.1`)
+    sub_delimiter: Option<&'a [u8]>,
+    /// Named capture groups are searched at most once per line, and only if needed
+    #[cfg(feature = "regex")]
+    captures: Option<Option<regex::bytes::Captures<'a>>>,
+}
+
+impl<'a> Record<'a> {
+    fn get_field(&self, b: &UserBounds) -> Result<&'a [u8], TucError> {
+        let r = b.try_into_range(self.fields.len())?;
+        if r.len() == 1 && self.json_values.get(r.start) == Some(&JsonField::Missing) {
+            return Err(TucError::OutOfBounds {
+                index: r.start as i32 + 1,
+            });
         }
+        let idx_start = self.fields[r.start].start;
+        let idx_end = self.fields[r.end - 1].end;
+        let field = &self.line[idx_start..idx_end];
+
+        Ok(select_within_field(field, b, self.sub_delimiter)?)
     }
 
-    if should_build_ranges_using_regex {
-        #[cfg(feature = "regex")]
-        fill_with_fields_locations_using_regex(
-            fields,
-            line,
-            if opt.greedy_delimiter {
-                &opt.regex_bag.as_ref().unwrap().greedy
-            } else {
-                &opt.regex_bag.as_ref().unwrap().normal
-            },
-        );
-    } else if opt.greedy_delimiter {
-        fill_with_fields_locations_greedy(fields, line, delimiter);
-    } else {
-        fill_with_fields_locations(fields, line, delimiter);
+    /// Check if the bound selects a single field that was a JSON value
+    /// other than a string (e.g. a number), that --json can print as it is.
+    fn is_json_value(&self, b: &UserBounds) -> bool {
+        b.try_into_range(self.fields.len())
+            .is_ok_and(|r| r.len() == 1 && self.json_values.get(r.start) == Some(&JsonField::Value))
     }
 
-    if opt.bounds_type == BoundsType::Characters && fields.len() > 2 {
-        // Unless the line is empty (which should have already been handled),
-        // then the empty-string delimiter generated ranges alongside each
-        // character, plus one at each boundary, e.g. _f_o_o_. We drop them.
-        fields.pop();
-        fields.drain(..1);
+    #[cfg(feature = "regex")]
+    fn get_capture(&mut self, name: &str, opt: &Opt) -> Option<&'a [u8]> {
+        let original_line = self.original_line;
+        self.captures
+            .get_or_insert_with(|| {
+                opt.regex_bag
+                    .as_ref()
+                    .and_then(|re_bag| re_bag.normal.captures(original_line))
+            })
+            .as_ref()
+            .and_then(|c| c.name(name))
+            .map(|m| m.as_bytes())
     }
 
-    let num_fields = fields.len();
+    #[cfg(not(feature = "regex"))]
+    fn get_capture(&mut self, _name: &str, _opt: &Opt) -> Option<&'a [u8]> {
+        None
+    }
 
-    if opt.only_delimited && num_fields == 1 {
-        // If there's only 1 field it means that there were no delimiters
-        // and when used alogside `only_delimited` we must skip the line
-        return Ok(());
+    /// Check if a placeholder can be found and it's not empty
+    /// (fallbacks are not taken into account).
+    fn has_content(&mut self, bof: &BoundOrFiller, opt: &Opt) -> bool {
+        match bof {
+            BoundOrFiller::Bound(b) => self.get_field(b).is_ok_and(|f| !f.is_empty()),
+            BoundOrFiller::Capture(name) => {
+                self.get_capture(name, opt).is_some_and(|c| !c.is_empty())
+            }
+            BoundOrFiller::WholeLine => !self.original_line.is_empty(),
+            BoundOrFiller::Filler(f) => !f.is_empty(),
+            BoundOrFiller::Env(name) => env_value(name).is_ok_and(|v| !v.is_empty()),
+            BoundOrFiller::Conditional { .. } => true,
+        }
     }
+}
 
-    if opt.json {
-        stdout.write_all(b"[")?;
+/// Key of a field in the object printed by --json-object (and the index
+/// printed by --with-index): its position (e.g. `-1` becomes `3` in a
+/// record of three fields), plus the sub-field or byte range, if any
+/// (e.g. `2.1`).
+fn field_key(b: &UserBounds, num_fields: usize) -> String {
+    let mut key = b.clone();
+    if let Ok(r) = b.try_into_range(num_fields) {
+        key.l = Side::Some(r.start as i32 + 1);
+        key.r = key.l;
     }
 
-    let mut _bounds: UserBoundsList;
-    let mut bounds = &opt.bounds;
+    key.to_string()
+}
 
-    if opt.complement {
-        _bounds = bounds.complement(num_fields)?;
-        bounds = &_bounds;
+/// Key of a field in the object printed by --json-object: its name, when
+/// the fields have one (see `Opt::field_names`), or else its `field_key`.
+#[cfg(feature = "json")]
+fn json_object_key(b: &UserBounds, num_fields: usize, opt: &Opt) -> String {
+    // a field missing from the record (e.g. printing a fallback) has a name too
+    let idx = match (b.try_into_range(num_fields), b.l) {
+        (Ok(r), _) if r.len() == 1 => Some(r.start),
+        (Err(_), Side::Some(l)) if l > 0 && b.l == b.r => Some(l as usize - 1),
+        _ => None,
+    };
+    let name = idx
+        .filter(|_| b.sub.is_none() && b.bytes.is_none())
+        .and_then(|idx| opt.field_names.get(idx));
 
-        if bounds.is_empty() {
-            // If the original bounds matched all the fields, the complement is empty
-            if !opt.only_delimited {
-                stdout.write_all(eol)?;
-            }
-            return Ok(());
-        }
+    match name {
+        Some(name) => name.clone(),
+        None => field_key(b, num_fields),
     }
+}
 
-    if opt.json || (opt.bounds_type == BoundsType::Characters && opt.replace_delimiter.is_some()) {
-        // Unpack bounds such as 1:3 or 2: into single-field bounds
-        // such as 1:1,2:2,3:3 etc...
+/// Print a key of --json-object, followed by `:` (and a space, as
+/// serde does, with --json-pretty).
+#[cfg(feature = "json")]
+fn write_object_key<W: Write>(key: &str, opt: &Opt, stdout: &mut W) -> Result<()> {
+    stdout.write_all(serde_json::to_string(key)?.as_bytes())?;
+    stdout.write_all(if opt.json_pretty { b": " } else { b":" })?;
+    Ok(())
+}
 
-        // Start by checking if we actually need to rewrite the bounds, since
-        // it's an expensive operation.
-        if bounds.iter().any(|b| {
-            matches!(
-                b,
-                BoundOrFiller::Bound(UserBounds {
-                    l: x,
-                    r: y,
-                    is_last: _,
-                    fallback_oob: _,
-                }) if x != y || x == &Side::Continue
-            )
-        }) {
-            _bounds = bounds.unpack(num_fields);
-            bounds = &_bounds;
-        }
+/// Apply to a field what changes its content before printing
+/// (--show-nonprinting, --map, the transformations of the placeholder,
+/// --truncate and the padding) and pass the result to `then`.
+fn with_formatted_field(
+    field: &[u8],
+    b: &UserBounds,
+    opt: &Opt,
+    then: impl FnOnce(&[u8]) -> Result<()>,
+) -> Result<()> {
+    let field = maybe_show_nonprinting(field, opt);
+    let field = match &opt.map {
+        Some(map) => map.apply(&field),
+        None => std::borrow::Cow::Borrowed(field.as_ref()),
+    };
+    let field = match &b.transform {
+        Some(transform) => transform.apply(&field),
+        None => std::borrow::Cow::Borrowed(field.as_ref()),
+    };
+    let field = match &b.humanize {
+        Some(humanize) => humanize.apply(&field),
+        None => std::borrow::Cow::Borrowed(field.as_ref()),
+    };
+    let field = match &opt.truncate {
+        Some(truncate) => truncate.apply(&field),
+        None => std::borrow::Cow::Borrowed(field.as_ref()),
+    };
+
+    if let Some(pad) = &b.pad {
+        let mut padded = Vec::with_capacity(pad.width.max(field.len()));
+        pad.write_padded(&mut padded, &field)?;
+        then(&padded)
+    } else if let Some(pad) = &opt.pad {
+        then(&pad.apply(&field))
+    } else {
+        then(&field)
     }
+}
 
-    match num_fields {
-        1 if bounds.len() == 1 => {
-            write_maybe_as_json!(stdout, line, opt.json);
-        }
-        _ => {
-            bounds.iter().try_for_each(|bof| -> Result<()> {
-                let b = match bof {
-                    BoundOrFiller::Filler(f) => {
-                        stdout.write_all(f.as_bytes())?;
-                        return Ok(());
-                    }
-                    BoundOrFiller::Bound(b) => b,
+fn write_bounds<W: Write>(
+    bounds: &[BoundOrFiller],
+    record: &mut Record,
+    opt: &Opt,
+    stdout: &mut W,
+) -> Result<()> {
+    #[cfg(feature = "json")]
+    let mut object_keys: Vec<String> = Vec::new();
+
+    for bof in bounds {
+        let b = match bof {
+            BoundOrFiller::Filler(f) => {
+                stdout.write_all(f)?;
+                continue;
+            }
+            BoundOrFiller::WholeLine => {
+                stdout.write_all(&maybe_show_nonprinting(record.original_line, opt))?;
+                continue;
+            }
+            BoundOrFiller::Env(name) => {
+                stdout.write_all(&env_value(name)?)?;
+                continue;
+            }
+            BoundOrFiller::Capture(name) => {
+                let output = match (record.get_capture(name, opt), &opt.fallback_oob) {
+                    (Some(capture), _) => capture,
+                    (None, Some(generic_fallback)) => generic_fallback,
+                    (None, None) => bail!("Capture group not found: {}", name),
                 };
 
-                let r = b.try_into_range(num_fields);
-
-                let output = if r.is_ok() {
-                    let r = r.unwrap();
-                    let idx_start = fields[r.start].start;
-                    let idx_end = fields[r.end - 1].end;
-                    &line[idx_start..idx_end]
-                } else if b.fallback_oob.is_some() {
-                    b.fallback_oob.as_ref().unwrap()
-                } else if let Some(generic_fallback) = &opt.fallback_oob {
-                    generic_fallback
+                stdout.write_all(&maybe_show_nonprinting(output, opt))?;
+                continue;
+            }
+            BoundOrFiller::Conditional {
+                condition,
+                then,
+                otherwise,
+            } => {
+                if record.has_content(condition, opt) {
+                    write_bounds(then, record, opt, stdout)?;
                 } else {
-                    return Err(r.unwrap_err());
-                };
+                    write_bounds(otherwise, record, opt, stdout)?;
+                }
+                continue;
+            }
+            BoundOrFiller::Bound(b) => b,
+        };
+
+        let (output, is_json_value) = match record.get_field(b) {
+            Ok(field) => (field, record.is_json_value(b)),
+            Err(e) => match (&b.fallback_oob, &opt.fallback_oob) {
+                (Some(fallback), _) => (fallback.as_slice(), false),
+                (None, Some(generic_fallback)) => (generic_fallback.as_slice(), false),
+                (None, None) => return Err(e.into()),
+            },
+        };
+
+        #[cfg(feature = "json")]
+        if opt.json_object {
+            let key = json_object_key(b, record.fields.len(), opt);
+            // a key is printed once, with the first field that has it
+            if object_keys.contains(&key) {
+                continue;
+            }
+            if !object_keys.is_empty() {
+                stdout.write_all(opt.join_delimiter().unwrap_or(&opt.delimiter))?;
+            }
+            write_object_key(&key, opt, stdout)?;
+            object_keys.push(key);
+        }
+
+        if opt.with_index {
+            let key = field_key(b, record.fields.len());
+            #[cfg(feature = "json")]
+            if opt.json {
+                write!(stdout, "[{},", serde_json::to_string(&key)?)?;
+            }
+            if !opt.json {
+                write!(stdout, "{}:", key)?;
+            }
+        }
 
-                let field_to_print = maybe_replace_delimiter(output, opt);
+        let field_to_print = maybe_unescape(output, opt);
+        let field_to_print = maybe_replace_delimiter(&field_to_print, opt);
+        with_formatted_field(&field_to_print, b, opt, |field_to_print| {
+            if is_json_value && opt.json && field_to_print == output {
+                // keep the type of the values of --json-input (e.g. numbers)
+                stdout.write_all(output)?;
+            } else {
                 write_maybe_as_json!(stdout, field_to_print, opt.json);
+            }
+            Ok(())
+        })?;
 
-                if opt.join && !b.is_last {
-                    stdout.write_all(
-                        opt.replace_delimiter
-                            .as_ref()
-                            .unwrap_or(&opt.delimiter)
-                            .as_bytes(),
-                    )?;
-                }
+        if opt.with_index && opt.json {
+            stdout.write_all(b"]")?;
+        }
 
-                Ok(())
-            })?;
+        // the fields of --json-object are separated before the key
+        if opt.join && !b.is_last && !opt.json_object {
+            stdout.write_all(opt.join_delimiter().unwrap_or(&opt.delimiter))?;
         }
     }
 
-    if opt.json {
-        stdout.write_all(b"]")?;
-    }
+    Ok(())
+}
+
+/// Print the fields requested by --add-field, after the selected ones.
+fn write_added_fields<W: Write>(opt: &Opt, record_number: usize, stdout: &mut W) -> Result<()> {
+    let delimiter = opt.join_delimiter().unwrap_or(&opt.delimiter);
+
+    for field in &opt.add_fields {
+        stdout.write_all(delimiter)?;
+        let field = field.render(record_number);
+        write_maybe_as_json!(stdout, field, opt.json);
+    }
+
+    Ok(())
+}
+
+/// Print where the delimiters were found, e.g. `4:"--" 9:"-"`
+/// (offsets are 1-indexed bytes, as --bytes would use them).
+fn write_delimiters<W: Write>(line: &[u8], fields: &[Range<usize>], stdout: &mut W) -> Result<()> {
+    for (idx, pair) in fields.windows(2).enumerate() {
+        if idx > 0 {
+            stdout.write_all(b" ")?;
+        }
+
+        let (start, end) = (pair[0].end, pair[1].start);
+        write!(stdout, "{}:\"", start + 1)?;
+        stdout.write_all(&show_nonprinting(&line[start..end]))?;
+        stdout.write_all(b"\"")?;
+    }
+
+    Ok(())
+}
+
+/// Print every field on its own line, preceded by its index and a TAB
+/// (e.g. `1\tfoo`), followed by an empty line that ends the record.
+fn write_pairs<W: Write>(
+    line: &[u8],
+    fields: &[Range<usize>],
+    opt: &Opt,
+    eol: &[u8],
+    stdout: &mut W,
+) -> Result<()> {
+    for (idx, field) in fields.iter().enumerate() {
+        write!(stdout, "{}\t", idx + 1)?;
+        stdout.write_all(&maybe_show_nonprinting(&line[field.clone()], opt))?;
+        stdout.write_all(eol)?;
+    }
 
     stdout.write_all(eol)?;
+    Ok(())
+}
+
+/// Print the fields whose content matches the regex of --field-match (or,
+/// with --field-drop, those that don't), separated by the delimiter.
+#[cfg(feature = "regex")]
+fn write_matching_fields<W: Write>(
+    line: &[u8],
+    fields: &[Range<usize>],
+    field_match: &Regex,
+    opt: &Opt,
+    stdout: &mut W,
+) -> Result<()> {
+    let delimiter = opt.join_delimiter().unwrap_or(&opt.delimiter);
+    let mut is_first = true;
+
+    for field in fields {
+        let text = &line[field.clone()];
+        if field_match.is_match(text) == opt.field_drop {
+            continue;
+        }
+
+        if !is_first {
+            stdout.write_all(delimiter)?;
+        }
+        is_first = false;
+        stdout.write_all(&maybe_show_nonprinting(text, opt))?;
+    }
 
     Ok(())
 }
 
-pub fn read_and_cut_str<B: BufRead, W: Write>(
-    stdin: &mut B,
+/// Print every field in its original order, except for fields `a` and `b`
+/// that exchange places. The delimiters are kept (or replaced, if requested).
+fn write_swapped<W: Write>(
+    line: &[u8],
+    fields: &[Range<usize>],
+    a: &UserBounds,
+    b: &UserBounds,
+    opt: &Opt,
     stdout: &mut W,
-    opt: Opt,
 ) -> Result<()> {
-    let line_buf: Vec<u8> = Vec::with_capacity(1024);
-    let mut bounds_as_ranges: Vec<Range<usize>> = Vec::with_capacity(16);
-    let mut compressed_line_buf = if opt.compress_delimiter {
-        Vec::with_capacity(line_buf.capacity())
-    } else {
-        Vec::new()
+    if fields.len() == 1 {
+        // no delimiter was found, there's nothing to swap
+        stdout.write_all(&maybe_show_nonprinting(line, opt))?;
+        return Ok(());
+    }
+
+    let idx_a = a.try_into_range(fields.len())?.start;
+    let idx_b = b.try_into_range(fields.len())?.start;
+
+    for (idx, field) in fields.iter().enumerate() {
+        let source = if idx == idx_a {
+            &fields[idx_b]
+        } else if idx == idx_b {
+            &fields[idx_a]
+        } else {
+            field
+        };
+
+        stdout.write_all(&maybe_show_nonprinting(&line[source.clone()], opt))?;
+
+        if let Some(next_field) = fields.get(idx + 1) {
+            match opt.join_delimiter() {
+                Some(new_delimiter) => stdout.write_all(new_delimiter)?,
+                None => stdout.write_all(&line[field.end..next_field.start])?,
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Print every field in its original order, replacing the fields
+/// requested by --set with their (formatted) value.
+fn write_with_set<W: Write>(record: &mut Record, opt: &Opt, stdout: &mut W) -> Result<()> {
+    let (line, fields) = (record.line, record.fields);
+    let targets = opt
+        .set
+        .iter()
+        .map(|(field, template)| Ok((field.try_into_range(fields.len())?.start, template)))
+        .collect::<Result<Vec<_>>>()?;
+
+    for (idx, field) in fields.iter().enumerate() {
+        // When the same field is set more than once, the last value wins
+        match targets.iter().rev().find(|(target, _)| *target == idx) {
+            Some((_, template)) => write_bounds(template, record, opt, stdout)?,
+            None => stdout.write_all(&maybe_show_nonprinting(&line[field.clone()], opt))?,
+        }
+
+        if let Some(next_field) = fields.get(idx + 1) {
+            match opt.join_delimiter() {
+                Some(new_delimiter) => stdout.write_all(new_delimiter)?,
+                None => stdout.write_all(&line[field.end..next_field.start])?,
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Print every field in its original order, with `value` inserted
+/// as the field number `position` (e.g. -1 appends it as the new last field).
+fn write_inserted<W: Write>(
+    line: &[u8],
+    fields: &[Range<usize>],
+    position: i32,
+    value: &[u8],
+    opt: &Opt,
+    stdout: &mut W,
+) -> Result<()> {
+    let num_fields = fields.len();
+    let idx = match position {
+        p if p > 0 && p as usize <= num_fields + 1 => p as usize - 1,
+        p if p < 0 && p.unsigned_abs() as usize <= num_fields + 1 => {
+            num_fields + 1 - p.unsigned_abs() as usize
+        }
+        _ => return Err(TucError::OutOfBounds { index: position }.into()),
     };
 
-    match opt.eol {
-        EOL::Newline => stdin.for_byte_line(|line| {
-            let line = line.strip_suffix(&[opt.eol as u8]).unwrap_or(line);
-            cut_str(
+    let new_delimiter = opt.join_delimiter().unwrap_or(&opt.delimiter);
+
+    for (field_idx, field) in fields.iter().enumerate() {
+        if field_idx == idx {
+            stdout.write_all(value)?;
+            stdout.write_all(new_delimiter)?;
+        }
+
+        stdout.write_all(&maybe_show_nonprinting(&line[field.clone()], opt))?;
+
+        if let Some(next_field) = fields.get(field_idx + 1) {
+            match opt.join_delimiter() {
+                Some(new_delimiter) => stdout.write_all(new_delimiter)?,
+                None => stdout.write_all(&line[field.end..next_field.start])?,
+            }
+        }
+    }
+
+    if idx == num_fields {
+        stdout.write_all(new_delimiter)?;
+        stdout.write_all(value)?;
+    }
+
+    Ok(())
+}
+
+/// Print the fields that are not selected by the bounds, each preceded by
+/// the delimiter found before it in the line (as if the selected fields were
+/// deleted). Bounds out of range select nothing.
+fn write_without_selected<W: Write>(
+    line: &[u8],
+    fields: &[Range<usize>],
+    opt: &Opt,
+    stdout: &mut W,
+) -> Result<()> {
+    let mut selected = vec![false; fields.len()];
+    for bof in opt.bounds.iter() {
+        if let BoundOrFiller::Bound(b) = bof {
+            if let Ok(r) = b.try_into_range(fields.len()) {
+                selected[r].iter_mut().for_each(|s| *s = true);
+            }
+        }
+    }
+
+    let mut is_first = true;
+    for (idx, field) in fields.iter().enumerate() {
+        if selected[idx] {
+            continue;
+        }
+
+        if !is_first {
+            stdout.write_all(&line[fields[idx - 1].end..field.start])?;
+        }
+
+        stdout.write_all(&maybe_show_nonprinting(&line[field.clone()], opt))?;
+        is_first = false;
+    }
+
+    Ok(())
+}
+
+/// Whether any of the selected fields matches the regex of --grep-fields.
+#[cfg(feature = "regex")]
+fn grep_fields(line: &[u8], fields: &[Range<usize>], opt: &Opt) -> bool {
+    let pattern = match &opt.grep_fields {
+        Some(pattern) => pattern,
+        None => return false,
+    };
+
+    opt.bounds.iter().any(|bof| match bof {
+        BoundOrFiller::Bound(b) => b.try_into_range(fields.len()).is_ok_and(|r| {
+            fields[r].iter().any(|field| {
+                select_within_field(&line[field.clone()], b, opt.sub_delimiter.as_deref())
+                    .is_ok_and(|field| pattern.is_match(field))
+            })
+        }),
+        _ => false,
+    })
+}
+
+#[cfg(not(feature = "regex"))]
+fn grep_fields(_line: &[u8], _fields: &[Range<usize>], _opt: &Opt) -> bool {
+    false
+}
+
+/// Remove the delimiters around the line, with --trim.
+fn trim_record<'a>(line: &'a [u8], opt: &Opt) -> &'a [u8] {
+    match (opt.trim, &opt.regex_bag) {
+        #[cfg(feature = "regex")]
+        (Some(trim_kind), Some(re_bag)) => trim_regex(line, &trim_kind, &re_bag.greedy),
+        (Some(trim_kind), _) => trim(line, &trim_kind, &opt.delimiter),
+        (None, _) => line,
+    }
+}
+
+/// Iterator over the fields of a line, see [`fields`].
+pub struct FieldsIter<'a> {
+    line: &'a [u8],
+    ranges: std::vec::IntoIter<Range<usize>>,
+}
+
+impl<'a> Iterator for FieldsIter<'a> {
+    type Item = &'a [u8];
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.ranges.next().map(|range| &self.line[range])
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.ranges.size_hint()
+    }
+}
+
+impl ExactSizeIterator for FieldsIter<'_> {}
+
+/// Split a line into fields as `tuc` would, honoring the delimiter
+/// (or regex), --greedy-delimiter, --compress-delimiter, --csv and --trim.
+/// The fields are borrowed from the line, so options that rewrite
+/// it (e.g. --json-input or --replace-delimiter) don't apply, and the
+/// fields of --csv keep their quotes.
+///
+/// ```rust
+/// # use tuc::options::Opt;
+/// let opt = Opt::builder().delimiter(",").greedy_delimiter(true).build().unwrap();
+/// let fields: Vec<&[u8]> = tuc::fields(b"a,,b,c", &opt).collect();
+/// assert_eq!(fields, [&b"a"[..], b"b", b"c"]);
+/// ```
+pub fn fields<'a>(line: &'a [u8], opt: &Opt) -> FieldsIter<'a> {
+    let line = trim_record(line, opt);
+    let mut ranges = Vec::new();
+
+    // A compressed delimiter splits the line as a greedy one would
+    let greedy = opt.greedy_delimiter || opt.compress_delimiter;
+
+    match (&opt.delimiters, &opt.regex_bag, quote_of(opt)) {
+        (Some(finder), ..) => finder.fill_with_fields_locations(&mut ranges, line, greedy),
+        #[cfg(feature = "regex")]
+        (_, Some(re_bag), _) => fill_with_fields_locations_using_regex(
+            &mut ranges,
+            line,
+            if greedy {
+                &re_bag.greedy
+            } else {
+                &re_bag.normal
+            },
+        ),
+        (_, _, Some(quote)) => {
+            fill_with_quoted_fields_locations(&mut ranges, line, &opt.delimiter, quote)
+        }
+        _ if opt.escape.is_some() => fill_with_escaped_fields_locations(
+            &mut ranges,
+            line,
+            &opt.delimiter,
+            opt.escape.unwrap(),
+        ),
+        _ if opt.respect_brackets => {
+            fill_with_bracketed_fields_locations(&mut ranges, line, &opt.delimiter)
+        }
+        _ if greedy => fill_with_fields_locations_greedy(&mut ranges, line, &opt.delimiter),
+        _ => fill_with_fields_locations(&mut ranges, line, &opt.delimiter),
+    }
+
+    if opt.bounds_type == BoundsType::Characters && ranges.len() > 2 {
+        // drop the empty ranges around the characters (see cut_str)
+        ranges.pop();
+        ranges.drain(..1);
+    }
+
+    FieldsIter {
+        line,
+        ranges: ranges.into_iter(),
+    }
+}
+
+/// Fill `fields` with the ranges of the fields of a (trimmed, non-empty)
+/// line and return the line they refer to, that differs from `line`
+/// when the delimiters were compressed or the line was JSON.
+#[cfg_attr(not(feature = "json"), allow(unused_variables, clippy::ptr_arg))]
+fn split_record<'a>(
+    line: &'a [u8],
+    opt: &'a Opt,
+    fields: &mut Vec<Range<usize>>,
+    json_values: &mut Vec<JsonField>,
+    compressed_line_buf: &'a mut Vec<u8>,
+) -> Result<&'a [u8], TucError> {
+    let mut line = line;
+    #[allow(unused_mut)]
+    let mut should_build_ranges_using_regex = opt.regex_bag.is_some() && cfg!(feature = "regex");
+    #[allow(unused_mut)]
+    let mut delimiter = &opt.delimiter;
+    let should_compress_delimiter = opt.compress_delimiter
+        && (opt.bounds_type == BoundsType::Fields || opt.bounds_type == BoundsType::Lines);
+
+    if opt.json_input {
+        #[cfg(feature = "json")]
+        {
+            fill_with_json_elements(
+                fields,
+                json_values,
                 line,
-                &opt,
-                stdout,
-                &mut bounds_as_ranges,
-                &mut compressed_line_buf,
-                &[opt.eol as u8],
-            )
-            // XXX Should map properly the error
-            .map_err(|x| std::io::Error::new(std::io::ErrorKind::Other, x.to_string()))
-            .and(Ok(true))
-        })?,
-        EOL::Zero => stdin.for_byte_record(opt.eol.into(), |line| {
-            let line = line.strip_suffix(&[opt.eol as u8]).unwrap_or(line);
-            cut_str(
+                &opt.delimiter,
+                opt.json_keys.as_deref(),
+                compressed_line_buf,
+            )?;
+            line = compressed_line_buf;
+        }
+    } else if opt.csv {
+        // --csv can't be used with --compress-delimiter, the buffer is free
+        let quote = quote_of(opt).unwrap();
+        fill_with_quoted_fields_locations(fields, line, delimiter, quote);
+        if unquote_fields(fields, line, quote, compressed_line_buf) {
+            line = compressed_line_buf;
+        }
+    } else {
+        if should_compress_delimiter {
+            match &opt.regex_bag {
+                #[cfg(feature = "regex")]
+                Some(re_bag) => {
+                    delimiter = opt.replace_delimiter.as_ref().unwrap(); // we checked earlier the invariant
+                    if let std::borrow::Cow::Owned(compressed) =
+                        compress_delimiter_with_regex(line, &re_bag.greedy, delimiter)
+                    {
+                        *compressed_line_buf = compressed;
+                        line = compressed_line_buf;
+                    }
+                    should_build_ranges_using_regex = false;
+                }
+                _ => {
+                    compress_delimiter(line, &opt.delimiter, compressed_line_buf);
+                    line = compressed_line_buf;
+                }
+            }
+        }
+
+        if let Some(finder) = &opt.delimiters {
+            finder.fill_with_fields_locations(fields, line, opt.greedy_delimiter);
+        } else if should_build_ranges_using_regex {
+            #[cfg(feature = "regex")]
+            fill_with_fields_locations_using_regex(
+                fields,
                 line,
-                &opt,
-                stdout,
-                &mut bounds_as_ranges,
-                &mut compressed_line_buf,
-                &[opt.eol as u8],
-            )
-            // XXX Should map properly the error
-            .map_err(|x| std::io::Error::new(std::io::ErrorKind::Other, x.to_string()))
-            .and(Ok(true))
-        })?,
+                if opt.greedy_delimiter {
+                    &opt.regex_bag.as_ref().unwrap().greedy
+                } else {
+                    &opt.regex_bag.as_ref().unwrap().normal
+                },
+            );
+        } else if let Some(quote) = quote_of(opt) {
+            fill_with_quoted_fields_locations(fields, line, delimiter, quote);
+        } else if let Some(escape) = opt.escape {
+            fill_with_escaped_fields_locations(fields, line, delimiter, escape);
+        } else if opt.respect_brackets {
+            fill_with_bracketed_fields_locations(fields, line, delimiter);
+        } else if opt.greedy_delimiter {
+            fill_with_fields_locations_greedy(fields, line, delimiter);
+        } else {
+            fill_with_fields_locations(fields, line, delimiter);
+        }
     }
 
-    Ok(())
+    if opt.bounds_type == BoundsType::Characters && fields.len() > 2 {
+        // Unless the line is empty (which should have already been handled),
+        // then the empty-string delimiter generated ranges alongside each
+        // character, plus one at each boundary, e.g. _f_o_o_. We drop them.
+        fields.pop();
+        fields.drain(..1);
+    }
+
+    Ok(line)
 }
 
-#[cfg(test)]
-mod tests {
-    use crate::{bounds::UserBoundsList, options::EOL};
+pub fn cut_str<W: Write>(
+    line: &[u8],
+    opt: &Opt,
+    stdout: &mut W,
+    fields: &mut Vec<Range<usize>>,
+    compressed_line_buf: &mut Vec<u8>,
+    eol: &[u8],
+    record_number: usize,
+) -> Result<(), TucError> {
+    if opt.regex_bag.is_some() {
+        if opt.compress_delimiter && opt.replace_delimiter.is_none() {
+            // TODO return a proper error; do not tie cli options to errors at this level
+            return Err(TucError::Other(anyhow!(
+                "Cannot use --regex and --compress-delimiter without --replace-delimiter"
+            )));
+        }
+
+        if opt.join && opt.join_delimiter().is_none() {
+            // TODO return a proper error; do not tie cli options to errors at this level
+            return Err(TucError::Other(anyhow!(
+                "Cannot use --regex and --join without --replace-delimiter"
+            )));
+        }
+    }
+
+    let original_line = line;
+    let line = trim_record(line, opt);
+
+    if line.is_empty() {
+        if !opt.only_delimited {
+            validate(line, std::slice::from_ref(&(0..0)), opt, record_number)?;
+            if opt.count {
+                stdout.write_all(b"0")?;
+                stdout.write_all(eol)?;
+            } else if opt.grep_fields.is_none() {
+                stdout.write_all(eol)?;
+            } else if grep_fields(line, std::slice::from_ref(&(0..0)), opt) {
+                stdout.write_all(original_line)?;
+                stdout.write_all(eol)?;
+            }
+        }
+        return Ok(());
+    }
+
+    // Allocated only for --json-input
+    let mut json_values = Vec::new();
+    let line = split_record(line, opt, fields, &mut json_values, compressed_line_buf)?;
+    let num_fields = fields.len();
+
+    if opt.only_delimited && num_fields == 1 {
+        // If there's only 1 field it means that there were no delimiters
+        // and when used alogside `only_delimited` we must skip the line
+        return Ok(());
+    }
+
+    validate(line, fields, opt, record_number)?;
+
+    if opt.count {
+        write!(stdout, "{}", num_fields)?;
+        stdout.write_all(eol)?;
+        return Ok(());
+    }
 
     #[cfg(feature = "regex")]
-    use crate::options::RegexBag;
+    if let Some(field_match) = &opt.field_match {
+        write_matching_fields(line, fields, field_match, opt, stdout)?;
+        write_added_fields(opt, record_number, stdout)?;
+        stdout.write_all(eol)?;
+        return Ok(());
+    }
+
+    if opt.grep_fields.is_some() {
+        if grep_fields(line, fields, opt) {
+            stdout.write_all(original_line)?;
+            stdout.write_all(eol)?;
+        }
+        return Ok(());
+    }
+
+    if opt.print_delimiters {
+        write_delimiters(line, fields, stdout)?;
+        stdout.write_all(eol)?;
+        return Ok(());
+    }
+
+    if opt.pairs {
+        return Ok(write_pairs(line, fields, opt, eol, stdout)?);
+    }
+
+    if opt.keep_original_delimiters {
+        write_without_selected(line, fields, opt, stdout)?;
+        write_added_fields(opt, record_number, stdout)?;
+        stdout.write_all(eol)?;
+        return Ok(());
+    }
+
+    if let Some((a, b)) = &opt.swap {
+        write_swapped(line, fields, a, b, opt, stdout)?;
+        write_added_fields(opt, record_number, stdout)?;
+        stdout.write_all(eol)?;
+        return Ok(());
+    }
+
+    if !opt.set.is_empty() {
+        let mut record = Record {
+            line,
+            original_line,
+            fields,
+            json_values: &json_values,
+            sub_delimiter: opt.sub_delimiter.as_deref(),
+            #[cfg(feature = "regex")]
+            captures: None,
+        };
+
+        write_with_set(&mut record, opt, stdout)?;
+        write_added_fields(opt, record_number, stdout)?;
+        stdout.write_all(eol)?;
+        return Ok(());
+    }
+
+    if let Some((position, value)) = &opt.insert {
+        let value = value.render(record_number);
+        write_inserted(line, fields, *position, &value, opt, stdout)?;
+        write_added_fields(opt, record_number, stdout)?;
+        stdout.write_all(eol)?;
+        return Ok(());
+    }
+
+    let mut _bounds: UserBoundsList;
+    let mut bounds = &opt.bounds;
+
+    if opt.complement {
+        _bounds = if opt.bounds_type == BoundsType::Characters {
+            // Removing every character leaves an empty line
+            bounds.complement_or_empty(num_fields)?
+        } else {
+            bounds.complement(num_fields)?
+        };
+        bounds = &_bounds;
+
+        if bounds.is_empty() {
+            // If the original bounds matched all the fields, the complement is empty
+            if opt.json {
+                stdout.write_all(if opt.json_object { b"{}" } else { b"[]" })?;
+            }
+            if !opt.only_delimited {
+                stdout.write_all(eol)?;
+            }
+            return Ok(());
+        }
+    }
+
+    if opt.json {
+        stdout.write_all(match (opt.json_object, opt.json_pretty) {
+            (false, false) => b"[",
+            (false, true) => b"[\n  ",
+            (true, false) => b"{",
+            (true, true) => b"{\n  ",
+        })?;
+    }
+
+    if opt.json
+        || opt.with_index
+        || (opt.bounds_type == BoundsType::Characters && opt.replace_delimiter.is_some())
+        || bounds.has_steps()
+    {
+        // Unpack bounds such as 1:3 or 2: into single-field bounds
+        // such as 1:1,2:2,3:3 etc... (1:5:2 becomes 1:1,3:3,5:5)
+
+        // Start by checking if we actually need to rewrite the bounds, since
+        // it's an expensive operation.
+        if bounds.iter().any(|b| {
+            matches!(
+                b,
+                BoundOrFiller::Bound(UserBounds {
+                    l: x,
+                    r: y,
+                    ..
+                }) if x != y || x == &Side::Continue
+            )
+        }) {
+            _bounds = bounds.unpack(num_fields);
+            bounds = &_bounds;
+        }
+    }
+
+    match (num_fields, bounds.first()) {
+        (1, Some(BoundOrFiller::Bound(b)))
+            if bounds.len() == 1
+                && !opt.with_index
+                && !b.has_modifiers()
+                && json_values.first() != Some(&JsonField::Missing) =>
+        {
+            #[cfg(feature = "json")]
+            if opt.json_object {
+                write_object_key(&json_object_key(b, 1, opt), opt, stdout)?;
+            }
+            let is_json_value = json_values.first() == Some(&JsonField::Value);
+            with_formatted_field(&maybe_unescape(line, opt), b, opt, |output| {
+                if is_json_value && opt.json && output == line {
+                    stdout.write_all(line)?;
+                } else {
+                    write_maybe_as_json!(stdout, output, opt.json);
+                }
+                Ok(())
+            })?;
+        }
+        _ => {
+            let mut record = Record {
+                line,
+                original_line,
+                fields,
+                json_values: &json_values,
+                sub_delimiter: opt.sub_delimiter.as_deref(),
+                #[cfg(feature = "regex")]
+                captures: None,
+            };
+
+            write_bounds(bounds, &mut record, opt, stdout)?;
+        }
+    }
+
+    write_added_fields(opt, record_number, stdout)?;
+
+    if opt.json {
+        stdout.write_all(match (opt.json_object, opt.json_pretty) {
+            (false, false) => b"]",
+            (false, true) => b"\n]",
+            (true, false) => b"}",
+            (true, true) => b"\n}",
+        })?;
+    }
+
+    stdout.write_all(eol)?;
+
+    Ok(())
+}
+
+/// Cut a line as `cut_str` would, but pass the selected fields to `sink`
+/// instead of printing them (e.g. for --msgpack or --exec). What decides
+/// how the fields are printed together (--json, --join, the delimiter
+/// in the output) does not apply.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn cut_str_into<S: RecordSink>(
+    line: &[u8],
+    opt: &Opt,
+    sink: &mut S,
+    fields: &mut Vec<Range<usize>>,
+    compressed_line_buf: &mut Vec<u8>,
+    values_buf: &mut Vec<u8>,
+    record_number: usize,
+) -> Result<(), TucError> {
+    let original_line = line;
+    let line = trim_record(line, opt);
+
+    if line.is_empty() {
+        if !opt.only_delimited {
+            validate(line, std::slice::from_ref(&(0..0)), opt, record_number)?;
+            sink.add_record(&[])?;
+        }
+        return Ok(());
+    }
+
+    let line = split_record(line, opt, fields, &mut Vec::new(), compressed_line_buf)?;
+    let num_fields = fields.len();
+
+    if opt.only_delimited && num_fields == 1 {
+        return Ok(());
+    }
+
+    validate(line, fields, opt, record_number)?;
+
+    // The values are stored one after the other, then split again
+    values_buf.clear();
+    let mut value_ends = Vec::with_capacity(opt.bounds.len());
+    let mut add_value = |value: &[u8]| {
+        values_buf.extend_from_slice(value);
+        value_ends.push(values_buf.len());
+        Ok(())
+    };
+
+    #[cfg(feature = "regex")]
+    if let Some(field_match) = &opt.field_match {
+        for field in fields.iter() {
+            let text = &line[field.clone()];
+            if field_match.is_match(text) != opt.field_drop {
+                add_value(&maybe_show_nonprinting(text, opt))?;
+            }
+        }
+    }
+
+    if opt.field_match.is_none() {
+        let mut _bounds: UserBoundsList;
+        let mut bounds = &opt.bounds;
+
+        if opt.complement {
+            _bounds = if opt.bounds_type == BoundsType::Characters {
+                bounds.complement_or_empty(num_fields)?
+            } else {
+                bounds.complement(num_fields)?
+            };
+            bounds = &_bounds;
+        }
+
+        // Every field is a value on its own, e.g. 1:3 becomes 1,2,3
+        if bounds.iter().any(|b| {
+            matches!(
+                b,
+                BoundOrFiller::Bound(UserBounds {
+                    l: x,
+                    r: y,
+                    ..
+                }) if x != y || x == &Side::Continue
+            )
+        }) {
+            _bounds = bounds.unpack(num_fields);
+            bounds = &_bounds;
+        }
+
+        match (num_fields, bounds.first()) {
+            (1, Some(BoundOrFiller::Bound(b))) if bounds.len() == 1 && !b.has_modifiers() => {
+                with_formatted_field(&maybe_unescape(line, opt), b, opt, &mut add_value)?;
+            }
+            _ => {
+                let record = Record {
+                    line,
+                    original_line,
+                    fields,
+                    json_values: &[],
+                    sub_delimiter: opt.sub_delimiter.as_deref(),
+                    #[cfg(feature = "regex")]
+                    captures: None,
+                };
+
+                for bof in bounds.iter() {
+                    // Formatted fields are rejected while parsing the arguments
+                    let BoundOrFiller::Bound(b) = bof else {
+                        continue;
+                    };
+
+                    let output = match record.get_field(b) {
+                        Ok(field) => field,
+                        Err(e) => match (&b.fallback_oob, &opt.fallback_oob) {
+                            (Some(fallback), _) => fallback,
+                            (None, Some(generic_fallback)) => generic_fallback,
+                            (None, None) => return Err(e),
+                        },
+                    };
+
+                    with_formatted_field(&maybe_unescape(output, opt), b, opt, &mut add_value)?;
+                }
+            }
+        }
+    }
+
+    for field in &opt.add_fields {
+        add_value(&field.render(record_number))?;
+    }
+
+    let mut start = 0;
+    let values: Vec<&[u8]> = value_ends
+        .iter()
+        .map(|&end| {
+            let value = &values_buf[start..end];
+            start = end;
+            value
+        })
+        .collect();
+
+    Ok(sink.add_record(&values)?)
+}
+
+/// Cut one record at a time, keeping what must survive between records
+/// (buffers, record number, --report counters).
+pub(crate) struct RecordCutter {
+    opt: Opt,
+    bounds_as_ranges: Vec<Range<usize>>,
+    compressed_line_buf: Vec<u8>,
+    stripped_line_buf: Vec<u8>,
+    record_buf: Vec<u8>,
+    /// Number of the last record, counting the lines of the preamble (see
+    /// `Opt::preamble_lines`), as --number, {NR} and the errors show it
+    record_number: usize,
+    report: Report,
+    /// Waiting to be taken by the caller (see `take_diagnostics`)
+    diagnostics: Vec<Diagnostic>,
+    /// Lines of a CSV record whose quoted field is still open
+    csv_record_buf: Vec<u8>,
+    in_quoted_field: bool,
+}
+
+impl RecordCutter {
+    pub(crate) fn new(opt: Opt) -> Self {
+        RecordCutter {
+            bounds_as_ranges: Vec::with_capacity(16),
+            compressed_line_buf: if opt.compress_delimiter {
+                Vec::with_capacity(1024)
+            } else {
+                Vec::new()
+            },
+            stripped_line_buf: Vec::new(),
+            record_buf: Vec::new(),
+            record_number: opt.preamble_lines(),
+            report: Report::default(),
+            diagnostics: Vec::new(),
+            csv_record_buf: Vec::new(),
+            in_quoted_field: false,
+            opt,
+        }
+    }
+
+    /// Cut a record (with or without its end of line) and write it to `stdout`.
+    /// With --csv a record can span several lines, if a quoted field
+    /// contains the end of line: its lines are put together first.
+    pub(crate) fn cut_record<W: Write>(&mut self, line: &[u8], stdout: &mut W) -> Result<()> {
+        self.join_csv_lines(line, |cutter, record| {
+            cutter.cut_whole_record(record, stdout)
+        })
+    }
+
+    /// Cut a record as `cut_record` does, passing its fields to `sink`.
+    pub(crate) fn cut_record_into<S: RecordSink>(
+        &mut self,
+        line: &[u8],
+        sink: &mut S,
+    ) -> Result<()> {
+        self.join_csv_lines(line, |cutter, record| {
+            cutter.cut_whole_record_into(record, sink)
+        })
+    }
+
+    /// Cut the last CSV record, if its quoted field was never closed.
+    pub(crate) fn finish<W: Write>(&mut self, stdout: &mut W) -> Result<()> {
+        self.finish_csv_record(|cutter, record| cutter.cut_whole_record(record, stdout))
+    }
+
+    /// Pass the last CSV record to `sink`, if its quoted field was never closed.
+    pub(crate) fn finish_into<S: RecordSink>(&mut self, sink: &mut S) -> Result<()> {
+        self.finish_csv_record(|cutter, record| cutter.cut_whole_record_into(record, sink))
+    }
+
+    fn join_csv_lines(
+        &mut self,
+        line: &[u8],
+        mut cut: impl FnMut(&mut Self, &[u8]) -> Result<()>,
+    ) -> Result<()> {
+        if !self.opt.csv {
+            return cut(self, line);
+        }
+
+        let eol = self.opt.eol as u8;
+        let line = line.strip_suffix(&[eol]).unwrap_or(line);
+        self.csv_record_buf.extend_from_slice(line);
+        if memchr::memchr_iter(quote_of(&self.opt).unwrap(), line).count() % 2 == 1 {
+            self.in_quoted_field = !self.in_quoted_field;
+        }
+
+        if self.in_quoted_field {
+            self.csv_record_buf.push(eol);
+            return Ok(());
+        }
+
+        self.cut_csv_record(cut)
+    }
+
+    fn finish_csv_record(&mut self, cut: impl FnMut(&mut Self, &[u8]) -> Result<()>) -> Result<()> {
+        if self.in_quoted_field {
+            self.in_quoted_field = false;
+            self.csv_record_buf.pop();
+            self.cut_csv_record(cut)?;
+        }
+
+        Ok(())
+    }
+
+    fn cut_csv_record(
+        &mut self,
+        mut cut: impl FnMut(&mut Self, &[u8]) -> Result<()>,
+    ) -> Result<()> {
+        let mut record = std::mem::take(&mut self.csv_record_buf);
+        let result = cut(self, &record);
+        record.clear();
+        self.csv_record_buf = record;
+        result
+    }
+
+    fn cut_whole_record_into<S: RecordSink>(&mut self, line: &[u8], sink: &mut S) -> Result<()> {
+        let RecordCutter {
+            opt,
+            bounds_as_ranges,
+            compressed_line_buf,
+            stripped_line_buf,
+            record_buf,
+            record_number,
+            report,
+            diagnostics,
+            ..
+        } = self;
+
+        let mut line = line.strip_suffix(&[opt.eol as u8]).unwrap_or(line);
+        *record_number += 1;
+        if opt.strip_ansi {
+            strip_ansi_escapes(line, stripped_line_buf);
+            line = stripped_line_buf;
+        }
+
+        cut_str_into(
+            line,
+            opt,
+            sink,
+            bounds_as_ranges,
+            compressed_line_buf,
+            record_buf,
+            *record_number,
+        )
+        .or_else(|e| skip_failed_record(e, opt, *record_number, report, diagnostics))
+    }
+
+    fn cut_whole_record<W: Write>(&mut self, line: &[u8], stdout: &mut W) -> Result<()> {
+        let RecordCutter {
+            opt,
+            bounds_as_ranges,
+            compressed_line_buf,
+            stripped_line_buf,
+            record_buf,
+            record_number,
+            report,
+            diagnostics,
+            ..
+        } = self;
+
+        let mut line = line.strip_suffix(&[opt.eol as u8]).unwrap_or(line);
+        *record_number += 1;
+        if opt.strip_ansi {
+            strip_ansi_escapes(line, stripped_line_buf);
+            line = stripped_line_buf;
+        }
+
+        // --number must know whether the record printed anything
+        if opt.errors == ErrorPolicy::FailFast && !opt.lenient && opt.number.is_none() {
+            cut_str(
+                line,
+                opt,
+                stdout,
+                bounds_as_ranges,
+                compressed_line_buf,
+                &[opt.eol as u8],
+                *record_number,
+            )
+            .or_else(|e| skip_failed_record(e, opt, *record_number, report, diagnostics))
+        } else {
+            // A record that fails must not leave partial output behind
+            record_buf.clear();
+            match cut_str(
+                line,
+                opt,
+                record_buf,
+                bounds_as_ranges,
+                compressed_line_buf,
+                &[opt.eol as u8],
+                *record_number,
+            ) {
+                Ok(()) => {
+                    if let Some(separator) = opt.number.as_ref().filter(|_| !record_buf.is_empty())
+                    {
+                        write!(stdout, "{}", record_number)?;
+                        stdout.write_all(separator)?;
+                    }
+                    stdout.write_all(record_buf).map_err(Into::into)
+                }
+                Err(e) => skip_failed_record(e, opt, *record_number, report, diagnostics),
+            }
+        }
+    }
+
+    /// Add the summary of --report to the diagnostics, if requested.
+    pub(crate) fn finish_report(&mut self) {
+        if self.opt.report {
+            self.report.records = self.record_number - self.opt.preamble_lines();
+            self.diagnostics
+                .push(Diagnostic::Report(self.report.summary(&self.opt)));
+        }
+    }
+
+    /// The diagnostics of the records cut so far, that were not taken yet.
+    pub(crate) fn take_diagnostics(&mut self) -> std::vec::Drain<'_, Diagnostic> {
+        self.diagnostics.drain(..)
+    }
+
+    /// Fail if any record was skipped by --errors collect.
+    pub(crate) fn check_errors(&self) -> Result<()> {
+        if self.report.errors > 0 {
+            bail!("{} records could not be processed", self.report.errors);
+        }
+
+        Ok(())
+    }
+}
+
+pub fn read_and_cut_str<B: BufRead, W: Write>(
+    stdin: &mut B,
+    stdout: &mut W,
+    opt: Opt,
+) -> Result<()> {
+    read_and_cut_str_with_diagnostics(stdin, stdout, opt, |_| ())
+}
+
+/// Same as `read_and_cut_str`, passing to `on_diagnostic` the warnings
+/// and errors of the skipped records and the summary of --report.
+pub fn read_and_cut_str_with_diagnostics<B: BufRead, W: Write>(
+    stdin: &mut B,
+    stdout: &mut W,
+    opt: Opt,
+    mut on_diagnostic: impl FnMut(Diagnostic),
+) -> Result<()> {
+    let eol = opt.eol;
+    let mut cutter = RecordCutter::new(opt);
+
+    let mut cut_record = |line: &[u8]| {
+        let result = cutter.cut_record(line, stdout);
+        cutter.take_diagnostics().for_each(&mut on_diagnostic);
+        result
+            .map_err(|x| std::io::Error::other(TucError::from(x)))
+            .and(Ok(true))
+    };
+
+    let result = match eol {
+        EOL::Newline => stdin.for_byte_line(&mut cut_record),
+        EOL::Zero => stdin.for_byte_record(eol.into(), &mut cut_record),
+    };
+
+    let result = result
+        .map_err(Into::into)
+        .and_then(|_| cutter.finish(stdout));
+
+    cutter.finish_report();
+    cutter.take_diagnostics().for_each(on_diagnostic);
+    result?;
+    cutter.check_errors()
+}
+
+/// Decide what to do with a record that could not be cut.
+///
+/// With --relaxed the records that fail a validation are skipped with a
+/// warning, and so are with --lenient the records with fields out of bounds.
+/// With --errors collect (or ignore) any failing record is skipped,
+/// reporting (or not) the error.
+fn skip_failed_record(
+    e: TucError,
+    opt: &Opt,
+    record_number: usize,
+    report: &mut Report,
+    diagnostics: &mut Vec<Diagnostic>,
+) -> Result<()> {
+    let validation_error = match &e {
+        TucError::Validation(validation_error) => Some(validation_error),
+        _ => None,
+    };
+
+    if let Some(validation_error) = validation_error {
+        report.add_failure(validation_error.rule);
+    }
+
+    match opt.errors {
+        ErrorPolicy::FailFast if opt.relaxed && validation_error.is_some() => {
+            diagnostics.push(Diagnostic::Skipped(e));
+        }
+        ErrorPolicy::FailFast
+            if opt.lenient
+                && matches!(
+                    e.inner(),
+                    TucError::OutOfBounds { .. } | TucError::InvertedRange
+                ) =>
+        {
+            let e = e.at_line(record_number);
+            diagnostics.push(Diagnostic::Skipped(e));
+        }
+        ErrorPolicy::FailFast => return Err(e.at_line(record_number).into()),
+        ErrorPolicy::Collect => {
+            diagnostics.push(Diagnostic::Failed {
+                record_number,
+                error: e,
+            });
+            report.errors += 1;
+        }
+        ErrorPolicy::Ignore => (),
+    }
+
+    report.skipped += 1;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        bounds::UserBoundsList,
+        options::{AddedField, ErrorPolicy, FieldsCount, Pad, Truncate, EOL},
+        transform::TransformPipeline,
+    };
+
+    #[cfg(feature = "regex")]
+    use crate::options::RegexBag;
+
+    use std::str::FromStr;
+
+    use super::*;
+
+    fn make_fields_opt() -> Opt {
+        Opt {
+            bounds_type: BoundsType::Fields,
+            delimiter: "-".into(),
+            ..Opt::default()
+        }
+    }
+
+    #[cfg(feature = "regex")]
+    fn make_regex_bag() -> RegexBag {
+        RegexBag {
+            normal: Regex::from_str("[.,]").unwrap(),
+            greedy: Regex::from_str("([.,])+").unwrap(),
+        }
+    }
+
+    #[cfg(feature = "regex")]
+    fn make_cut_characters_regex_bag() -> RegexBag {
+        RegexBag {
+            normal: Regex::from_str("\\b|\\B").unwrap(),
+            greedy: Regex::from_str("(\\b|\\B)+").unwrap(),
+        }
+    }
+
+    #[test]
+    fn test_fill_with_fields_locations() {
+        let mut v_range: Vec<Range<usize>> = Vec::new();
+
+        v_range.clear();
+        fill_with_fields_locations(&mut v_range, b"", b"-");
+        assert_eq!(v_range, vec![] as Vec<Range<usize>>);
+
+        v_range.clear();
+        fill_with_fields_locations(&mut v_range, b"a", b"-");
+        assert_eq!(v_range, vec![Range { start: 0, end: 1 }]);
+
+        v_range.clear();
+        fill_with_fields_locations(&mut v_range, b"a-b", b"-");
+        assert_eq!(
+            v_range,
+            vec![Range { start: 0, end: 1 }, Range { start: 2, end: 3 }]
+        );
+
+        v_range.clear();
+        fill_with_fields_locations(&mut v_range, b"-a-", b"-");
+        assert_eq!(
+            v_range,
+            vec![
+                Range { start: 0, end: 0 },
+                Range { start: 1, end: 2 },
+                Range { start: 3, end: 3 }
+            ]
+        );
+
+        v_range.clear();
+        fill_with_fields_locations(&mut v_range, b"a--", b"-");
+        assert_eq!(
+            v_range,
+            vec![
+                Range { start: 0, end: 1 },
+                Range { start: 2, end: 2 },
+                Range { start: 3, end: 3 }
+            ]
+        );
+    }
+
+    #[test]
+    fn test_fill_with_fields_locations_greedy() {
+        let mut v_range: Vec<Range<usize>> = Vec::new();
+        let empty_vec: Vec<Range<usize>> = vec![];
+
+        v_range.clear();
+        fill_with_fields_locations_greedy(&mut v_range, b"", b"-");
+        assert_eq!(v_range, empty_vec);
+
+        v_range.clear();
+        fill_with_fields_locations_greedy(&mut v_range, b"a", b"-");
+        assert_eq!(v_range, vec![Range { start: 0, end: 1 }]);
+
+        v_range.clear();
+        fill_with_fields_locations_greedy(&mut v_range, b"-", b"-");
+        assert_eq!(
+            v_range,
+            vec![Range { start: 0, end: 0 }, Range { start: 1, end: 1 }]
+        );
+
+        v_range.clear();
+        fill_with_fields_locations_greedy(&mut v_range, b"-a--b", b"-");
+        assert_eq!(
+            v_range,
+            vec![
+                Range { start: 0, end: 0 },
+                Range { start: 1, end: 2 },
+                Range { start: 4, end: 5 }
+            ]
+        );
+
+        v_range.clear();
+        fill_with_fields_locations_greedy(&mut v_range, b"-a--", b"-");
+        assert_eq!(
+            v_range,
+            vec![
+                Range { start: 0, end: 0 },
+                Range { start: 1, end: 2 },
+                Range { start: 4, end: 4 }
+            ]
+        );
+    }
+
+    #[test]
+    fn test_fill_with_quoted_fields_locations() {
+        let mut v_range: Vec<Range<usize>> = Vec::new();
+
+        fill_with_quoted_fields_locations(&mut v_range, b"", b",", b'"');
+        assert!(v_range.is_empty());
+
+        fill_with_quoted_fields_locations(&mut v_range, br#"a,"b,c",d"#, b",", b'"');
+        assert_eq!(v_range, vec![0..1, 2..7, 8..9]);
+
+        fill_with_quoted_fields_locations(&mut v_range, br#""a"",b",,"#, b",", b'"');
+        assert_eq!(v_range, vec![0..7, 8..8, 9..9]);
+
+        fill_with_quoted_fields_locations(&mut v_range, br#"a::"b::c"::"#, b"::", b'"');
+        assert_eq!(v_range, vec![0..1, 3..9, 11..11]);
+
+        fill_with_quoted_fields_locations(&mut v_range, b"a 'b c' \"d e", b" ", b'\'');
+        assert_eq!(v_range, vec![0..1, 2..7, 8..10, 11..12]);
+    }
+
+    #[test]
+    fn test_unquote_fields() {
+        let mut buffer = Vec::new();
+        let line = br#"a,"b,""c""","",d"e"#;
+        let mut v_range: Vec<Range<usize>> = Vec::new();
+        fill_with_quoted_fields_locations(&mut v_range, line, b",", b'"');
+
+        assert!(unquote_fields(&mut v_range, line, b'"', &mut buffer));
+        assert_eq!(buffer, br#"a,b,"c",,d"e"#);
+        assert_eq!(v_range, vec![0..1, 2..7, 8..8, 9..12]);
+
+        let mut v_range = vec![0..1, 2..3];
+        assert!(!unquote_fields(&mut v_range, b"a,b", b'"', &mut buffer));
+        assert_eq!(v_range, vec![0..1, 2..3]);
+    }
+
+    #[test]
+    fn test_fill_with_escaped_fields_locations() {
+        let mut v_range: Vec<Range<usize>> = Vec::new();
+
+        fill_with_escaped_fields_locations(&mut v_range, b"", b",", b'\\');
+        assert!(v_range.is_empty());
+
+        fill_with_escaped_fields_locations(&mut v_range, br"a\,b,c", b",", b'\\');
+        assert_eq!(v_range, vec![0..4, 5..6]);
+
+        fill_with_escaped_fields_locations(&mut v_range, br"a\\,b\\\,c", b",", b'\\');
+        assert_eq!(v_range, vec![0..3, 4..10]);
+    }
+
+    #[test]
+    fn test_fill_with_bracketed_fields_locations() {
+        let mut v_range: Vec<Range<usize>> = Vec::new();
+
+        fill_with_bracketed_fields_locations(&mut v_range, b"", b", ");
+        assert!(v_range.is_empty());
+
+        fill_with_bracketed_fields_locations(&mut v_range, b"f(a, b), g(c)", b", ");
+        assert_eq!(v_range, vec![0..7, 9..13]);
+
+        fill_with_bracketed_fields_locations(&mut v_range, b"{a,[b,(c)]},d),e", b",");
+        assert_eq!(v_range, vec![0..11, 12..14, 15..16]);
+    }
+
+    #[cfg(feature = "regex")]
+    #[test]
+    fn cut_str_it_selects_the_fields_by_content() {
+        let mut opt = make_fields_opt();
+        opt.field_match = Some(Regex::new("^[0-9]+$").unwrap());
+        let (mut output, mut buffer1, mut buffer2) = (Vec::new(), Vec::new(), Vec::new());
+
+        let line = b"a-1-b-22";
+        cut_str(
+            line,
+            &opt,
+            &mut output,
+            &mut buffer1,
+            &mut buffer2,
+            b"\n",
+            1,
+        )
+        .unwrap();
+        assert_eq!(output, b"1-22\n".as_slice());
+
+        opt.field_drop = true;
+        opt.replace_delimiter = Some(b",".to_vec());
+        output.clear();
+        cut_str(
+            line,
+            &opt,
+            &mut output,
+            &mut buffer1,
+            &mut buffer2,
+            b"\n",
+            1,
+        )
+        .unwrap();
+        assert_eq!(output, b"a,b\n".as_slice());
+    }
+
+    #[test]
+    fn cut_str_it_counts_the_fields() {
+        let mut opt = make_fields_opt();
+        opt.count = true;
+        let (mut output, mut buffer1, mut buffer2) = make_cut_str_buffers();
+
+        for line in [&b"a-b--c"[..], b"", b"a"] {
+            cut_str(
+                line,
+                &opt,
+                &mut output,
+                &mut buffer1,
+                &mut buffer2,
+                b"\n",
+                1,
+            )
+            .unwrap();
+        }
+        assert_eq!(output, b"4\n0\n1\n".as_slice());
+
+        opt.greedy_delimiter = true;
+        output.clear();
+        cut_str(
+            b"a-b--c",
+            &opt,
+            &mut output,
+            &mut buffer1,
+            &mut buffer2,
+            b"\n",
+            1,
+        )
+        .unwrap();
+        assert_eq!(output, b"3\n".as_slice());
+    }
+
+    #[test]
+    fn cut_str_it_prints_the_index_of_the_fields() {
+        let mut opt = make_fields_opt();
+        opt.with_index = true;
+        opt.join = true;
+        opt.bounds = UserBoundsList::from_str("-1,1:2").unwrap();
+        let (mut output, mut buffer1, mut buffer2) = make_cut_str_buffers();
+
+        cut_str(
+            b"a-b-c",
+            &opt,
+            &mut output,
+            &mut buffer1,
+            &mut buffer2,
+            b"\n",
+            1,
+        )
+        .unwrap();
+        assert_eq!(output, b"3:c-1:a-2:b\n".as_slice());
+
+        output.clear();
+        opt.bounds = UserBoundsList::from_str("1").unwrap();
+        cut_str(
+            b"a",
+            &opt,
+            &mut output,
+            &mut buffer1,
+            &mut buffer2,
+            b"\n",
+            1,
+        )
+        .unwrap();
+        assert_eq!(output, b"1:a\n".as_slice());
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn cut_str_it_prints_the_index_of_the_fields_as_json() {
+        let mut opt = make_fields_opt();
+        opt.with_index = true;
+        opt.json = true;
+        opt.join = true;
+        opt.replace_delimiter = Some(b",".to_vec());
+        opt.bounds = UserBoundsList::from_str("2:").unwrap();
+        let (mut output, mut buffer1, mut buffer2) = make_cut_str_buffers();
+
+        cut_str(
+            b"a-b-c",
+            &opt,
+            &mut output,
+            &mut buffer1,
+            &mut buffer2,
+            b"\n",
+            1,
+        )
+        .unwrap();
+        assert_eq!(output, b"[[\"2\",\"b\"],[\"3\",\"c\"]]\n".as_slice());
+    }
+
+    #[test]
+    fn cut_str_it_unescapes_the_fields() {
+        let mut opt = make_fields_opt();
+        opt.escape = Some(b'\\');
+        opt.unescape = true;
+        opt.bounds = UserBoundsList::from_str("1,2").unwrap();
+        let (mut output, mut buffer1, mut buffer2) = (Vec::new(), Vec::new(), Vec::new());
+
+        let line = br"a\-b\\-c\d";
+        cut_str(
+            line,
+            &opt,
+            &mut output,
+            &mut buffer1,
+            &mut buffer2,
+            b"\n",
+            1,
+        )
+        .unwrap();
+        assert_eq!(output, b"a-b\\c\\d\n".as_slice());
+    }
+
+    #[test]
+    fn test_read_and_cut_str_echo_non_delimited_strings() {
+        // read_and_cut_str is difficult to test, let's verify at least
+        // that it reads the input and appears to call cut_str
+
+        let opt = make_fields_opt();
+        let mut input = b"foo".as_slice();
+        let mut output = Vec::new();
+        read_and_cut_str(&mut input, &mut output, opt).unwrap();
+        assert_eq!(output, b"foo\n".as_slice());
+    }
+
+    fn make_cut_str_buffers() -> (Vec<u8>, Vec<Range<usize>>, Vec<u8>) {
+        let output = Vec::new();
+        let bounds_as_ranges = Vec::new();
+        let compressed_line_buffer = Vec::new();
+        (output, bounds_as_ranges, compressed_line_buffer)
+    }
+
+    #[test]
+    fn cut_str_echo_non_delimited_strings() {
+        let opt = make_fields_opt();
+        let eol = &[EOL::Newline as u8];
+
+        let line = b"foo";
+
+        // non-empty line missing the delimiter
+        let (mut output, mut buffer1, mut buffer2) = make_cut_str_buffers();
+        cut_str(line, &opt, &mut output, &mut buffer1, &mut buffer2, eol, 1).unwrap();
+        assert_eq!(output, b"foo\n".as_slice());
+
+        // empty line
+        let line = b"";
+        let (mut output, mut buffer1, mut buffer2) = make_cut_str_buffers();
+        cut_str(line, &opt, &mut output, &mut buffer1, &mut buffer2, eol, 1).unwrap();
+        assert_eq!(output, b"\n".as_slice());
+    }
+
+    #[test]
+    fn cut_str_skip_non_delimited_strings_when_requested() {
+        let mut opt = make_fields_opt();
+        let eol = &[EOL::Newline as u8];
+
+        opt.only_delimited = true;
+
+        // non-empty line missing the delimiter
+        let line = b"foo";
+        let (mut output, mut buffer1, mut buffer2) = make_cut_str_buffers();
+        cut_str(line, &opt, &mut output, &mut buffer1, &mut buffer2, eol, 1).unwrap();
+        assert_eq!(output, b"".as_slice());
+
+        // empty line
+        let line = b"";
+        let (mut output, mut buffer1, mut buffer2) = make_cut_str_buffers();
+        cut_str(line, &opt, &mut output, &mut buffer1, &mut buffer2, eol, 1).unwrap();
+        assert_eq!(output, b"".as_slice());
+    }
+
+    #[test]
+    fn cut_str_it_cut_a_field() {
+        let mut opt = make_fields_opt();
+        let (mut output, mut buffer1, mut buffer2) = make_cut_str_buffers();
+        let eol = &[EOL::Newline as u8];
+
+        let line = b"a-b-c";
+        opt.bounds = UserBoundsList::from_str("1").unwrap();
+
+        cut_str(line, &opt, &mut output, &mut buffer1, &mut buffer2, eol, 1).unwrap();
+        assert_eq!(output, b"a\n".as_slice());
+    }
+
+    #[test]
+    fn cut_str_it_cut_ranges() {
+        let mut opt = make_fields_opt();
+        let (mut output, mut buffer1, mut buffer2) = make_cut_str_buffers();
+        let eol = &[EOL::Newline as u8];
+
+        let line = b"a-b-c";
+        opt.bounds = UserBoundsList::from_str("1,1:3").unwrap();
+
+        cut_str(line, &opt, &mut output, &mut buffer1, &mut buffer2, eol, 1).unwrap();
+        assert_eq!(output, b"aa-b-c\n".as_slice());
+    }
+
+    #[cfg(feature = "regex")]
+    #[test]
+    fn cut_str_regex_it_grep_fields() {
+        let mut opt = make_fields_opt();
+        let (mut output, mut buffer1, mut buffer2) = make_cut_str_buffers();
+        let eol = &[EOL::Newline as u8];
+        opt.bounds = UserBoundsList::from_str("2,4").unwrap();
+        opt.grep_fields = Some(Regex::new("^x").unwrap());
+
+        for line in ["a-x1-c", "x-b-c", "a-b-c-x", "a-b"] {
+            cut_str(
+                line.as_bytes(),
+                &opt,
+                &mut output,
+                &mut buffer1,
+                &mut buffer2,
+                eol,
+                1,
+            )
+            .unwrap();
+        }
+        assert_eq!(output, b"a-x1-c\na-b-c-x\n".as_slice());
+    }
+
+    #[cfg(feature = "regex")]
+    #[test]
+    fn cut_str_regex_it_cut_a_field() {
+        let mut opt = make_fields_opt();
+        let (mut output, mut buffer1, mut buffer2) = make_cut_str_buffers();
+        let eol = &[EOL::Newline as u8];
+
+        let line = b"a.b,c";
+        opt.bounds = UserBoundsList::from_str("1,2,3").unwrap();
+        opt.regex_bag = Some(make_regex_bag());
+
+        cut_str(line, &opt, &mut output, &mut buffer1, &mut buffer2, eol, 1).unwrap();
+        assert_eq!(output, b"abc\n".as_slice());
+    }
+
+    #[cfg(feature = "regex")]
+    #[test]
+    fn cut_str_regex_it_format_named_captures() {
+        let mut opt = make_fields_opt();
+        let (mut output, mut buffer1, mut buffer2) = make_cut_str_buffers();
+        let eol = &[EOL::Newline as u8];
+
+        let line = b"a.b,c";
+        opt.bounds = UserBoundsList::from_str("{2}{sep}{1}").unwrap();
+        opt.regex_bag = Some(RegexBag {
+            normal: Regex::from_str("(?<sep>[.,])").unwrap(),
+            greedy: Regex::from_str("((?<sep>[.,]))+").unwrap(),
+        });
+
+        cut_str(line, &opt, &mut output, &mut buffer1, &mut buffer2, eol, 1).unwrap();
+        assert_eq!(output, b"b.a\n".as_slice());
+
+        // the group did not participate in the match
+        opt.bounds = UserBoundsList::from_str("{1}{missing}").unwrap();
+        opt.regex_bag = Some(RegexBag {
+            normal: Regex::from_str("[.,]|(?<missing>x)").unwrap(),
+            greedy: Regex::from_str("([.,]|(?<missing>x))+").unwrap(),
+        });
+
+        output.clear();
+        let res = cut_str(line, &opt, &mut output, &mut buffer1, &mut buffer2, eol, 1);
+        assert_eq!(
+            res.unwrap_err().to_string(),
+            "Capture group not found: missing"
+        );
+
+        opt.fallback_oob = Some("?".into());
+        output.clear();
+        cut_str(line, &opt, &mut output, &mut buffer1, &mut buffer2, eol, 1).unwrap();
+        assert_eq!(output, b"a?\n".as_slice());
+    }
+
+    #[test]
+    fn cut_str_it_format_the_whole_line() {
+        let mut opt = make_fields_opt();
+        let (mut output, mut buffer1, mut buffer2) = make_cut_str_buffers();
+        let eol = &[EOL::Newline as u8];
+
+        let line = b"-a-b-";
+        opt.bounds = UserBoundsList::from_str("{2}: {0}").unwrap();
+        opt.trim = Some(Trim::Both);
+
+        cut_str(line, &opt, &mut output, &mut buffer1, &mut buffer2, eol, 1).unwrap();
+        assert_eq!(output, b"b: -a-b-\n".as_slice());
+
+        // not affected by the single-field shortcut
+        let line = b"-a-";
+        opt.bounds = UserBoundsList::from_str("{LINE}").unwrap();
+
+        output.clear();
+        cut_str(line, &opt, &mut output, &mut buffer1, &mut buffer2, eol, 1).unwrap();
+        assert_eq!(output, b"-a-\n".as_slice());
+    }
+
+    #[test]
+    fn cut_str_it_format_environment_variables_not_expanded() {
+        let mut opt = make_fields_opt();
+        let (mut output, mut buffer1, mut buffer2) = make_cut_str_buffers();
+        let eol = &[EOL::Newline as u8];
+        opt.bounds = UserBoundsList::from_str("{1}@{$TUC_TEST_CUT_HOST}").unwrap();
+
+        std::env::set_var("TUC_TEST_CUT_HOST", "box");
+        cut_str(
+            b"a-b",
+            &opt,
+            &mut output,
+            &mut buffer1,
+            &mut buffer2,
+            eol,
+            1,
+        )
+        .unwrap();
+        assert_eq!(output, b"a@box\n".as_slice());
+
+        std::env::remove_var("TUC_TEST_CUT_HOST");
+        assert!(cut_str(
+            b"a-b",
+            &opt,
+            &mut output,
+            &mut buffer1,
+            &mut buffer2,
+            eol,
+            1
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn cut_str_it_format_conditional_segments() {
+        let mut opt = make_fields_opt();
+        let (mut output, mut buffer1, mut buffer2) = make_cut_str_buffers();
+        let eol = &[EOL::Newline as u8];
+        opt.bounds = UserBoundsList::from_str("{1}{3? ({3})}{2?:-}").unwrap();
+
+        let line = b"a--c";
+        cut_str(line, &opt, &mut output, &mut buffer1, &mut buffer2, eol, 1).unwrap();
+        assert_eq!(output, b"a (c)-\n".as_slice());
+
+        let line = b"a-b";
+        output.clear();
+        cut_str(line, &opt, &mut output, &mut buffer1, &mut buffer2, eol, 1).unwrap();
+        assert_eq!(output, b"a\n".as_slice());
+    }
+
+    #[test]
+    fn cut_str_it_format_fields_with_padding() {
+        let mut opt = make_fields_opt();
+        let (mut output, mut buffer1, mut buffer2) = make_cut_str_buffers();
+        let eol = &[EOL::Newline as u8];
+        opt.bounds = UserBoundsList::from_str("{1:<4}|{2:>4}|{3:^5=-}").unwrap();
+
+        let line = b"a-bb";
+        cut_str(line, &opt, &mut output, &mut buffer1, &mut buffer2, eol, 1).unwrap();
+        assert_eq!(output, b"a   |  bb|  -  \n".as_slice());
+
+        // not affected by the single-field shortcut
+        opt.bounds = UserBoundsList::from_str("{1:>4}").unwrap();
+        output.clear();
+        cut_str(b"a", &opt, &mut output, &mut buffer1, &mut buffer2, eol, 1).unwrap();
+        assert_eq!(output, b"   a\n".as_slice());
+    }
+
+    #[test]
+    fn cut_str_it_humanize_numbers() {
+        let mut opt = make_fields_opt();
+        let (mut output, mut buffer1, mut buffer2) = make_cut_str_buffers();
+        let eol = &[EOL::Newline as u8];
+        opt.bounds = UserBoundsList::from_str("{1|human} {2|size} {3|size}").unwrap();
+
+        let line = b"1234567-2048-n/a";
+        cut_str(line, &opt, &mut output, &mut buffer1, &mut buffer2, eol, 1).unwrap();
+        assert_eq!(output, b"1,234,567 2.0 KiB n/a\n".as_slice());
+    }
+
+    #[test]
+    fn cut_str_it_swap_two_fields() {
+        let mut opt = make_fields_opt();
+        let (mut output, mut buffer1, mut buffer2) = make_cut_str_buffers();
+        let eol = &[EOL::Newline as u8];
+        opt.swap = Some((
+            UserBounds::from_str("1").unwrap(),
+            UserBounds::from_str("-1").unwrap(),
+        ));
+
+        let line = b"a-b--c";
+        cut_str(line, &opt, &mut output, &mut buffer1, &mut buffer2, eol, 1).unwrap();
+        assert_eq!(output, b"c-b--a\n".as_slice());
+
+        // non-delimited lines are printed as they are
+        output.clear();
+        cut_str(
+            b"abc",
+            &opt,
+            &mut output,
+            &mut buffer1,
+            &mut buffer2,
+            eol,
+            1,
+        )
+        .unwrap();
+        assert_eq!(output, b"abc\n".as_slice());
+
+        opt.swap = Some((
+            UserBounds::from_str("1").unwrap(),
+            UserBounds::from_str("4").unwrap(),
+        ));
+        output.clear();
+        let res = cut_str(
+            b"a-b",
+            &opt,
+            &mut output,
+            &mut buffer1,
+            &mut buffer2,
+            eol,
+            1,
+        );
+        assert!(matches!(res, Err(TucError::OutOfBounds { index: 4 })));
+        assert_eq!(res.unwrap_err().to_string(), "Out of bounds: 4");
+    }
+
+    #[test]
+    fn cut_str_it_complement_keeping_original_delimiters() {
+        let mut opt = make_fields_opt();
+        let (mut output, mut buffer1, mut buffer2) = make_cut_str_buffers();
+        let eol = &[EOL::Newline as u8];
+        opt.complement = true;
+        opt.keep_original_delimiters = true;
+        opt.greedy_delimiter = true;
+
+        opt.bounds = UserBoundsList::from_str("2").unwrap();
+        cut_str(
+            b"a--b-c",
+            &opt,
+            &mut output,
+            &mut buffer1,
+            &mut buffer2,
+            eol,
+            1,
+        )
+        .unwrap();
+        assert_eq!(output, b"a-c\n".as_slice());
+
+        opt.bounds = UserBoundsList::from_str("1,3").unwrap();
+        output.clear();
+        cut_str(
+            b"a--b-c---d",
+            &opt,
+            &mut output,
+            &mut buffer1,
+            &mut buffer2,
+            eol,
+            1,
+        )
+        .unwrap();
+        assert_eq!(output, b"b---d\n".as_slice());
+
+        // bounds out of range select nothing
+        opt.bounds = UserBoundsList::from_str("5").unwrap();
+        output.clear();
+        cut_str(
+            b"a--b",
+            &opt,
+            &mut output,
+            &mut buffer1,
+            &mut buffer2,
+            eol,
+            1,
+        )
+        .unwrap();
+        assert_eq!(output, b"a--b\n".as_slice());
+    }
+
+    #[test]
+    fn cut_str_it_pad_fields() {
+        let mut opt = make_fields_opt();
+        let (mut output, mut buffer1, mut buffer2) = make_cut_str_buffers();
+        let eol = &[EOL::Newline as u8];
+        opt.bounds = UserBoundsList::from_str("{1}|{2:>4}|").unwrap();
+        opt.pad = Some(Pad::from_str("3=.").unwrap());
+
+        let line = b"a-b";
+        cut_str(line, &opt, &mut output, &mut buffer1, &mut buffer2, eol, 1).unwrap();
+        assert_eq!(output, b"a..|   b|\n".as_slice());
+
+        // also when a single field is found
+        opt.bounds = UserBoundsList::from_str("1").unwrap();
+        output.clear();
+        cut_str(b"a", &opt, &mut output, &mut buffer1, &mut buffer2, eol, 1).unwrap();
+        assert_eq!(output, b"a..\n".as_slice());
+    }
+
+    #[test]
+    fn cut_str_it_maps_fields() {
+        let mut opt = make_fields_opt();
+        let (mut output, mut buffer1, mut buffer2) = make_cut_str_buffers();
+        let eol = &[EOL::Newline as u8];
+        opt.bounds = UserBoundsList::from_str("{2}:{1}").unwrap();
+        opt.map = Some(TransformPipeline::from_str("trim|upper").unwrap());
+
+        cut_str(
+            b" a - b ",
+            &opt,
+            &mut output,
+            &mut buffer1,
+            &mut buffer2,
+            eol,
+            1,
+        )
+        .unwrap();
+        assert_eq!(output, b"B:A\n".as_slice());
+
+        // also when a single field is found
+        opt.bounds = UserBoundsList::from_str("1").unwrap();
+        output.clear();
+        cut_str(
+            b" a ",
+            &opt,
+            &mut output,
+            &mut buffer1,
+            &mut buffer2,
+            eol,
+            1,
+        )
+        .unwrap();
+        assert_eq!(output, b"A\n".as_slice());
+    }
+
+    #[test]
+    fn cut_str_it_transforms_single_placeholders() {
+        let mut opt = make_fields_opt();
+        let (mut output, mut buffer1, mut buffer2) = make_cut_str_buffers();
+        let eol = &[EOL::Newline as u8];
+        opt.bounds = UserBoundsList::from_str("{2:upper}:{1}:{3:trim:lower}").unwrap();
+
+        cut_str(
+            b"a-b- C ",
+            &opt,
+            &mut output,
+            &mut buffer1,
+            &mut buffer2,
+            eol,
+            1,
+        )
+        .unwrap();
+        assert_eq!(output, b"B:a:c\n".as_slice());
+    }
 
-    use std::str::FromStr;
+    #[test]
+    fn cut_str_it_truncate_fields() {
+        let mut opt = make_fields_opt();
+        let (mut output, mut buffer1, mut buffer2) = make_cut_str_buffers();
+        let eol = &[EOL::Newline as u8];
+        opt.bounds = UserBoundsList::from_str("{1}|{2:<5}|").unwrap();
+        opt.truncate = Some(Truncate::from_str("3").unwrap());
 
-    use super::*;
+        let line = b"abcd-ab";
+        cut_str(line, &opt, &mut output, &mut buffer1, &mut buffer2, eol, 1).unwrap();
+        assert_eq!(output, "ab…|ab   |\n".as_bytes());
 
-    fn make_fields_opt() -> Opt {
-        Opt {
-            bounds_type: BoundsType::Fields,
-            delimiter: "-".into(),
-            ..Opt::default()
-        }
+        // also when a single field is found
+        opt.bounds = UserBoundsList::from_str("1").unwrap();
+        output.clear();
+        cut_str(
+            b"abcd",
+            &opt,
+            &mut output,
+            &mut buffer1,
+            &mut buffer2,
+            eol,
+            1,
+        )
+        .unwrap();
+        assert_eq!(output, "ab…\n".as_bytes());
     }
 
-    #[cfg(feature = "regex")]
-    fn make_regex_bag() -> RegexBag {
-        RegexBag {
-            normal: Regex::from_str("[.,]").unwrap(),
-            greedy: Regex::from_str("([.,])+").unwrap(),
-        }
+    #[test]
+    fn cut_str_it_insert_a_field() {
+        let mut opt = make_fields_opt();
+        let (mut output, mut buffer1, mut buffer2) = make_cut_str_buffers();
+        let eol = &[EOL::Newline as u8];
+
+        let mut insert = |position: i32, value: &str, line: &[u8], opt: &mut Opt| {
+            opt.insert = Some((position, AddedField::from_str(value).unwrap()));
+            output.clear();
+            cut_str(line, opt, &mut output, &mut buffer1, &mut buffer2, eol, 3)
+                .map(|_| String::from_utf8(output.clone()).unwrap())
+        };
+
+        assert_eq!(insert(1, "x", b"a-b", &mut opt).unwrap(), "x-a-b\n");
+        assert_eq!(insert(2, "x", b"a-b", &mut opt).unwrap(), "a-x-b\n");
+        assert_eq!(insert(3, "x", b"a-b", &mut opt).unwrap(), "a-b-x\n");
+        assert_eq!(insert(-1, "x", b"a-b", &mut opt).unwrap(), "a-b-x\n");
+        assert_eq!(insert(-3, "x", b"a-b", &mut opt).unwrap(), "x-a-b\n");
+        assert_eq!(insert(2, "{NR}", b"a", &mut opt).unwrap(), "a-3\n");
+        assert!(insert(4, "x", b"a-b", &mut opt).is_err());
+        assert!(insert(-4, "x", b"a-b", &mut opt).is_err());
+
+        // original delimiters are preserved
+        opt.greedy_delimiter = true;
+        assert_eq!(insert(2, "x", b"a--b", &mut opt).unwrap(), "a--x-b\n");
     }
 
-    #[cfg(feature = "regex")]
-    fn make_cut_characters_regex_bag() -> RegexBag {
-        RegexBag {
-            normal: Regex::from_str("\\b|\\B").unwrap(),
-            greedy: Regex::from_str("(\\b|\\B)+").unwrap(),
-        }
+    #[test]
+    fn cut_str_it_cut_sub_fields() {
+        let mut opt = make_fields_opt();
+        let (mut output, mut buffer1, mut buffer2) = make_cut_str_buffers();
+        let eol = &[EOL::Newline as u8];
+        opt.delimiter = " ".into();
+        opt.sub_delimiter = Some("@".into());
+
+        opt.bounds = UserBoundsList::from_str("{2.-1}/{2.1}/{1.1}/{2.3=none}").unwrap();
+        let line = b"GET alice@host 200";
+        cut_str(line, &opt, &mut output, &mut buffer1, &mut buffer2, eol, 1).unwrap();
+        assert_eq!(output, b"host/alice/GET/none\n".as_slice());
+
+        // also when the line has no delimiter
+        opt.bounds = UserBoundsList::from_str("1.2").unwrap();
+        output.clear();
+        cut_str(
+            b"a@b",
+            &opt,
+            &mut output,
+            &mut buffer1,
+            &mut buffer2,
+            eol,
+            1,
+        )
+        .unwrap();
+        assert_eq!(output, b"b\n".as_slice());
     }
 
     #[test]
-    fn test_fill_with_fields_locations() {
-        let mut v_range: Vec<Range<usize>> = Vec::new();
+    fn cut_str_it_cut_bytes_of_fields() {
+        let mut opt = make_fields_opt();
+        let (mut output, mut buffer1, mut buffer2) = make_cut_str_buffers();
+        let eol = &[EOL::Newline as u8];
 
-        v_range.clear();
-        fill_with_fields_locations(&mut v_range, b"", b"-");
-        assert_eq!(v_range, vec![] as Vec<Range<usize>>);
+        opt.bounds = UserBoundsList::from_str("{2[1:3]}/{1[-2:]}/{3[5]=none}").unwrap();
+        let line = b"abcd-0123456";
+        cut_str(line, &opt, &mut output, &mut buffer1, &mut buffer2, eol, 1).unwrap();
+        assert_eq!(output, b"012/cd/none\n".as_slice());
+
+        // also when the line has no delimiter
+        opt.bounds = UserBoundsList::from_str("1[2:]").unwrap();
+        output.clear();
+        cut_str(
+            b"abc",
+            &opt,
+            &mut output,
+            &mut buffer1,
+            &mut buffer2,
+            eol,
+            1,
+        )
+        .unwrap();
+        assert_eq!(output, b"bc\n".as_slice());
+
+        opt.bounds = UserBoundsList::from_str("1[5]").unwrap();
+        assert!(cut_str(
+            b"abc",
+            &opt,
+            &mut output,
+            &mut buffer1,
+            &mut buffer2,
+            eol,
+            1
+        )
+        .is_err());
+    }
 
-        v_range.clear();
-        fill_with_fields_locations(&mut v_range, b"a", b"-");
-        assert_eq!(v_range, vec![Range { start: 0, end: 1 }]);
+    #[test]
+    fn cut_str_it_set_fields() {
+        let mut opt = make_fields_opt();
+        let (mut output, mut buffer1, mut buffer2) = make_cut_str_buffers();
+        let eol = &[EOL::Newline as u8];
+        opt.greedy_delimiter = true;
+        opt.set = vec![
+            (
+                UserBounds::from_str("2").unwrap(),
+                UserBoundsList::from_template("x").unwrap(),
+            ),
+            (
+                UserBounds::from_str("-1").unwrap(),
+                UserBoundsList::from_template("<{1}{-1}>").unwrap(),
+            ),
+        ];
+
+        cut_str(
+            b"a--b-c",
+            &opt,
+            &mut output,
+            &mut buffer1,
+            &mut buffer2,
+            eol,
+            1,
+        )
+        .unwrap();
+        assert_eq!(output, b"a--x-<ac>\n".as_slice());
+
+        // out of bounds
+        assert!(cut_str(b"a", &opt, &mut output, &mut buffer1, &mut buffer2, eol, 1).is_err());
+    }
 
-        v_range.clear();
-        fill_with_fields_locations(&mut v_range, b"a-b", b"-");
-        assert_eq!(
-            v_range,
-            vec![Range { start: 0, end: 1 }, Range { start: 2, end: 3 }]
-        );
+    #[test]
+    fn cut_str_it_assert_the_number_of_fields() {
+        let mut opt = make_fields_opt();
+        let (mut output, mut buffer1, mut buffer2) = make_cut_str_buffers();
+        let eol = &[EOL::Newline as u8];
+        opt.assert_fields = Some(FieldsCount::from_str("2:3").unwrap());
+
+        cut_str(
+            b"a-b",
+            &opt,
+            &mut output,
+            &mut buffer1,
+            &mut buffer2,
+            eol,
+            1,
+        )
+        .unwrap();
+        assert_eq!(output, b"a-b\n".as_slice());
 
-        v_range.clear();
-        fill_with_fields_locations(&mut v_range, b"-a-", b"-");
+        let err = cut_str(b"a", &opt, &mut output, &mut buffer1, &mut buffer2, eol, 2);
         assert_eq!(
-            v_range,
-            vec![
-                Range { start: 0, end: 0 },
-                Range { start: 1, end: 2 },
-                Range { start: 3, end: 3 }
-            ]
+            err.unwrap_err().to_string(),
+            "Record 2: expected 2 to 3 fields, found 1"
         );
 
-        v_range.clear();
-        fill_with_fields_locations(&mut v_range, b"a--", b"-");
-        assert_eq!(
-            v_range,
-            vec![
-                Range { start: 0, end: 1 },
-                Range { start: 2, end: 2 },
-                Range { start: 3, end: 3 }
-            ]
-        );
+        let err = cut_str(b"", &opt, &mut output, &mut buffer1, &mut buffer2, eol, 3);
+        assert!(err.is_err());
     }
 
     #[test]
-    fn test_fill_with_fields_locations_greedy() {
-        let mut v_range: Vec<Range<usize>> = Vec::new();
-        let empty_vec: Vec<Range<usize>> = vec![];
+    fn read_and_cut_str_it_skips_invalid_records_when_relaxed() {
+        let mut opt = make_fields_opt();
+        opt.assert_fields = Some(FieldsCount::from_str("2").unwrap());
 
-        v_range.clear();
-        fill_with_fields_locations_greedy(&mut v_range, b"", b"-");
-        assert_eq!(v_range, empty_vec);
+        let mut input = b"a-b\nc\nd-e\n".as_slice();
+        let mut output = Vec::new();
+        let err = read_and_cut_str(&mut input, &mut output, opt).unwrap_err();
+        assert_eq!(err.to_string(), "Record 2: expected 2 fields, found 1");
+        assert_eq!(output, b"a-b\n".as_slice());
 
-        v_range.clear();
-        fill_with_fields_locations_greedy(&mut v_range, b"a", b"-");
-        assert_eq!(v_range, vec![Range { start: 0, end: 1 }]);
+        let mut opt = make_fields_opt();
+        opt.assert_fields = Some(FieldsCount::from_str("2").unwrap());
+        opt.relaxed = true;
 
-        v_range.clear();
-        fill_with_fields_locations_greedy(&mut v_range, b"-", b"-");
+        let mut input = b"a-b\nc\nd-e\n".as_slice();
+        let mut output = Vec::new();
+        read_and_cut_str(&mut input, &mut output, opt).unwrap();
+        assert_eq!(output, b"a-b\nd-e\n".as_slice());
+    }
+
+    #[test]
+    fn fields_it_splits_a_line() {
+        let mut opt = make_fields_opt();
         assert_eq!(
-            v_range,
-            vec![Range { start: 0, end: 0 }, Range { start: 1, end: 1 }]
+            fields(b"a-b--c", &opt).collect::<Vec<_>>(),
+            [&b"a"[..], b"b", b"", b"c"]
         );
+        assert_eq!(fields(b"", &opt).count(), 0);
 
-        v_range.clear();
-        fill_with_fields_locations_greedy(&mut v_range, b"-a--b", b"-");
+        opt.trim = Some(Trim::Both);
+        opt.compress_delimiter = true;
         assert_eq!(
-            v_range,
-            vec![
-                Range { start: 0, end: 0 },
-                Range { start: 1, end: 2 },
-                Range { start: 4, end: 5 }
-            ]
+            fields(b"-a-b--c-", &opt).collect::<Vec<_>>(),
+            [&b"a"[..], b"b", b"c"]
         );
 
-        v_range.clear();
-        fill_with_fields_locations_greedy(&mut v_range, b"-a--", b"-");
+        let mut opt = make_fields_opt();
+        opt.csv = true;
         assert_eq!(
-            v_range,
-            vec![
-                Range { start: 0, end: 0 },
-                Range { start: 1, end: 2 },
-                Range { start: 4, end: 4 }
-            ]
+            fields(b"\"a-b\"-c", &opt).collect::<Vec<_>>(),
+            [&b"\"a-b\""[..], b"c"]
+        );
+
+        let mut opt = make_fields_opt();
+        opt.quote = Some(b'\'');
+        assert_eq!(
+            fields(b"'a-b'-\"c-d\"", &opt).collect::<Vec<_>>(),
+            [&b"'a-b'"[..], b"\"c", b"d\""]
         );
     }
 
     #[test]
-    fn test_read_and_cut_str_echo_non_delimited_strings() {
-        // read_and_cut_str is difficult to test, let's verify at least
-        // that it reads the input and appears to call cut_str
+    fn read_and_cut_str_it_skips_records_out_of_bounds_when_lenient() {
+        let mut opt = make_fields_opt();
+        opt.bounds = UserBoundsList::from_str("{1}/{2}").unwrap();
+        opt.lenient = true;
 
-        let opt = make_fields_opt();
-        let mut input = b"foo".as_slice();
+        let mut input = b"a-b\nc\nd-e\n".as_slice();
         let mut output = Vec::new();
         read_and_cut_str(&mut input, &mut output, opt).unwrap();
-        assert_eq!(output, b"foo\n".as_slice());
-    }
-
-    fn make_cut_str_buffers() -> (Vec<u8>, Vec<Range<usize>>, Vec<u8>) {
-        let output = Vec::new();
-        let bounds_as_ranges = Vec::new();
-        let compressed_line_buffer = Vec::new();
-        (output, bounds_as_ranges, compressed_line_buffer)
+        assert_eq!(output, b"a/b\nd/e\n".as_slice());
     }
 
     #[test]
-    fn cut_str_echo_non_delimited_strings() {
-        let opt = make_fields_opt();
-        let eol = &[EOL::Newline as u8];
+    fn read_and_cut_str_it_reads_csv_records_across_lines() {
+        let mut opt = make_fields_opt();
+        opt.delimiter = b",".to_vec();
+        opt.csv = true;
+        opt.bounds = UserBoundsList::from_str("2,1").unwrap();
 
-        let line = b"foo";
+        let mut input = b"a,\"b\n\"\"c\"\"\n\",d\ne,f\ng,\"h".as_slice();
+        let mut output = Vec::new();
+        read_and_cut_str(&mut input, &mut output, opt).unwrap();
+        assert_eq!(output, b"b\n\"c\"\na\nfe\n\"hg\n".as_slice());
+    }
 
-        // non-empty line missing the delimiter
-        let (mut output, mut buffer1, mut buffer2) = make_cut_str_buffers();
-        cut_str(line, &opt, &mut output, &mut buffer1, &mut buffer2, eol).unwrap();
-        assert_eq!(output, b"foo\n".as_slice());
+    #[test]
+    fn read_and_cut_str_it_follows_the_error_policy() {
+        let read_and_cut = |errors| {
+            let mut opt = make_fields_opt();
+            opt.bounds = UserBoundsList::from_str("{1}/{2}").unwrap();
+            opt.errors = errors;
+
+            let mut input = b"a-b\nc\nd-e\nf\n".as_slice();
+            let mut output = Vec::new();
+            let result = read_and_cut_str(&mut input, &mut output, opt);
+            (result.map_err(|e| e.to_string()), output)
+        };
+
+        // the failing record does not leave partial output
+        assert_eq!(
+            read_and_cut(ErrorPolicy::Collect),
+            (
+                Err("2 records could not be processed".into()),
+                b"a/b\nd/e\n".to_vec()
+            )
+        );
+        assert_eq!(
+            read_and_cut(ErrorPolicy::Ignore),
+            (Ok(()), b"a/b\nd/e\n".to_vec())
+        );
 
-        // empty line
-        let line = b"";
-        let (mut output, mut buffer1, mut buffer2) = make_cut_str_buffers();
-        cut_str(line, &opt, &mut output, &mut buffer1, &mut buffer2, eol).unwrap();
-        assert_eq!(output, b"\n".as_slice());
+        let (result, output) = read_and_cut(ErrorPolicy::FailFast);
+        assert_eq!(result, Err("line 2: Out of bounds: 2".into()));
+        assert_eq!(output, b"a/b\nc/".to_vec());
     }
 
     #[test]
-    fn cut_str_skip_non_delimited_strings_when_requested() {
+    fn cut_str_it_add_fields() {
         let mut opt = make_fields_opt();
-        let eol = &[EOL::Newline as u8];
-
-        opt.only_delimited = true;
-
-        // non-empty line missing the delimiter
-        let line = b"foo";
         let (mut output, mut buffer1, mut buffer2) = make_cut_str_buffers();
-        cut_str(line, &opt, &mut output, &mut buffer1, &mut buffer2, eol).unwrap();
-        assert_eq!(output, b"".as_slice());
+        let eol = &[EOL::Newline as u8];
+        opt.bounds = UserBoundsList::from_str("2,1").unwrap();
+        opt.add_fields = vec![
+            AddedField::from_str("x").unwrap(),
+            AddedField::from_str("n{NR}").unwrap(),
+        ];
+
+        cut_str(
+            b"a-b",
+            &opt,
+            &mut output,
+            &mut buffer1,
+            &mut buffer2,
+            eol,
+            7,
+        )
+        .unwrap();
+        assert_eq!(output, b"ba-x-n7\n".as_slice());
 
-        // empty line
-        let line = b"";
-        let (mut output, mut buffer1, mut buffer2) = make_cut_str_buffers();
-        cut_str(line, &opt, &mut output, &mut buffer1, &mut buffer2, eol).unwrap();
-        assert_eq!(output, b"".as_slice());
+        opt.join = true;
+        opt.replace_delimiter = Some(",".into());
+        output.clear();
+        cut_str(
+            b"a-b",
+            &opt,
+            &mut output,
+            &mut buffer1,
+            &mut buffer2,
+            eol,
+            8,
+        )
+        .unwrap();
+        assert_eq!(output, b"b,a,x,n8\n".as_slice());
+
+        #[cfg(feature = "json")]
+        {
+            opt.json = true;
+            output.clear();
+            cut_str(
+                b"a-b",
+                &opt,
+                &mut output,
+                &mut buffer1,
+                &mut buffer2,
+                eol,
+                9,
+            )
+            .unwrap();
+            assert_eq!(output, b"[\"b\",\"a\",\"x\",\"n9\"]\n".as_slice());
+        }
     }
 
     #[test]
-    fn cut_str_it_cut_a_field() {
+    fn cut_str_it_separates_output_and_replace_delimiter() {
         let mut opt = make_fields_opt();
         let (mut output, mut buffer1, mut buffer2) = make_cut_str_buffers();
         let eol = &[EOL::Newline as u8];
-
-        let line = b"a-b-c";
-        opt.bounds = UserBoundsList::from_str("1").unwrap();
-
-        cut_str(line, &opt, &mut output, &mut buffer1, &mut buffer2, eol).unwrap();
-        assert_eq!(output, b"a\n".as_slice());
+        opt.bounds = UserBoundsList::from_str("1:2,4").unwrap();
+        opt.join = true;
+        opt.output_delimiter = Some(",".into());
+
+        cut_str(
+            b"a-b-c-d",
+            &opt,
+            &mut output,
+            &mut buffer1,
+            &mut buffer2,
+            eol,
+            1,
+        )
+        .unwrap();
+        assert_eq!(output, b"a-b,d\n".as_slice());
+
+        opt.replace_delimiter = Some("_".into());
+        output.clear();
+        cut_str(
+            b"a-b-c-d",
+            &opt,
+            &mut output,
+            &mut buffer1,
+            &mut buffer2,
+            eol,
+            2,
+        )
+        .unwrap();
+        assert_eq!(output, b"a_b,d\n".as_slice());
     }
 
     #[test]
-    fn cut_str_it_cut_ranges() {
+    fn cut_str_it_print_pairs() {
         let mut opt = make_fields_opt();
         let (mut output, mut buffer1, mut buffer2) = make_cut_str_buffers();
         let eol = &[EOL::Newline as u8];
-
-        let line = b"a-b-c";
-        opt.bounds = UserBoundsList::from_str("1,1:3").unwrap();
-
-        cut_str(line, &opt, &mut output, &mut buffer1, &mut buffer2, eol).unwrap();
-        assert_eq!(output, b"aa-b-c\n".as_slice());
+        opt.pairs = true;
+
+        cut_str(
+            b"a--b",
+            &opt,
+            &mut output,
+            &mut buffer1,
+            &mut buffer2,
+            eol,
+            1,
+        )
+        .unwrap();
+        assert_eq!(output, b"1\ta\n2\t\n3\tb\n\n".as_slice());
     }
 
-    #[cfg(feature = "regex")]
     #[test]
-    fn cut_str_regex_it_cut_a_field() {
+    fn cut_str_it_print_delimiters() {
         let mut opt = make_fields_opt();
         let (mut output, mut buffer1, mut buffer2) = make_cut_str_buffers();
         let eol = &[EOL::Newline as u8];
+        opt.print_delimiters = true;
 
-        let line = b"a.b,c";
-        opt.bounds = UserBoundsList::from_str("1,2,3").unwrap();
-        opt.regex_bag = Some(make_regex_bag());
+        let line = b"a--b-c";
+        cut_str(line, &opt, &mut output, &mut buffer1, &mut buffer2, eol, 1).unwrap();
+        assert_eq!(output, b"2:\"-\" 3:\"-\" 5:\"-\"\n".as_slice());
 
-        cut_str(line, &opt, &mut output, &mut buffer1, &mut buffer2, eol).unwrap();
-        assert_eq!(output, b"abc\n".as_slice());
+        opt.greedy_delimiter = true;
+        output.clear();
+        cut_str(line, &opt, &mut output, &mut buffer1, &mut buffer2, eol, 1).unwrap();
+        assert_eq!(output, b"2:\"--\" 5:\"-\"\n".as_slice());
+
+        output.clear();
+        cut_str(
+            b"abc",
+            &opt,
+            &mut output,
+            &mut buffer1,
+            &mut buffer2,
+            eol,
+            1,
+        )
+        .unwrap();
+        assert_eq!(output, b"\n".as_slice());
     }
 
     #[test]
@@ -714,7 +3143,7 @@ mod tests {
         let line = b"a-b-c";
         opt.bounds = UserBoundsList::from_str("1,3").unwrap();
 
-        cut_str(line, &opt, &mut output, &mut buffer1, &mut buffer2, eol).unwrap();
+        cut_str(line, &opt, &mut output, &mut buffer1, &mut buffer2, eol, 1).unwrap();
         assert_eq!(output, b"ac\n".as_slice());
     }
 
@@ -730,14 +3159,14 @@ mod tests {
         let (mut output, mut buffer1, mut buffer2) = make_cut_str_buffers();
         opt.compress_delimiter = false;
 
-        cut_str(line, &opt, &mut output, &mut buffer1, &mut buffer2, eol).unwrap();
+        cut_str(line, &opt, &mut output, &mut buffer1, &mut buffer2, eol, 1).unwrap();
         assert_eq!(output, b"\n".as_slice());
 
         // now we do it again while compressing delimiters
         let (mut output, mut buffer1, mut buffer2) = make_cut_str_buffers();
         opt.compress_delimiter = true;
 
-        cut_str(line, &opt, &mut output, &mut buffer1, &mut buffer2, eol).unwrap();
+        cut_str(line, &opt, &mut output, &mut buffer1, &mut buffer2, eol, 1).unwrap();
         assert_eq!(output, b"a\n".as_slice());
 
         // and again but this time requesting a full range
@@ -745,7 +3174,7 @@ mod tests {
         opt.bounds = UserBoundsList::from_str("1:").unwrap();
         opt.compress_delimiter = true;
 
-        cut_str(line, &opt, &mut output, &mut buffer1, &mut buffer2, eol).unwrap();
+        cut_str(line, &opt, &mut output, &mut buffer1, &mut buffer2, eol, 1).unwrap();
         assert_eq!(output, b"-a-b-\n".as_slice());
 
         // let's check with a line that doesn't start/end with delimiters
@@ -754,7 +3183,7 @@ mod tests {
         opt.bounds = UserBoundsList::from_str("1:").unwrap();
         opt.compress_delimiter = true;
 
-        cut_str(line, &opt, &mut output, &mut buffer1, &mut buffer2, eol).unwrap();
+        cut_str(line, &opt, &mut output, &mut buffer1, &mut buffer2, eol, 1).unwrap();
         assert_eq!(output, b"a-b\n".as_slice());
     }
 
@@ -772,7 +3201,7 @@ mod tests {
         opt.replace_delimiter = None;
 
         assert_eq!(
-            cut_str(line, &opt, &mut output, &mut buffer1, &mut buffer2, eol)
+            cut_str(line, &opt, &mut output, &mut buffer1, &mut buffer2, eol, 1)
                 .err()
                 .map(|x| x.to_string()),
             Some(
@@ -795,7 +3224,7 @@ mod tests {
         opt.regex_bag = Some(make_regex_bag());
         opt.replace_delimiter = Some("-".into());
 
-        cut_str(line, &opt, &mut output, &mut buffer1, &mut buffer2, eol).unwrap();
+        cut_str(line, &opt, &mut output, &mut buffer1, &mut buffer2, eol, 1).unwrap();
         assert_eq!(output, b"abc\n".as_slice());
 
         let line = b".,a,,,b..c";
@@ -805,7 +3234,7 @@ mod tests {
         opt.regex_bag = Some(make_regex_bag());
         opt.replace_delimiter = Some("-".into());
 
-        cut_str(line, &opt, &mut output, &mut buffer1, &mut buffer2, eol).unwrap();
+        cut_str(line, &opt, &mut output, &mut buffer1, &mut buffer2, eol, 1).unwrap();
         assert_eq!(output, b"-a-b-c\n".as_slice());
     }
 
@@ -821,7 +3250,7 @@ mod tests {
         opt.bounds_type = BoundsType::Characters;
         opt.regex_bag = Some(make_cut_characters_regex_bag());
 
-        cut_str(line, &opt, &mut output, &mut buffer1, &mut buffer2, eol).unwrap();
+        cut_str(line, &opt, &mut output, &mut buffer1, &mut buffer2, eol, 1).unwrap();
         assert_eq!(output, "🤩\n".as_bytes());
     }
 
@@ -839,7 +3268,7 @@ mod tests {
         opt.replace_delimiter = Some("-".into());
         opt.join = true; // implied when using BoundsType::Characters
 
-        cut_str(line, &opt, &mut output, &mut buffer1, &mut buffer2, eol).unwrap();
+        cut_str(line, &opt, &mut output, &mut buffer1, &mut buffer2, eol, 1).unwrap();
         assert_eq!(&String::from_utf8_lossy(&output), "😁-🤩-😝-😎\n");
     }
 
@@ -853,7 +3282,7 @@ mod tests {
         opt.bounds = UserBoundsList::from_str("2").unwrap();
         opt.eol = EOL::Zero;
 
-        cut_str(line, &opt, &mut output, &mut buffer1, &mut buffer2, eol).unwrap();
+        cut_str(line, &opt, &mut output, &mut buffer1, &mut buffer2, eol, 1).unwrap();
         assert_eq!(output, b"b\0".as_slice());
     }
 
@@ -867,10 +3296,40 @@ mod tests {
         opt.bounds = UserBoundsList::from_str("2").unwrap();
         opt.complement = true;
 
-        cut_str(line, &opt, &mut output, &mut buffer1, &mut buffer2, eol).unwrap();
+        cut_str(line, &opt, &mut output, &mut buffer1, &mut buffer2, eol, 1).unwrap();
         assert_eq!(output, b"ac\n".as_slice());
     }
 
+    #[cfg(feature = "regex")]
+    #[test]
+    fn cut_str_it_complement_characters() {
+        let mut opt = make_fields_opt();
+        let (mut output, mut buffer1, mut buffer2) = make_cut_str_buffers();
+        let eol = &[EOL::Newline as u8];
+
+        opt.bounds = UserBoundsList::from_str("1").unwrap();
+        opt.bounds_type = BoundsType::Characters;
+        opt.regex_bag = Some(make_cut_characters_regex_bag());
+        opt.complement = true;
+
+        cut_str(
+            "😁🤩😝".as_bytes(),
+            &opt,
+            &mut output,
+            &mut buffer1,
+            &mut buffer2,
+            eol,
+            1,
+        )
+        .unwrap();
+        assert_eq!(output, "🤩😝\n".as_bytes());
+
+        // removing every character leaves an empty line
+        output.clear();
+        cut_str(b"a", &opt, &mut output, &mut buffer1, &mut buffer2, eol, 1).unwrap();
+        assert_eq!(output, b"\n".as_slice());
+    }
+
     #[test]
     fn cut_str_it_join_fields() {
         let mut opt = make_fields_opt();
@@ -881,7 +3340,7 @@ mod tests {
         opt.bounds = UserBoundsList::from_str("1,3").unwrap();
         opt.join = true;
 
-        cut_str(line, &opt, &mut output, &mut buffer1, &mut buffer2, eol).unwrap();
+        cut_str(line, &opt, &mut output, &mut buffer1, &mut buffer2, eol, 1).unwrap();
         assert_eq!(output, b"a-c\n".as_slice());
     }
 
@@ -896,7 +3355,7 @@ mod tests {
         opt.join = true;
         opt.replace_delimiter = Some("*".into());
 
-        cut_str(line, &opt, &mut output, &mut buffer1, &mut buffer2, eol).unwrap();
+        cut_str(line, &opt, &mut output, &mut buffer1, &mut buffer2, eol, 1).unwrap();
         assert_eq!(output, b"a*c\n".as_slice());
     }
 
@@ -914,7 +3373,7 @@ mod tests {
         opt.join = true;
 
         assert_eq!(
-            cut_str(line, &opt, &mut output, &mut buffer1, &mut buffer2, eol)
+            cut_str(line, &opt, &mut output, &mut buffer1, &mut buffer2, eol, 1)
                 .err()
                 .map(|x| x.to_string()),
             Some("Cannot use --regex and --join without --replace-delimiter".to_owned())
@@ -935,7 +3394,7 @@ mod tests {
         opt.join = true;
         opt.replace_delimiter = Some("<->".into());
 
-        cut_str(line, &opt, &mut output, &mut buffer1, &mut buffer2, eol).unwrap();
+        cut_str(line, &opt, &mut output, &mut buffer1, &mut buffer2, eol, 1).unwrap();
         assert_eq!(output, b"a<->c\n".as_slice());
     }
 
@@ -948,7 +3407,7 @@ mod tests {
         let line = b"a-b-c";
         opt.bounds = UserBoundsList::from_str("{1} < {3} > {2}").unwrap();
 
-        cut_str(line, &opt, &mut output, &mut buffer1, &mut buffer2, eol).unwrap();
+        cut_str(line, &opt, &mut output, &mut buffer1, &mut buffer2, eol, 1).unwrap();
         assert_eq!(output, b"a < c > b\n".as_slice());
     }
 
@@ -962,7 +3421,7 @@ mod tests {
         opt.bounds = UserBoundsList::from_str("2").unwrap();
         opt.greedy_delimiter = true;
 
-        cut_str(line, &opt, &mut output, &mut buffer1, &mut buffer2, eol).unwrap();
+        cut_str(line, &opt, &mut output, &mut buffer1, &mut buffer2, eol, 1).unwrap();
         assert_eq!(output, b"b\n".as_slice());
 
         // check that, opposite to compress_delimiter, the delimiter is kept long
@@ -974,7 +3433,7 @@ mod tests {
         opt.bounds = UserBoundsList::from_str("2:3").unwrap();
         opt.greedy_delimiter = true;
 
-        cut_str(line, &opt, &mut output, &mut buffer1, &mut buffer2, eol).unwrap();
+        cut_str(line, &opt, &mut output, &mut buffer1, &mut buffer2, eol, 1).unwrap();
         assert_eq!(output, b"b---c\n".as_slice());
     }
 
@@ -993,7 +3452,7 @@ mod tests {
         opt.delimiter = "[.,]".into();
         opt.regex_bag = Some(make_regex_bag());
 
-        cut_str(line, &opt, &mut output, &mut buffer1, &mut buffer2, eol).unwrap();
+        cut_str(line, &opt, &mut output, &mut buffer1, &mut buffer2, eol, 1).unwrap();
         assert_eq!(output, b"b..,,c\n".as_slice());
     }
 
@@ -1008,7 +3467,7 @@ mod tests {
         opt.bounds = UserBoundsList::from_str("1,3,-1").unwrap();
 
         let (mut output, mut buffer1, mut buffer2) = make_cut_str_buffers();
-        cut_str(line, &opt, &mut output, &mut buffer1, &mut buffer2, eol).unwrap();
+        cut_str(line, &opt, &mut output, &mut buffer1, &mut buffer2, eol, 1).unwrap();
         assert_eq!(output, b"abc\n".as_slice());
 
         // check Trim::Left
@@ -1016,7 +3475,7 @@ mod tests {
         opt.bounds = UserBoundsList::from_str("1,3,-3").unwrap();
 
         let (mut output, mut buffer1, mut buffer2) = make_cut_str_buffers();
-        cut_str(line, &opt, &mut output, &mut buffer1, &mut buffer2, eol).unwrap();
+        cut_str(line, &opt, &mut output, &mut buffer1, &mut buffer2, eol, 1).unwrap();
         assert_eq!(output, b"abc\n".as_slice());
 
         // check Trim::Right
@@ -1024,7 +3483,7 @@ mod tests {
         opt.bounds = UserBoundsList::from_str("3,5,-1").unwrap();
 
         let (mut output, mut buffer1, mut buffer2) = make_cut_str_buffers();
-        cut_str(line, &opt, &mut output, &mut buffer1, &mut buffer2, eol).unwrap();
+        cut_str(line, &opt, &mut output, &mut buffer1, &mut buffer2, eol, 1).unwrap();
         assert_eq!(output, b"abc\n".as_slice());
     }
 
@@ -1043,7 +3502,7 @@ mod tests {
         opt.bounds = UserBoundsList::from_str("1,3,-1").unwrap();
 
         let (mut output, mut buffer1, mut buffer2) = make_cut_str_buffers();
-        cut_str(line, &opt, &mut output, &mut buffer1, &mut buffer2, eol).unwrap();
+        cut_str(line, &opt, &mut output, &mut buffer1, &mut buffer2, eol, 1).unwrap();
         assert_eq!(output, b"abc\n".as_slice());
 
         // check Trim::Left
@@ -1051,7 +3510,7 @@ mod tests {
         opt.bounds = UserBoundsList::from_str("1,3,-3").unwrap();
 
         let (mut output, mut buffer1, mut buffer2) = make_cut_str_buffers();
-        cut_str(line, &opt, &mut output, &mut buffer1, &mut buffer2, eol).unwrap();
+        cut_str(line, &opt, &mut output, &mut buffer1, &mut buffer2, eol, 1).unwrap();
         assert_eq!(output, b"abc\n".as_slice());
 
         // check Trim::Right
@@ -1059,10 +3518,11 @@ mod tests {
         opt.bounds = UserBoundsList::from_str("3,5,-1").unwrap();
 
         let (mut output, mut buffer1, mut buffer2) = make_cut_str_buffers();
-        cut_str(line, &opt, &mut output, &mut buffer1, &mut buffer2, eol).unwrap();
+        cut_str(line, &opt, &mut output, &mut buffer1, &mut buffer2, eol, 1).unwrap();
         assert_eq!(output, b"abc\n".as_slice());
     }
 
+    #[cfg(feature = "json")]
     #[test]
     fn cut_str_it_produce_json_output() {
         let mut opt = make_fields_opt();
@@ -1075,7 +3535,7 @@ mod tests {
         opt.bounds = UserBoundsList::from_str("1,3").unwrap();
         opt.join = true;
 
-        cut_str(line, &opt, &mut output, &mut buffer1, &mut buffer2, eol).unwrap();
+        cut_str(line, &opt, &mut output, &mut buffer1, &mut buffer2, eol, 1).unwrap();
         assert_eq!(
             output,
             br#"["a","c"]
@@ -1084,6 +3544,121 @@ mod tests {
         );
     }
 
+    #[cfg(feature = "json")]
+    #[test]
+    fn cut_str_it_produce_json_object_output() {
+        let mut opt = make_fields_opt();
+        opt.json = true;
+        opt.json_object = true;
+        opt.replace_delimiter = Some(",".into());
+        opt.join = true;
+        let (mut output, mut buffer1, mut buffer2) = make_cut_str_buffers();
+        let eol = &[EOL::Newline as u8];
+
+        opt.bounds = UserBoundsList::from_str("1,-1,2[1],5=x").unwrap();
+        cut_str(
+            b"a-bc-d",
+            &opt,
+            &mut output,
+            &mut buffer1,
+            &mut buffer2,
+            eol,
+            1,
+        )
+        .unwrap();
+        assert_eq!(
+            output,
+            br#"{"1":"a","3":"d","2[1]":"b","5":"x"}
+"#
+            .as_slice()
+        );
+
+        output.clear();
+        opt.bounds = UserBoundsList::from_str("1").unwrap();
+        cut_str(b"a", &opt, &mut output, &mut buffer1, &mut buffer2, eol, 1).unwrap();
+        assert_eq!(output, b"{\"1\":\"a\"}\n".as_slice());
+
+        // a key is printed only once
+        output.clear();
+        opt.bounds = UserBoundsList::from_str("1,1,1:2,1").unwrap();
+        cut_str(
+            b"a-b",
+            &opt,
+            &mut output,
+            &mut buffer1,
+            &mut buffer2,
+            eol,
+            1,
+        )
+        .unwrap();
+        assert_eq!(output, b"{\"1\":\"a\",\"2\":\"b\"}\n".as_slice());
+
+        // the names of the fields are the keys, when known
+        output.clear();
+        opt.field_names = vec!["name".into(), "qty".into()];
+        opt.bounds = UserBoundsList::from_str("2,1,3=none").unwrap();
+        cut_str(
+            b"a-b",
+            &opt,
+            &mut output,
+            &mut buffer1,
+            &mut buffer2,
+            eol,
+            1,
+        )
+        .unwrap();
+        assert_eq!(
+            output,
+            b"{\"qty\":\"b\",\"name\":\"a\",\"3\":\"none\"}\n".as_slice()
+        );
+
+        output.clear();
+        opt.field_names = Vec::new();
+        opt.json_pretty = true;
+        opt.replace_delimiter = Some(",\n  ".into());
+        opt.bounds = UserBoundsList::from_str("1,2").unwrap();
+        cut_str(
+            b"a-b",
+            &opt,
+            &mut output,
+            &mut buffer1,
+            &mut buffer2,
+            eol,
+            1,
+        )
+        .unwrap();
+        assert_eq!(
+            output,
+            b"{\n  \"1\": \"a\",\n  \"2\": \"b\"\n}\n".as_slice()
+        );
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn cut_str_it_produce_pretty_json_output() {
+        let mut opt = make_fields_opt();
+        opt.json = true;
+        opt.json_pretty = true;
+        opt.replace_delimiter = Some(",\n  ".into());
+        opt.join = true;
+        opt.bounds = UserBoundsList::from_str("1,3").unwrap();
+        let (mut output, mut buffer1, mut buffer2) = make_cut_str_buffers();
+        let eol = &[EOL::Newline as u8];
+
+        cut_str(
+            b"a-b-c",
+            &opt,
+            &mut output,
+            &mut buffer1,
+            &mut buffer2,
+            eol,
+            1,
+        )
+        .unwrap();
+        assert_eq!(output, b"[\n  \"a\",\n  \"c\"\n]\n".as_slice());
+    }
+
+    #[cfg(feature = "json")]
     #[test]
     fn cut_str_json_with_single_field_is_still_an_array() {
         let mut opt = make_fields_opt();
@@ -1096,7 +3671,7 @@ mod tests {
         opt.bounds = UserBoundsList::from_str("1").unwrap();
         opt.join = true;
 
-        cut_str(line, &opt, &mut output, &mut buffer1, &mut buffer2, eol).unwrap();
+        cut_str(line, &opt, &mut output, &mut buffer1, &mut buffer2, eol, 1).unwrap();
         assert_eq!(
             output,
             br#"["a"]
@@ -1105,6 +3680,7 @@ mod tests {
         );
     }
 
+    #[cfg(feature = "json")]
     #[test]
     fn cut_str_complement_works_with_json() {
         let mut opt = make_fields_opt();
@@ -1118,7 +3694,7 @@ mod tests {
         opt.bounds = UserBoundsList::from_str("2,2:3,-1").unwrap();
         opt.join = true;
 
-        cut_str(line, &opt, &mut output, &mut buffer1, &mut buffer2, eol).unwrap();
+        cut_str(line, &opt, &mut output, &mut buffer1, &mut buffer2, eol, 1).unwrap();
         assert_eq!(
             output,
             br#"["a","c","a","a","b"]
@@ -1127,7 +3703,7 @@ mod tests {
         );
     }
 
-    #[cfg(feature = "regex")]
+    #[cfg(all(feature = "regex", feature = "json"))]
     #[test]
     fn cut_str_json_on_characters_works() {
         let mut opt = make_fields_opt();
@@ -1142,7 +3718,7 @@ mod tests {
         opt.replace_delimiter = Some(",".into());
         opt.regex_bag = Some(make_cut_characters_regex_bag());
 
-        cut_str(line, &opt, &mut output, &mut buffer1, &mut buffer2, eol).unwrap();
+        cut_str(line, &opt, &mut output, &mut buffer1, &mut buffer2, eol, 1).unwrap();
         assert_eq!(
             &String::from_utf8_lossy(&output),
             r#"["😁","🤩","😝","😎"]
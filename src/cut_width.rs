@@ -0,0 +1,219 @@
+use anyhow::{bail, Result};
+use bstr::io::BufReadExt;
+use bstr::ByteSlice;
+use std::io::{BufRead, Write};
+use std::ops::Range;
+use unicode_width::UnicodeWidthChar;
+
+use crate::bounds::userboundslist::env_value;
+use crate::bounds::{BoundOrFiller, Side, UserBounds};
+use crate::error::TucError;
+use crate::options::{Opt, EOL};
+
+/// Columns taken by a character on the terminal (2 for East Asian wide
+/// characters, 0 for combining and control characters).
+pub(crate) fn char_width(c: char) -> usize {
+    c.width().unwrap_or(0)
+}
+
+/// Byte range of the characters displayed entirely within the columns of
+/// `b` (1-indexed, inclusive). A wide character that would be split is left
+/// out. A bound starting outside of the line is out of bounds, while an end
+/// past the last column is clamped to it, so that `1:80` keeps a shorter
+/// line as it is. Nothing is selected from a line without columns (e.g. empty).
+fn width_range(line: &[u8], b: &UserBounds) -> Result<Range<usize>, TucError> {
+    let total = line.chars().map(char_width).sum::<usize>() as i64;
+    if total == 0 {
+        return Ok(0..0);
+    }
+
+    let is_outside = |v: i32| i64::from(v) > total || i64::from(v) < -total;
+
+    let start_col = match b.l {
+        Side::Continue => 0,
+        Side::Some(v) if is_outside(v) => return Err(TucError::OutOfBounds { index: v }),
+        Side::Some(v) if v > 0 => i64::from(v) - 1,
+        Side::Some(v) => total + i64::from(v),
+    };
+    let end_col = match b.r {
+        Side::Continue => total,
+        Side::Some(v) if v > 0 => i64::from(v).min(total),
+        Side::Some(v) if is_outside(v) => return Err(TucError::OutOfBounds { index: v }),
+        Side::Some(v) => total + i64::from(v) + 1,
+    };
+
+    if end_col <= start_col {
+        return Err(TucError::InvertedRange);
+    }
+
+    let mut range: Option<Range<usize>> = None;
+    let mut col: i64 = 0;
+    // Zero-width characters (e.g. combining marks) go with the previous one
+    let mut is_selected = start_col == 0 && end_col > 0;
+
+    for (start, end, c) in line.char_indices() {
+        let width = char_width(c) as i64;
+        if width > 0 {
+            is_selected = col >= start_col && col + width <= end_col;
+        }
+
+        if is_selected {
+            range = Some(range.map_or(start..end, |r| r.start..end));
+        }
+
+        col += width;
+    }
+
+    Ok(range.unwrap_or(0..0))
+}
+
+fn cut_width<W: Write>(line: &[u8], opt: &Opt, stdout: &mut W) -> Result<()> {
+    opt.bounds.iter().try_for_each(|bof| -> Result<()> {
+        let value;
+        let output = match bof {
+            BoundOrFiller::Bound(b) => {
+                match width_range(line, b) {
+                    Ok(r) => stdout.write_all(&line[r])?,
+                    Err(e) => match (&b.fallback_oob, &opt.fallback_oob) {
+                        (Some(fallback), _) => stdout.write_all(fallback)?,
+                        (None, Some(generic_fallback)) => stdout.write_all(generic_fallback)?,
+                        (None, None) => return Err(e.into()),
+                    },
+                }
+
+                if opt.join && !b.is_last {
+                    stdout.write_all(opt.join_delimiter().unwrap_or(&opt.delimiter))?;
+                }
+
+                return Ok(());
+            }
+            BoundOrFiller::Filler(f) => f,
+            BoundOrFiller::WholeLine => line,
+            BoundOrFiller::Env(name) => {
+                value = env_value(name)?;
+                &value
+            }
+            BoundOrFiller::Capture(_) => {
+                bail!("Named placeholders are not supported when cutting by width")
+            }
+            BoundOrFiller::Conditional { .. } => {
+                bail!("Conditional formatting is not supported when cutting by width")
+            }
+        };
+
+        stdout.write_all(output)?;
+
+        Ok(())
+    })
+}
+
+fn cut_width_line<W: Write>(
+    line: &[u8],
+    line_number: usize,
+    opt: &Opt,
+    stdout: &mut W,
+) -> std::io::Result<bool> {
+    cut_width(line, opt, stdout)
+        .map_err(|x| std::io::Error::other(TucError::from(x).at_line(line_number)))?;
+    stdout.write_all(&[opt.eol.into()])?;
+    Ok(true)
+}
+
+/// Cut every line by terminal display columns (--width).
+pub fn read_and_cut_width<R: BufRead, W: Write>(
+    stdin: &mut R,
+    stdout: &mut W,
+    opt: &Opt,
+) -> Result<()> {
+    let mut line_number = opt.preamble_lines();
+    let mut cut_line = |line: &[u8]| {
+        line_number += 1;
+        cut_width_line(line, line_number, opt, stdout)
+    };
+
+    match opt.eol {
+        EOL::Newline => stdin.for_byte_line(&mut cut_line)?,
+        EOL::Zero => stdin.for_byte_record(opt.eol.into(), &mut cut_line)?,
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use crate::bounds::{BoundsType, UserBoundsList};
+
+    use super::*;
+
+    fn cut(input: &str, bounds: &str) -> String {
+        let opt = Opt {
+            bounds_type: BoundsType::Width,
+            bounds: UserBoundsList::from_str(bounds).unwrap(),
+            ..Opt::default()
+        };
+        let mut output = Vec::new();
+        read_and_cut_width(&mut input.as_bytes(), &mut output, &opt).unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn it_cuts_by_display_columns() {
+        assert_eq!(cut("hello\n", "1:3"), "hel\n");
+        assert_eq!(cut("hello\n", "1:80"), "hello\n");
+        assert_eq!(cut("hello\n", "-2:"), "lo\n");
+        assert_eq!(cut("hello\nab\n", "2"), "e\nb\n");
+        assert_eq!(cut("\n", "1:3"), "\n");
+    }
+
+    #[test]
+    fn it_applies_the_fallbacks_out_of_the_line() {
+        assert_eq!(cut("abc\n", "5=x"), "x\n");
+        assert_eq!(cut("abc\n", "-5:=x"), "x\n");
+        assert_eq!(cut("abc\n", "2:9=x"), "bc\n");
+
+        let opt = Opt {
+            bounds_type: BoundsType::Width,
+            bounds: UserBoundsList::from_str("1,5").unwrap(),
+            fallback_oob: Some(b"?".to_vec()),
+            ..Opt::default()
+        };
+        let mut output = Vec::new();
+        read_and_cut_width(&mut "abc\n".as_bytes(), &mut output, &opt).unwrap();
+        assert_eq!(output, b"a?\n");
+    }
+
+    #[test]
+    fn it_fails_out_of_the_line_without_fallbacks() {
+        let opt = Opt {
+            bounds_type: BoundsType::Width,
+            bounds: UserBoundsList::from_str("5").unwrap(),
+            ..Opt::default()
+        };
+        let res = read_and_cut_width(&mut "abc\n".as_bytes(), &mut Vec::new(), &opt);
+        assert_eq!(res.unwrap_err().to_string(), "line 1: Out of bounds: 5");
+
+        let opt = Opt {
+            bounds_type: BoundsType::Width,
+            bounds: UserBoundsList::from_str("-1:1").unwrap(),
+            ..Opt::default()
+        };
+        let res = read_and_cut_width(&mut "abc\n".as_bytes(), &mut Vec::new(), &opt);
+        assert_eq!(
+            res.unwrap_err().to_string(),
+            "line 1: Field left value cannot be greater than right value"
+        );
+    }
+
+    #[test]
+    fn it_does_not_split_wide_characters() {
+        // Every character takes two columns
+        assert_eq!(cut("日本語\n", "1:4"), "日本\n");
+        assert_eq!(cut("日本語\n", "1:5"), "日本\n");
+        assert_eq!(cut("日本語\n", "2:6"), "本語\n");
+        assert_eq!(cut("a日b\n", "-3:"), "日b\n");
+        // The combining accent stays with its letter
+        assert_eq!(cut("e\u{301}te\n", "1:2"), "e\u{301}t\n");
+    }
+}
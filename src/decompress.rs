@@ -0,0 +1,197 @@
+use std::fmt;
+use std::io::{BufRead, BufReader, Error, ErrorKind, Read, Result};
+use std::path::Path;
+use std::str::FromStr;
+
+/// Compression formats that tuc can read, each behind its own
+/// cargo feature (`gzip`, `zstd` and `xz`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    Gzip,
+    Zstd,
+    Xz,
+}
+
+impl Compression {
+    /// Guess the format from the extension of a file (`.gz`, `.zst`, `.xz`).
+    pub fn from_extension(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()? {
+            "gz" => Some(Compression::Gzip),
+            "zst" => Some(Compression::Zstd),
+            "xz" => Some(Compression::Xz),
+            _ => None,
+        }
+    }
+
+    /// Detect the format from the first bytes of the data.
+    pub fn from_magic(data: &[u8]) -> Option<Self> {
+        if data.starts_with(b"\x1F\x8B") {
+            Some(Compression::Gzip)
+        } else if data.starts_with(b"\x28\xB5\x2F\xFD") {
+            Some(Compression::Zstd)
+        } else if data.starts_with(b"\xFD7zXZ\x00") {
+            Some(Compression::Xz)
+        } else {
+            None
+        }
+    }
+
+    /// Was tuc compiled with the feature that reads this format?
+    pub fn is_supported(self) -> bool {
+        match self {
+            Compression::Gzip => cfg!(feature = "gzip"),
+            Compression::Zstd => cfg!(feature = "zstd"),
+            Compression::Xz => cfg!(feature = "xz"),
+        }
+    }
+}
+
+impl fmt::Display for Compression {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Compression::Gzip => "gzip",
+            Compression::Zstd => "zstd",
+            Compression::Xz => "xz",
+        })
+    }
+}
+
+/// Format of the compressed input (--decompress).
+/// `Auto` detects it from the first bytes of every input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Decompress {
+    Auto,
+    Format(Compression),
+}
+
+impl FromStr for Decompress {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "auto" => Ok(Decompress::Auto),
+            "gzip" => Ok(Decompress::Format(Compression::Gzip)),
+            "zstd" => Ok(Decompress::Format(Compression::Zstd)),
+            "xz" => Ok(Decompress::Format(Compression::Xz)),
+            _ => Err("Valid formats are auto, gzip, zstd and xz".into()),
+        }
+    }
+}
+
+impl fmt::Display for Decompress {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Decompress::Auto => f.write_str("auto"),
+            Decompress::Format(compression) => compression.fmt(f),
+        }
+    }
+}
+
+/// Wrap `reader` so that it reads the decompressed data.
+/// With `Decompress::Auto` the format is detected from the first bytes,
+/// and data that is not compressed is read as it is.
+pub fn decompress(reader: Box<dyn Read>, decompress: Decompress) -> Result<Box<dyn Read>> {
+    let mut reader = BufReader::new(reader);
+    let compression = match decompress {
+        Decompress::Format(compression) => compression,
+        Decompress::Auto => match Compression::from_magic(reader.fill_buf()?) {
+            Some(compression) => compression,
+            None => return Ok(Box::new(reader)),
+        },
+    };
+
+    match compression {
+        #[cfg(feature = "gzip")]
+        Compression::Gzip => Ok(Box::new(flate2::bufread::MultiGzDecoder::new(reader))),
+        #[cfg(feature = "zstd")]
+        Compression::Zstd => ruzstd::decoding::StreamingDecoder::new(reader)
+            .map(|decoder| Box::new(decoder) as Box<dyn Read>)
+            .map_err(|e| Error::new(ErrorKind::InvalidData, e.to_string())),
+        #[cfg(feature = "xz")]
+        Compression::Xz => Ok(Box::new(xz2::bufread::XzDecoder::new_multi_decoder(reader))),
+        #[allow(unreachable_patterns)]
+        compression => Err(Error::new(
+            ErrorKind::Unsupported,
+            format!(
+                "The {} input requires `tuc` to be compiled with `{}` support",
+                compression, compression
+            ),
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read_all(data: Vec<u8>, format: &str) -> Result<String> {
+        let mut output = String::new();
+        decompress(
+            Box::new(std::io::Cursor::new(data)),
+            format.parse().unwrap(),
+        )?
+        .read_to_string(&mut output)?;
+        Ok(output)
+    }
+
+    #[test]
+    fn it_detects_the_format() {
+        assert_eq!(
+            Compression::from_extension(Path::new("a/b.csv.gz")),
+            Some(Compression::Gzip)
+        );
+        assert_eq!(Compression::from_extension(Path::new("b.csv")), None);
+        assert_eq!(
+            Compression::from_magic(b"\xFD7zXZ\x00\x00"),
+            Some(Compression::Xz)
+        );
+        assert_eq!(Compression::from_magic(b"a,b\n"), None);
+
+        assert_eq!(read_all(b"a,b\n".to_vec(), "auto").unwrap(), "a,b\n");
+        assert!("zip".parse::<Decompress>().is_err());
+    }
+
+    #[cfg(feature = "gzip")]
+    #[test]
+    fn it_reads_gzip() {
+        use std::io::Write;
+
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::fast());
+        encoder.write_all(b"a,b\n").unwrap();
+        let data = encoder.finish().unwrap();
+
+        assert_eq!(read_all(data.clone(), "auto").unwrap(), "a,b\n");
+        assert_eq!(read_all(data, "gzip").unwrap(), "a,b\n");
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn it_reads_zstd() {
+        let data = ruzstd::encoding::compress_to_vec(
+            &b"a,b\n"[..],
+            ruzstd::encoding::CompressionLevel::Fastest,
+        );
+
+        assert_eq!(read_all(data.clone(), "auto").unwrap(), "a,b\n");
+        assert_eq!(read_all(data, "zstd").unwrap(), "a,b\n");
+    }
+
+    #[cfg(feature = "xz")]
+    #[test]
+    fn it_reads_xz() {
+        use std::io::Write;
+
+        let mut encoder = xz2::write::XzEncoder::new(Vec::new(), 1);
+        encoder.write_all(b"a,b\n").unwrap();
+        let data = encoder.finish().unwrap();
+
+        assert_eq!(read_all(data.clone(), "auto").unwrap(), "a,b\n");
+        assert_eq!(read_all(data, "xz").unwrap(), "a,b\n");
+    }
+
+    #[cfg(not(feature = "gzip"))]
+    #[test]
+    fn it_fails_without_the_feature() {
+        assert!(read_all(b"\x1F\x8B".to_vec(), "auto").is_err());
+    }
+}
@@ -0,0 +1,183 @@
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+
+use crate::validation::ValidationError;
+
+/// Errors of the library functions (e.g. `cut_str` or the parsing of bounds),
+/// so that callers can match on them instead of comparing messages.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum TucError {
+    /// The record has nothing at this position (e.g. field 4 of `a-b-c`)
+    OutOfBounds {
+        index: i32,
+    },
+    /// The range selects nothing in this record (e.g. `3:-3` in `a-b-c`)
+    InvertedRange,
+    /// A bound or a format string could not be parsed
+    ParseBounds {
+        message: String,
+    },
+    /// A record failed --assert or --assert-fields
+    Validation(ValidationError),
+    Io(io::Error),
+    /// Any other failure, such as invalid JSON input
+    Other(anyhow::Error),
+    /// An error of the given input line (and file, when known)
+    AtLine {
+        path: Option<PathBuf>,
+        line: usize,
+        error: Box<TucError>,
+    },
+}
+
+impl TucError {
+    pub(crate) fn parse_bounds(e: impl fmt::Display) -> Self {
+        TucError::ParseBounds {
+            message: e.to_string(),
+        }
+    }
+
+    /// Tell which input line failed. Validation errors already do.
+    pub(crate) fn at_line(self, line: usize) -> Self {
+        match self {
+            TucError::Validation(_) | TucError::AtLine { .. } => self,
+            error => TucError::AtLine {
+                path: None,
+                line,
+                error: Box::new(error),
+            },
+        }
+    }
+
+    /// The error itself, without the line where it happened.
+    pub fn inner(&self) -> &TucError {
+        match self {
+            TucError::AtLine { error, .. } => error.inner(),
+            error => error,
+        }
+    }
+}
+
+impl fmt::Display for TucError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TucError::OutOfBounds { index } => write!(f, "Out of bounds: {}", index),
+            TucError::InvertedRange => {
+                f.write_str("Field left value cannot be greater than right value")
+            }
+            TucError::ParseBounds { message } => f.write_str(message),
+            TucError::Validation(e) => e.fmt(f),
+            TucError::Io(e) => e.fmt(f),
+            TucError::Other(e) => e.fmt(f),
+            TucError::AtLine {
+                path: Some(path),
+                line,
+                error,
+            } => write!(f, "{}, line {}: {}", path.display(), line, error),
+            TucError::AtLine {
+                path: None,
+                line,
+                error,
+            } => write!(f, "line {}: {}", line, error),
+        }
+    }
+}
+
+impl std::error::Error for TucError {}
+
+/// What a cut has to tell besides its output (e.g. the records skipped by
+/// --lenient). The library never prints them: it's up to the caller.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Diagnostic {
+    /// A record skipped by --relaxed or --lenient
+    Skipped(TucError),
+    /// A record that failed with --errors collect
+    Failed {
+        record_number: usize,
+        error: TucError,
+    },
+    /// The summary of --report
+    Report(String),
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Diagnostic::Skipped(error) => write!(f, "tuc: warning: {} (skipped)", error),
+            Diagnostic::Failed {
+                error: TucError::Validation(error),
+                ..
+            } => write!(f, "tuc: {}", error),
+            Diagnostic::Failed {
+                record_number,
+                error,
+            } => write!(f, "tuc: Record {}: {}", record_number, error),
+            Diagnostic::Report(summary) => f.write_str(summary),
+        }
+    }
+}
+
+impl From<io::Error> for TucError {
+    fn from(e: io::Error) -> Self {
+        TucError::Io(e)
+    }
+}
+
+impl From<ValidationError> for TucError {
+    fn from(e: ValidationError) -> Self {
+        TucError::Validation(e)
+    }
+}
+
+#[cfg(feature = "json")]
+impl From<serde_json::Error> for TucError {
+    fn from(e: serde_json::Error) -> Self {
+        TucError::Other(e.into())
+    }
+}
+
+/// Recover the original error, if it's known (e.g. an `OutOfBounds` that
+/// went through functions returning `anyhow::Error`).
+impl From<anyhow::Error> for TucError {
+    fn from(e: anyhow::Error) -> Self {
+        let e = match e.downcast::<TucError>() {
+            Ok(e) => return e,
+            Err(e) => e,
+        };
+        let e = match e.downcast::<ValidationError>() {
+            Ok(e) => return TucError::Validation(e),
+            Err(e) => e,
+        };
+        match e.downcast::<io::Error>() {
+            Ok(e) => TucError::Io(e),
+            Err(e) => TucError::Other(e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::anyhow;
+
+    #[test]
+    fn it_recovers_known_errors_from_anyhow() {
+        let e = TucError::from(anyhow::Error::from(TucError::OutOfBounds { index: 4 }));
+        assert!(matches!(e, TucError::OutOfBounds { index: 4 }));
+        assert_eq!(e.to_string(), "Out of bounds: 4");
+
+        let e = TucError::from(anyhow::Error::from(io::Error::other("broken")));
+        assert!(matches!(e, TucError::Io(_)));
+
+        let e = TucError::OutOfBounds { index: 4 }.at_line(12);
+        assert!(matches!(e.inner(), TucError::OutOfBounds { index: 4 }));
+        assert_eq!(e.to_string(), "line 12: Out of bounds: 4");
+
+        let e = TucError::from(anyhow!("something else"));
+        assert!(matches!(e, TucError::Other(_)));
+        assert_eq!(e.to_string(), "something else");
+    }
+}
@@ -0,0 +1,290 @@
+use crate::bounds::userboundslist::env_value;
+use crate::bounds::{BoundOrFiller, UserBoundsList, UserBoundsTrait};
+use crate::error::Diagnostic;
+use crate::options::Opt;
+use crate::record_sink::{read_and_cut_into, RecordSink};
+use anyhow::{anyhow, bail, Result};
+use std::collections::VecDeque;
+use std::ffi::OsString;
+use std::fmt;
+use std::io::BufRead;
+use std::process::{Child, Command, Stdio};
+use std::str::FromStr;
+
+/// Split a command line into words, the way a shell would do with
+/// quotes and backslashes (but without any expansion).
+pub(crate) fn split_words(s: &str) -> Result<Vec<String>> {
+    let mut words = Vec::new();
+    let mut word: Option<String> = None;
+    let mut chars = s.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' => {
+                let word = word.get_or_insert_with(String::new);
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some(c) => word.push(c),
+                        None => bail!("unterminated quote in `{}`", s),
+                    }
+                }
+            }
+            '"' => {
+                let word = word.get_or_insert_with(String::new);
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some(c @ ('"' | '\\')) => word.push(c),
+                            Some(c) => {
+                                word.push('\\');
+                                word.push(c);
+                            }
+                            None => bail!("unterminated quote in `{}`", s),
+                        },
+                        Some(c) => word.push(c),
+                        None => bail!("unterminated quote in `{}`", s),
+                    }
+                }
+            }
+            '\\' => {
+                let c = chars.next().unwrap_or('\\');
+                word.get_or_insert_with(String::new).push(c);
+            }
+            c if c.is_whitespace() => words.extend(word.take()),
+            c => word.get_or_insert_with(String::new).push(c),
+        }
+    }
+
+    words.extend(word);
+    Ok(words)
+}
+
+/// Command run for every record by --exec (e.g. `echo {1} {-1}`).
+/// Each word is a template, whose placeholders become the fields of the
+/// record. No shell is involved, so the fields are never re-interpreted.
+#[derive(Debug, Clone)]
+pub struct ExecCommand {
+    text: String,
+    words: Vec<UserBoundsList>,
+}
+
+impl FromStr for ExecCommand {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let words = split_words(s)?
+            .iter()
+            .map(|word| UserBoundsList::from_template(word))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if words.is_empty() {
+            bail!("--exec requires a command, e.g. --exec 'echo {{1}}'");
+        }
+
+        let is_supported = |bof: &BoundOrFiller| match bof {
+            BoundOrFiller::Filler(_) | BoundOrFiller::Env(_) => true,
+            BoundOrFiller::Bound(b) => !b.has_modifiers(),
+            _ => false,
+        };
+
+        if !words.iter().all(|word| word.iter().all(is_supported)) {
+            bail!("--exec supports only text and fields, e.g. --exec 'echo {{1}} {{-1}}'");
+        }
+
+        Ok(ExecCommand {
+            text: s.to_owned(),
+            words,
+        })
+    }
+}
+
+impl fmt::Display for ExecCommand {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.text)
+    }
+}
+
+#[cfg(unix)]
+fn os_string(bytes: Vec<u8>) -> OsString {
+    std::os::unix::ffi::OsStringExt::from_vec(bytes)
+}
+
+#[cfg(not(unix))]
+fn os_string(bytes: Vec<u8>) -> OsString {
+    String::from_utf8_lossy(&bytes).into_owned().into()
+}
+
+impl ExecCommand {
+    /// The arguments of the command for a record with the given fields.
+    /// A range of fields (e.g. `{2:}`) becomes a single argument,
+    /// with the fields separated by a space.
+    pub fn render(&self, values: &[&[u8]]) -> Result<Vec<OsString>> {
+        self.words
+            .iter()
+            .map(|word| {
+                let mut arg = Vec::new();
+                for bof in word.iter() {
+                    match bof {
+                        BoundOrFiller::Filler(text) => arg.extend_from_slice(text),
+                        BoundOrFiller::Env(name) => arg.extend_from_slice(&env_value(name)?),
+                        BoundOrFiller::Bound(b) => {
+                            match (b.try_into_range(values.len()), &b.fallback_oob) {
+                                (Ok(r), _) => arg.extend_from_slice(&values[r].join(&b' ')),
+                                (Err(_), Some(fallback)) => arg.extend_from_slice(fallback),
+                                (Err(e), None) => return Err(e.into()),
+                            }
+                        }
+                        _ => unreachable!("rejected while parsing"),
+                    }
+                }
+                Ok(os_string(arg))
+            })
+            .collect()
+    }
+}
+
+/// Start the command that receives the whole output (--tee-cmd, e.g.
+/// `sort -u`). Its own output goes straight to stdout.
+pub fn spawn_output_command(command: &str) -> Result<Child> {
+    let args = split_words(command)?;
+    if args.is_empty() {
+        bail!("--tee-cmd requires a command, e.g. --tee-cmd 'sort -u'");
+    }
+
+    Command::new(&args[0])
+        .args(&args[1..])
+        .stdin(Stdio::piped())
+        .spawn()
+        .map_err(|e| anyhow!("Cannot run {}: {}", args[0], e))
+}
+
+/// Wait for the command of --tee-cmd, once its input has been closed.
+pub fn wait_output_command(mut child: Child) -> Result<()> {
+    let status = child.wait()?;
+    if !status.success() {
+        bail!("--tee-cmd failed ({})", status);
+    }
+
+    Ok(())
+}
+
+/// Run the command of --exec for every record,
+/// with up to `max_procs` commands running at once.
+struct Executor {
+    command: ExecCommand,
+    max_procs: usize,
+    running: VecDeque<Child>,
+    failures: usize,
+}
+
+impl Executor {
+    fn wait_oldest(&mut self) -> Result<()> {
+        if let Some(mut child) = self.running.pop_front() {
+            if !child.wait()?.success() {
+                self.failures += 1;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn finish(mut self) -> Result<()> {
+        while !self.running.is_empty() {
+            self.wait_oldest()?;
+        }
+
+        if self.failures > 0 {
+            bail!("{} commands failed", self.failures);
+        }
+
+        Ok(())
+    }
+}
+
+impl RecordSink for Executor {
+    fn add_record(&mut self, values: &[&[u8]]) -> Result<()> {
+        // An empty line has a single, empty, field
+        let args = if values.is_empty() {
+            self.command.render(&[b""])?
+        } else {
+            self.command.render(values)?
+        };
+
+        if self.running.len() == self.max_procs {
+            self.wait_oldest()?;
+        }
+
+        // The input of tuc is not meant for the commands
+        let child = Command::new(&args[0])
+            .args(&args[1..])
+            .stdin(Stdio::null())
+            .spawn()
+            .map_err(|e| anyhow!("Cannot run {}: {}", args[0].to_string_lossy(), e))?;
+
+        self.running.push_back(child);
+        Ok(())
+    }
+}
+
+/// Cut every record and run the command of --exec with its fields.
+pub fn read_and_exec<B: BufRead>(
+    stdin: &mut B,
+    opt: Opt,
+    on_diagnostic: impl FnMut(Diagnostic),
+) -> Result<()> {
+    let executor = Executor {
+        command: opt
+            .exec
+            .clone()
+            .ok_or_else(|| anyhow!("invariant error. --exec is required"))?,
+        max_procs: opt.max_procs,
+        running: VecDeque::new(),
+        failures: 0,
+    };
+
+    read_and_cut_into(stdin, opt, executor, on_diagnostic)?.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_splits_words_like_a_shell() {
+        assert_eq!(split_words("echo  a b").unwrap(), ["echo", "a", "b"]);
+        assert_eq!(
+            split_words(r#"a 'b c' "d \"e\"" f\ g '' x\y"#).unwrap(),
+            ["a", "b c", "d \"e\"", "f g", "", "xy"]
+        );
+        assert!(split_words("echo 'a").is_err());
+    }
+
+    #[test]
+    fn it_renders_the_arguments() {
+        let values: [&[u8]; 3] = [b"a", b"b c", b"d"];
+
+        let command = ExecCommand::from_str("echo {1} 'x={-1}' {2:} {9=none}").unwrap();
+        assert_eq!(
+            command.render(&values).unwrap(),
+            ["echo", "a", "x=d", "b c d", "none"]
+        );
+        assert_eq!(command.to_string(), "echo {1} 'x={-1}' {2:} {9=none}");
+
+        let command = ExecCommand::from_str("echo {4}").unwrap();
+        assert!(command.render(&values).is_err());
+
+        #[cfg(unix)]
+        assert_eq!(
+            ExecCommand::from_str("echo {2}")
+                .unwrap()
+                .render(&[b"a", b"\xff"])
+                .unwrap(),
+            [OsString::from("echo"), os_string(b"\xff".to_vec())]
+        );
+
+        assert!(ExecCommand::from_str("").is_err());
+        assert!(ExecCommand::from_str("echo {1:<5}").is_err());
+    }
+}
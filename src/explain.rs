@@ -0,0 +1,352 @@
+use crate::bounds::userbounds::Align;
+use crate::bounds::{BoundOrFiller, BoundsType, Side};
+use crate::options::{ColumnarOutput, Opt, Trim, EOL};
+use serde_json::{json, Value};
+
+#[cfg(feature = "fast-lane")]
+use crate::fast_lane::FastOpt;
+#[cfg(feature = "fast-lane")]
+use std::convert::TryFrom;
+
+fn lossy(bytes: &[u8]) -> String {
+    String::from_utf8_lossy(bytes).into_owned()
+}
+
+fn side_to_json(side: Side) -> Value {
+    match side {
+        Side::Some(idx) => json!(idx),
+        Side::Continue => Value::Null,
+    }
+}
+
+fn bound_or_filler_to_json(bof: &BoundOrFiller) -> Value {
+    match bof {
+        BoundOrFiller::Bound(b) => json!({
+            "type": "bound",
+            "left": side_to_json(b.l),
+            "right": side_to_json(b.r),
+            "fallback": b.fallback_oob.as_deref().map(lossy),
+            "pad": b.pad.map(|pad| json!({
+                "align": match pad.align {
+                    Align::Left => "left",
+                    Align::Right => "right",
+                    Align::Center => "center",
+                },
+                "width": pad.width,
+            })),
+            "pipe": b.humanize.map(|humanize| humanize.to_string()),
+            "sub": b.sub.as_ref().map(|sub| json!({
+                "left": side_to_json(sub.l),
+                "right": side_to_json(sub.r),
+            })),
+            "bytes": b.bytes.as_ref().map(|bytes| json!({
+                "left": side_to_json(bytes.l),
+                "right": side_to_json(bytes.r),
+            })),
+        }),
+        BoundOrFiller::Filler(text) => json!({ "type": "filler", "text": lossy(text) }),
+        BoundOrFiller::Capture(name) => json!({ "type": "capture", "name": name }),
+        BoundOrFiller::WholeLine => json!({ "type": "line" }),
+        BoundOrFiller::Env(name) => json!({ "type": "env", "name": name }),
+        BoundOrFiller::Conditional {
+            condition,
+            then,
+            otherwise,
+        } => json!({
+            "type": "conditional",
+            "condition": bound_or_filler_to_json(condition),
+            "then": list_to_json(then),
+            "otherwise": list_to_json(otherwise),
+        }),
+    }
+}
+
+fn list_to_json(list: &[BoundOrFiller]) -> Value {
+    list.iter().map(bound_or_filler_to_json).collect()
+}
+
+#[cfg(feature = "fast-lane")]
+fn uses_fast_lane(opt: &Opt) -> bool {
+    FastOpt::try_from(opt).is_ok()
+}
+
+#[cfg(not(feature = "fast-lane"))]
+fn uses_fast_lane(_opt: &Opt) -> bool {
+    false
+}
+
+/// Name of the implementation that will cut the input
+/// (the same choice made by the `tuc` binary).
+fn engine(opt: &Opt) -> &'static str {
+    match opt.bounds_type {
+        BoundsType::Bytes => "bytes",
+        BoundsType::Lines => "lines",
+        BoundsType::Width => "width",
+        _ if uses_fast_lane(opt) => "fast-lane",
+        _ => "str",
+    }
+}
+
+/// How the input gets split into parts.
+fn finder(opt: &Opt) -> &'static str {
+    match opt.bounds_type {
+        BoundsType::Bytes if opt.whole_stream => "none",
+        BoundsType::Bytes | BoundsType::Lines | BoundsType::Width => "eol",
+        _ if opt.json_input => "json",
+        _ if opt.csv => "csv",
+        _ if opt.quote.is_some() => "quoted",
+        _ if opt.escape.is_some() => "escaped",
+        _ if opt.respect_brackets => "bracketed",
+        _ if opt.regex_bag.is_some() && opt.greedy_delimiter => "greedy-regex",
+        _ if opt.regex_bag.is_some() => "regex",
+        _ if opt.delimiters.is_some() && opt.greedy_delimiter => "greedy-multi-substring",
+        _ if opt.delimiters.is_some() => "multi-substring",
+        _ if uses_fast_lane(opt) => "memchr",
+        _ if opt.greedy_delimiter => "greedy-substring",
+        _ => "substring",
+    }
+}
+
+/// Describe how `opt` is going to be used: the parsed bounds,
+/// the engine that will cut the input, how delimiters are found
+/// and every option after normalization.
+pub fn explain(opt: &Opt) -> Value {
+    json!({
+        "bounds_type": match opt.bounds_type {
+            BoundsType::Bytes => "bytes",
+            BoundsType::Characters => "characters",
+            BoundsType::Fields => "fields",
+            BoundsType::Lines => "lines",
+            BoundsType::Width => "width",
+        },
+        "bounds": list_to_json(&opt.bounds),
+        "engine": engine(opt),
+        "finder": finder(opt),
+        "options": {
+            "delimiter": lossy(&opt.delimiter),
+            "delimiters": opt
+                .delimiters
+                .as_ref()
+                .map(|finder| finder.delimiters().iter().map(|d| lossy(d)).collect::<Vec<_>>()),
+            "sub_delimiter": opt.sub_delimiter.as_deref().map(lossy),
+            "line_pattern": line_pattern_text(opt),
+            "eol": match opt.eol {
+                EOL::Newline => "newline",
+                EOL::Zero => "zero",
+            },
+            "only_delimited": opt.only_delimited,
+            "greedy_delimiter": opt.greedy_delimiter,
+            "compress_delimiter": opt.compress_delimiter,
+            "replace_delimiter": opt.replace_delimiter.as_deref().map(lossy),
+            "output_delimiter": opt.output_delimiter.as_deref().map(lossy),
+            "trim": opt.trim.map(|trim| match trim {
+                Trim::Left => "left",
+                Trim::Right => "right",
+                Trim::Both => "both",
+            }),
+            "complement": opt.complement,
+            "keep_original_delimiters": opt.keep_original_delimiters,
+            "join": opt.join,
+            "json": opt.json,
+            "json_pretty": opt.json_pretty,
+            "json_object": opt.json_object,
+            "msgpack": opt.msgpack,
+            "fallback_oob": opt.fallback_oob.as_deref().map(lossy),
+            "regex": regex_text(opt),
+            "strip_ansi": opt.strip_ansi,
+            "show_nonprinting": opt.show_nonprinting,
+            "swap": opt.swap.as_ref().map(|(a, b)| [a.to_string(), b.to_string()]),
+            "set": opt.set.iter().map(|(field, template)| json!({
+                "field": field.to_string(),
+                "value": list_to_json(template),
+            })).collect::<Vec<_>>(),
+            "insert": opt.insert.as_ref().map(|(position, value)| json!({
+                "position": position,
+                "value": value.to_string(),
+            })),
+            "map": opt.map.as_ref().map(|map| map.to_string()),
+            "truncate": opt.truncate.as_ref().map(|truncate| json!({
+                "width": truncate.width,
+                "ellipsis": lossy(&truncate.ellipsis),
+            })),
+            "pad": opt.pad.as_ref().map(|pad| json!({
+                "width": pad.width,
+                "fill": pad.fill.to_string(),
+                "align": pad.align.to_string(),
+            })),
+            "output_buffer_size": opt.output_buffer_size,
+            "whole_stream": opt.whole_stream,
+            "no_split_codepoints": opt.no_split_codepoints,
+            "skip_empty": opt.skip_empty,
+            "json_input": opt.json_input,
+            "csv": opt.csv,
+            "quote": opt.quote.map(|quote| (quote as char).to_string()),
+            "escape": opt.escape.map(|escape| (escape as char).to_string()),
+            "unescape": opt.unescape,
+            "respect_brackets": opt.respect_brackets,
+            "json_keys": opt.json_keys,
+            "assert_fields": opt.assert_fields.map(|count| count.to_string()),
+            "assertions": assertions_text(opt),
+            "grep_fields": grep_fields_text(opt),
+            "field_match": field_match_text(opt),
+            "field_drop": opt.field_drop,
+            "relaxed": opt.relaxed,
+            "lenient": opt.lenient,
+            "report": opt.report,
+            "errors": opt.errors.to_string(),
+            "add_fields": opt.add_fields.iter().map(|f| f.to_string()).collect::<Vec<_>>(),
+            "print_delimiters": opt.print_delimiters,
+            "count": opt.count,
+            "with_index": opt.with_index,
+            "pairs": opt.pairs,
+            "timing": opt.timing,
+            "copy": opt.copy,
+            "columnar": opt.columnar.as_ref().map(|columnar| match columnar {
+                ColumnarOutput::Parquet(path) => json!({
+                    "format": "parquet",
+                    "path": path.to_string_lossy(),
+                }),
+                ColumnarOutput::Arrow => json!({ "format": "arrow" }),
+            }),
+            "exec": opt.exec.as_ref().map(|exec| exec.to_string()),
+            "max_procs": opt.max_procs,
+            "tee_cmd": opt.tee_cmd,
+            "timestamp": opt.timestamp.as_ref().map(|format| format.to_string()),
+            "wrap": opt.wrap.as_ref().map(|wrap| json!({
+                "width": wrap.width,
+                "prefix": wrap.prefix,
+            })),
+            "seek": opt.seek,
+            "print_offset": opt.print_offset,
+            "paste": opt.paste,
+            "files": opt.files,
+            "decompress": opt.decompress.map(|format| format.to_string()),
+            "in_place": opt.in_place,
+            "skip": opt.skip,
+            "number": opt.number.as_deref().map(lossy),
+            "skip_print": opt.skip_print,
+            "keep_header": opt.keep_header,
+            "cut_header": opt.cut_header,
+            "translate": opt.translate.map(|translation| translation.to_string()),
+            "analyze": opt.analyze,
+            "sniff": opt.sniff,
+            "column_types": opt.column_types.iter().map(|t| t.to_string()).collect::<Vec<_>>(),
+        },
+        "args": opt.to_args(),
+    })
+}
+
+#[cfg(feature = "regex")]
+fn regex_text(opt: &Opt) -> Option<&str> {
+    opt.regex_bag
+        .as_ref()
+        .map(|regex_bag| regex_bag.normal.as_str())
+}
+
+#[cfg(not(feature = "regex"))]
+fn regex_text(_opt: &Opt) -> Option<&str> {
+    None
+}
+
+#[cfg(feature = "regex")]
+fn assertions_text(opt: &Opt) -> Vec<String> {
+    opt.assertions.iter().map(|a| a.to_string()).collect()
+}
+
+#[cfg(not(feature = "regex"))]
+fn assertions_text(_opt: &Opt) -> Vec<String> {
+    Vec::new()
+}
+
+#[cfg(feature = "regex")]
+fn grep_fields_text(opt: &Opt) -> Option<&str> {
+    opt.grep_fields.as_ref().map(|regex| regex.as_str())
+}
+
+#[cfg(not(feature = "regex"))]
+fn grep_fields_text(_opt: &Opt) -> Option<&str> {
+    None
+}
+
+#[cfg(feature = "regex")]
+fn field_match_text(opt: &Opt) -> Option<&str> {
+    opt.field_match.as_ref().map(|regex| regex.as_str())
+}
+
+#[cfg(not(feature = "regex"))]
+fn field_match_text(_opt: &Opt) -> Option<&str> {
+    None
+}
+
+#[cfg(feature = "regex")]
+fn line_pattern_text(opt: &Opt) -> Option<String> {
+    opt.line_pattern.as_ref().map(|pattern| pattern.to_string())
+}
+
+#[cfg(not(feature = "regex"))]
+fn line_pattern_text(_opt: &Opt) -> Option<String> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::OsString;
+
+    fn explain_args(args: &[&str]) -> Value {
+        explain(&Opt::try_from_args(args.iter().map(OsString::from)).unwrap())
+    }
+
+    #[test]
+    fn it_explains_the_bounds() {
+        let explanation = explain_args(&["-f", "{1:3}-{-1=none}{2?x:y}"]);
+
+        assert_eq!(explanation["bounds_type"], "fields");
+        assert_eq!(
+            explanation["bounds"],
+            json!([
+                {"type": "bound", "left": 1, "right": 3, "fallback": null, "pad": null, "pipe": null, "sub": null, "bytes": null},
+                {"type": "filler", "text": "-"},
+                {"type": "bound", "left": -1, "right": -1, "fallback": "none", "pad": null, "pipe": null, "sub": null, "bytes": null},
+                {
+                    "type": "conditional",
+                    "condition": {"type": "bound", "left": 2, "right": 2, "fallback": null, "pad": null, "pipe": null, "sub": null, "bytes": null},
+                    "then": [{"type": "filler", "text": "x"}],
+                    "otherwise": [{"type": "filler", "text": "y"}],
+                },
+            ])
+        );
+
+        let explanation = explain_args(&["-f", "{2|size:>8}"]);
+        assert_eq!(explanation["bounds"][0]["pipe"], "size");
+        assert_eq!(
+            explanation["bounds"][0]["pad"],
+            json!({"align": "right", "width": 8})
+        );
+
+        let explanation = explain_args(&["-f", "2[1:8]"]);
+        assert_eq!(
+            explanation["bounds"][0]["bytes"],
+            json!({"left": 1, "right": 8})
+        );
+    }
+
+    #[test]
+    fn it_explains_the_engine() {
+        let explanation = explain_args(&["-d", "::", "-f", "2:", "-g"]);
+        assert_eq!(explanation["engine"], "str");
+        assert_eq!(explanation["finder"], "greedy-substring");
+        assert_eq!(explanation["options"]["delimiter"], "::");
+        assert_eq!(
+            explanation["args"],
+            json!(["--fields", "2:", "--delimiter", "::", "--greedy-delimiter"])
+        );
+
+        let explanation = explain_args(&["-l", "1"]);
+        assert_eq!(explanation["engine"], "lines");
+        assert_eq!(explanation["finder"], "eol");
+
+        #[cfg(feature = "fast-lane")]
+        assert_eq!(explain_args(&["-d", ",", "-f", "1"])["finder"], "memchr");
+    }
+}
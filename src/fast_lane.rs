@@ -1,6 +1,9 @@
+use crate::bounds::userboundslist::env_value;
 use crate::bounds::{BoundOrFiller, BoundsType, Side, UserBounds, UserBoundsList, UserBoundsTrait};
-use crate::options::{Opt, Trim, EOL};
-use anyhow::Result;
+use crate::error::TucError;
+use crate::options::{ErrorPolicy, Opt, Trim, EOL};
+use crate::transform::FieldTransform;
+use anyhow::{bail, Result};
 use bstr::ByteSlice;
 use std::convert::TryFrom;
 use std::io::Write;
@@ -28,8 +31,8 @@ fn cut_str_fast_lane<W: Write>(
 ) -> Result<()> {
     let mut buffer = initial_buffer;
 
-    if opt.trim.is_some() {
-        buffer = trim(buffer, opt.trim.as_ref().unwrap(), opt.delimiter)
+    if let Some(trim_kind) = &opt.trim {
+        buffer = trim(buffer, trim_kind, opt.delimiter)
     }
 
     if buffer.is_empty() {
@@ -76,7 +79,10 @@ fn cut_str_fast_lane<W: Write>(
     let num_fields = fields.len() - 1;
 
     match num_fields {
-        1 if bounds.len() == 1 && fields[1] == buffer.len() + 1 => {
+        1 if bounds.len() == 1
+            && matches!(&bounds[0], BoundOrFiller::Bound(b) if !b.has_modifiers())
+            && fields[1] == buffer.len() + 1 =>
+        {
             stdout.write_all(buffer)?;
         }
         _ => {
@@ -88,6 +94,18 @@ fn cut_str_fast_lane<W: Write>(
                     BoundOrFiller::Bound(b) => {
                         output_parts(buffer, b, fields, stdout, opt)?;
                     }
+                    BoundOrFiller::WholeLine => {
+                        stdout.write_all(initial_buffer)?;
+                    }
+                    BoundOrFiller::Env(name) => {
+                        stdout.write_all(&env_value(name)?)?;
+                    }
+                    BoundOrFiller::Capture(_) => {
+                        bail!("Named placeholders are not supported by FastOpt")
+                    }
+                    BoundOrFiller::Conditional { .. } => {
+                        bail!("Conditional formatting is not supported by FastOpt")
+                    }
                 };
                 Ok(())
             })?;
@@ -109,23 +127,34 @@ fn output_parts<W: Write>(
     stdout: &mut W,
     opt: &FastOpt,
 ) -> Result<()> {
-    let r = b.try_into_range(fields.len() - 1);
-
-    let output = if r.is_ok() {
-        let r = r.unwrap();
-        let idx_start = fields[r.start];
-        let idx_end = fields[r.end] - 1;
-        &line[idx_start..idx_end]
-    } else if b.fallback_oob.is_some() {
-        b.fallback_oob.as_ref().unwrap()
-    } else if let Some(generic_fallback) = opt.fallback_oob {
-        generic_fallback
-    } else {
-        return Err(r.unwrap_err());
+    let output = match b.try_into_range(fields.len() - 1) {
+        Ok(r) => {
+            let idx_start = fields[r.start];
+            let idx_end = fields[r.end] - 1;
+            &line[idx_start..idx_end]
+        }
+        Err(e) => match (&b.fallback_oob, opt.fallback_oob) {
+            (Some(fallback), _) => fallback,
+            (None, Some(generic_fallback)) => generic_fallback,
+            (None, None) => return Err(e.into()),
+        },
     };
 
-    let field_to_print = output;
-    stdout.write_all(field_to_print)?;
+    let output = match &b.transform {
+        Some(transform) => transform.apply(output),
+        None => std::borrow::Cow::Borrowed(output),
+    };
+
+    let field_to_print = match &b.humanize {
+        Some(humanize) => humanize.apply(&output),
+        None => std::borrow::Cow::Borrowed(output.as_ref()),
+    };
+
+    if let Some(pad) = &b.pad {
+        pad.write_padded(stdout, &field_to_print)?;
+    } else {
+        stdout.write_all(&field_to_print)?;
+    }
 
     if opt.join && !b.is_last {
         stdout.write_all(&[opt.delimiter])?;
@@ -143,6 +172,8 @@ pub struct FastOpt<'a> {
     only_delimited: bool,
     trim: Option<Trim>,
     fallback_oob: Option<&'a [u8]>,
+    /// Input lines consumed before the first line we cut (see `Opt::preamble_lines`)
+    preamble_lines: usize,
 }
 
 impl<'a> TryFrom<&'a Opt> for FastOpt<'a> {
@@ -159,7 +190,42 @@ impl<'a> TryFrom<&'a Opt> for FastOpt<'a> {
             || value.json
             || value.bounds_type != BoundsType::Fields
             || value.replace_delimiter.is_some()
+            || value.output_delimiter.is_some()
+            || value.number.is_some()
             || value.regex_bag.is_some()
+            || value.delimiters.is_some()
+            || value.grep_fields.is_some()
+            || value.bounds.has_conditionals()
+            || value.bounds.has_byte_ranges()
+            || value.bounds.has_steps()
+            || value.strip_ansi
+            || value.show_nonprinting
+            || value.swap.is_some()
+            || value.insert.is_some()
+            || value.sub_delimiter.is_some()
+            || !value.set.is_empty()
+            || value.truncate.is_some()
+            || value.map.is_some()
+            || value.pad.is_some()
+            || value.print_delimiters
+            || value.count
+            || value.with_index
+            || value.pairs
+            || value.columnar.is_some()
+            || value.exec.is_some()
+            || value.keep_original_delimiters
+            || value.json_input
+            || value.csv
+            || value.quote.is_some()
+            || value.escape.is_some()
+            || value.respect_brackets
+            || value.field_match.is_some()
+            || !value.add_fields.is_empty()
+            || value.assert_fields.is_some()
+            || !value.assertions.is_empty()
+            || value.report
+            || value.lenient
+            || value.errors != ErrorPolicy::FailFast
         {
             return Err(
                 "FastOpt supports solely forward fields, join and single-character delimiters",
@@ -175,6 +241,7 @@ impl<'a> TryFrom<&'a Opt> for FastOpt<'a> {
             only_delimited: value.only_delimited,
             trim: value.trim,
             fallback_oob: value.fallback_oob.as_deref(),
+            preamble_lines: value.preamble_lines(),
         })
     }
 }
@@ -187,20 +254,18 @@ pub fn read_and_cut_text_as_bytes<R: BufRead, W: Write>(
     let mut fields: Vec<usize> = Vec::with_capacity(16);
 
     let last_interesting_field = opt.bounds.last_interesting_field;
+    let mut line_number = opt.preamble_lines;
+
+    let mut cut_line = |line: &[u8]| {
+        line_number += 1;
+        cut_str_fast_lane(line, opt, stdout, &mut fields, last_interesting_field)
+            .map_err(|x| io::Error::other(TucError::from(x).at_line(line_number)))
+            .and(Ok(true))
+    };
 
     match opt.eol {
-        EOL::Newline => stdin.for_byte_line(|line| {
-            cut_str_fast_lane(line, opt, stdout, &mut fields, last_interesting_field)
-                // XXX Should map properly the error
-                .map_err(|x| io::Error::new(io::ErrorKind::Other, x.to_string()))
-                .and(Ok(true))
-        })?,
-        EOL::Zero => stdin.for_byte_record(opt.eol.into(), |line| {
-            cut_str_fast_lane(line, opt, stdout, &mut fields, last_interesting_field)
-                // XXX Should map properly the error
-                .map_err(|x| io::Error::new(io::ErrorKind::Other, x.to_string()))
-                .and(Ok(true))
-        })?,
+        EOL::Newline => stdin.for_byte_line(&mut cut_line)?,
+        EOL::Zero => stdin.for_byte_record(opt.eol.into(), &mut cut_line)?,
     }
 
     Ok(())
@@ -226,6 +291,7 @@ mod tests {
             only_delimited: false,
             trim: None,
             fallback_oob: None,
+            preamble_lines: 0,
         }
     }
 
@@ -513,6 +579,61 @@ mod tests {
         );
     }
 
+    #[test]
+    fn cut_str_it_format_the_whole_line() {
+        let mut opt = make_fields_opt("{2}: {0}");
+        opt.trim = Some(Trim::Both);
+        let (mut output, mut fields) = make_cut_str_buffers();
+
+        let line = b"-a-b-";
+
+        cut_str_fast_lane(
+            line,
+            &opt,
+            &mut output,
+            &mut fields,
+            opt.bounds.last_interesting_field,
+        )
+        .unwrap();
+        assert_eq!(output, b"b: -a-b-\n".as_slice());
+    }
+
+    #[test]
+    fn cut_str_it_format_fields_with_padding() {
+        let opt = make_fields_opt("{1:<4}|{2:>4}|{3:^5=-}");
+        let (mut output, mut fields) = make_cut_str_buffers();
+
+        let line = b"a-bb";
+
+        cut_str_fast_lane(
+            line,
+            &opt,
+            &mut output,
+            &mut fields,
+            opt.bounds.last_interesting_field,
+        )
+        .unwrap();
+        assert_eq!(output, b"a   |  bb|  -  \n".as_slice());
+    }
+
+    #[test]
+    fn cut_str_it_humanize_numbers() {
+        let opt = make_fields_opt("{1|human} {2|size:>8}");
+        let (mut output, mut fields) = make_cut_str_buffers();
+
+        let line = b"1234567-2048";
+
+        cut_str_fast_lane(
+            line,
+            &opt,
+            &mut output,
+            &mut fields,
+            opt.bounds.last_interesting_field,
+        )
+        .unwrap();
+        assert_eq!(output, b"1,234,567  2.0 KiB\n".as_slice());
+    }
+
     #[test]
     fn cut_str_it_trim_fields() {
         let mut opt = make_fields_opt("1,3,-1");
@@ -11,25 +11,93 @@ const HELP: &str = concat!(
     r#"
 Cut text (or bytes) where a delimiter matches, then keep the desired parts.
 
-The data is read from standard input.
+The data is read from the given files, in order, or from standard input
+when there are none. A file named - means standard input.
 
 USAGE:
-    tuc [FLAGS] [OPTIONS]
+    tuc [FLAGS] [OPTIONS] [FILE]...
 
 FLAGS:
     -g, --greedy-delimiter        Match consecutive delimiters as if it was one
     -p, --compress-delimiter      Print only the first delimiter of a sequence
     -s, --only-delimited          Print only lines containing the delimiter
+    --number                      Prefix every output line with the number of
+                                  its input line (--number-separator, default
+                                  TAB, goes in between)
+    --skip-print                  With --skip, print the skipped lines as they
+                                  are
+    --keep-header                 Print the first line as it is, and cut only
+                                  the following ones
+    --cut-header                  Cut the first line too, but don't filter or
+                                  check it (e.g. with --grep-fields, --assert)
     -V, --version                 Print version information
     -z, --zero-terminated         Line delimiter is NUL (\0), not LF (\n)
     -h, --help                    Print this help and exit
+    --no-config                   Ignore the config file
     -m, --complement              Invert fields (e.g. '2' becomes '1,3:')
+    --keep-original-delimiters    With --complement, remove the selected fields
+                                  but keep the delimiters between the others
     -j, --(no-)join               Print selected parts with delimiter in between
-    --json                        Print fields as a JSON array of strings
+    --(no-)json                   Print fields as a JSON array of strings
+    --json-pretty                 Like --json, but print one field per line
+    --json-object                 Print fields as a JSON object, keyed by their
+                                  position (e.g. {"1":"a","3":"c"}), or by the
+                                  fields of the header with --keep-header
+    --msgpack                     Print fields as a MessagePack array of strings
+    --json-input                  Read every line as a JSON array, whose
+                                  elements are the fields, or as a JSON object
+                                  when --fields names its keys (-f user.id,msg)
+                                  or JSON Pointers (-f '{/user/id}: {/msg}')
+    --csv                         Split fields as CSV: a delimiter (default ,)
+                                  within double quotes is part of the field,
+                                  printed without the quotes
+    --escape[=char]               Ignore the delimiters preceded by this escape
+                                  character (default \)
+    --unescape                    Remove the escape character from the fields
+    --respect-brackets            Ignore the delimiters within (), [] or {}
+    -i, --in-place[=suffix]       Replace the input files with the output,
+                                  keeping a backup (file + suffix) if given
+                                  (e.g. --in-place=.bak or -i.bak)
+    --arrow                       Write the fields on stdout as the columns of
+                                  an Arrow IPC stream
+    --timestamp[=format]          Prefix every record with the current time (in
+                                  UTC, default format '%Y-%m-%dT%H:%M:%SZ')
+    --print-offset                Print on stderr, at the end, the byte offset
+                                  of the end of the input (to use with --seek)
+    --excel-columns               Allow spreadsheet column letters as fields
+                                  (e.g. 'A,C:F,AA' means '1,3:6,27')
+    --strip-ansi                  Remove ANSI escape sequences (e.g. colors)
+    -v, --show-nonprinting        Display control characters (e.g. ^I for TAB)
+                                  and invalid UTF-8 bytes (e.g. \xA0)
+    --print-delimiters            Print where the delimiters were found in each
+                                  line, as byte offset and matched text
+    --pairs                       Print every field on its own line, preceded
+                                  by its index and a TAB
+    --with-index                  Print the index of every field before it
+                                  (e.g. 3:c, or ["3","c"] with --json)
+    --count                       Print the number of fields of each line
+                                  (e.g. with -g, consecutive delimiters are one)
+    --explain=json                Print how the options were understood (parsed
+                                  fields, engine, normalized options) and exit
+    --to-awk                      Print an equivalent awk command and exit
+    --to-cut                      Print an equivalent cut command and exit.
+                                  Not every option can be translated
+    --analyze                     Look at the beginning of the input, print
+                                  its delimiters, end of line and a suggested
+                                  command, then exit
+    --sniff                       Cut with the delimiter that splits the first
+                                  lines most consistently (TAB , ; | : or space)
+    --timing                      Print on stderr the time spent reading,
+                                  cutting and writing, with records and bytes
+                                  per second
+    --copy                        Copy the output to the clipboard too, using
+                                  the OSC 52 terminal sequence (works over SSH)
 
 OPTIONS:
     -f, --fields <bounds>         Fields to keep, 1-indexed, comma separated.
                                   Use colon (:) to match a range (inclusive).
+                                  Add a step to keep one field every N
+                                  (e.g. 1:10:2 or ::2).
                                   Use equal (=) to apply out of bound fallback.
                                   Fields can be negative (-1 is the last field).
                                   [default: 1:]
@@ -41,6 +109,7 @@ OPTIONS:
                                     -f 3,2   => cb
                                     -f 3,1:2 => ca-b
                                     -f -3:-2 => b-c
+                                    -f ::2   => ac
                                     -f 1,8=fallback => afallback
 
                                   To re-apply the delimiter add -j, to replace
@@ -51,25 +120,177 @@ OPTIONS:
                                     -f '({1}, {2})' => (a, b)
 
                                   You can escape { and } using {{ and }}.
+                                  Use {0} (or {LINE}) for the whole line.
+                                  Use {$VAR} (or {env:VAR}) for the value of
+                                  the environment variable VAR.
+
+                                  When using --regex, named capture groups
+                                  (from the first match) can be used too
+                                  e.g. -e '(?<sep>[;,])' -f '{1}{sep}{3}'
+
+                                  Use {N?text:other} to print text only when
+                                  field N exists and is not empty (other
+                                  otherwise). The text can contain fields
+                                  e.g. -f '{1}{3? ({3})}' => a (c)
+
+                                  Pad a field to a width (in columns)
+                                  aligning it left (<), right (>) or center (^)
+                                  e.g. -f '{1:<4}|{2:>3}' => a   |  b
+
+                                  Make numbers readable with |human
+                                  (thousands separators) or |size (KiB, MiB...)
+                                  e.g. -f '{1|human} {2|size}' => 1,234 1.5 KiB
+
+                                  Transform a single field like --map does
+                                  with :name (trim, lower, upper...)
+                                  e.g. -f '{1:upper} {2:trim:lower}' => A b
+
+                                  Split a field again using -D and keep some
+                                  of its parts with field.part (e.g. 2.1)
+                                  e.g. -d ' ' -D @ -f 2.-1 on 'a b@c' => c
+
+                                  Keep only some bytes of a field with
+                                  field[bytes] (or -f field --bytes bytes)
+                                  e.g. -d ' ' -f '2[1:3]' on 'a 1234' => 123
 
     -b, --bytes <bounds>          Same as --fields, but it keeps bytes
+                                  (of each line, unless --whole-stream is used)
     -c, --characters <bounds>     Same as --fields, but it keeps characters
     -l, --lines <bounds>          Same as --fields, but it keeps lines
                                   Implies --join. To merge lines, use --no-join
+                                  /regex/:/regex/ keeps the lines from one
+                                  matching the first regex to the next one
+                                  matching the second (both optional)
+        --skip-empty              With --lines, ignore the empty lines
+                                  (they are not printed nor counted)
+        --skip <n>                Ignore the first n lines of the input (e.g.
+                                  a preamble), before cutting the others
+        --width <bounds>          Same as --fields, but it keeps the terminal
+                                  columns of each line (wide characters take
+                                  two and are never split). A range can end
+                                  past the end of the line (e.g. 1:80), but
+                                  not start after it
     -d, --delimiter <delimiter>   Delimiter used by --fields to cut the text
-                                  [default: \t]
+                                  [default: \t]. Repeat it to cut on any of
+                                  them (--join uses the first one)
+    -D, --sub-delimiter <delimiter>
+                                  Delimiter used to split a field again, when
+                                  using sub-fields (e.g. -f 2.1)
     -e, --regex <some regex>      Use a regular expression as delimiter
     -r, --replace-delimiter <new> Replace the delimiter with the provided text.
-                                  Implies --join
+                                  Implies --join. With --bytes, it's printed
+                                  between the selected ranges
+        --output-delimiter <new>  Print the provided text between the fields,
+                                  but not inside them (e.g. in -f 1:3).
+                                  Implies --join. Overrides --replace-delimiter
+                                  between the fields
     -t, --trim <type>             Trim the delimiter (greedy). Valid values are
                                   (l|L)eft, (r|R)ight, (b|B)oth
-        --fallback-oob <fallback> Generic fallback output for any field that
-                                  cannot be found (oob stands for out of bound).
+                                  With --lines, it trims the empty lines
+        --swap <field> <field>    Print every field, but exchange the position
+                                  of two of them (e.g. --swap 1 -1)
+        --insert <n> <value>      Print every field, with value inserted as the
+                                  field n (e.g. --insert -1 new appends it).
+                                  Same placeholders as --add-field
+        --set <field=value>       Print every field, but replace the content of
+                                  one of them (e.g. --set 3=redacted). The value
+                                  can be formatted as -f (e.g. --set '2={2}.0').
+                                  Repeatable
+        --grep-fields <regex>     Print the whole record, unchanged, when one of
+                                  the selected fields matches the regex
+        --field-match <regex>     Print the fields that match the regex,
+                                  wherever they are, separated by the delimiter
+        --field-drop              With --field-match, print the other fields
+        --assert-fields <n|min:max>
+                                  Fail if a record doesn't have n fields (or a
+                                  number of fields in the range min:max)
+        --assert <field~regex>    Fail if a field doesn't match the regex
+                                  (e.g. --assert '2~^\d+$'). Repeatable
+        --relaxed                 With --assert-fields or --assert, print a
+                                  warning and skip the record instead of failing
+        --lenient                 Print a warning and skip the lines with fields
+                                  out of bounds instead of failing (use
+                                  --errors ignore to skip them silently)
+        --report                  Print on stderr, at the end, how many records
+                                  were read, skipped or failed each assertion
+        --errors <policy>         What to do with a record that can't be cut
+                                  or fails an assertion. Valid values are
+                                  fail-fast (stop), collect (skip it and print
+                                  the error, then fail at the end of the input)
+                                  and ignore (skip it) [default: fail-fast]
+        --map <transforms>        Transform every printed field, in order:
+                                  trim, lower, upper, squeeze-spaces
+                                  (e.g. --map 'trim|lower')
+        --truncate <n[=ellipsis]> Cut fields longer than n characters, ending
+                                  them with an ellipsis [default: …]
+        --pad <n[=char]>          Fill fields shorter than n characters with
+                                  char, to align them [default: space]
+        --pad-align <align>       Side of the text within the padding of --pad.
+                                  Valid values are left, right and center
+                                  [default: left]
+        --add-field <value>       Append a field to every output record. Use
+                                  {NR} for the number of the input line and
+                                  {env:VAR} for environment variables.
+                                  Repeatable
+        --fallback-oob <fallback> Generic fallback output for any field or byte
+                                  that cannot be found (oob stands for out of
+                                  bound).
                                   It's overridden by any fallback assigned to a
-                                  specific field (see -f for help)
+                                  specific field (see -f for help). Use @path
+                                  to read it from a file, env:VAR to read it
+                                  from an environment variable
+        --whole-stream            Cut the bytes of the whole input, rather than
+                                  the bytes of each line
+        --no-split-codepoints     Do not print partial UTF-8 characters when
+                                  cutting bytes: a character partly selected
+                                  is printed whole
+        --json-keys <key,key,...> Read every line as a JSON object, whose values
+                                  for the given keys are the fields. Use dots
+                                  to reach nested values (e.g. user.id)
+        --quote <char>            Ignore the delimiters within this quote
+                                  character (with --csv, instead of ")
+        --decompress <format>     Decompress the input: auto, gzip, zstd or xz.
+                                  Files ending in .gz, .zst or .xz always are
+        --paste <path>            Append to every line the line with the same
+                                  number in path, joined by the delimiter.
+                                  Can be repeated
+        --parquet <path>          Write the fields as the columns of a Parquet
+                                  file (e.g. -f 1,3 --parquet out.parquet)
+        --types <type,type,...>   Type of each column for --parquet and --arrow:
+                                  str (default), int, float, bool
+        --exec <command>          Run a command for every record, replacing
+                                  its placeholders with the selected fields
+                                  (e.g. --exec 'mv {1} {2}'). No shell is used
+        --max-procs <N>           With --exec, run up to N commands at once
+        --seek <bytes>            Skip the input up to the first record that
+                                  starts at this byte offset (or after it)
+        --wrap <n[=prefix]>       Break output lines wider than n columns,
+                                  starting the next line with prefix
+        --tee-cmd <command>       Pipe the output through a command (e.g.
+                                  --tee-cmd 'sort -u') and print its output
+        --output-buffer-size <n>  Size in bytes of the output buffer. Use a
+                                  bigger buffer for throughput, a smaller one
+                                  for latency [default: 65536]
+        --config <path>           Read the default options from this file
+                                  (see Config file) instead of
+                                  ~/.config/tuc/config.toml
+        --preset <name>           Use the options of a preset of the config file
+                                  (the command line overrides them)
 
 Options precedence:
-    --trim and --compress-delimiter are applied before --fields or similar
+    --strip-ansi, --trim and --compress-delimiter are applied before --fields
+    or similar
+
+Config file:
+    Default values for delimiter, replace-delimiter, trim, eol (newline or
+    zero), greedy-delimiter, compress-delimiter, join and json, written as
+    `key = "value"` or `key = true` (a subset of TOML). The command line
+    overrides them. A [presets.NAME] table can hold any long option (e.g.
+    fields = "1,7"), used with --preset NAME
+
+Environment:
+    TUC_OPTS holds options that come before those of the command line (e.g.
+    TUC_OPTS="-d , -j"), split as a shell would. The command line wins
 
 Memory consumption:
     --characters and --fields read and allocate memory one line at a time
@@ -78,7 +299,8 @@ Memory consumption:
     are ordered and non-negative (e.g. -l 1,3:4,4,7), otherwise it allocates
     the whole input in memory (it also happens when -p or -m are being used)
 
-    --bytes allocate the whole input in memory
+    --bytes allocate memory one line at a time, unless --whole-stream is used
+    (then it allocates the whole input in memory)
 
 Colors:
     Help is displayed using colors. Colors will be suppressed in the
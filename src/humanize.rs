@@ -0,0 +1,155 @@
+use anyhow::{bail, Result};
+use std::borrow::Cow;
+use std::fmt;
+use std::str::FromStr;
+
+/// Make numbers easier to read, e.g. `{2|human}` or `{2|size}`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Humanize {
+    /// Add thousands separators, e.g. 1234567 => 1,234,567
+    Thousands,
+    /// Convert a number of bytes using binary suffixes, e.g. 1536 => 1.5 KiB
+    Size,
+}
+
+impl FromStr for Humanize {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "human" => Humanize::Thousands,
+            "size" => Humanize::Size,
+            _ => bail!(
+                "Field format error: unknown pipe `{}` (use human or size)",
+                s
+            ),
+        })
+    }
+}
+
+impl fmt::Display for Humanize {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Humanize::Thousands => write!(f, "human"),
+            Humanize::Size => write!(f, "size"),
+        }
+    }
+}
+
+impl Humanize {
+    /// Reformat `text` if it's a number, otherwise return it unchanged.
+    pub fn apply<'a>(&self, text: &'a [u8]) -> Cow<'a, [u8]> {
+        match self {
+            Humanize::Thousands => add_thousands_separators(text),
+            Humanize::Size => to_binary_size(text),
+        }
+    }
+}
+
+fn add_thousands_separators(text: &[u8]) -> Cow<'_, [u8]> {
+    let (sign, number) = match text.first() {
+        Some(b'-' | b'+') => text.split_at(1),
+        _ => text.split_at(0),
+    };
+
+    let int_len = number
+        .iter()
+        .position(|c| !c.is_ascii_digit())
+        .unwrap_or(number.len());
+    let (int_part, decimals) = number.split_at(int_len);
+
+    let is_number = int_len > 0
+        && (decimals.is_empty()
+            || (decimals[0] == b'.' && decimals[1..].iter().all(u8::is_ascii_digit)));
+
+    if !is_number || int_len <= 3 {
+        return Cow::Borrowed(text);
+    }
+
+    let mut output = Vec::with_capacity(text.len() + int_len / 3);
+    output.extend_from_slice(sign);
+    for (idx, digit) in int_part.iter().enumerate() {
+        if idx > 0 && (int_len - idx) % 3 == 0 {
+            output.push(b',');
+        }
+        output.push(*digit);
+    }
+    output.extend_from_slice(decimals);
+
+    Cow::Owned(output)
+}
+
+fn to_binary_size(text: &[u8]) -> Cow<'_, [u8]> {
+    const UNITS: [&str; 7] = ["B", "KiB", "MiB", "GiB", "TiB", "PiB", "EiB"];
+
+    let Some(bytes) = std::str::from_utf8(text)
+        .ok()
+        .and_then(|s| s.parse::<u64>().ok())
+    else {
+        return Cow::Borrowed(text);
+    };
+
+    if bytes < 1024 {
+        return Cow::Owned(format!("{bytes} B").into_bytes());
+    }
+
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+
+    Cow::Owned(format!("{value:.1} {}", UNITS[unit]).into_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn human(s: &str) -> String {
+        String::from_utf8(Humanize::Thousands.apply(s.as_bytes()).into_owned()).unwrap()
+    }
+
+    fn size(s: &str) -> String {
+        String::from_utf8(Humanize::Size.apply(s.as_bytes()).into_owned()).unwrap()
+    }
+
+    #[test]
+    fn it_adds_thousands_separators() {
+        assert_eq!(human("1"), "1");
+        assert_eq!(human("123"), "123");
+        assert_eq!(human("1234"), "1,234");
+        assert_eq!(human("1234567"), "1,234,567");
+        assert_eq!(human("-123456.789"), "-123,456.789");
+        assert_eq!(human("+1000"), "+1,000");
+    }
+
+    #[test]
+    fn it_leaves_non_numbers_untouched() {
+        assert_eq!(human(""), "");
+        assert_eq!(human("abcdef"), "abcdef");
+        assert_eq!(human("12345a"), "12345a");
+        assert_eq!(human("1234.5.6"), "1234.5.6");
+        assert_eq!(human("-"), "-");
+        assert_eq!(size("12k"), "12k");
+        assert_eq!(size("-1"), "-1");
+    }
+
+    #[test]
+    fn it_converts_to_binary_sizes() {
+        assert_eq!(size("0"), "0 B");
+        assert_eq!(size("1023"), "1023 B");
+        assert_eq!(size("1024"), "1.0 KiB");
+        assert_eq!(size("1536"), "1.5 KiB");
+        assert_eq!(size("10485760"), "10.0 MiB");
+        assert_eq!(size("18446744073709551615"), "16.0 EiB");
+    }
+
+    #[test]
+    fn it_parses_pipe_names() {
+        assert_eq!(Humanize::from_str("human").unwrap(), Humanize::Thousands);
+        assert_eq!(Humanize::from_str("size").unwrap(), Humanize::Size);
+        assert!(Humanize::from_str("upper").is_err());
+    }
+}
@@ -0,0 +1,166 @@
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{Error, Read, Result};
+use std::path::PathBuf;
+use std::rc::Rc;
+
+use crate::decompress::{decompress, Compression, Decompress};
+
+/// Reader that reads the input files one after the other, as if they were
+/// a single input. `-` means standard input. Every file ends with the end
+/// of line, so that its last record is never joined with the next file.
+///
+/// Files ending in `.gz`, `.zst` or `.xz` are decompressed, as is any
+/// input when `decompress` is given.
+pub struct FilesReader {
+    paths: VecDeque<PathBuf>,
+    decompress: Option<Decompress>,
+    current: Option<Box<dyn Read>>,
+    eol: u8,
+    /// Last byte read from the current file
+    last_byte: Option<u8>,
+    line_index: LineIndex,
+}
+
+/// How many lines were read from every input file, to find out which file
+/// a line of the whole input comes from (e.g. to report an error).
+#[derive(Debug, Clone, Default)]
+pub struct LineIndex(Rc<RefCell<Vec<(PathBuf, usize)>>>);
+
+impl LineIndex {
+    /// The file of a line of the whole input, and its line number within
+    /// that file (both 1-indexed).
+    pub fn locate(&self, line: usize) -> Option<(PathBuf, usize)> {
+        let mut first_line = 0;
+        for (path, lines) in self.0.borrow().iter() {
+            if line <= first_line + lines {
+                return Some((path.clone(), line - first_line));
+            }
+            first_line += lines;
+        }
+
+        None
+    }
+
+    fn open(&self, path: PathBuf) {
+        self.0.borrow_mut().push((path, 0));
+    }
+
+    fn add_lines(&self, lines: usize) {
+        if let Some((_, count)) = self.0.borrow_mut().last_mut() {
+            *count += lines;
+        }
+    }
+}
+
+impl FilesReader {
+    pub fn new(paths: Vec<PathBuf>, eol: u8, decompress: Option<Decompress>) -> Self {
+        FilesReader {
+            paths: paths.into(),
+            decompress,
+            current: None,
+            eol,
+            last_byte: None,
+            line_index: LineIndex::default(),
+        }
+    }
+
+    /// Where the lines read so far come from (it keeps being updated).
+    pub fn line_index(&self) -> LineIndex {
+        self.line_index.clone()
+    }
+}
+
+fn open(path: &PathBuf, format: Option<Decompress>) -> Result<Box<dyn Read>> {
+    let input: Box<dyn Read> =
+        if path.as_os_str() == "-" {
+            Box::new(std::io::stdin().lock())
+        } else {
+            Box::new(File::open(path).map_err(|e| {
+                Error::new(e.kind(), format!("Cannot open {}: {}", path.display(), e))
+            })?)
+        };
+
+    match format.or_else(|| Compression::from_extension(path).map(Decompress::Format)) {
+        Some(format) => decompress(input, format)
+            .map_err(|e| Error::new(e.kind(), format!("Cannot read {}: {}", path.display(), e))),
+        None => Ok(input),
+    }
+}
+
+impl Read for FilesReader {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+
+        loop {
+            let current = match &mut self.current {
+                Some(current) => current,
+                None => match self.paths.pop_front() {
+                    Some(path) => {
+                        let input = open(&path, self.decompress)?;
+                        self.line_index.open(path);
+                        self.current.insert(input)
+                    }
+                    None => return Ok(0),
+                },
+            };
+
+            let n = current.read(buf)?;
+            if n > 0 {
+                self.last_byte = Some(buf[n - 1]);
+                self.line_index
+                    .add_lines(memchr::memchr_iter(self.eol, &buf[..n]).count());
+                return Ok(n);
+            }
+
+            self.current = None;
+            if self.last_byte.take().is_some_and(|byte| byte != self.eol) {
+                buf[0] = self.eol;
+                self.line_index.add_lines(1);
+                return Ok(1);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_reads_the_files_in_order() {
+        let dir = std::env::temp_dir();
+        let path_a = dir.join(format!("tuc-test-{}.a", std::process::id()));
+        let path_b = dir.join(format!("tuc-test-{}.b", std::process::id()));
+        let path_c = dir.join(format!("tuc-test-{}.c", std::process::id()));
+        std::fs::write(&path_a, "a\nb").unwrap();
+        std::fs::write(&path_b, "").unwrap();
+        std::fs::write(&path_c, "c\n").unwrap();
+
+        let mut reader = FilesReader::new(
+            vec![path_a.clone(), path_b.clone(), path_c.clone()],
+            b'\n',
+            None,
+        );
+        let line_index = reader.line_index();
+        let mut output = String::new();
+        let result = reader.read_to_string(&mut output);
+
+        for path in [&path_a, &path_b, &path_c] {
+            std::fs::remove_file(path).unwrap();
+        }
+
+        result.unwrap();
+        assert_eq!(output, "a\nb\nc\n");
+        assert_eq!(line_index.locate(2), Some((path_a, 2)));
+        assert_eq!(line_index.locate(3), Some((path_c, 1)));
+        assert_eq!(line_index.locate(4), None);
+
+        let mut reader = FilesReader::new(vec!["/does/not/exist".into()], b'\n', None);
+        let err = reader.read_to_end(&mut Vec::new()).unwrap_err();
+        assert!(err.to_string().starts_with("Cannot open /does/not/exist: "));
+    }
+}
@@ -0,0 +1,238 @@
+use anyhow::{bail, Result};
+use serde_json::Value;
+use std::ops::Range;
+
+use crate::cut_str::JsonField;
+
+fn push_element(
+    buffer: &mut Vec<Range<usize>>,
+    json_values: &mut Vec<JsonField>,
+    output: &mut Vec<u8>,
+    value: Option<&Value>,
+) {
+    let start = output.len();
+    json_values.push(match value {
+        Some(Value::String(_)) => JsonField::String,
+        Some(_) => JsonField::Value,
+        None => JsonField::Missing,
+    });
+
+    match value {
+        Some(Value::String(s)) => output.extend_from_slice(s.as_bytes()),
+        Some(value) => output.extend_from_slice(value.to_string().as_bytes()),
+        None => (),
+    }
+
+    buffer.push(Range {
+        start,
+        end: output.len(),
+    });
+}
+
+/// Find the value of a key, whose parts can be separated by dots to reach
+/// nested objects or array elements (e.g. `user.id` or `tags.0`).
+/// A key containing dots is used as it is, when present.
+/// Keys starting with `/` are JSON Pointers (e.g. `/user/id`).
+fn lookup<'a>(record: &'a Value, key: &str) -> Option<&'a Value> {
+    if key.starts_with('/') {
+        return record.pointer(key);
+    }
+
+    let object = record.as_object()?;
+    if let Some(value) = object.get(key) {
+        return Some(value);
+    }
+
+    let mut parts = key.split('.');
+    let mut value = object.get(parts.next()?)?;
+    for part in parts {
+        value = match value {
+            Value::Object(object) => object.get(part)?,
+            Value::Array(elements) => elements.get(part.parse::<usize>().ok()?)?,
+            _ => return None,
+        };
+    }
+
+    Some(value)
+}
+
+/// Parse a record as a JSON array (or as a JSON object, when `keys` are given)
+/// and write its elements into `output`, separated by `delimiter`, while
+/// filling `buffer` with the ranges of the elements.
+///
+/// Strings are written without quotes and escapes, any other value
+/// is written as JSON (and marked as such in `json_values`, so that
+/// --json can print it back with its type). Keys missing from an object are
+/// out of bounds, so that their fallbacks apply.
+/// Nested values can be reached by joining the keys with dots (e.g. `user.id`).
+pub fn fill_with_json_elements(
+    buffer: &mut Vec<Range<usize>>,
+    json_values: &mut Vec<JsonField>,
+    line: &[u8],
+    delimiter: &[u8],
+    keys: Option<&[String]>,
+    output: &mut Vec<u8>,
+) -> Result<()> {
+    buffer.clear();
+    json_values.clear();
+    output.clear();
+
+    let record: Value = match serde_json::from_slice(line) {
+        Ok(record) => record,
+        Err(e) => bail!("Invalid JSON record: {}", e),
+    };
+
+    match (&record, keys) {
+        (Value::Array(elements), None) => {
+            for (idx, element) in elements.iter().enumerate() {
+                if idx > 0 {
+                    output.extend_from_slice(delimiter);
+                }
+                push_element(buffer, json_values, output, Some(element));
+            }
+        }
+        // JSON Pointers can reach into any value, keys only into objects
+        (record, Some(keys))
+            if record.is_object() || keys.iter().all(|key| key.starts_with('/')) =>
+        {
+            for (idx, key) in keys.iter().enumerate() {
+                if idx > 0 {
+                    output.extend_from_slice(delimiter);
+                }
+                push_element(buffer, json_values, output, lookup(record, key));
+            }
+        }
+        (_, None) => bail!("Invalid JSON record: expected an array"),
+        (_, Some(_)) => bail!("Invalid JSON record: expected an object"),
+    }
+
+    if buffer.is_empty() {
+        // An empty array is a record with one empty field
+        push_element(
+            buffer,
+            json_values,
+            output,
+            Some(&Value::String(String::new())),
+        );
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn elements(line: &str, keys: Option<&[String]>) -> Result<Vec<String>> {
+        let mut buffer = Vec::new();
+        let mut json_values = Vec::new();
+        let mut output = Vec::new();
+        fill_with_json_elements(
+            &mut buffer,
+            &mut json_values,
+            line.as_bytes(),
+            b"\t",
+            keys,
+            &mut output,
+        )?;
+        Ok(buffer
+            .into_iter()
+            .map(|r| String::from_utf8(output[r].to_vec()).unwrap())
+            .collect())
+    }
+
+    #[test]
+    fn it_reads_the_elements_of_arrays() {
+        assert_eq!(
+            elements(
+                r#"["a,b", "tab\tquote\"", 3, null, [1], {"x": true}]"#,
+                None
+            )
+            .unwrap(),
+            ["a,b", "tab\tquote\"", "3", "null", "[1]", r#"{"x":true}"#]
+        );
+        assert_eq!(elements("[]", None).unwrap(), [""]);
+        assert_eq!(
+            elements("{}", None).unwrap_err().to_string(),
+            "Invalid JSON record: expected an array"
+        );
+        assert!(elements("[1,", None)
+            .unwrap_err()
+            .to_string()
+            .starts_with("Invalid JSON record: "));
+    }
+
+    #[test]
+    fn it_marks_the_values_that_are_not_strings() {
+        let mut buffer = Vec::new();
+        let mut json_values = Vec::new();
+        let mut output = Vec::new();
+        let line = br#"["a", "3", 3, true, null, [1]]"#;
+        fill_with_json_elements(
+            &mut buffer,
+            &mut json_values,
+            line,
+            b"\t",
+            None,
+            &mut output,
+        )
+        .unwrap();
+        assert_eq!(
+            json_values,
+            [
+                JsonField::String,
+                JsonField::String,
+                JsonField::Value,
+                JsonField::Value,
+                JsonField::Value,
+                JsonField::Value
+            ]
+        );
+
+        let keys = ["missing".to_owned()];
+        let line = br#"{"a": 1}"#;
+        fill_with_json_elements(
+            &mut buffer,
+            &mut json_values,
+            line,
+            b"\t",
+            Some(&keys),
+            &mut output,
+        )
+        .unwrap();
+        assert_eq!(json_values, [JsonField::Missing]);
+    }
+
+    #[test]
+    fn it_reads_the_values_of_objects() {
+        let keys = ["name".to_owned(), "missing".to_owned(), "age".to_owned()];
+        assert_eq!(
+            elements(r#"{"age": 42, "name": "Jane"}"#, Some(&keys)).unwrap(),
+            ["Jane", "", "42"]
+        );
+
+        let keys = ["user.id".to_owned(), "tags.1".to_owned(), "a.b".to_owned()];
+        assert_eq!(
+            elements(
+                r#"{"user": {"id": 7}, "tags": ["x", "y"], "a.b": 1, "a": {"b": 2}}"#,
+                Some(&keys)
+            )
+            .unwrap(),
+            ["7", "y", "1"]
+        );
+        assert_eq!(
+            elements(r#"{"user": 1, "tags": []}"#, Some(&keys)).unwrap(),
+            ["", "", ""]
+        );
+
+        let pointers = ["/0/id".to_owned(), "/1".to_owned(), "/0/a~1b".to_owned()];
+        assert_eq!(
+            elements(r#"[{"id": 7, "a/b": "x"}, "y"]"#, Some(&pointers)).unwrap(),
+            ["7", "y", "x"]
+        );
+        assert_eq!(
+            elements("[1]", Some(&keys)).unwrap_err().to_string(),
+            "Invalid JSON record: expected an object"
+        );
+    }
+}
@@ -1,9 +1,48 @@
+// the json! literal describing every option in explain.rs is large
+#![recursion_limit = "256"]
+
+pub mod analyze;
+mod ansi;
+mod args;
 pub mod bounds;
+pub mod clipboard;
+#[cfg(feature = "columnar")]
+pub mod columnar;
+mod config;
 pub mod cut_bytes;
 pub mod cut_lines;
+pub mod cut_reader;
 pub mod cut_str;
+pub mod cut_width;
+pub mod decompress;
+pub mod error;
+pub mod exec;
+#[cfg(feature = "json")]
+pub mod explain;
 #[cfg(feature = "fast-lane")]
 pub mod fast_lane;
 pub mod help;
+pub mod humanize;
+pub mod input_files;
+#[cfg(feature = "json")]
+mod json_input;
+pub mod line_cutter;
+#[cfg(feature = "json")]
+pub mod msgpack;
+pub mod multi_fixed;
+mod nonprinting;
 pub mod options;
+pub mod paste;
 mod read_utils;
+pub mod record_sink;
+pub mod seek;
+#[cfg(feature = "serde")]
+mod serialize;
+pub mod timestamp;
+pub mod timing;
+pub mod transform;
+pub mod translate;
+pub mod validation;
+pub mod wrap;
+
+pub use cut_str::{fields, FieldsIter};
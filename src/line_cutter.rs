@@ -0,0 +1,126 @@
+use anyhow::anyhow;
+use std::io::Write;
+
+use crate::bounds::BoundsType;
+use crate::cut_str::RecordCutter;
+use crate::error::{Diagnostic, TucError};
+use crate::options::Opt;
+
+/// Cut one line at a time, reusing the same buffers, for programs that
+/// read their input on their own (e.g. from a socket or a channel).
+/// Only fields and characters are supported.
+///
+/// ```rust
+/// # use tuc::line_cutter::LineCutter;
+/// # use tuc::options::Opt;
+/// let opt = Opt::try_from_args(["-d", ",", "-f", "2"].map(Into::into)).unwrap();
+/// let mut cutter = LineCutter::new(opt).unwrap();
+///
+/// let mut output = Vec::new();
+/// cutter.cut(b"a,b", &mut output).unwrap();
+/// cutter.cut(b"c,d\n", &mut output).unwrap();
+/// cutter.finish(&mut output).unwrap();
+/// assert_eq!(output, b"b\nd\n");
+/// ```
+pub struct LineCutter {
+    cutter: RecordCutter,
+}
+
+impl LineCutter {
+    pub fn new(opt: Opt) -> Result<Self, TucError> {
+        if !matches!(opt.bounds_type, BoundsType::Fields | BoundsType::Characters) {
+            return Err(TucError::Other(anyhow!(
+                "LineCutter supports only fields and characters"
+            )));
+        }
+
+        Ok(LineCutter {
+            cutter: RecordCutter::new(opt),
+        })
+    }
+
+    /// Cut a line (with or without its end of line) and write it to `output`,
+    /// followed by the end of line. With --csv, the lines of a record whose
+    /// quoted field contains the end of line are written together, once
+    /// the record is complete.
+    pub fn cut<W: Write>(&mut self, line: &[u8], output: &mut W) -> Result<(), TucError> {
+        Ok(self.cutter.cut_record(line, output)?)
+    }
+
+    /// Write what's still pending (the last record of --csv, if its quoted
+    /// field was never closed) and add the summary of --report to the
+    /// diagnostics. Fails if any line was skipped by --errors collect.
+    pub fn finish<W: Write>(&mut self, output: &mut W) -> Result<(), TucError> {
+        self.cutter.finish(output)?;
+        self.cutter.finish_report();
+        Ok(self.cutter.check_errors()?)
+    }
+
+    /// The warnings and errors of the lines skipped since the last call
+    /// (e.g. by --lenient) and, after `finish`, the summary of --report.
+    pub fn diagnostics(&mut self) -> impl Iterator<Item = Diagnostic> + '_ {
+        self.cutter.take_diagnostics()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_opt(args: &[&str]) -> Opt {
+        Opt::try_from_args(args.iter().map(Into::into)).unwrap()
+    }
+
+    #[test]
+    fn it_cuts_a_line_at_a_time() {
+        let mut cutter = LineCutter::new(make_opt(&["-d", ",", "-f", "2,1", "-j"])).unwrap();
+
+        let mut output = Vec::new();
+        cutter.cut(b"a,b\n", &mut output).unwrap();
+        cutter.cut(b"c,d", &mut output).unwrap();
+        assert_eq!(output, b"b,a\nd,c\n");
+
+        assert!(matches!(
+            cutter.cut(b"e", &mut output),
+            Err(TucError::AtLine { line: 3, .. })
+        ));
+    }
+
+    #[test]
+    fn it_keeps_the_csv_records_together() {
+        let mut cutter = LineCutter::new(make_opt(&["--csv", "-f", "2"])).unwrap();
+
+        let mut output = Vec::new();
+        cutter.cut(b"a,\"b", &mut output).unwrap();
+        assert_eq!(output, b"");
+        cutter.cut(b"c\",d", &mut output).unwrap();
+        cutter.finish(&mut output).unwrap();
+        assert_eq!(output, b"b\nc\n");
+
+        assert!(LineCutter::new(make_opt(&["-l", "1"])).is_err());
+    }
+
+    #[test]
+    fn it_returns_the_diagnostics_instead_of_printing_them() {
+        let opt = make_opt(&["-d", ",", "-f", "{1}/{2}", "--lenient", "--report"]);
+        let mut cutter = LineCutter::new(opt).unwrap();
+
+        let mut output = Vec::new();
+        cutter.cut(b"a", &mut output).unwrap();
+        let diagnostics: Vec<String> = cutter.diagnostics().map(|d| d.to_string()).collect();
+        assert_eq!(
+            diagnostics,
+            ["tuc: warning: line 1: Out of bounds: 2 (skipped)"]
+        );
+
+        cutter.cut(b"b,c", &mut output).unwrap();
+        assert_eq!(cutter.diagnostics().count(), 0);
+
+        cutter.finish(&mut output).unwrap();
+        assert!(matches!(
+            cutter.diagnostics().collect::<Vec<_>>().as_slice(),
+            [Diagnostic::Report(summary)] if summary.starts_with("tuc: report: 2 records, 1 skipped")
+        ));
+        assert_eq!(output, b"b/c\n");
+    }
+}
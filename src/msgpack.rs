@@ -0,0 +1,126 @@
+use crate::error::Diagnostic;
+use crate::options::Opt;
+use crate::record_sink::{read_and_cut_into, RecordSink};
+use anyhow::Result;
+use std::convert::TryFrom;
+use std::io::{BufRead, Write};
+
+fn write_array_len<W: Write>(output: &mut W, len: usize) -> Result<()> {
+    match len {
+        0..=15 => output.write_all(&[0x90 | len as u8])?,
+        16..=0xffff => {
+            output.write_all(&[0xdc])?;
+            output.write_all(&(len as u16).to_be_bytes())?;
+        }
+        _ => {
+            output.write_all(&[0xdd])?;
+            output.write_all(&u32::try_from(len)?.to_be_bytes())?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Write `value` as a string, or as binary data if it's not valid UTF-8.
+fn write_str<W: Write>(output: &mut W, value: &[u8]) -> Result<()> {
+    let len = value.len();
+
+    if std::str::from_utf8(value).is_err() {
+        match len {
+            0..=0xff => output.write_all(&[0xc4, len as u8])?,
+            0x100..=0xffff => {
+                output.write_all(&[0xc5])?;
+                output.write_all(&(len as u16).to_be_bytes())?;
+            }
+            _ => {
+                output.write_all(&[0xc6])?;
+                output.write_all(&u32::try_from(len)?.to_be_bytes())?;
+            }
+        }
+    } else {
+        match len {
+            0..=31 => output.write_all(&[0xa0 | len as u8])?,
+            32..=0xff => output.write_all(&[0xd9, len as u8])?,
+            0x100..=0xffff => {
+                output.write_all(&[0xda])?;
+                output.write_all(&(len as u16).to_be_bytes())?;
+            }
+            _ => {
+                output.write_all(&[0xdb])?;
+                output.write_all(&u32::try_from(len)?.to_be_bytes())?;
+            }
+        }
+    }
+
+    output.write_all(value)?;
+    Ok(())
+}
+
+/// Write every record as a MessagePack array of strings (--msgpack).
+/// Fields that are not valid UTF-8 are written as binary data.
+pub struct MsgpackWriter<W: Write> {
+    output: W,
+}
+
+impl<W: Write> MsgpackWriter<W> {
+    pub fn new(output: W) -> Self {
+        MsgpackWriter { output }
+    }
+}
+
+impl<W: Write> RecordSink for MsgpackWriter<W> {
+    fn add_record(&mut self, values: &[&[u8]]) -> Result<()> {
+        write_array_len(&mut self.output, values.len())?;
+        for value in values {
+            write_str(&mut self.output, value)?;
+        }
+
+        Ok(())
+    }
+}
+
+pub fn read_and_write_msgpack<B: BufRead, W: Write>(
+    stdin: &mut B,
+    stdout: &mut W,
+    opt: Opt,
+    on_diagnostic: impl FnMut(Diagnostic),
+) -> Result<()> {
+    read_and_cut_into(stdin, opt, MsgpackWriter::new(stdout), on_diagnostic)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encode(values: &[&str]) -> Vec<u8> {
+        let values: Vec<&[u8]> = values.iter().map(|v| v.as_bytes()).collect();
+        let mut output = Vec::new();
+        MsgpackWriter::new(&mut output).add_record(&values).unwrap();
+        output
+    }
+
+    #[test]
+    fn it_encodes_records_as_arrays_of_strings() {
+        assert_eq!(encode(&[]), [0x90]);
+        assert_eq!(encode(&["a", ""]), [0x92, 0xa1, b'a', 0xa0]);
+
+        let long = "x".repeat(40);
+        assert_eq!(encode(&[&long])[..3], [0x91, 0xd9, 40]);
+
+        let long = "x".repeat(300);
+        assert_eq!(encode(&[&long])[..4], [0x91, 0xda, 0x01, 0x2c]);
+
+        let many = vec!["a"; 20];
+        assert_eq!(encode(&many)[..4], [0xdc, 0x00, 20, 0xa1]);
+    }
+
+    #[test]
+    fn it_encodes_invalid_utf8_as_binary() {
+        let mut output = Vec::new();
+        MsgpackWriter::new(&mut output)
+            .add_record(&[&b"a\xff"[..]])
+            .unwrap();
+        assert_eq!(output, [0x91, 0xc4, 2, b'a', 0xff]);
+    }
+}
@@ -0,0 +1,104 @@
+use aho_corasick::{AhoCorasick, MatchKind};
+use anyhow::{bail, Result};
+use std::ops::Range;
+
+/// Finder of any of several fixed delimiters (e.g. `-d , -d ';'`), without
+/// the need for a regex. When delimiters overlap the longest one wins.
+#[derive(Debug, Clone)]
+pub struct MultiFixedFinder {
+    delimiters: Vec<Vec<u8>>,
+    automaton: AhoCorasick,
+}
+
+impl MultiFixedFinder {
+    pub fn new(delimiters: Vec<Vec<u8>>) -> Result<Self> {
+        if delimiters.iter().any(|delimiter| delimiter.is_empty()) {
+            bail!("the delimiters cannot be empty");
+        }
+
+        let automaton = AhoCorasick::builder()
+            .match_kind(MatchKind::LeftmostLongest)
+            .build(&delimiters)?;
+
+        Ok(MultiFixedFinder {
+            delimiters,
+            automaton,
+        })
+    }
+
+    pub fn delimiters(&self) -> &[Vec<u8>] {
+        &self.delimiters
+    }
+
+    /// Split a string into parts and fill a buffer with ranges
+    /// that match those parts. With `greedy`, consecutive delimiters
+    /// (even different ones) count as one.
+    pub(crate) fn fill_with_fields_locations(
+        &self,
+        buffer: &mut Vec<Range<usize>>,
+        line: &[u8],
+        greedy: bool,
+    ) {
+        buffer.clear();
+
+        if line.is_empty() {
+            return;
+        }
+
+        let mut prev_part_start = 0;
+        let mut prev_delimiter_end = None;
+
+        for mat in self.automaton.find_iter(line) {
+            if !(greedy && prev_delimiter_end == Some(mat.start())) {
+                buffer.push(Range {
+                    start: prev_part_start,
+                    end: mat.start(),
+                });
+            }
+
+            prev_part_start = mat.end();
+            prev_delimiter_end = Some(mat.end());
+        }
+
+        buffer.push(Range {
+            start: prev_part_start,
+            end: line.len(),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fields<'a>(line: &'a [u8], delimiters: &[&str], greedy: bool) -> Vec<&'a [u8]> {
+        let finder =
+            MultiFixedFinder::new(delimiters.iter().map(|d| d.as_bytes().to_vec()).collect())
+                .unwrap();
+        let mut buffer = Vec::new();
+        finder.fill_with_fields_locations(&mut buffer, line, greedy);
+        buffer.into_iter().map(|range| &line[range]).collect()
+    }
+
+    #[test]
+    fn it_splits_on_any_delimiter() {
+        assert_eq!(
+            fields(b"a,b;c,,d", &[",", ";"], false),
+            [&b"a"[..], b"b", b"c", b"", b"d"]
+        );
+        assert_eq!(
+            fields(b"a::b:c", &[":", "::"], false),
+            [&b"a"[..], b"b", b"c"]
+        );
+        assert_eq!(fields(b"", &[",", ";"], false).len(), 0);
+    }
+
+    #[test]
+    fn it_merges_consecutive_delimiters_when_greedy() {
+        assert_eq!(
+            fields(b",a,;b;;c", &[",", ";"], true),
+            [&b""[..], b"a", b"b", b"c"]
+        );
+        assert!(MultiFixedFinder::new(vec![b",".to_vec(), Vec::new()]).is_err());
+    }
+}
@@ -0,0 +1,62 @@
+use bstr::ByteSlice;
+use std::borrow::Cow;
+use std::io::Write;
+
+/// Make control characters and invalid UTF-8 visible.
+///
+/// ASCII control characters use caret notation (e.g. `^I` for TAB, `^?` for DEL),
+/// other control characters are displayed as `\u{85}` and invalid bytes as `\xA0`.
+pub fn show_nonprinting(text: &[u8]) -> Cow<'_, [u8]> {
+    if std::str::from_utf8(text).is_ok_and(|s| !s.chars().any(char::is_control)) {
+        return Cow::Borrowed(text);
+    }
+
+    let mut output = Vec::with_capacity(text.len() + 8);
+
+    for chunk in ByteSlice::utf8_chunks(text) {
+        for c in chunk.valid().chars() {
+            match c {
+                '\x00'..='\x1f' => output.extend_from_slice(&[b'^', c as u8 + 64]),
+                '\x7f' => output.extend_from_slice(b"^?"),
+                c if c.is_control() => {
+                    let _ = write!(output, "\\u{{{:x}}}", c as u32);
+                }
+                c => output.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes()),
+            }
+        }
+
+        for b in chunk.invalid() {
+            let _ = write!(output, "\\x{b:02X}");
+        }
+    }
+
+    Cow::Owned(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_leaves_printable_text_untouched() {
+        assert!(matches!(show_nonprinting(b"foo bar"), Cow::Borrowed(_)));
+        assert!(matches!(
+            show_nonprinting("über 😁".as_bytes()),
+            Cow::Borrowed(_)
+        ));
+    }
+
+    #[test]
+    fn it_uses_caret_notation_for_ascii_control_characters() {
+        assert_eq!(
+            show_nonprinting(b"a\tb\r\x00\x1b\x7f"),
+            &b"a^Ib^M^@^[^?"[..]
+        );
+    }
+
+    #[test]
+    fn it_escapes_other_control_characters_and_invalid_bytes() {
+        assert_eq!(show_nonprinting("a\u{85}b".as_bytes()), &b"a\\u{85}b"[..]);
+        assert_eq!(show_nonprinting(b"a\xA0b\xff"), &b"a\\xA0b\\xFF"[..]);
+    }
+}
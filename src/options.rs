@@ -1,18 +1,57 @@
-use crate::bounds::{BoundsType, UserBoundsList};
-use anyhow::Result;
+use crate::args::resolve_fallback;
+use crate::bounds::userbounds::Align;
+use crate::bounds::{BoundsType, UserBounds, UserBoundsList};
+use crate::decompress::Decompress;
+use crate::error::TucError;
+use crate::exec::ExecCommand;
+use crate::multi_fixed::MultiFixedFinder;
+use crate::timestamp::TimestampFormat;
+use crate::transform::TransformPipeline;
+use crate::translate::Translation;
+use crate::wrap::Wrap;
+use anyhow::{bail, Result};
+use bstr::ByteSlice;
+use std::borrow::Cow;
+use std::fmt;
+use std::path::PathBuf;
 use std::str::FromStr;
 
+#[cfg(feature = "regex")]
+use crate::bounds::LinePattern;
+#[cfg(feature = "regex")]
+use crate::validation::FieldAssertion;
 #[cfg(feature = "regex")]
 use regex::bytes::Regex;
 
 #[cfg(feature = "regex")]
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct RegexBag {
     pub normal: Regex,
     pub greedy: Regex,
 }
 
+/// The regex that splits the characters of --characters
+#[cfg(feature = "regex")]
+pub(crate) const CHARACTERS_REGEX: &str = "\\b|\\B";
+
+#[cfg(feature = "regex")]
+impl RegexBag {
+    pub(crate) fn new(regex_text: &str) -> Result<Self> {
+        let malformed =
+            |e| anyhow::anyhow!("runtime error. The regular expression is malformed. {e}");
+        Ok(RegexBag {
+            normal: Regex::new(regex_text).map_err(malformed)?,
+            greedy: Regex::new(&format!("({})+", regex_text)).map_err(malformed)?,
+        })
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "lowercase")
+)]
 #[repr(u8)]
 pub enum EOL {
     Zero = 0,
@@ -28,9 +67,11 @@ impl From<EOL> for u8 {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
+#[non_exhaustive]
 pub struct Opt {
     pub delimiter: Vec<u8>,
+    pub sub_delimiter: Option<Vec<u8>>,
     pub eol: EOL,
     pub bounds: UserBoundsList,
     pub bounds_type: BoundsType,
@@ -38,22 +79,144 @@ pub struct Opt {
     pub greedy_delimiter: bool,
     pub compress_delimiter: bool,
     pub replace_delimiter: Option<Vec<u8>>,
+    /// Printed between the fields of the output, but never inside them.
+    /// Takes precedence over `replace_delimiter` (see `Opt::join_delimiter`)
+    pub output_delimiter: Option<Vec<u8>>,
     pub trim: Option<Trim>,
     pub version: bool,
     pub complement: bool,
     pub join: bool,
     pub json: bool,
+    pub json_pretty: bool,
+    pub json_object: bool,
+    /// Names of the fields, that --json-object uses as keys instead of the
+    /// field numbers (the fields of the header, with --keep-header)
+    pub field_names: Vec<String>,
+    pub msgpack: bool,
     pub fallback_oob: Option<Vec<u8>>,
+    pub strip_ansi: bool,
+    pub show_nonprinting: bool,
+    pub swap: Option<(UserBounds, UserBounds)>,
+    pub insert: Option<(i32, AddedField)>,
+    pub set: Vec<(UserBounds, UserBoundsList)>,
+    pub truncate: Option<Truncate>,
+    pub map: Option<TransformPipeline>,
+    pub pad: Option<Pad>,
+    pub output_buffer_size: usize,
+    pub whole_stream: bool,
+    pub no_split_codepoints: bool,
+    pub print_delimiters: bool,
+    pub pairs: bool,
+    pub explain: bool,
+    pub translate: Option<Translation>,
+    pub analyze: bool,
+    pub sniff: bool,
+    pub timing: bool,
+    pub copy: bool,
+    pub columnar: Option<ColumnarOutput>,
+    pub column_types: Vec<ColumnType>,
+    pub exec: Option<ExecCommand>,
+    pub max_procs: usize,
+    pub tee_cmd: Option<String>,
+    pub timestamp: Option<TimestampFormat>,
+    pub wrap: Option<Wrap>,
+    pub seek: u64,
+    pub print_offset: bool,
+    pub paste: Vec<PathBuf>,
+    pub files: Vec<PathBuf>,
+    pub decompress: Option<Decompress>,
+    /// Suffix of the backup of --in-place (empty for no backup)
+    pub in_place: Option<String>,
+    pub skip: usize,
+    /// Separator between the record number and the output (--number)
+    pub number: Option<Vec<u8>>,
+    pub skip_print: bool,
+    pub keep_header: bool,
+    pub cut_header: bool,
+    pub keep_original_delimiters: bool,
+    pub skip_empty: bool,
+    pub json_input: bool,
+    pub csv: bool,
+    pub quote: Option<u8>,
+    pub escape: Option<u8>,
+    pub unescape: bool,
+    pub respect_brackets: bool,
+    pub json_keys: Option<Vec<String>>,
+    pub add_fields: Vec<AddedField>,
+    pub assert_fields: Option<FieldsCount>,
+    pub relaxed: bool,
+    pub lenient: bool,
+    #[cfg(feature = "regex")]
+    pub assertions: Vec<FieldAssertion>,
+    #[cfg(not(feature = "regex"))]
+    pub assertions: Vec<()>,
+    pub report: bool,
+    pub errors: ErrorPolicy,
+    /// Set when --delimiter is repeated (`delimiter` is the first one)
+    pub delimiters: Option<MultiFixedFinder>,
     #[cfg(feature = "regex")]
     pub regex_bag: Option<RegexBag>,
     #[cfg(not(feature = "regex"))]
     pub regex_bag: Option<()>,
+    #[cfg(feature = "regex")]
+    pub grep_fields: Option<Regex>,
+    #[cfg(not(feature = "regex"))]
+    pub grep_fields: Option<()>,
+    /// Set when --lines is a range of lines between regex matches
+    /// (`bounds` then keeps every line)
+    #[cfg(feature = "regex")]
+    pub line_pattern: Option<LinePattern>,
+    #[cfg(not(feature = "regex"))]
+    pub line_pattern: Option<()>,
+    /// Keep (or, with `field_drop`, remove) the fields whose content
+    /// matches, wherever they are
+    #[cfg(feature = "regex")]
+    pub field_match: Option<Regex>,
+    #[cfg(not(feature = "regex"))]
+    pub field_match: Option<()>,
+    pub field_drop: bool,
+    /// Print the number of fields of each record instead of cutting it
+    pub count: bool,
+    /// Print the index of every field before it (e.g. `3:value`)
+    pub with_index: bool,
+}
+
+impl Opt {
+    /// Build the options with typed setters (see `OptBuilder`)
+    pub fn builder() -> OptBuilder {
+        OptBuilder::default()
+    }
+
+    /// Number of input lines consumed before the records to cut
+    /// (by --skip and --keep-header/--cut-header).
+    pub fn preamble_lines(&self) -> usize {
+        self.skip + usize::from(self.keep_header || self.cut_header)
+    }
+
+    /// Replace the environment variables of the format strings (e.g.
+    /// `{$HOME}`) with their values, so that they are read only once.
+    pub fn expand_env(&mut self) -> Result<(), TucError> {
+        self.bounds.expand_env()?;
+        self.set
+            .iter_mut()
+            .try_for_each(|(_, template)| template.expand_env())
+    }
+
+    /// Delimiter printed between the fields of the output: --output-delimiter
+    /// if given, otherwise --replace-delimiter (that also replaces the
+    /// delimiter inside the fields). None keeps the original delimiter.
+    pub fn join_delimiter(&self) -> Option<&[u8]> {
+        self.output_delimiter
+            .as_deref()
+            .or(self.replace_delimiter.as_deref())
+    }
 }
 
 impl Default for Opt {
     fn default() -> Self {
         Opt {
             delimiter: "-".into(),
+            sub_delimiter: None,
             eol: EOL::Newline,
             bounds: UserBoundsList::from_str("1:").unwrap(),
             bounds_type: BoundsType::Fields,
@@ -61,18 +224,272 @@ impl Default for Opt {
             greedy_delimiter: false,
             compress_delimiter: false,
             replace_delimiter: None,
+            output_delimiter: None,
             trim: None,
             version: false,
             complement: false,
             join: false,
             json: false,
+            json_pretty: false,
+            json_object: false,
+            field_names: Vec::new(),
+            msgpack: false,
             fallback_oob: None,
+            strip_ansi: false,
+            show_nonprinting: false,
+            swap: None,
+            insert: None,
+            set: Vec::new(),
+            truncate: None,
+            map: None,
+            pad: None,
+            output_buffer_size: 64 * 1024,
+            whole_stream: false,
+            no_split_codepoints: false,
+            print_delimiters: false,
+            pairs: false,
+            explain: false,
+            translate: None,
+            analyze: false,
+            sniff: false,
+            timing: false,
+            copy: false,
+            columnar: None,
+            column_types: Vec::new(),
+            exec: None,
+            max_procs: 1,
+            tee_cmd: None,
+            timestamp: None,
+            wrap: None,
+            seek: 0,
+            print_offset: false,
+            paste: Vec::new(),
+            files: Vec::new(),
+            decompress: None,
+            in_place: None,
+            skip: 0,
+            number: None,
+            skip_print: false,
+            keep_header: false,
+            cut_header: false,
+            keep_original_delimiters: false,
+            skip_empty: false,
+            json_input: false,
+            csv: false,
+            quote: None,
+            escape: None,
+            unescape: false,
+            respect_brackets: false,
+            json_keys: None,
+            add_fields: Vec::new(),
+            assert_fields: None,
+            relaxed: false,
+            lenient: false,
+            assertions: Vec::new(),
+            report: false,
+            errors: ErrorPolicy::FailFast,
+            delimiters: None,
             regex_bag: None,
+            grep_fields: None,
+            line_pattern: None,
+            field_match: None,
+            field_drop: false,
+            count: false,
+            with_index: false,
+        }
+    }
+}
+
+/// Build an `Opt` using typed setters instead of command line arguments.
+///
+/// `build()` validates the options with the same rules as the `tuc`
+/// command line (see `Opt::try_from_args`). Options without a setter
+/// can be changed on the built `Opt`.
+///
+/// ```rust
+/// # use tuc::options::{Opt, Trim};
+/// let opt = Opt::builder()
+///     .delimiter(",")
+///     .fields("3,1".parse().unwrap())
+///     .trim(Trim::Both)
+///     .join(true)
+///     .build()
+///     .unwrap();
+/// assert_eq!(opt.delimiter, b",");
+/// assert!(opt.join);
+///
+/// assert!(Opt::builder().lines("1".parse().unwrap()).json(true).build().is_err());
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct OptBuilder {
+    bounds: Option<(BoundsType, UserBoundsList)>,
+    delimiter: Option<String>,
+    replace_delimiter: Option<String>,
+    fallback_oob: Option<String>,
+    trim: Option<Trim>,
+    join: bool,
+    complement: bool,
+    only_delimited: bool,
+    greedy_delimiter: bool,
+    compress_delimiter: bool,
+    json: bool,
+    zero_terminated: bool,
+}
+
+impl OptBuilder {
+    /// What to cut and how (replaces any previous bounds)
+    pub fn bounds(mut self, bounds_type: BoundsType, bounds: UserBoundsList) -> Self {
+        self.bounds = Some((bounds_type, bounds));
+        self
+    }
+
+    pub fn fields(self, bounds: UserBoundsList) -> Self {
+        self.bounds(BoundsType::Fields, bounds)
+    }
+
+    pub fn characters(self, bounds: UserBoundsList) -> Self {
+        self.bounds(BoundsType::Characters, bounds)
+    }
+
+    pub fn bytes(self, bounds: UserBoundsList) -> Self {
+        self.bounds(BoundsType::Bytes, bounds)
+    }
+
+    pub fn lines(self, bounds: UserBoundsList) -> Self {
+        self.bounds(BoundsType::Lines, bounds)
+    }
+
+    pub fn delimiter(mut self, delimiter: impl Into<String>) -> Self {
+        self.delimiter = Some(delimiter.into());
+        self
+    }
+
+    pub fn replace_delimiter(mut self, replace_delimiter: impl Into<String>) -> Self {
+        self.replace_delimiter = Some(replace_delimiter.into());
+        self
+    }
+
+    /// Same as --fallback-oob (`file:` and `env:` prefixes included)
+    pub fn fallback_oob(mut self, fallback_oob: impl Into<String>) -> Self {
+        self.fallback_oob = Some(fallback_oob.into());
+        self
+    }
+
+    pub fn trim(mut self, trim: Trim) -> Self {
+        self.trim = Some(trim);
+        self
+    }
+
+    pub fn join(mut self, join: bool) -> Self {
+        self.join = join;
+        self
+    }
+
+    pub fn complement(mut self, complement: bool) -> Self {
+        self.complement = complement;
+        self
+    }
+
+    pub fn only_delimited(mut self, only_delimited: bool) -> Self {
+        self.only_delimited = only_delimited;
+        self
+    }
+
+    pub fn greedy_delimiter(mut self, greedy_delimiter: bool) -> Self {
+        self.greedy_delimiter = greedy_delimiter;
+        self
+    }
+
+    pub fn compress_delimiter(mut self, compress_delimiter: bool) -> Self {
+        self.compress_delimiter = compress_delimiter;
+        self
+    }
+
+    pub fn json(mut self, json: bool) -> Self {
+        self.json = json;
+        self
+    }
+
+    pub fn zero_terminated(mut self, zero_terminated: bool) -> Self {
+        self.zero_terminated = zero_terminated;
+        self
+    }
+
+    /// Validate the options and build them
+    pub fn build(self) -> Result<Opt> {
+        let (bounds_type, bounds) = self
+            .bounds
+            .unwrap_or_else(|| (BoundsType::Fields, UserBoundsList::from_str("1:").unwrap()));
+
+        if self.delimiter.is_some() && bounds_type != BoundsType::Fields {
+            bail!("runtime error. --delimiter is available only for --fields");
+        }
+
+        if self.replace_delimiter.is_some() && self.json {
+            bail!("runtime error. The use of --replace with --json is not supported");
         }
+
+        let delimiter: Vec<u8> = match bounds_type {
+            BoundsType::Fields => self.delimiter.unwrap_or_else(|| "\t".into()).into(),
+            BoundsType::Lines => "\n".into(),
+            _ => Vec::new(),
+        };
+
+        let replace_delimiter: Option<Vec<u8>> = if self.json {
+            Some(",".into())
+        } else if bounds_type == BoundsType::Characters {
+            Some("".into())
+        } else {
+            self.replace_delimiter.map(Into::into)
+        };
+
+        #[cfg(feature = "regex")]
+        let regex_bag = (bounds_type == BoundsType::Characters)
+            .then(|| RegexBag::new(CHARACTERS_REGEX))
+            .transpose()?;
+
+        #[cfg(not(feature = "regex"))]
+        let regex_bag = None;
+
+        let join = self.join
+            || self.json
+            || replace_delimiter.is_some()
+            || bounds_type == BoundsType::Lines
+            || bounds_type == BoundsType::Characters;
+
+        let opt = Opt {
+            delimiter,
+            eol: if self.zero_terminated {
+                EOL::Zero
+            } else {
+                EOL::Newline
+            },
+            bounds,
+            bounds_type,
+            only_delimited: self.only_delimited,
+            greedy_delimiter: self.greedy_delimiter,
+            compress_delimiter: self.compress_delimiter,
+            replace_delimiter,
+            trim: self.trim,
+            complement: self.complement,
+            join,
+            json: self.json,
+            fallback_oob: self.fallback_oob.map(resolve_fallback).transpose()?,
+            regex_bag,
+            ..Opt::default()
+        };
+
+        opt.validate()?;
+        Ok(opt)
     }
 }
 
 #[derive(Debug, Clone, Copy, Eq, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "lowercase")
+)]
 pub enum Trim {
     Left,
     Right,
@@ -91,3 +508,518 @@ impl FromStr for Trim {
         })
     }
 }
+
+/// What to do when a record cannot be cut or fails a validation.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum ErrorPolicy {
+    /// Stop at the first error
+    FailFast,
+    /// Skip the record and print the error, then fail once the input is over
+    Collect,
+    /// Skip the record silently
+    Ignore,
+}
+
+impl FromStr for ErrorPolicy {
+    type Err = Box<dyn std::error::Error>;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "fail-fast" => ErrorPolicy::FailFast,
+            "collect" => ErrorPolicy::Collect,
+            "ignore" => ErrorPolicy::Ignore,
+            _ => return Err("Valid error policies are fail-fast, collect, ignore".into()),
+        })
+    }
+}
+
+impl fmt::Display for ErrorPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            ErrorPolicy::FailFast => "fail-fast",
+            ErrorPolicy::Collect => "collect",
+            ErrorPolicy::Ignore => "ignore",
+        })
+    }
+}
+
+/// Columnar format written instead of text (--parquet, --arrow).
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum ColumnarOutput {
+    /// Parquet file
+    Parquet(PathBuf),
+    /// Arrow IPC stream, written on stdout
+    Arrow,
+}
+
+/// Type of a column of the columnar output (--types).
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum ColumnType {
+    Str,
+    Int,
+    Float,
+    Bool,
+}
+
+impl FromStr for ColumnType {
+    type Err = Box<dyn std::error::Error>;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "str" => ColumnType::Str,
+            "int" => ColumnType::Int,
+            "float" => ColumnType::Float,
+            "bool" => ColumnType::Bool,
+            _ => return Err("Valid column types are str, int, float, bool".into()),
+        })
+    }
+}
+
+impl fmt::Display for ColumnType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            ColumnType::Str => "str",
+            ColumnType::Int => "int",
+            ColumnType::Float => "float",
+            ColumnType::Bool => "bool",
+        })
+    }
+}
+
+/// Maximum width (in characters) of a printed field, including the ellipsis
+/// that replaces the text that doesn't fit (e.g. `10` or `10=...`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Truncate {
+    pub width: usize,
+    pub ellipsis: Vec<u8>,
+}
+
+impl FromStr for Truncate {
+    type Err = Box<dyn std::error::Error>;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (width, ellipsis) = s.split_once('=').unwrap_or((s, "…"));
+        let width = width
+            .parse::<usize>()
+            .ok()
+            .filter(|width| *width > 0)
+            .ok_or("Truncate width must be a positive number (e.g. 10 or 10=...)")?;
+
+        if ellipsis.chars().count() > width {
+            return Err("Truncate ellipsis cannot be wider than the width".into());
+        }
+
+        Ok(Truncate {
+            width,
+            ellipsis: ellipsis.into(),
+        })
+    }
+}
+
+impl Truncate {
+    pub fn apply<'a>(&self, text: &'a [u8]) -> Cow<'a, [u8]> {
+        let mut char_indices = text.char_indices();
+        if char_indices.nth(self.width).is_none() {
+            return Cow::Borrowed(text);
+        }
+
+        let keep = self.width.saturating_sub(self.ellipsis.chars().count());
+        let end = text
+            .char_indices()
+            .nth(keep)
+            .map_or(text.len(), |(start, _, _)| start);
+
+        let mut output = Vec::with_capacity(end + self.ellipsis.len());
+        output.extend_from_slice(&text[..end]);
+        output.extend_from_slice(&self.ellipsis);
+        Cow::Owned(output)
+    }
+}
+
+/// Minimum width (in characters) of a printed field, filled with `fill`
+/// on the side opposite to `align` (e.g. `10` or `10=.`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pad {
+    pub width: usize,
+    pub fill: char,
+    pub align: Align,
+}
+
+impl FromStr for Pad {
+    type Err = Box<dyn std::error::Error>;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (width, fill) = s.split_once('=').unwrap_or((s, " "));
+        let width = width
+            .parse::<usize>()
+            .map_err(|_| "Pad width must be a positive number (e.g. 10 or 10=.)")?;
+
+        let mut chars = fill.chars();
+        let fill = match (chars.next(), chars.next()) {
+            (Some(c), None) => c,
+            _ => return Err("Pad fill must be a single character (e.g. 10=.)".into()),
+        };
+
+        Ok(Pad {
+            width,
+            fill,
+            align: Align::Left,
+        })
+    }
+}
+
+impl Pad {
+    pub fn apply<'a>(&self, text: &'a [u8]) -> Cow<'a, [u8]> {
+        let len = text.chars().count();
+        if len >= self.width {
+            return Cow::Borrowed(text);
+        }
+
+        let missing = self.width - len;
+        let (left, right) = match self.align {
+            Align::Left => (0, missing),
+            Align::Right => (missing, 0),
+            Align::Center => (missing / 2, missing - missing / 2),
+        };
+
+        let mut fill = [0; 4];
+        let fill = self.fill.encode_utf8(&mut fill).as_bytes();
+
+        let mut output = Vec::with_capacity(text.len() + missing * fill.len());
+        output.extend(fill.repeat(left));
+        output.extend_from_slice(text);
+        output.extend(fill.repeat(right));
+        Cow::Owned(output)
+    }
+}
+
+/// Number of fields every record must have, either exact (`3`)
+/// or as an inclusive range (`2:4`, `2:` or `:4`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FieldsCount {
+    pub min: usize,
+    pub max: usize,
+}
+
+impl FromStr for FieldsCount {
+    type Err = Box<dyn std::error::Error>;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parse = |n: &str, default: usize| match n {
+            "" => Ok(default),
+            n => n.parse::<usize>().map_err(|_| {
+                "The number of fields must be a positive number or a range (e.g. 3 or 2:4)"
+            }),
+        };
+
+        let (min, max) = match s.split_once(':') {
+            Some((min, max)) => (parse(min, 1)?, parse(max, usize::MAX)?),
+            None if s.is_empty() => return Err("The number of fields cannot be empty".into()),
+            None => (parse(s, 0)?, parse(s, 0)?),
+        };
+
+        if min > max {
+            return Err("The minimum number of fields cannot be greater than the maximum".into());
+        }
+
+        Ok(FieldsCount { min, max })
+    }
+}
+
+impl fmt::Display for FieldsCount {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match (self.min, self.max) {
+            (min, max) if min == max => write!(f, "{}", min),
+            (min, usize::MAX) => write!(f, "{}:", min),
+            (min, max) => write!(f, "{}:{}", min, max),
+        }
+    }
+}
+
+impl FieldsCount {
+    pub fn contains(&self, num_fields: usize) -> bool {
+        (self.min..=self.max).contains(&num_fields)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum AddedFieldPart {
+    Text(String),
+    RecordNumber,
+}
+
+/// Field appended to every output record (e.g. `batch-{NR}` or `{env:HOSTNAME}`).
+///
+/// Environment variables are resolved once, while parsing, while `{NR}`
+/// is replaced by the number of the record being cut (starting from 1).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AddedField {
+    parts: Vec<AddedFieldPart>,
+}
+
+impl FromStr for AddedField {
+    type Err = Box<dyn std::error::Error>;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = Vec::new();
+        let mut text = String::new();
+        let mut rest = s;
+
+        while let Some(idx) = rest.find(['{', '}']) {
+            text.push_str(&rest[..idx]);
+            rest = &rest[idx..];
+
+            if rest.starts_with("{{") || rest.starts_with("}}") {
+                text.push_str(&rest[..1]);
+                rest = &rest[2..];
+                continue;
+            }
+
+            let end = match (rest.starts_with('{'), rest.find('}')) {
+                (true, Some(end)) => end,
+                _ => return Err(format!("Unmatched brace in field: {}", s).into()),
+            };
+
+            match &rest[1..end] {
+                "NR" => {
+                    if !text.is_empty() {
+                        parts.push(AddedFieldPart::Text(std::mem::take(&mut text)));
+                    }
+                    parts.push(AddedFieldPart::RecordNumber);
+                }
+                placeholder => match placeholder.strip_prefix("env:") {
+                    Some(name) => text.push_str(
+                        &std::env::var(name)
+                            .map_err(|_| format!("Environment variable not set: {}", name))?,
+                    ),
+                    None => {
+                        return Err(format!(
+                            "Unknown placeholder {{{}}} (valid ones are {{NR}} and {{env:VAR}})",
+                            placeholder
+                        )
+                        .into())
+                    }
+                },
+            }
+
+            rest = &rest[end + 1..];
+        }
+
+        text.push_str(rest);
+        if !text.is_empty() || parts.is_empty() {
+            parts.push(AddedFieldPart::Text(text));
+        }
+
+        Ok(AddedField { parts })
+    }
+}
+
+impl fmt::Display for AddedField {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for part in &self.parts {
+            match part {
+                AddedFieldPart::Text(text) => {
+                    write!(f, "{}", text.replace('{', "{{").replace('}', "}}"))?
+                }
+                AddedFieldPart::RecordNumber => write!(f, "{{NR}}")?,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl AddedField {
+    pub fn render(&self, record_number: usize) -> Cow<'_, [u8]> {
+        match self.parts.as_slice() {
+            [AddedFieldPart::Text(text)] => Cow::Borrowed(text.as_bytes()),
+            parts => {
+                let mut output = Vec::new();
+                for part in parts {
+                    match part {
+                        AddedFieldPart::Text(text) => output.extend_from_slice(text.as_bytes()),
+                        AddedFieldPart::RecordNumber => {
+                            output.extend_from_slice(record_number.to_string().as_bytes())
+                        }
+                    }
+                }
+                Cow::Owned(output)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_builds_the_options() {
+        let opt = Opt::builder()
+            .fields(UserBoundsList::from_str("2,1").unwrap())
+            .delimiter(",")
+            .replace_delimiter(";")
+            .fallback_oob("none")
+            .trim(Trim::Left)
+            .join(true)
+            .build()
+            .unwrap();
+
+        assert_eq!(opt.bounds_type, BoundsType::Fields);
+        assert_eq!(opt.bounds.to_string(), "2,1");
+        assert_eq!(opt.delimiter, b",");
+        assert_eq!(opt.replace_delimiter.as_deref(), Some(&b";"[..]));
+        assert_eq!(opt.fallback_oob.as_deref(), Some(&b"none"[..]));
+        assert_eq!(opt.trim, Some(Trim::Left));
+        assert!(opt.join);
+
+        let opt = Opt::builder().build().unwrap();
+        assert_eq!(opt.bounds.to_string(), "1:");
+        assert!(!opt.join);
+
+        let opt = Opt::builder()
+            .lines(UserBoundsList::from_str("1:3").unwrap())
+            .complement(true)
+            .build()
+            .unwrap();
+        assert_eq!(opt.bounds_type, BoundsType::Lines);
+        assert!(opt.complement);
+
+        #[cfg(feature = "regex")]
+        {
+            let opt = Opt::builder()
+                .characters(UserBoundsList::from_str("2,1").unwrap())
+                .build()
+                .unwrap();
+            assert_eq!(opt.replace_delimiter.as_deref(), Some(&b""[..]));
+            assert!(opt.join);
+        }
+
+        assert!(Opt::builder()
+            .bytes(UserBoundsList::from_str("1").unwrap())
+            .delimiter(",")
+            .build()
+            .is_err());
+    }
+
+    #[test]
+    fn it_validates_the_options_as_the_command_line_does() {
+        let error = |builder: OptBuilder, args: &[&str]| {
+            let built = builder.build().unwrap_err().to_string();
+            let parsed = Opt::try_from_args(args.iter().map(Into::into))
+                .unwrap_err()
+                .to_string();
+            assert_eq!(built, parsed);
+        };
+
+        error(
+            Opt::builder()
+                .bytes(UserBoundsList::from_str("1").unwrap())
+                .json(true),
+            &["-b", "1", "--json"],
+        );
+        error(
+            Opt::builder()
+                .bounds(BoundsType::Width, UserBoundsList::from_str("1:2").unwrap())
+                .complement(true),
+            &["--width", "1:2", "-m"],
+        );
+        error(
+            Opt::builder()
+                .fields(UserBoundsList::from_str("{1:<3}").unwrap())
+                .json(true),
+            &["-f", "{1:<3}", "--json"],
+        );
+    }
+
+    #[test]
+    fn it_pads_short_text() {
+        let pad = Pad::from_str("5").unwrap();
+        assert_eq!(pad.apply(b"abc"), &b"abc  "[..]);
+        assert_eq!(pad.apply(b"abcdef"), &b"abcdef"[..]);
+
+        let pad = Pad {
+            align: Align::Right,
+            ..Pad::from_str("5=0").unwrap()
+        };
+        assert_eq!(pad.apply(b"42"), &b"00042"[..]);
+
+        let pad = Pad {
+            align: Align::Center,
+            ..Pad::from_str("6=·").unwrap()
+        };
+        assert_eq!(pad.apply("è".as_bytes()), "··è···".as_bytes());
+
+        assert!(Pad::from_str("x").is_err());
+        assert!(Pad::from_str("5=").is_err());
+        assert!(Pad::from_str("5=ab").is_err());
+    }
+
+    #[test]
+    fn it_truncates_long_text() {
+        let truncate = Truncate::from_str("5").unwrap();
+        assert_eq!(truncate.apply(b"abcde"), &b"abcde"[..]);
+        assert_eq!(truncate.apply("abcdef".as_bytes()), "abcd…".as_bytes());
+        assert_eq!(truncate.apply("àèìòùx".as_bytes()), "àèìò…".as_bytes());
+
+        let truncate = Truncate::from_str("5=...").unwrap();
+        assert_eq!(truncate.apply(b"abcdef"), &b"ab..."[..]);
+
+        let truncate = Truncate::from_str("3=").unwrap();
+        assert_eq!(truncate.apply(b"abcdef"), &b"abc"[..]);
+
+        let truncate = Truncate::from_str("3=...").unwrap();
+        assert_eq!(truncate.apply(b"abcdef"), &b"..."[..]);
+
+        assert!(Truncate::from_str("-3").is_err());
+        assert!(Truncate::from_str("x").is_err());
+        assert!(Truncate::from_str("0").is_err());
+        assert_eq!(
+            Truncate::from_str("1=......").unwrap_err().to_string(),
+            "Truncate ellipsis cannot be wider than the width"
+        );
+    }
+
+    #[test]
+    fn it_parses_fields_count() {
+        let count = |s: &str| FieldsCount::from_str(s).map(|c| (c.min, c.max));
+
+        assert_eq!(count("3").unwrap(), (3, 3));
+        assert_eq!(count("2:4").unwrap(), (2, 4));
+        assert_eq!(count("2:").unwrap(), (2, usize::MAX));
+        assert_eq!(count(":4").unwrap(), (1, 4));
+        assert!(count("").is_err());
+        assert!(count("4:2").is_err());
+        assert!(count("-1").is_err());
+
+        assert_eq!(FieldsCount::from_str("2:").unwrap().to_string(), "2:");
+        assert_eq!(FieldsCount::from_str(":4").unwrap().to_string(), "1:4");
+        assert!(FieldsCount::from_str("2:4").unwrap().contains(4));
+        assert!(!FieldsCount::from_str("2:4").unwrap().contains(5));
+    }
+
+    #[test]
+    fn it_renders_added_fields() {
+        let render = |s: &str, nr| {
+            String::from_utf8(AddedField::from_str(s).unwrap().render(nr).into_owned()).unwrap()
+        };
+
+        std::env::set_var("TUC_TEST_ADDED_FIELD", "host1");
+
+        assert_eq!(render("", 1), "");
+        assert_eq!(render("batch", 1), "batch");
+        assert_eq!(render("{NR}", 42), "42");
+        assert_eq!(render("{env:TUC_TEST_ADDED_FIELD}-{NR}", 3), "host1-3");
+        assert_eq!(render("{{NR}} }}", 3), "{NR} }");
+
+        assert_eq!(
+            AddedField::from_str("{{x}}-{NR}").unwrap().to_string(),
+            "{{x}}-{NR}"
+        );
+
+        assert!(AddedField::from_str("{env:TUC_TEST_MISSING_VARIABLE}").is_err());
+        assert!(AddedField::from_str("{1}").is_err());
+        assert!(AddedField::from_str("{NR").is_err());
+        assert!(AddedField::from_str("a}b").is_err());
+    }
+}
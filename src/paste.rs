@@ -0,0 +1,106 @@
+use std::io::{BufRead, Read, Result};
+
+/// Reader that merges the inputs in lockstep, the way `paste` does
+/// (--paste): record N of every input, joined by the delimiter,
+/// becomes record N of the output.
+///
+/// The output ends when every input is over. A shorter input
+/// contributes empty values from then on.
+pub struct PasteReader {
+    inputs: Vec<Box<dyn BufRead>>,
+    delimiter: Vec<u8>,
+    eol: u8,
+    record: Vec<u8>,
+    /// Merged record, served from `pos` onward
+    output: Vec<u8>,
+    pos: usize,
+}
+
+impl PasteReader {
+    pub fn new(inputs: Vec<Box<dyn BufRead>>, delimiter: Vec<u8>, eol: u8) -> Self {
+        PasteReader {
+            inputs,
+            delimiter,
+            eol,
+            record: Vec::new(),
+            output: Vec::new(),
+            pos: 0,
+        }
+    }
+
+    /// Merge the next record of every input into the output buffer.
+    /// Returns false once every input is over.
+    fn merge_next_records(&mut self) -> Result<bool> {
+        self.output.clear();
+        self.pos = 0;
+
+        let mut any_record = false;
+        for (idx, input) in self.inputs.iter_mut().enumerate() {
+            if idx > 0 {
+                self.output.extend_from_slice(&self.delimiter);
+            }
+
+            self.record.clear();
+            if input.read_until(self.eol, &mut self.record)? == 0 {
+                continue;
+            }
+
+            any_record = true;
+            let mut record = &self.record[..];
+            if let Some(rest) = record.strip_suffix(&[self.eol]) {
+                record = rest;
+                // "\r\n" ends a line too, and the "\r" must not end up mid record
+                if self.eol == b'\n' {
+                    record = record.strip_suffix(b"\r").unwrap_or(record);
+                }
+            }
+            self.output.extend_from_slice(record);
+        }
+
+        self.output.push(self.eol);
+        Ok(any_record)
+    }
+}
+
+impl Read for PasteReader {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        if self.pos == self.output.len() && !self.merge_next_records()? {
+            return Ok(0);
+        }
+
+        let pending = &self.output[self.pos..];
+        let n = pending.len().min(buf.len());
+        buf[..n].copy_from_slice(&pending[..n]);
+        self.pos += n;
+        Ok(n)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn paste(inputs: &[&'static [u8]], delimiter: &str, eol: u8) -> Vec<u8> {
+        let inputs = inputs
+            .iter()
+            .map(|input| Box::new(*input) as Box<dyn BufRead>)
+            .collect();
+        let mut reader = PasteReader::new(inputs, delimiter.into(), eol);
+
+        let mut output = Vec::new();
+        reader.read_to_end(&mut output).unwrap();
+        output
+    }
+
+    #[test]
+    fn it_merges_the_records() {
+        assert_eq!(paste(&[b"a\nb\n", b"1\r\n2\n"], ",", b'\n'), b"a,1\nb,2\n");
+        assert_eq!(
+            paste(&[b"a\nb\nc", b"1\n"], "--", b'\n'),
+            b"a--1\nb--\nc--\n"
+        );
+        assert_eq!(paste(&[b"a\n", b"1\n2\n"], ",", b'\n'), b"a,1\n,2\n");
+        assert_eq!(paste(&[b"a\0b\0", b"1\0"], ",", b'\0'), b"a,1\0b,\0");
+        assert_eq!(paste(&[b"", b""], ",", b'\n'), b"");
+    }
+}
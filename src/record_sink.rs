@@ -0,0 +1,99 @@
+use crate::cut_str::RecordCutter;
+use crate::error::{Diagnostic, TucError};
+use crate::options::{Opt, EOL};
+use anyhow::Result;
+use bstr::io::BufReadExt;
+use std::io::BufRead;
+
+/// Output that is not text (e.g. --msgpack, --parquet): it receives the
+/// fields of each record, already cut.
+pub trait RecordSink {
+    /// `values` is empty when the record was an empty line.
+    fn add_record(&mut self, values: &[&[u8]]) -> Result<()>;
+}
+
+/// Cut every record and pass its fields to `sink`, and to `on_diagnostic`
+/// the warnings and errors of the skipped records and the summary of --report.
+pub fn read_and_cut_into<B: BufRead, S: RecordSink>(
+    stdin: &mut B,
+    opt: Opt,
+    mut sink: S,
+    mut on_diagnostic: impl FnMut(Diagnostic),
+) -> Result<S> {
+    let eol = opt.eol;
+    let mut cutter = RecordCutter::new(opt);
+
+    let mut cut_record = |line: &[u8]| {
+        let result = cutter.cut_record_into(line, &mut sink);
+        cutter.take_diagnostics().for_each(&mut on_diagnostic);
+        result
+            .map_err(|x| std::io::Error::other(TucError::from(x)))
+            .and(Ok(true))
+    };
+
+    let result = match eol {
+        EOL::Newline => stdin.for_byte_line(&mut cut_record),
+        EOL::Zero => stdin.for_byte_record(eol.into(), &mut cut_record),
+    };
+
+    let result = result
+        .map_err(Into::into)
+        .and_then(|_| cutter.finish_into(&mut sink));
+
+    cutter.finish_report();
+    cutter.take_diagnostics().for_each(on_diagnostic);
+    result?;
+    cutter.check_errors()?;
+    Ok(sink)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bounds::UserBoundsList;
+    use std::str::FromStr;
+
+    impl RecordSink for Vec<Vec<Vec<u8>>> {
+        fn add_record(&mut self, values: &[&[u8]]) -> Result<()> {
+            self.push(values.iter().map(|v| v.to_vec()).collect());
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn it_passes_the_fields_of_each_record() {
+        let opt = Opt {
+            bounds: UserBoundsList::from_str("3,1").unwrap(),
+            ..Opt::default()
+        };
+
+        let records = read_and_cut_into(
+            &mut "a-b-\"c\"\n\nd-e-f".as_bytes(),
+            opt,
+            Vec::new(),
+            |_| (),
+        );
+        assert_eq!(
+            records.unwrap(),
+            [
+                vec![b"\"c\"".to_vec(), b"a".to_vec()],
+                vec![],
+                vec![b"f".to_vec(), b"d".to_vec()]
+            ]
+        );
+    }
+
+    #[test]
+    fn it_passes_the_fields_as_bytes() {
+        let opt = Opt {
+            bounds: UserBoundsList::from_str("2:").unwrap(),
+            ..Opt::default()
+        };
+
+        let records = read_and_cut_into(&mut &b"a-\xff-c\xfe"[..], opt, Vec::new(), |_| ());
+        assert_eq!(
+            records.unwrap(),
+            [vec![b"\xff".to_vec(), b"c\xfe".to_vec()]]
+        );
+    }
+}
@@ -0,0 +1,82 @@
+use std::io::{BufRead, Read, Result};
+
+/// Move the position of stdin to `offset`, if stdin is a file.
+/// Returns false if it's not possible (e.g. stdin is a pipe).
+#[cfg(unix)]
+fn seek_stdin_file(offset: u64) -> bool {
+    use std::io::{Seek, SeekFrom};
+    use std::os::unix::io::AsFd;
+
+    // The duplicated descriptor shares its position with stdin
+    std::io::stdin()
+        .as_fd()
+        .try_clone_to_owned()
+        .map(std::fs::File::from)
+        .and_then(|mut file| file.seek(SeekFrom::Start(offset)))
+        .is_ok()
+}
+
+#[cfg(not(unix))]
+fn seek_stdin_file(_offset: u64) -> bool {
+    false
+}
+
+/// Start reading stdin from the first record that begins at `offset` or
+/// after it (--seek), and return the offset of that record.
+///
+/// A file is seeked directly, any other input is read and discarded.
+/// It must be called before anything else reads stdin.
+pub fn seek_stdin(offset: u64, eol: u8) -> Result<u64> {
+    if offset == 0 {
+        return Ok(0);
+    }
+
+    let mut stdin = std::io::stdin().lock();
+
+    // Look at the byte before the offset, to know if a record starts there
+    let before_offset = offset - 1;
+    if !seek_stdin_file(before_offset) {
+        let skipped = std::io::copy(&mut (&mut stdin).take(before_offset), &mut std::io::sink())?;
+        if skipped < before_offset {
+            return Ok(skipped);
+        }
+    }
+
+    let mut partial_record = Vec::new();
+    stdin.read_until(eol, &mut partial_record)?;
+    Ok(before_offset + partial_record.len() as u64)
+}
+
+/// Reader that keeps track of the offset in the input
+/// of the next byte to read (--print-offset).
+pub struct OffsetReader<R> {
+    inner: R,
+    pub offset: u64,
+}
+
+impl<R: Read> OffsetReader<R> {
+    pub fn new(inner: R, offset: u64) -> Self {
+        OffsetReader { inner, offset }
+    }
+}
+
+impl<R: Read> Read for OffsetReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.offset += n as u64;
+        Ok(n)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_keeps_track_of_the_offset() {
+        let mut reader = OffsetReader::new(&b"abc\ndef"[..], 10);
+        let mut output = String::new();
+        reader.read_to_string(&mut output).unwrap();
+        assert_eq!(reader.offset, 17);
+    }
+}
@@ -0,0 +1,81 @@
+//! Serde support (behind the `serde` feature), so that options can be
+//! stored as presets (e.g. in JSON or TOML) and loaded back.
+//!
+//! Bounds are (de)serialized as they are written on the command line
+//! (e.g. `"1,3:"`) and an `Opt` as its command line arguments (see
+//! `Opt::to_args`), so it's validated exactly as `tuc` would.
+
+use serde::de::{self, Deserialize, Deserializer};
+use serde::ser::{Serialize, Serializer};
+use std::str::FromStr;
+
+use crate::bounds::{UserBounds, UserBoundsList};
+use crate::options::Opt;
+
+/// (De)serialize a type through its `Display` and `FromStr` implementations
+macro_rules! serde_as_str {
+    ($type:ty) => {
+        impl Serialize for $type {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.collect_str(self)
+            }
+        }
+
+        impl<'de> Deserialize<'de> for $type {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                let text = String::deserialize(deserializer)?;
+                <$type>::from_str(&text).map_err(de::Error::custom)
+            }
+        }
+    };
+}
+
+serde_as_str!(UserBounds);
+serde_as_str!(UserBoundsList);
+
+impl Serialize for Opt {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.to_args().serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Opt {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let args = Vec::<String>::deserialize(deserializer)?;
+        Opt::try_from_args(args.into_iter().map(Into::into)).map_err(de::Error::custom)
+    }
+}
+
+#[cfg(all(test, feature = "json"))]
+mod tests {
+    use super::*;
+    use crate::options::{Trim, EOL};
+
+    #[test]
+    fn it_round_trips_the_bounds() {
+        let bounds = UserBoundsList::from_str("1,3:,{2}-{1}").unwrap();
+        let json = serde_json::to_string(&bounds).unwrap();
+        assert_eq!(json, r#""1,3:,{2}-{1}""#);
+        let same_bounds: UserBoundsList = serde_json::from_str(&json).unwrap();
+        assert_eq!(same_bounds.to_string(), bounds.to_string());
+
+        let bound: UserBounds = serde_json::from_str(r#""-2""#).unwrap();
+        assert_eq!(bound, UserBounds::from_str("-2").unwrap());
+        assert!(serde_json::from_str::<UserBounds>(r#""x""#).is_err());
+    }
+
+    #[test]
+    fn it_round_trips_the_options() {
+        let opt = Opt::try_from_args(["-d", ",", "-f", "2:", "-j"].map(Into::into)).unwrap();
+        let json = serde_json::to_string(&opt).unwrap();
+        assert_eq!(json, r#"["--fields","2:","--delimiter",",","--join"]"#);
+        let same_opt: Opt = serde_json::from_str(&json).unwrap();
+        assert_eq!(same_opt.to_args(), opt.to_args());
+
+        assert!(serde_json::from_str::<Opt>(r#"["-j","--no-join"]"#).is_err());
+
+        assert_eq!(serde_json::to_string(&Trim::Both).unwrap(), r#""both""#);
+        let eol: EOL = serde_json::from_str(r#""zero""#).unwrap();
+        assert!(matches!(eol, EOL::Zero));
+    }
+}
@@ -0,0 +1,233 @@
+use std::fmt;
+use std::io::{Result, Write};
+use std::str::FromStr;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// strftime-like format of --timestamp. Times are in UTC.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TimestampFormat(String);
+
+impl Default for TimestampFormat {
+    fn default() -> Self {
+        TimestampFormat("%Y-%m-%dT%H:%M:%SZ".to_owned())
+    }
+}
+
+impl FromStr for TimestampFormat {
+    type Err = Box<dyn std::error::Error>;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let mut chars = s.chars();
+        while let Some(c) = chars.next() {
+            if c == '%' {
+                match chars.next() {
+                    Some('Y' | 'm' | 'd' | 'H' | 'M' | 'S' | 's' | 'F' | 'T' | '%') => (),
+                    _ => {
+                        return Err(
+                            "Valid timestamp specifiers are %Y %m %d %H %M %S %s %F %T %%".into(),
+                        )
+                    }
+                }
+            }
+        }
+
+        Ok(TimestampFormat(s.to_owned()))
+    }
+}
+
+impl fmt::Display for TimestampFormat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// Year, month and day of a number of days since 1970-01-01
+/// (see http://howardhinnant.github.io/date_algorithms.html#civil_from_days).
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+
+    (yoe + era * 400 + i64::from(month <= 2), month, day)
+}
+
+impl TimestampFormat {
+    /// Format `secs` (seconds since the Unix epoch, in UTC).
+    pub fn format(&self, secs: u64) -> String {
+        let (year, month, day) = civil_from_days((secs / 86_400) as i64);
+        let (hour, minute, second) = (secs / 3600 % 24, secs / 60 % 60, secs % 60);
+
+        let mut output = String::new();
+        let mut chars = self.0.chars();
+        while let Some(c) = chars.next() {
+            if c != '%' {
+                output.push(c);
+                continue;
+            }
+
+            output.push_str(&match chars.next() {
+                Some('Y') => format!("{:04}", year),
+                Some('m') => format!("{:02}", month),
+                Some('d') => format!("{:02}", day),
+                Some('H') => format!("{:02}", hour),
+                Some('M') => format!("{:02}", minute),
+                Some('S') => format!("{:02}", second),
+                Some('s') => secs.to_string(),
+                Some('F') => format!("{:04}-{:02}-{:02}", year, month, day),
+                Some('T') => format!("{:02}:{:02}:{:02}", hour, minute, second),
+                _ => "%".to_owned(),
+            });
+        }
+
+        output
+    }
+}
+
+/// Seconds since the Unix epoch
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
+/// Writer that prefixes every record with the current time and a space
+/// (--timestamp). The formatted time is refreshed at most once per second.
+/// The time is taken when the record is written, so any buffering
+/// must happen in `inner`, after the timestamp.
+pub struct TimestampWriter<W> {
+    inner: W,
+    format: Option<TimestampFormat>,
+    clock: fn() -> u64,
+    eol: u8,
+    at_record_start: bool,
+    cached_secs: Option<u64>,
+    cached: Vec<u8>,
+}
+
+impl<W: Write> TimestampWriter<W> {
+    pub fn new(inner: W, format: Option<TimestampFormat>, eol: u8) -> Self {
+        TimestampWriter {
+            inner,
+            format,
+            clock: now,
+            eol,
+            at_record_start: true,
+            cached_secs: None,
+            cached: Vec::new(),
+        }
+    }
+
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    fn write_timestamp(&mut self) -> Result<()> {
+        let secs = (self.clock)();
+
+        if let Some(format) = &self.format {
+            if self.cached_secs != Some(secs) {
+                self.cached = format.format(secs).into_bytes();
+                self.cached.push(b' ');
+                self.cached_secs = Some(secs);
+            }
+        }
+
+        self.inner.write_all(&self.cached)
+    }
+}
+
+impl<W: Write> Write for TimestampWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> Result<usize> {
+        if self.format.is_none() {
+            return self.inner.write(buf);
+        }
+
+        let mut rest = buf;
+        while !rest.is_empty() {
+            if self.at_record_start {
+                self.write_timestamp()?;
+                self.at_record_start = false;
+            }
+
+            let end = match memchr::memchr(self.eol, rest) {
+                Some(idx) => {
+                    self.at_record_start = true;
+                    idx + 1
+                }
+                None => rest.len(),
+            };
+
+            self.inner.write_all(&rest[..end])?;
+            rest = &rest[end..];
+        }
+
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        self.inner.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU64, Ordering};
+
+    #[test]
+    fn it_formats_timestamps() {
+        let format = TimestampFormat::default();
+        assert_eq!(format.format(0), "1970-01-01T00:00:00Z");
+        assert_eq!(format.format(951_827_696), "2000-02-29T12:34:56Z");
+        assert_eq!(format.format(1_767_225_599), "2025-12-31T23:59:59Z");
+
+        let format = TimestampFormat::from_str("%F %T (%s) 100%%").unwrap();
+        assert_eq!(format.format(86_400), "1970-01-02 00:00:00 (86400) 100%");
+
+        assert!(TimestampFormat::from_str("%Q").is_err());
+        assert!(TimestampFormat::from_str("%").is_err());
+    }
+
+    #[test]
+    fn it_prefixes_every_record() {
+        let mut writer = TimestampWriter::new(Vec::new(), Some(TimestampFormat::default()), b'\n');
+        writer.write_all(b"a\nb").unwrap();
+        writer.write_all(b"c\n").unwrap();
+
+        let output = String::from_utf8(writer.get_ref().clone()).unwrap();
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].ends_with("Z a"));
+        assert!(lines[1].ends_with("Z bc"));
+
+        let mut writer = TimestampWriter::new(Vec::new(), None, b'\n');
+        writer.write_all(b"a\n").unwrap();
+        assert_eq!(writer.get_ref(), b"a\n");
+    }
+
+    static CLOCK: AtomicU64 = AtomicU64::new(0);
+
+    #[test]
+    fn it_takes_the_time_when_the_record_is_written() {
+        let format = TimestampFormat::from_str("%s").unwrap();
+        let buffered = std::io::BufWriter::new(Vec::new());
+        let mut writer = TimestampWriter::new(buffered, Some(format), b'\n');
+        writer.clock = || CLOCK.load(Ordering::SeqCst);
+
+        CLOCK.store(1, Ordering::SeqCst);
+        writer.write_all(b"a\n").unwrap();
+        CLOCK.store(2, Ordering::SeqCst);
+        writer.write_all(b"b\n").unwrap();
+        CLOCK.store(3, Ordering::SeqCst);
+
+        // nothing has been flushed yet
+        assert!(writer.get_ref().get_ref().is_empty());
+        writer.flush().unwrap();
+        assert_eq!(writer.get_ref().get_ref(), b"1 a\n2 b\n");
+    }
+}
@@ -0,0 +1,141 @@
+use std::io::{Read, Result, Write};
+use std::time::{Duration, Instant};
+
+/// Reader that keeps track of the time spent reading
+/// and of how many bytes and records went through it.
+pub struct TimedReader<R> {
+    inner: R,
+    eol: u8,
+    last_byte: Option<u8>,
+    pub elapsed: Duration,
+    pub bytes: u64,
+    pub records: u64,
+}
+
+impl<R: Read> TimedReader<R> {
+    pub fn new(inner: R, eol: u8) -> Self {
+        TimedReader {
+            inner,
+            eol,
+            last_byte: None,
+            elapsed: Duration::ZERO,
+            bytes: 0,
+            records: 0,
+        }
+    }
+
+    /// Number of records read, including a last one without end of line.
+    pub fn total_records(&self) -> u64 {
+        match self.last_byte {
+            Some(byte) if byte != self.eol => self.records + 1,
+            _ => self.records,
+        }
+    }
+}
+
+impl<R: Read> Read for TimedReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        let start = Instant::now();
+        let n = self.inner.read(buf)?;
+        self.elapsed += start.elapsed();
+
+        if n > 0 {
+            self.bytes += n as u64;
+            self.records += memchr::memchr_iter(self.eol, &buf[..n]).count() as u64;
+            self.last_byte = Some(buf[n - 1]);
+        }
+
+        Ok(n)
+    }
+}
+
+/// Writer that keeps track of the time spent writing.
+pub struct TimedWriter<W> {
+    inner: W,
+    pub elapsed: Duration,
+}
+
+impl<W: Write> TimedWriter<W> {
+    pub fn new(inner: W) -> Self {
+        TimedWriter {
+            inner,
+            elapsed: Duration::ZERO,
+        }
+    }
+}
+
+impl<W: Write> Write for TimedWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> Result<usize> {
+        let start = Instant::now();
+        let n = self.inner.write(buf)?;
+        self.elapsed += start.elapsed();
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        let start = Instant::now();
+        self.inner.flush()?;
+        self.elapsed += start.elapsed();
+        Ok(())
+    }
+}
+
+/// Summary of where the time went: reading, cutting (finding the
+/// delimiters and formatting the output) and writing.
+pub fn report<R: Read, W: Write>(
+    total: Duration,
+    reader: &TimedReader<R>,
+    writer: &TimedWriter<W>,
+) -> String {
+    let cut = total.saturating_sub(reader.elapsed + writer.elapsed);
+    let secs = total.as_secs_f64().max(f64::EPSILON);
+    let records = reader.total_records();
+
+    format!(
+        "tuc: timing: read {:.2?}, cut {:.2?}, write {:.2?}, total {:.2?}\n\
+         tuc: timing: {} records ({:.0} records/s), {} bytes ({:.2} MiB/s)",
+        reader.elapsed,
+        cut,
+        writer.elapsed,
+        total,
+        records,
+        records as f64 / secs,
+        reader.bytes,
+        reader.bytes as f64 / secs / (1024.0 * 1024.0),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read_all(input: &[u8], eol: u8) -> TimedReader<&[u8]> {
+        let mut reader = TimedReader::new(input, eol);
+        let mut output = Vec::new();
+        reader.read_to_end(&mut output).unwrap();
+        assert_eq!(output, input);
+        reader
+    }
+
+    #[test]
+    fn it_counts_bytes_and_records() {
+        let reader = read_all(b"a\nb\nc", b'\n');
+        assert_eq!(reader.bytes, 5);
+        assert_eq!(reader.total_records(), 3);
+
+        assert_eq!(read_all(b"a\nb\n", b'\n').total_records(), 2);
+        assert_eq!(read_all(b"a\0b\n", b'\0').total_records(), 2);
+        assert_eq!(read_all(b"", b'\n').total_records(), 0);
+    }
+
+    #[test]
+    fn it_reports_the_timings() {
+        let reader = read_all(b"a\nb\n", b'\n');
+        let mut writer = TimedWriter::new(Vec::new());
+        writer.write_all(b"a\n").unwrap();
+
+        let report = report(Duration::from_secs(1), &reader, &writer);
+        assert!(report.starts_with("tuc: timing: read "));
+        assert!(report.ends_with("2 records (2 records/s), 4 bytes (0.00 MiB/s)"));
+    }
+}
@@ -0,0 +1,155 @@
+use anyhow::{bail, Result};
+use bstr::ByteSlice;
+use std::borrow::Cow;
+use std::fmt;
+use std::str::FromStr;
+
+/// Change the text of a field before it's printed.
+pub trait FieldTransform {
+    fn apply<'a>(&self, text: &'a [u8]) -> Cow<'a, [u8]>;
+}
+
+/// Built-in transforms, e.g. `--map trim`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Transform {
+    /// Remove the whitespace around the text
+    Trim,
+    Lower,
+    Upper,
+    /// Replace every sequence of spaces and tabs with a single space
+    SqueezeSpaces,
+}
+
+impl FromStr for Transform {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "trim" => Transform::Trim,
+            "lower" => Transform::Lower,
+            "upper" => Transform::Upper,
+            "squeeze-spaces" => Transform::SqueezeSpaces,
+            _ => bail!(
+                "unknown transform `{}` (use trim, lower, upper or squeeze-spaces)",
+                s
+            ),
+        })
+    }
+}
+
+impl fmt::Display for Transform {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Transform::Trim => "trim",
+            Transform::Lower => "lower",
+            Transform::Upper => "upper",
+            Transform::SqueezeSpaces => "squeeze-spaces",
+        })
+    }
+}
+
+impl FieldTransform for Transform {
+    fn apply<'a>(&self, text: &'a [u8]) -> Cow<'a, [u8]> {
+        match self {
+            Transform::Trim => Cow::Borrowed(text.trim()),
+            Transform::Lower if text.is_ascii() && !text.iter().any(u8::is_ascii_uppercase) => {
+                Cow::Borrowed(text)
+            }
+            Transform::Lower => Cow::Owned(text.to_lowercase()),
+            Transform::Upper if text.is_ascii() && !text.iter().any(u8::is_ascii_lowercase) => {
+                Cow::Borrowed(text)
+            }
+            Transform::Upper => Cow::Owned(text.to_uppercase()),
+            Transform::SqueezeSpaces => squeeze_spaces(text),
+        }
+    }
+}
+
+fn squeeze_spaces(text: &[u8]) -> Cow<'_, [u8]> {
+    let is_space = |c: &u8| *c == b' ' || *c == b'\t';
+    let needs_squeeze =
+        text.windows(2).any(|w| is_space(&w[0]) && is_space(&w[1])) || text.contains(&b'\t');
+
+    if !needs_squeeze {
+        return Cow::Borrowed(text);
+    }
+
+    let mut output = Vec::with_capacity(text.len());
+    for (idx, c) in text.iter().enumerate() {
+        if !is_space(c) {
+            output.push(*c);
+        } else if idx == 0 || !is_space(&text[idx - 1]) {
+            output.push(b' ');
+        }
+    }
+
+    Cow::Owned(output)
+}
+
+/// Transforms applied one after the other, e.g. `trim|lower` (--map).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TransformPipeline(pub Vec<Transform>);
+
+impl FromStr for TransformPipeline {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.split('|')
+            .map(Transform::from_str)
+            .collect::<Result<Vec<_>>>()
+            .map(TransformPipeline)
+    }
+}
+
+impl fmt::Display for TransformPipeline {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (idx, transform) in self.0.iter().enumerate() {
+            if idx > 0 {
+                f.write_str("|")?;
+            }
+            write!(f, "{transform}")?;
+        }
+
+        Ok(())
+    }
+}
+
+impl FieldTransform for TransformPipeline {
+    fn apply<'a>(&self, text: &'a [u8]) -> Cow<'a, [u8]> {
+        self.0
+            .iter()
+            .fold(Cow::Borrowed(text), |text, transform| match text {
+                Cow::Borrowed(text) => transform.apply(text),
+                Cow::Owned(text) => Cow::Owned(transform.apply(&text).into_owned()),
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn map(pipeline: &str, text: &str) -> String {
+        let pipeline = TransformPipeline::from_str(pipeline).unwrap();
+        String::from_utf8(pipeline.apply(text.as_bytes()).into_owned()).unwrap()
+    }
+
+    #[test]
+    fn it_applies_the_transforms_in_order() {
+        assert_eq!(map("trim", "  a b \t"), "a b");
+        assert_eq!(map("lower", "ÀbC"), "àbc");
+        assert_eq!(map("upper", "àbc"), "ÀBC");
+        assert_eq!(map("squeeze-spaces", " a  \t b "), " a b ");
+        assert_eq!(map("squeeze-spaces|trim|upper", "  a   b "), "A B");
+    }
+
+    #[test]
+    fn it_parses_and_displays_the_pipeline() {
+        let pipeline = TransformPipeline::from_str("trim|lower").unwrap();
+        assert_eq!(pipeline.0, [Transform::Trim, Transform::Lower]);
+        assert_eq!(pipeline.to_string(), "trim|lower");
+
+        assert!(TransformPipeline::from_str("trim|reverse").is_err());
+        assert!(TransformPipeline::from_str("").is_err());
+    }
+}
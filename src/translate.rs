@@ -0,0 +1,403 @@
+use crate::bounds::{BoundOrFiller, BoundsType, Side, UserBounds};
+use crate::options::{Opt, EOL};
+use anyhow::{bail, Result};
+use std::fmt;
+
+/// Command printed by --to-awk or --to-cut.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Translation {
+    Awk,
+    Cut,
+}
+
+impl fmt::Display for Translation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Translation::Awk => "awk",
+            Translation::Cut => "cut",
+        })
+    }
+}
+
+/// Quote `text` for a POSIX shell.
+fn shell_quote(text: &str) -> String {
+    if !text.is_empty()
+        && text
+            .bytes()
+            .all(|c| c.is_ascii_alphanumeric() || b",.:_-=/".contains(&c))
+    {
+        return text.to_owned();
+    }
+
+    format!("'{}'", text.replace('\'', "'\\''"))
+}
+
+/// Fail if `opt` uses an option that `command` has no equivalent for.
+/// The options are checked as `Opt::to_args` prints them, so that
+/// any new option is rejected until it's explicitly supported.
+fn check_options(opt: &Opt, command: Translation, supported: &[&str]) -> Result<()> {
+    if opt.delimiters.is_some() {
+        bail!("multiple --delimiter cannot be translated to {}", command);
+    }
+
+    let args = opt.to_args();
+    // The input files come last, and both commands accept them
+    let mut args = args[..args.len() - opt.files.len()].iter();
+
+    while let Some(arg) = args.next() {
+        let flag = arg.split('=').next().unwrap_or(arg);
+
+        match flag {
+            "--fields" | "--bytes" | "--delimiter" | "--replace-delimiter" | "--regex" => {
+                if !supported.contains(&flag) {
+                    bail!("{} cannot be translated to {}", flag, command);
+                }
+                // skip the value
+                args.next();
+            }
+            "--to-awk" | "--to-cut" => (),
+            _ if supported.contains(&flag) => (),
+            _ => bail!("{} cannot be translated to {}", flag, command),
+        }
+    }
+
+    Ok(())
+}
+
+/// Text of the delimiter that separates the fields in the output, if any
+/// (it's also printed between the fields of a range).
+fn output_delimiter(opt: &Opt) -> &[u8] {
+    opt.replace_delimiter.as_deref().unwrap_or(&opt.delimiter)
+}
+
+fn cut_side(side: Side) -> Result<Option<i32>> {
+    match side {
+        Side::Some(idx) if idx > 0 => Ok(Some(idx)),
+        Side::Some(_) => bail!("cut does not support negative fields"),
+        Side::Continue => Ok(None),
+    }
+}
+
+/// Translate the options into an equivalent `cut` command.
+fn to_cut(opt: &Opt) -> Result<String> {
+    check_options(
+        opt,
+        Translation::Cut,
+        &[
+            "--fields",
+            "--bytes",
+            "--delimiter",
+            "--replace-delimiter",
+            "--join",
+            "--complement",
+            "--only-delimited",
+            "--zero-terminated",
+        ],
+    )?;
+
+    let mut list = Vec::new();
+    let mut next_allowed = 1;
+
+    for bof in opt.bounds.iter() {
+        let b = match bof {
+            BoundOrFiller::Bound(b) if b.step.is_some() => {
+                bail!("cut does not support steps (e.g. 1:10:2)")
+            }
+            BoundOrFiller::Bound(b) if !b.has_modifiers() && b.fallback_oob.is_none() => b,
+            _ => bail!("cut does not support format strings"),
+        };
+
+        let (l, r) = (cut_side(b.l)?, cut_side(b.r)?);
+        let start = l.unwrap_or(1);
+        if start < next_allowed || r.is_some_and(|r| r < start) {
+            bail!("cut prints the fields in the order they are found, without repeating them");
+        }
+
+        list.push(match (l, r) {
+            (Some(l), Some(r)) if l == r => l.to_string(),
+            (l, r) => format!(
+                "{}-{}",
+                l.map_or(String::new(), |l| l.to_string()),
+                r.map_or(String::new(), |r| r.to_string())
+            ),
+        });
+
+        next_allowed = match r {
+            Some(r) => r + 1,
+            None => i32::MAX,
+        };
+    }
+
+    let mut args = vec!["cut".to_owned()];
+
+    if opt.bounds_type == BoundsType::Fields {
+        if opt.delimiter.len() != 1 {
+            bail!("cut supports only single-byte delimiters");
+        }
+        args.extend([
+            "-d".into(),
+            shell_quote(&String::from_utf8_lossy(&opt.delimiter)),
+        ]);
+        args.extend(["-f".into(), list.join(",")]);
+
+        // Without --join tuc prints the selected fields one after the other
+        let single_range = opt.bounds.iter().count() == 1;
+        if opt.replace_delimiter.is_some() {
+            let delimiter = String::from_utf8_lossy(output_delimiter(opt));
+            args.push(format!("--output-delimiter={}", shell_quote(&delimiter)));
+        } else if !opt.join && !single_range {
+            let only_single_fields = opt.bounds.iter().all(|bof| match bof {
+                BoundOrFiller::Bound(b) => b.l == b.r && b.l != Side::Continue,
+                _ => false,
+            });
+            if !only_single_fields {
+                bail!("cut cannot print ranges of fields without a delimiter between them (use --join)");
+            }
+            args.push("--output-delimiter=''".into());
+        }
+
+        if opt.only_delimited {
+            args.push("-s".into());
+        }
+    } else {
+        args.extend(["-b".into(), list.join(",")]);
+
+        if let Some(delimiter) = &opt.replace_delimiter {
+            let delimiter = String::from_utf8_lossy(delimiter);
+            args.push(format!("--output-delimiter={}", shell_quote(&delimiter)));
+        }
+    }
+
+    if opt.complement {
+        args.push("--complement".into());
+    }
+
+    if let EOL::Zero = opt.eol {
+        args.push("-z".into());
+    }
+
+    Ok(args.join(" "))
+}
+
+/// Text of an awk string literal.
+fn awk_string(text: &[u8]) -> String {
+    let mut output = String::from("\"");
+    for c in String::from_utf8_lossy(text).chars() {
+        match c {
+            '"' => output.push_str("\\\""),
+            '\\' => output.push_str("\\\\"),
+            '\n' => output.push_str("\\n"),
+            '\t' => output.push_str("\\t"),
+            c => output.push(c),
+        }
+    }
+    output.push('"');
+    output
+}
+
+/// Value of awk's FS, matching the delimiter literally.
+fn awk_field_separator(delimiter: &[u8]) -> String {
+    match delimiter {
+        // A single space means "any run of blanks" to awk
+        b" " => "[ ]".to_owned(),
+        [_] => String::from_utf8_lossy(delimiter).into_owned(),
+        _ => String::from_utf8_lossy(delimiter)
+            .chars()
+            .map(|c| match c {
+                '.' | '[' | ']' | '(' | ')' | '*' | '+' | '?' | '{' | '}' | '|' | '^' | '$'
+                | '\\' | '/' => format!("\\{}", c),
+                c => c.to_string(),
+            })
+            .collect(),
+    }
+}
+
+#[cfg(feature = "regex")]
+fn regex_text(opt: &Opt) -> Option<&str> {
+    opt.regex_bag
+        .as_ref()
+        .map(|regex_bag| regex_bag.normal.as_str())
+}
+
+#[cfg(not(feature = "regex"))]
+fn regex_text(_opt: &Opt) -> Option<&str> {
+    None
+}
+
+/// awk expression of the field number of `side`, e.g. `2` or `NF-1`.
+fn awk_side(side: Side, default: &str) -> String {
+    match side {
+        Side::Some(idx) if idx > 0 => idx.to_string(),
+        Side::Some(-1) => "NF".to_owned(),
+        Side::Some(idx) => format!("NF-{}", -idx - 1),
+        Side::Continue => default.to_owned(),
+    }
+}
+
+fn awk_bound(b: &UserBounds) -> String {
+    if b.l == b.r && b.l != Side::Continue {
+        return match awk_side(b.l, "1") {
+            side if side.contains('-') => format!("$({})", side),
+            side => format!("${}", side),
+        };
+    }
+
+    format!("range({}, {})", awk_side(b.l, "1"), awk_side(b.r, "NF"))
+}
+
+/// Translate the options into an equivalent `awk` command.
+fn to_awk(opt: &Opt) -> Result<String> {
+    check_options(
+        opt,
+        Translation::Awk,
+        &[
+            "--fields",
+            "--delimiter",
+            "--replace-delimiter",
+            "--regex",
+            "--join",
+            "--only-delimited",
+        ],
+    )?;
+
+    let mut output = Vec::new();
+    let mut uses_range = false;
+
+    for bof in opt.bounds.iter() {
+        match bof {
+            BoundOrFiller::Filler(text) => output.push(awk_string(text)),
+            BoundOrFiller::WholeLine => output.push("$0".to_owned()),
+            BoundOrFiller::Env(name) => {
+                output.push(format!("ENVIRON[{}]", awk_string(name.as_bytes())))
+            }
+            BoundOrFiller::Bound(b) if b.step.is_some() => {
+                bail!("awk translation does not support steps (e.g. 1:10:2)")
+            }
+            BoundOrFiller::Bound(b) if !b.has_modifiers() && b.fallback_oob.is_none() => {
+                let expr = awk_bound(b);
+                uses_range |= expr.starts_with("range");
+                output.push(expr);
+
+                if opt.join && !b.is_last {
+                    output.push(awk_string(output_delimiter(opt)));
+                }
+            }
+            _ => bail!("awk translation supports only fields and text in format strings"),
+        }
+    }
+
+    let field_separator = match regex_text(opt) {
+        Some(_) if uses_range => bail!("awk cannot print ranges of fields split by a regex"),
+        Some(regex_text) => regex_text.to_owned(),
+        None => awk_field_separator(&opt.delimiter),
+    };
+
+    let mut program = format!("BEGIN {{ FS = {}", awk_string(field_separator.as_bytes()));
+    if uses_range {
+        program.push_str(&format!("; SEP = {}", awk_string(output_delimiter(opt))));
+    }
+    program.push_str(" }\n");
+
+    if uses_range {
+        program.push_str(
+            "function range(from, to,    out, i) { for (i = from; i <= to; i++) out = out (i > from ? SEP : \"\") $i; return out }\n",
+        );
+    }
+
+    if opt.only_delimited {
+        program.push_str("NF < 2 { next }\n");
+    } else if matches!(opt.bounds.list.as_slice(), [BoundOrFiller::Bound(_)]) {
+        // A line without delimiters is printed as it is
+        program.push_str("NF < 2 { print; next }\n");
+    }
+
+    program.push_str(&format!("{{ print {} }}", output.join(" ")));
+
+    Ok(format!("awk {}", shell_quote(&program)))
+}
+
+/// Print a command that does the same as tuc with these options
+/// (--to-awk, --to-cut). Fails if there's no equivalent command.
+pub fn translate(opt: &Opt, command: Translation) -> Result<String> {
+    let mut translation = match command {
+        Translation::Awk => to_awk(opt)?,
+        Translation::Cut => to_cut(opt)?,
+    };
+
+    for path in &opt.files {
+        translation.push(' ');
+        translation.push_str(&shell_quote(&path.to_string_lossy()));
+    }
+
+    Ok(translation)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::OsString;
+
+    fn translate_args(args: &[&str]) -> Result<String> {
+        let opt = Opt::try_from_args(args.iter().map(OsString::from))?;
+        let command = if args.contains(&"--to-awk") {
+            Translation::Awk
+        } else {
+            Translation::Cut
+        };
+        translate(&opt, command)
+    }
+
+    #[test]
+    fn it_translates_to_cut() {
+        assert_eq!(
+            translate_args(&["--to-cut", "-d", ",", "-f", "1", "a.csv", "my file"]).unwrap(),
+            "cut -d , -f 1 a.csv 'my file'"
+        );
+        assert_eq!(
+            translate_args(&["--to-cut", "-d", ",", "-f", "1,3"]).unwrap(),
+            "cut -d , -f 1,3 --output-delimiter=''"
+        );
+        assert_eq!(
+            translate_args(&["--to-cut", "-d", " ", "-f", ":2", "-j", "-s"]).unwrap(),
+            "cut -d ' ' -f -2 -s"
+        );
+        assert_eq!(
+            translate_args(&["--to-cut", "-b", "2:4", "-r", ";", "-m"]).unwrap(),
+            "cut -b 2-4 --output-delimiter=';' --complement"
+        );
+
+        assert!(translate_args(&["--to-cut", "-f", "3,1"]).is_err());
+        assert!(translate_args(&["--to-cut", "-f", "-1"]).is_err());
+        assert!(translate_args(&["--to-cut", "-f", "1,3:"]).is_err());
+        assert!(translate_args(&["--to-cut", "-f", "1:3,2", "-j"]).is_err());
+        assert!(translate_args(&["--to-cut", "-d", "::", "-f", "1"]).is_err());
+        assert!(translate_args(&["--to-cut", "-f", "1", "--trim", "b"]).is_err());
+    }
+
+    #[test]
+    fn it_translates_to_awk() {
+        assert_eq!(
+            translate_args(&["--to-awk", "-d", ",", "-f", "2"]).unwrap(),
+            "awk 'BEGIN { FS = \",\" }\nNF < 2 { print; next }\n{ print $2 }'"
+        );
+        assert_eq!(
+            translate_args(&["--to-awk", "-d", ".", "-f", "{1}: {-2}", "-s"]).unwrap(),
+            "awk 'BEGIN { FS = \".\" }\nNF < 2 { next }\n{ print $1 \": \" $(NF-1) }'"
+        );
+        assert!(
+            translate_args(&["--to-awk", "-d", "::", "-f", "1,2:", "-j"])
+                .unwrap()
+                .contains("FS = \"::\"; SEP = \"::\"")
+        );
+        assert!(translate_args(&["--to-awk", "-d", "|.", "-f", "1"])
+            .unwrap()
+            .contains("FS = \"\\\\|\\\\.\""));
+
+        assert!(translate_args(&["--to-awk", "-f", "{1}@{$HOST}"])
+            .unwrap()
+            .contains("{ print $1 \"@\" ENVIRON[\"HOST\"] }"));
+
+        assert!(translate_args(&["--to-awk", "-f", "{1|human}"]).is_err());
+        assert!(translate_args(&["--to-awk", "-f", "1", "--json"]).is_err());
+    }
+}
@@ -0,0 +1,276 @@
+use crate::options::Opt;
+use anyhow::Result;
+use std::fmt;
+use std::ops::Range;
+
+#[cfg(feature = "regex")]
+use crate::bounds::{UserBounds, UserBoundsTrait};
+#[cfg(feature = "regex")]
+use crate::cut_str::select_within_field;
+#[cfg(feature = "regex")]
+use regex::bytes::Regex;
+#[cfg(feature = "regex")]
+use std::str::FromStr;
+
+/// Pattern that a field must match (e.g. `2~^\d+$`).
+#[cfg(feature = "regex")]
+#[derive(Debug, Clone)]
+pub struct FieldAssertion {
+    pub bounds: UserBounds,
+    pub pattern: Regex,
+}
+
+#[cfg(feature = "regex")]
+impl FromStr for FieldAssertion {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (bounds, pattern) = s.split_once('~').ok_or_else(|| {
+            anyhow::anyhow!("An assertion must be a field and a regex, e.g. '2~^\\d+$'")
+        })?;
+
+        let bounds = UserBounds::from_str(bounds)?;
+        if bounds.fallback_oob.is_some() {
+            anyhow::bail!("An assertion cannot have a fallback");
+        }
+
+        let pattern = Regex::new(pattern)
+            .map_err(|e| anyhow::anyhow!("The regular expression is malformed. {}", e))?;
+
+        Ok(FieldAssertion { bounds, pattern })
+    }
+}
+
+#[cfg(feature = "regex")]
+impl fmt::Display for FieldAssertion {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}~{}", self.bounds, self.pattern.as_str())
+    }
+}
+
+/// The validation that a record did not pass.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rule {
+    /// --assert-fields
+    FieldsCount,
+    /// --assert, by position on the command line
+    Assertion(usize),
+}
+
+/// A record that failed a validation (--assert-fields, --assert).
+#[derive(Debug)]
+pub struct ValidationError {
+    pub record_number: usize,
+    pub rule: Rule,
+    reason: String,
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Record {}: {}", self.record_number, self.reason)
+    }
+}
+
+impl std::error::Error for ValidationError {}
+
+/// Check that the fields of a record satisfy --assert-fields and every --assert.
+pub fn validate(
+    line: &[u8],
+    fields: &[Range<usize>],
+    opt: &Opt,
+    record_number: usize,
+) -> Result<(), ValidationError> {
+    let fail = |rule, reason| ValidationError {
+        record_number,
+        rule,
+        reason,
+    };
+
+    if let Some(count) = &opt.assert_fields {
+        if !count.contains(fields.len()) {
+            let expected = match (count.min, count.max) {
+                (min, max) if min == max => min.to_string(),
+                (min, usize::MAX) => format!("at least {}", min),
+                (min, max) => format!("{} to {}", min, max),
+            };
+
+            return Err(fail(
+                Rule::FieldsCount,
+                format!("expected {} fields, found {}", expected, fields.len()),
+            ));
+        }
+    }
+
+    #[cfg(feature = "regex")]
+    for (idx, assertion) in opt.assertions.iter().enumerate() {
+        let field = assertion
+            .bounds
+            .try_into_range(fields.len())
+            .ok()
+            .map(|r| &line[fields[r.start].start..fields[r.end - 1].end])
+            .and_then(|field| {
+                select_within_field(field, &assertion.bounds, opt.sub_delimiter.as_deref()).ok()
+            });
+
+        match field {
+            Some(field) if assertion.pattern.is_match(field) => (),
+            Some(field) => {
+                return Err(fail(
+                    Rule::Assertion(idx),
+                    format!(
+                        "field {} ({:?}) does not match {}",
+                        assertion.bounds,
+                        String::from_utf8_lossy(field),
+                        assertion.pattern.as_str()
+                    ),
+                ))
+            }
+            None => {
+                return Err(fail(
+                    Rule::Assertion(idx),
+                    format!("field {} is missing", assertion.bounds),
+                ))
+            }
+        }
+    }
+
+    #[cfg(not(feature = "regex"))]
+    let _ = line;
+
+    Ok(())
+}
+
+/// Counters printed by --report once the whole input has been read.
+#[derive(Debug, Default)]
+pub struct Report {
+    pub records: usize,
+    pub skipped: usize,
+    /// Errors printed by --errors collect
+    pub errors: usize,
+    fields_count_failures: usize,
+    assertion_failures: Vec<usize>,
+}
+
+impl Report {
+    pub fn add_failure(&mut self, rule: Rule) {
+        match rule {
+            Rule::FieldsCount => self.fields_count_failures += 1,
+            Rule::Assertion(idx) => {
+                if self.assertion_failures.len() <= idx {
+                    self.assertion_failures.resize(idx + 1, 0);
+                }
+                self.assertion_failures[idx] += 1;
+            }
+        }
+    }
+
+    /// One line for the records, plus one line for each validation rule.
+    pub fn summary(&self, opt: &Opt) -> String {
+        let mut lines = vec![format!(
+            "tuc: report: {} records, {} skipped",
+            self.records, self.skipped
+        )];
+
+        if let Some(count) = &opt.assert_fields {
+            lines.push(format!(
+                "tuc: report: {} failed --assert-fields {}",
+                self.fields_count_failures, count
+            ));
+        }
+
+        #[cfg(feature = "regex")]
+        for (idx, assertion) in opt.assertions.iter().enumerate() {
+            lines.push(format!(
+                "tuc: report: {} failed --assert {}",
+                self.assertion_failures.get(idx).unwrap_or(&0),
+                assertion
+            ));
+        }
+
+        lines.join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::options::FieldsCount;
+    use std::str::FromStr;
+
+    fn fields_of(line: &str) -> Vec<Range<usize>> {
+        let mut start = 0;
+        let mut fields = Vec::new();
+        for part in line.split('-') {
+            fields.push(start..start + part.len());
+            start += part.len() + 1;
+        }
+        fields
+    }
+
+    fn check(opt: &Opt, line: &str) -> Result<(), String> {
+        validate(line.as_bytes(), &fields_of(line), opt, 7).map_err(|e| e.to_string())
+    }
+
+    #[test]
+    fn it_validates_the_number_of_fields() {
+        let opt = Opt {
+            assert_fields: Some(FieldsCount::from_str("2").unwrap()),
+            ..Opt::default()
+        };
+
+        assert_eq!(check(&opt, "a-b"), Ok(()));
+        assert_eq!(
+            check(&opt, "a"),
+            Err("Record 7: expected 2 fields, found 1".into())
+        );
+    }
+
+    #[cfg(feature = "regex")]
+    #[test]
+    fn it_validates_fields_against_patterns() {
+        let opt = Opt {
+            assertions: vec![
+                FieldAssertion::from_str(r"2~^\d+$").unwrap(),
+                FieldAssertion::from_str("-1~x").unwrap(),
+            ],
+            ..Opt::default()
+        };
+
+        assert_eq!(check(&opt, "a-12-x"), Ok(()));
+        assert_eq!(
+            check(&opt, "a-b1-x"),
+            Err(r#"Record 7: field 2 ("b1") does not match ^\d+$"#.into())
+        );
+        assert_eq!(check(&opt, "a"), Err("Record 7: field 2 is missing".into()));
+        assert_eq!(
+            check(&opt, "a-1-y").unwrap_err(),
+            "Record 7: field -1 (\"y\") does not match x"
+        );
+
+        assert!(FieldAssertion::from_str("2").is_err());
+        assert!(FieldAssertion::from_str("2=x~a").is_err());
+        assert!(FieldAssertion::from_str("2~(").is_err());
+    }
+
+    #[test]
+    fn it_summarizes_the_failures() {
+        let opt = Opt {
+            assert_fields: Some(FieldsCount::from_str("2:").unwrap()),
+            ..Opt::default()
+        };
+
+        let mut report = Report {
+            records: 10,
+            skipped: 3,
+            ..Report::default()
+        };
+        report.add_failure(Rule::FieldsCount);
+        report.add_failure(Rule::Assertion(1));
+
+        assert_eq!(
+            report.summary(&opt),
+            "tuc: report: 10 records, 3 skipped\ntuc: report: 1 failed --assert-fields 2:"
+        );
+        assert_eq!(report.assertion_failures, [0, 1]);
+    }
+}
@@ -0,0 +1,196 @@
+use std::fmt;
+use std::io::{Result, Write};
+use std::str::FromStr;
+
+use crate::cut_width::char_width;
+
+/// Columns taken by `s` on the terminal
+fn str_width(s: &str) -> usize {
+    s.chars().map(char_width).sum()
+}
+
+/// Maximum width (in terminal columns) of an output line, and the text
+/// that starts every continuation line (e.g. `80` or `80=  `).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Wrap {
+    pub width: usize,
+    pub prefix: String,
+}
+
+impl FromStr for Wrap {
+    type Err = Box<dyn std::error::Error>;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let (width, prefix) = s.split_once('=').unwrap_or((s, ""));
+        let width = width
+            .parse::<usize>()
+            .map_err(|_| "Wrap width must be a positive number (e.g. 80 or 80=...)")?;
+
+        if str_width(prefix) >= width {
+            return Err("Wrap width must be greater than the length of the prefix".into());
+        }
+
+        Ok(Wrap {
+            width,
+            prefix: prefix.to_owned(),
+        })
+    }
+}
+
+impl fmt::Display for Wrap {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}={}", self.width, self.prefix)
+    }
+}
+
+/// Writer that breaks every line longer than the width of --wrap,
+/// starting each continuation line with its prefix.
+pub struct WrapWriter<W> {
+    inner: W,
+    wrap: Option<Wrap>,
+    eol: u8,
+    /// Columns taken so far on the current line
+    column: usize,
+    /// The first bytes of a character split across two writes
+    pending: Vec<u8>,
+}
+
+impl<W: Write> WrapWriter<W> {
+    pub fn new(inner: W, wrap: Option<Wrap>, eol: u8) -> Self {
+        WrapWriter {
+            inner,
+            wrap,
+            eol,
+            column: 0,
+            pending: Vec::new(),
+        }
+    }
+
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+}
+
+impl<W: Write> WrapWriter<W> {
+    fn write_wrapped(&mut self, buf: &[u8]) -> Result<()> {
+        let wrap = match &self.wrap {
+            Some(wrap) => wrap,
+            None => return self.inner.write_all(buf),
+        };
+
+        let mut start = 0;
+        let mut idx = 0;
+        while idx < buf.len() {
+            if buf[idx] == self.eol {
+                self.column = 0;
+                idx += 1;
+                continue;
+            }
+
+            let (c, size) = bstr::decode_utf8(&buf[idx..]);
+            let width = match c {
+                Some(c) => char_width(c),
+                // wait for the rest of the character
+                None if idx + size == buf.len() && size < 4 && buf[idx] >= 0xC0 => {
+                    self.pending.extend_from_slice(&buf[idx..]);
+                    break;
+                }
+                // invalid UTF-8 is displayed as a replacement character
+                None => 1,
+            };
+
+            // Zero-width characters (e.g. combining marks) stay with the
+            // previous one, while a character wider than the whole line
+            // gets a line of its own
+            if width > 0 && self.column > 0 && self.column + width > wrap.width {
+                self.inner.write_all(&buf[start..idx])?;
+                self.inner.write_all(&[self.eol])?;
+                self.inner.write_all(wrap.prefix.as_bytes())?;
+                self.column = str_width(&wrap.prefix);
+                start = idx;
+            }
+
+            self.column += width;
+            idx += size;
+        }
+
+        self.inner.write_all(&buf[start..idx])
+    }
+}
+
+impl<W: Write> Write for WrapWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> Result<usize> {
+        if self.pending.is_empty() {
+            self.write_wrapped(buf)?;
+        } else {
+            let mut joined = std::mem::take(&mut self.pending);
+            joined.extend_from_slice(buf);
+            self.write_wrapped(&joined)?;
+        }
+
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        // the rest of the character is not coming: write what we have
+        if !self.pending.is_empty() {
+            self.inner.write_all(&std::mem::take(&mut self.pending))?;
+            self.column += 1;
+        }
+
+        self.inner.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn wrap(spec: &str, chunks: &[&str]) -> String {
+        let mut writer = WrapWriter::new(Vec::new(), Some(Wrap::from_str(spec).unwrap()), b'\n');
+        for chunk in chunks {
+            writer.write_all(chunk.as_bytes()).unwrap();
+        }
+        String::from_utf8(writer.get_ref().clone()).unwrap()
+    }
+
+    #[test]
+    fn it_wraps_long_lines() {
+        assert_eq!(wrap("3", &["abcdefg\nab\nabc\n"]), "abc\ndef\ng\nab\nabc\n");
+        assert_eq!(wrap("4=> ", &["abcdefg\n"]), "abcd\n> ef\n> g\n");
+        assert_eq!(wrap("2", &["àè", "ì\nò"]), "àè\nì\nò");
+
+        assert!(Wrap::from_str("x").is_err());
+        assert!(Wrap::from_str("2=..").is_err());
+
+        let mut writer = WrapWriter::new(Vec::new(), None, b'\n');
+        writer.write_all(b"abc\n").unwrap();
+        assert_eq!(writer.get_ref(), b"abc\n");
+    }
+
+    #[test]
+    fn it_wraps_by_display_width() {
+        // wide characters take 2 columns, combining marks none
+        assert_eq!(wrap("4", &["日本語\n"]), "日本\n語\n");
+        assert_eq!(wrap("3", &["a日本\n"]), "a日\n本\n");
+        assert_eq!(
+            wrap("2", &["e\u{301}e\u{301}e\u{301}\n"]),
+            "e\u{301}e\u{301}\ne\u{301}\n"
+        );
+        assert_eq!(wrap("1", &["日本\n"]), "日\n本\n");
+        assert_eq!(wrap("5=日 ", &["abcdef\n"]), "abcde\n日 f\n");
+        assert!(Wrap::from_str("3=日 ").is_err());
+
+        // a character split across writes
+        let bytes = "日本語".as_bytes();
+        let mut writer = WrapWriter::new(Vec::new(), Some(Wrap::from_str("4").unwrap()), b'\n');
+        writer.write_all(&bytes[..4]).unwrap();
+        writer.write_all(&bytes[4..]).unwrap();
+        assert_eq!(writer.get_ref(), "日本\n語".as_bytes());
+
+        let mut writer = WrapWriter::new(Vec::new(), Some(Wrap::from_str("4").unwrap()), b'\n');
+        writer.write_all(&bytes[..4]).unwrap();
+        writer.flush().unwrap();
+        assert_eq!(writer.get_ref(), &bytes[..4]);
+    }
+}
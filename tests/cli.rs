@@ -125,9 +125,42 @@ fn it_cuts_on_characters() {
 fn it_cuts_on_bytes() {
     let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
 
-    let assert = cmd.args(["--bytes", "3:"]).write_stdin("über").assert();
+    let assert = cmd
+        .args(["--bytes", "3:"])
+        .write_stdin("über\nbanana")
+        .assert();
+
+    assert.success().stdout("ber\nnana\n");
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+
+    let assert = cmd
+        .args(["--bytes", "3:", "--whole-stream"])
+        .write_stdin("über\nbanana")
+        .assert();
+
+    assert.success().stdout("ber\nbanana");
+}
+
+#[test]
+fn it_cuts_on_bytes_without_splitting_codepoints() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+
+    let assert = cmd
+        .args(["--bytes", "2:4", "--no-split-codepoints"])
+        .write_stdin("über\nbanana")
+        .assert();
 
-    assert.success().stdout("ber");
+    assert.success().stdout("übe\nana\n");
+
+    for (bytes, expected) in [("2", "é\n"), ("3", "é\n"), ("1:2", "aé\n")] {
+        let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+        let assert = cmd
+            .args(["--bytes", bytes, "--no-split-codepoints"])
+            .write_stdin("aé\n")
+            .assert();
+        assert.success().stdout(expected);
+    }
 }
 
 #[test]
@@ -159,7 +192,22 @@ fn it_can_complement_the_fields() {
         .write_stdin("a b c")
         .assert();
 
-    assert.failure().stderr("Error: the complement is empty\n");
+    assert
+        .failure()
+        .stderr("Error: line 1: the complement is empty\n");
+}
+
+#[cfg(feature = "regex")]
+#[test]
+fn it_can_complement_the_characters() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+
+    let assert = cmd
+        .args(["-m", "-c", "1"])
+        .write_stdin("héllo\na\n\nab\n")
+        .assert();
+
+    assert.success().stdout("éllo\n\n\nb\n");
 }
 
 #[test]
@@ -255,6 +303,59 @@ fn it_join_lines() {
     assert.success().stdout("ca\n");
 }
 
+#[test]
+fn it_reads_the_fallback_from_the_environment() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+
+    let assert = cmd
+        .env("FALLBACK", "null")
+        .args(["-d", ",", "-f", "{1}:{3}", "--fallback-oob", "env:FALLBACK"])
+        .write_stdin("a,b")
+        .assert();
+
+    assert.success().stdout("a:null\n");
+}
+
+#[test]
+fn it_translates_the_options_to_cut() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+
+    let assert = cmd
+        .args(["-d", ":", "-f", "1,6:", "-j", "--to-cut"])
+        .assert();
+
+    assert.success().stdout("cut -d : -f 1,6-\n");
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+
+    let assert = cmd.args(["-f", "-1", "--to-cut"]).assert();
+
+    assert
+        .failure()
+        .stderr("tuc: runtime error. cut does not support negative fields\n");
+}
+
+#[test]
+fn it_cuts_bytes_of_the_selected_fields() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+
+    let assert = cmd
+        .args(["-d", " ", "-f", "1,2", "-j", "--bytes", "1:7"])
+        .write_stdin("9f86d081884c7d65 README.md")
+        .assert();
+
+    assert.success().stdout("9f86d08 README.\n");
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+
+    let assert = cmd
+        .args(["-d", " ", "-f", "{2}: {1[1:7]}"])
+        .write_stdin("9f86d081884c7d65 README.md")
+        .assert();
+
+    assert.success().stdout("README.md: 9f86d08\n");
+}
+
 #[test]
 fn it_format_fields() {
     let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
@@ -275,229 +376,1910 @@ fn it_format_fields() {
 }
 
 #[test]
-fn it_format_field_1_even_with_no_matching_parameters() {
+fn it_format_conditional_segments() {
     let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
 
-    let assert = cmd.args(["-f", "Say {1}"]).write_stdin("hello").assert();
+    let assert = cmd
+        .args(["-d", ",", "-f", "{1}{2? <{2}>}{3?:, no phone}"])
+        .write_stdin("alice,alice@example.com,555\nbob,,")
+        .assert();
 
-    assert.success().stdout("Say hello\n");
+    assert
+        .success()
+        .stdout("alice <alice@example.com>\nbob, no phone\n");
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+
+    let assert = cmd.args(["-l", "{1?yes:no}"]).assert();
+
+    assert.failure().stderr(
+        "tuc: runtime error. Conditional formatting is available only for --fields and --characters\n",
+    );
 }
 
 #[test]
-fn it_cuts_using_a_greedy_delimiter() {
+fn it_format_fields_with_width_and_alignment() {
     let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
 
     let assert = cmd
-        .args(["-g", "-d", "-", "-f", "1,2"])
-        .write_stdin("a---b")
+        .args(["-d", " ", "-f", "{1:<6}|{2:>4}|{3:^7}|"])
+        .write_stdin("apple 3 red\nkiwi 12 green")
         .assert();
 
-    assert.success().stdout("ab\n");
+    assert
+        .success()
+        .stdout("apple |   3|  red  |\nkiwi  |  12| green |\n");
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+
+    let assert = cmd.args(["-l", "{1:<4}"]).assert();
+
+    assert
+        .failure()
+        .stderr("tuc: runtime error. Placeholder modifiers (e.g. {1:<10}) are available only for --fields\n");
 }
 
-#[cfg(feature = "regex")]
 #[test]
-fn it_cuts_using_a_regex() {
+fn it_humanize_numeric_fields() {
     let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
 
     let assert = cmd
-        .args(["-e", "[.,]", "-f", "1,5"])
-        .write_stdin("a..,,b")
+        .args(["-d", " ", "-f", "{1}: {2|human} requests, {3|size}"])
+        .write_stdin("/index.html 1234567 5368709120")
         .assert();
 
-    assert.success().stdout("ab\n");
+    assert
+        .success()
+        .stdout("/index.html: 1,234,567 requests, 5.0 GiB\n");
 }
 
-#[cfg(feature = "regex")]
 #[test]
-fn it_cuts_using_a_greedy_delimiter_and_a_regex() {
+fn it_swap_two_fields() {
     let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
 
     let assert = cmd
-        .args(["-g", "-e", "[.,]", "-f", "1,2"])
-        .write_stdin("a..,,b")
+        .args(["-d", " ", "--swap", "1", "-1"])
+        .write_stdin("a  b c")
         .assert();
 
-    assert.success().stdout("ab\n");
-}
+    assert.success().stdout("c  b a\n");
 
-#[test]
-fn it_accept_any_kind_of_range_as_long_as_its_safe() {
     let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
 
-    let assert = cmd.args(["-l", "2:-2"]).write_stdin("a\nb\nc\nd").assert();
+    let assert = cmd
+        .args(["-d", ",", "--swap", "2,3", "-r", ";"])
+        .write_stdin("a,b,c")
+        .assert();
 
-    assert.success().stdout("b\nc\n");
+    assert.success().stdout("a;c;b\n");
 
     let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
 
-    let assert = cmd.args(["-l", "2:-4"]).write_stdin("a\nb\nc\nd").assert();
+    let assert = cmd.args(["--swap", "1:2", "3"]).assert();
 
     assert
         .failure()
-        .stderr("Error: Field left value cannot be greater than right value\n");
+        .stderr("tuc: runtime error. --swap requires two fields, e.g. --swap 1 3\n");
 }
 
 #[test]
-fn it_fails_if_there_are_unknown_arguments() {
+fn it_truncate_long_fields() {
     let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
 
-    let assert = cmd.args(["--whatever"]).write_stdin("foobar").assert();
+    let assert = cmd
+        .args(["-d", " ", "-f", "{1}: {2:}", "--truncate", "8"])
+        .write_stdin("ERROR connection refused by peer")
+        .assert();
 
-    assert.failure().stderr(
-        "tuc: unexpected arguments [\"--whatever\"]\nTry 'tuc --help' for more information.\n",
+    assert.success().stdout("ERROR: connect…\n");
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+
+    let assert = cmd
+        .args(["-d", " ", "-f", "2:", "-j", "--truncate", "10=..."])
+        .write_stdin("ERROR connection refused by peer")
+        .assert();
+
+    assert.success().stdout("connect...\n");
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+
+    let assert = cmd
+        .env("RUST_BACKTRACE", "0")
+        .args(["-f", "1", "--truncate", "1=..."])
+        .write_stdin("abc")
+        .assert();
+
+    assert.code(2).stderr(
+        "Error: failed to parse '1=...': Truncate ellipsis cannot be wider than the width\n",
     );
 }
 
 #[test]
-fn it_fails_if_both_join_and_nojoin_are_used_at_once() {
+fn it_pads_short_fields() {
     let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
 
-    let assert = cmd.args(["-j", "--no-join"]).write_stdin("foobar").assert();
+    let assert = cmd
+        .args(["-d", " ", "-f", "{1}|{2}|", "--pad", "5"])
+        .write_stdin("GET 200\nPOST 404")
+        .assert();
 
-    assert.failure().stderr(
-        "tuc: runtime error. It's not possible to use --join and --no-join simultaneously\n",
-    );
+    assert.success().stdout("GET  |200  |\nPOST |404  |\n");
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+
+    let assert = cmd
+        .args(["-d", " ", "-f", "2", "--pad", "5=0", "--pad-align", "right"])
+        .write_stdin("a 42")
+        .assert();
+
+    assert.success().stdout("00042\n");
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+
+    let assert = cmd.args(["-f", "1", "--pad-align", "right"]).assert();
+
+    assert
+        .failure()
+        .stderr("tuc: runtime error. --pad-align requires --pad\n");
 }
 
 #[test]
-fn it_fails_if_both_replace_and_nojoin_are_used_at_once() {
+fn it_accepts_a_custom_output_buffer_size() {
     let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
 
     let assert = cmd
-        .args(["-r", "x", "--no-join"])
-        .write_stdin("foobar")
+        .args(["-d", " ", "-f", "2", "--output-buffer-size", "1"])
+        .write_stdin("a b\nc d")
         .assert();
 
-    assert.failure().stderr(
-        "tuc: runtime error. You can't pass --no-join when using --replace, which implies --join\n",
-    );
+    assert.success().stdout("b\nd\n");
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+
+    let assert = cmd.args(["--output-buffer-size", "lots"]).assert();
+
+    assert
+        .failure()
+        .stderr(predicates::str::contains("failed to parse 'lots'"));
 }
 
-#[cfg(feature = "regex")]
 #[test]
-fn it_fails_when_the_regex_is_malformed() {
+fn it_print_where_delimiters_are_found() {
     let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
 
-    let assert = cmd.args(["-e", "["]).write_stdin("foobar").assert();
+    let assert = cmd
+        .args(["-d", " ", "-g", "--print-delimiters"])
+        .write_stdin("a  b\tc d")
+        .assert();
 
-    assert.failure().stderr(predicates::str::starts_with(
-        "tuc: runtime error. The regular expression is malformed.",
-    ));
+    assert.success().stdout("2:\"  \" 7:\" \"\n");
+}
 
-    let assert = cmd.args(["-e", "[", "-g"]).write_stdin("foobar").assert();
+#[test]
+fn it_print_every_field_with_its_index() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
 
-    assert.failure().stderr(predicates::str::starts_with(
-        "tuc: runtime error. The regular expression is malformed.",
-    ));
+    let assert = cmd
+        .args(["-d", ",", "--pairs"])
+        .write_stdin("a,b\nc")
+        .assert();
+
+    assert.success().stdout("1\ta\n2\tb\n\n1\tc\n\n");
 }
 
-#[cfg(not(feature = "regex"))]
 #[test]
-fn does_not_panic_if_attemtping_to_use_regex_arg_with_noregex_build() {
+fn it_copies_the_output_to_the_clipboard() {
     let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
 
-    let assert = cmd.args(["-e", "."]).write_stdin("foobar").assert();
+    let assert = cmd
+        .args(["-d", ",", "-f", "2", "--copy"])
+        .write_stdin("a,b")
+        .assert();
 
-    assert.failure().stderr(
-        "tuc: unexpected arguments [\"-e\", \".\"]\nTry 'tuc --help' for more information.\n",
-    );
+    assert.success().stdout("b\n").stderr("\x1b]52;c;Ygo=\x07");
 }
 
+#[cfg(feature = "json")]
 #[test]
-fn it_emit_output_as_json() {
+fn it_prints_pretty_json() {
     let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
 
     let assert = cmd
-        .args(["--json", "-d", "/", "-f", "1,2,1:3"])
-        .write_stdin("a/b/c/d")
+        .args(["-d", ",", "-f", "1,3:", "--json-pretty"])
+        .write_stdin("a,b,c,d\ne,f,g,h")
         .assert();
 
-    assert.success().stdout(
-        r#"["a","b","a","b","c"]
-"#,
-    );
+    assert
+        .success()
+        .stdout("[\n  \"a\",\n  \"c\",\n  \"d\"\n]\n[\n  \"e\",\n  \"g\",\n  \"h\"\n]\n");
 }
 
-#[cfg(feature = "regex")]
+#[cfg(feature = "columnar")]
 #[test]
-fn it_emit_output_as_json_even_when_cutting_on_chars() {
+fn it_writes_the_fields_as_parquet() {
+    let path = std::env::temp_dir().join(format!("tuc-test-{}.parquet", std::process::id()));
     let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
 
     let assert = cmd
-        .args(["--json", "-c", "1,2,1:3"])
-        .write_stdin("abcd")
+        .args(["-d", ",", "-f", "1,3", "--types", "str,int", "--parquet"])
+        .arg(&path)
+        .write_stdin("a,b,1\nc,d,2\n")
         .assert();
 
-    assert.success().stdout(
-        r#"["a","b","a","b","c"]
-"#,
-    );
+    assert.success().stdout("");
+    let output = std::fs::read(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert!(output.starts_with(b"PAR1"));
 }
 
 #[test]
-fn it_does_not_allow_to_replace_delimiter_with_json() {
+fn it_requires_columnar_output_for_types() {
     let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
 
-    let assert = cmd.args(["--json", "-r", "x"]).assert();
+    let assert = cmd.args(["-f", "1", "--types", "int"]).assert();
 
     assert
         .failure()
-        .stderr("tuc: runtime error. The use of --replace with --json is not supported\n");
+        .stderr("tuc: runtime error. --types requires --parquet or --arrow\n");
 }
 
+#[cfg(feature = "json")]
 #[test]
-fn it_is_not_allowed_to_use_character_with_nojoin() {
+fn it_prints_fields_as_msgpack() {
     let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
 
-    let assert = cmd.args(["-c", "1", "--no-join"]).assert();
+    let assert = cmd
+        .args(["-d", ",", "-f", "2,1", "--msgpack"])
+        .write_stdin("a,bc\nd,e")
+        .assert();
 
-    assert.failure().stderr(
-        "tuc: runtime error. Since --characters implies --join, you can\'t pass --no-join\n",
-    );
+    assert
+        .success()
+        .stdout(&b"\x92\xa2bc\xa1a\x92\xa1e\xa1d"[..]);
 }
 
-#[cfg(not(feature = "regex"))]
+#[cfg(feature = "json")]
 #[test]
-fn it_cannot_use_characters_without_regex() {
+fn it_prints_invalid_utf8_fields_as_msgpack_binary() {
     let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
 
-    let assert = cmd.args(["-c", "1"]).assert();
+    let assert = cmd
+        .args(["-d", ",", "-f", "1:", "--msgpack"])
+        .write_stdin(&b"a\xff,b"[..])
+        .assert();
 
-    assert.failure().stderr(
-        "tuc: runtime error. The use of --characters requires `tuc` to be compiled with `regex` support\n",
-    );
+    assert.success().stdout(&b"\x92\xc4\x02a\xff\xa1b"[..]);
 }
 
+#[cfg(unix)]
 #[test]
-fn it_does_not_support_json_on_lines() {
+fn it_runs_a_command_for_every_record() {
     let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
 
-    let assert = cmd.args(["-l", "1", "--json"]).assert();
+    let assert = cmd
+        .args(["-d", ",", "--exec", "printf '%s|%s\\n' {2} {1}"])
+        .write_stdin("a,b c\n$HOME,;rm")
+        .assert();
 
-    assert.failure().stderr(
-        "tuc: runtime error. --json support is available only for --fields and --characters\n",
-    );
+    assert.success().stdout("b c|a\n;rm|$HOME\n");
 }
 
+#[cfg(unix)]
 #[test]
-fn it_does_not_support_json_on_bytes() {
+fn it_passes_invalid_utf8_fields_to_the_command() {
     let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
 
-    let assert = cmd.args(["-b", "1", "--json"]).assert();
+    let assert = cmd
+        .args(["-d", ",", "--exec", "printf '%s|\\n' {2}"])
+        .write_stdin(&b"a,b\xff"[..])
+        .assert();
 
-    assert.failure().stderr(
-        "tuc: runtime error. --json support is available only for --fields and --characters\n",
-    );
+    assert.success().stdout(&b"b\xff|\n"[..]);
 }
 
+#[cfg(unix)]
 #[test]
-fn it_cannot_format_fields_alongside_json() {
+fn it_fails_when_a_command_fails() {
     let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
 
-    let assert = cmd.args(["-f", "a{1}b", "--json"]).assert();
+    let assert = cmd
+        .args(["--exec", "test {1} = ok", "--max-procs", "2"])
+        .write_stdin("ok\nko\nko")
+        .assert();
 
     assert
         .failure()
-        .stderr("tuc: runtime error. Cannot format fields when using --json\n");
+        .stderr(predicates::str::contains("2 commands failed"));
+}
+
+#[cfg(unix)]
+#[test]
+fn it_pipes_the_output_through_a_command() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+
+    let assert = cmd
+        .args(["-d", ",", "-f", "2", "--tee-cmd", "sort -u"])
+        .write_stdin("1,b\n2,a\n3,b")
+        .assert();
+
+    assert.success().stdout("a\nb\n");
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+
+    let assert = cmd
+        .args(["--tee-cmd", "sh -c 'cat > /dev/null; exit 3'"])
+        .write_stdin("a")
+        .assert();
+
+    assert
+        .failure()
+        .stderr("Error: --tee-cmd failed (exit status: 3)\n");
+}
+
+#[test]
+fn it_prefixes_records_with_a_timestamp() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+
+    let assert = cmd
+        .args(["-d", ",", "-f", "2", "--timestamp=[%Y]"])
+        .write_stdin("a,b")
+        .assert();
+
+    assert
+        .success()
+        .stdout(predicates::str::starts_with("[20"))
+        .stdout(predicates::str::ends_with("] b\n"));
+}
+
+#[test]
+fn it_wraps_long_lines() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+
+    let assert = cmd
+        .args(["-d", ",", "-f", "2", "--wrap", "6=  "])
+        .write_stdin("a,the quick brown fox\nb,dog")
+        .assert();
+
+    assert
+        .success()
+        .stdout("the qu\n  ick \n  brow\n  n fo\n  x\ndog\n");
+}
+
+#[test]
+fn it_seeks_to_the_next_record() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+
+    let assert = cmd
+        .args(["-d", ",", "-f", "2", "--seek", "3", "--print-offset"])
+        .write_stdin("a,1\nb,2\nc,3\n")
+        .assert();
+
+    assert.success().stdout("2\n3\n").stderr("12\n");
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+
+    let assert = cmd
+        .args(["-d", ",", "-f", "2", "--seek", "4"])
+        .write_stdin("a,1\nb,2\nc,3\n")
+        .assert();
+
+    assert.success().stdout("2\n3\n");
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+
+    let assert = cmd
+        .args(["-d", ",", "-f", "2", "--seek", "5"])
+        .write_stdin("a,1\nb,2\nc,3\n")
+        .assert();
+
+    assert.success().stdout("3\n");
+}
+
+#[test]
+fn it_pastes_the_lines_of_a_file() {
+    let path = std::env::temp_dir().join(format!("tuc-test-{}.paste", std::process::id()));
+    std::fs::write(&path, "1,x\n2,y\n").unwrap();
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+
+    let assert = cmd
+        .args(["-d", ",", "-f", "1,3"])
+        .arg("--paste")
+        .arg(&path)
+        .write_stdin("a,b\nc,d\ne,f\n")
+        .assert();
+
+    std::fs::remove_file(&path).unwrap();
+    assert.success().stdout("a1\nc2\ne\n");
+}
+
+#[test]
+fn it_reads_the_input_files() {
+    let path = std::env::temp_dir().join(format!("tuc-test-{}.input", std::process::id()));
+    std::fs::write(&path, "a,b\nc,d").unwrap();
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+
+    let assert = cmd
+        .args(["-d", ",", "-f", "2"])
+        .arg(&path)
+        .arg("-")
+        .arg(&path)
+        .write_stdin("e,f\n")
+        .assert();
+
+    std::fs::remove_file(&path).unwrap();
+    assert.success().stdout("b\nd\nf\nb\nd\n");
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+
+    let assert = cmd.args(["-f", "1", "/does/not/exist"]).assert();
+
+    assert.failure().stderr(predicates::str::starts_with(
+        "Error: Cannot open /does/not/exist: ",
+    ));
+}
+
+#[test]
+fn it_edits_the_files_in_place() {
+    let path = std::env::temp_dir().join(format!("tuc-test-{}.in-place", std::process::id()));
+    let backup_path = path.with_extension("in-place.bak");
+    std::fs::write(&path, "a,b,c\nd,e,f\n").unwrap();
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+
+    let assert = cmd
+        .args(["-d", ",", "-f", "1,3", "-j", "--in-place=.bak"])
+        .arg(&path)
+        .assert();
+
+    let output = std::fs::read_to_string(&path).unwrap();
+    let backup = std::fs::read_to_string(&backup_path).unwrap();
+    std::fs::remove_file(&backup_path).unwrap();
+
+    assert.success().stdout("");
+    assert_eq!(output, "a,c\nd,f\n");
+    assert_eq!(backup, "a,b,c\nd,e,f\n");
+
+    // A failure leaves the file as it was
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+
+    let assert = cmd.args(["-d", ",", "-f", "3", "-i"]).arg(&path).assert();
+
+    let output = std::fs::read_to_string(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert.failure();
+    assert_eq!(output, "a,c\nd,f\n");
+}
+
+#[test]
+fn it_edits_the_files_in_place_with_a_short_suffix() {
+    let path = std::env::temp_dir().join(format!("tuc-test-{}.short-in-place", std::process::id()));
+    let backup_path = path.with_extension("short-in-place.orig");
+    std::fs::write(&path, "a,b\n").unwrap();
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+
+    let assert = cmd
+        .args(["-d", ",", "-f", "2", "-i.orig"])
+        .arg(&path)
+        .assert();
+
+    let output = std::fs::read_to_string(&path).unwrap();
+    let backup = std::fs::read_to_string(&backup_path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    std::fs::remove_file(&backup_path).unwrap();
+
+    assert.success().stdout("");
+    assert_eq!(output, "b\n");
+    assert_eq!(backup, "a,b\n");
+}
+
+#[cfg(feature = "gzip")]
+#[test]
+fn it_reads_compressed_input() {
+    use std::io::Write;
+
+    let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::fast());
+    encoder.write_all(b"a,b\nc,d\n").unwrap();
+    let data = encoder.finish().unwrap();
+
+    let path = std::env::temp_dir().join(format!("tuc-test-{}.csv.gz", std::process::id()));
+    std::fs::write(&path, &data).unwrap();
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+
+    let assert = cmd.args(["-d", ",", "-f", "2"]).arg(&path).assert();
+
+    std::fs::remove_file(&path).unwrap();
+    assert.success().stdout("b\nd\n");
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+
+    let assert = cmd
+        .args(["-d", ",", "-f", "1", "--decompress", "auto"])
+        .write_stdin(data)
+        .assert();
+
+    assert.success().stdout("a\nc\n");
+}
+
+#[test]
+fn it_splits_csv_fields() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+
+    let assert = cmd
+        .args(["--csv", "-f", "2,-1", "-j"])
+        .write_stdin("a,\"b,\"\"c\"\"\",d\ne,\"f\ng\",h\n")
+        .assert();
+
+    assert.success().stdout("b,\"c\",d\nf\ng,h\n");
+}
+
+#[test]
+fn it_ignores_the_delimiters_within_quotes() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+
+    let assert = cmd
+        .args(["-d", " ", "-f", "2", "--quote", "'"])
+        .write_stdin("a 'b c' d\n'e' f\n")
+        .assert();
+
+    assert.success().stdout("'b c'\nf\n");
+}
+
+#[test]
+fn it_ignores_the_escaped_delimiters() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+
+    let assert = cmd
+        .args(["-d", ",", "-f", "1", "--escape", "--unescape"])
+        .write_stdin("a\\,b,c\nd\\\\,e\n")
+        .assert();
+
+    assert.success().stdout("a,b\nd\\\n");
+}
+
+#[test]
+fn it_ignores_the_delimiters_within_brackets() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+
+    let assert = cmd
+        .args(["-d", ", ", "-f", "2", "--respect-brackets"])
+        .write_stdin("f(a, b), g(c, [d, e])\n")
+        .assert();
+
+    assert.success().stdout("g(c, [d, e])\n");
+}
+
+#[test]
+fn it_prints_the_fallback_of_the_bytes_out_of_bounds() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+
+    let assert = cmd
+        .args(["-b", "1,4=?,5", "--fallback-oob", "-"])
+        .write_stdin("abcde\nab\n")
+        .assert();
+
+    assert.success().stdout("ade\na?-\n");
+}
+
+#[test]
+fn it_formats_the_bytes() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+
+    let assert = cmd
+        .args(["-b", "<{1:4}> <{-2:}>"])
+        .write_stdin("2024-01-01T10\n")
+        .assert();
+
+    assert.success().stdout("<2024> <10>\n");
+}
+
+#[cfg(feature = "regex")]
+#[test]
+fn it_cuts_the_lines_between_patterns() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+
+    let assert = cmd
+        .args(["-l", "/^BEGIN/:/^END/"])
+        .write_stdin("a\nBEGIN\nb\nEND\nc\n")
+        .assert();
+
+    assert.success().stdout("BEGIN\nb\nEND\n");
+}
+
+#[cfg(feature = "regex")]
+#[test]
+fn it_selects_the_fields_by_content() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+
+    let assert = cmd
+        .args(["-d", " ", "--field-match", r"^\d+\.\d+\.\d+\.\d+$"])
+        .write_stdin("10.0.0.1 GET /a 1.2.3.4\nnone\n")
+        .assert();
+
+    assert.success().stdout("10.0.0.1 1.2.3.4\n\n");
+}
+
+#[test]
+fn it_counts_the_fields() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+
+    let assert = cmd
+        .args(["-d", " ", "-g", "--count"])
+        .write_stdin("a  b c\n\nd\n")
+        .assert();
+
+    assert.success().stdout("3\n0\n1\n");
+}
+
+#[test]
+fn it_prints_the_index_of_the_fields() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+
+    let assert = cmd
+        .args(["-d", ",", "-f", "-1,1:2", "-j", "--with-index"])
+        .write_stdin("a,b,c\n")
+        .assert();
+
+    assert.success().stdout("3:c,1:a,2:b\n");
+}
+
+#[test]
+fn it_cuts_by_display_width() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+
+    let assert = cmd
+        .args(["--width", "1:5"])
+        .write_stdin("日本語テキスト\nabcdefgh\nab\n")
+        .assert();
+
+    assert.success().stdout("日本\nabcde\nab\n");
+}
+
+#[test]
+fn it_cuts_by_display_width_out_of_the_line() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    let assert = cmd.args(["--width", "1,5=x"]).write_stdin("abc\n").assert();
+    assert.success().stdout("ax\n");
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    let assert = cmd
+        .args(["--width", "5", "--fallback-oob", "?"])
+        .write_stdin("abc\n")
+        .assert();
+    assert.success().stdout("?\n");
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    let assert = cmd
+        .env("RUST_BACKTRACE", "0")
+        .args(["--width", "5"])
+        .write_stdin("abc\n")
+        .assert();
+    assert
+        .failure()
+        .code(3)
+        .stderr("Error: line 1: Out of bounds: 5\n");
+}
+
+#[test]
+fn it_maps_the_fields() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+
+    let assert = cmd
+        .args([
+            "-d",
+            ",",
+            "-f",
+            "2,1",
+            "-j",
+            "--map",
+            "squeeze-spaces|trim|upper",
+        ])
+        .write_stdin(" a  b ,  c\n")
+        .assert();
+
+    assert.success().stdout("C,A B\n");
+}
+
+#[test]
+fn it_transforms_single_placeholders() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+
+    let assert = cmd
+        .args(["-d", ",", "-f", "{2:trim:upper} {1}"])
+        .write_stdin("a, b \n")
+        .assert();
+
+    assert.success().stdout("B a\n");
+}
+
+#[test]
+fn it_numbers_the_output_lines() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+
+    let assert = cmd
+        .args(["-d", ",", "-f", "{2}={1}", "-s", "--number"])
+        .args(["--number-separator", ": "])
+        .write_stdin("a,b\nnope\nc,d\n")
+        .assert();
+
+    assert.success().stdout("1: b=a\n3: d=c\n");
+}
+
+#[test]
+fn it_numbers_the_records_by_input_line_after_the_preamble() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+
+    let assert = cmd
+        .args(["-d", ",", "-f", "{1}:{2}", "--keep-header", "--number"])
+        .args(["--add-field", "{NR}", "--errors", "collect"])
+        .write_stdin("k,v\na,b\nnope\n")
+        .assert();
+
+    assert
+        .failure()
+        .stdout("k,v\n2\ta:b,2\n")
+        .stderr("tuc: Record 3: Out of bounds: 2\nError: 1 records could not be processed\n");
+}
+
+#[test]
+fn it_skips_the_first_lines() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+
+    let assert = cmd
+        .args(["-d", ",", "-f", "2", "--skip", "2"])
+        .write_stdin("# generated\n# by me\na,b\nc,d\n")
+        .assert();
+
+    assert.success().stdout("b\nd\n");
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+
+    let assert = cmd
+        .args([
+            "-d",
+            ",",
+            "-f",
+            "2",
+            "--skip",
+            "1",
+            "--skip-print",
+            "--keep-header",
+        ])
+        .write_stdin("# generated\nname,age\nalice,31\n")
+        .assert();
+
+    assert.success().stdout("# generated\nname,age\n31\n");
+}
+
+#[test]
+fn it_keeps_the_header() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+
+    let assert = cmd
+        .args(["-d", ",", "-f", "2", "--keep-header"])
+        .write_stdin("name,age\nalice,31\nbob,27\n")
+        .assert();
+
+    assert.success().stdout("name,age\n31\n27\n");
+}
+
+#[cfg(feature = "regex")]
+#[test]
+fn it_cuts_the_header_without_checking_it() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+
+    let assert = cmd
+        .args([
+            "-d",
+            ",",
+            "-f",
+            "2,1",
+            "-j",
+            "--cut-header",
+            "--assert",
+            "2~^[0-9]+$",
+        ])
+        .write_stdin("name,age\nalice,31\nbob,27\n")
+        .assert();
+
+    assert.success().stdout("age,name\n31,alice\n27,bob\n");
+}
+
+#[test]
+fn it_cuts_ranges_with_a_step() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+
+    let assert = cmd
+        .args(["-d", ",", "-f", "1:5:2,::3", "-j"])
+        .write_stdin("a,b,c,d,e,f\n")
+        .assert();
+
+    assert.success().stdout("a,c,e,a,d\n");
+
+    // an inverted range fails as it does without a step
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+
+    let assert = cmd
+        .env("RUST_BACKTRACE", "0")
+        .args(["-d", ",", "-f", "-1:1:2"])
+        .write_stdin("a,b,c\n")
+        .assert();
+
+    assert
+        .failure()
+        .code(3)
+        .stderr("Error: line 1: Field left value cannot be greater than right value\n");
+}
+
+#[test]
+fn it_prints_the_output_delimiter_between_fields() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+
+    let assert = cmd
+        .args(["-d", ":", "-f", "1:2,-1", "--output-delimiter", " | "])
+        .write_stdin("a:b:c:d\n")
+        .assert();
+
+    assert.success().stdout("a:b | d\n");
+}
+
+#[test]
+fn it_analyzes_the_input() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+
+    let assert = cmd.arg("--analyze").write_stdin("a|b|c\nd|e|f\n").assert();
+
+    assert.success().stdout(
+        "records sampled: 2\n\
+         end of line: LF\n\
+         byte order mark: no\n\
+         double quotes: no\n\
+         delimiters:\n  \
+           '|'   3 fields in 2 records\n\
+         suggested: tuc -d '|' -f 1:\n",
+    );
+}
+
+#[test]
+fn it_formats_fields_with_environment_variables() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+
+    let assert = cmd
+        .env("TUC_TEST_REGION", "eu-1")
+        .args(["-d", ",", "-f", "{2} ({$TUC_TEST_REGION})"])
+        .write_stdin("a,b")
+        .assert();
+
+    assert.success().stdout("b (eu-1)\n");
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+
+    let assert = cmd
+        .env("RUST_BACKTRACE", "0")
+        .env_remove("TUC_TEST_REGION")
+        .args(["-d", ",", "-f", "{2} ({$TUC_TEST_REGION})"])
+        .write_stdin("a,b")
+        .assert();
+
+    assert
+        .failure()
+        .code(2)
+        .stderr("Error: Field format error: environment variable not set: TUC_TEST_REGION\n");
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn it_reads_fields_from_json_records() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+
+    let assert = cmd
+        .args(["--json-input", "-f", "2,1", "--json"])
+        .write_stdin("[\"a,b\", \"c\\\"d\", 3]\n")
+        .assert();
+
+    assert.success().stdout("[\"c\\\"d\",\"a,b\"]\n");
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+
+    let assert = cmd
+        .args(["--json-input", "-f", "1:", "--json"])
+        .write_stdin("[\"3\", 3, 1.5, true, null, [1, \"x\"], {\"a\": 1}]\n[false]\n")
+        .assert();
+
+    assert
+        .success()
+        .stdout("[\"3\",3,1.5,true,null,[1,\"x\"],{\"a\":1}]\n[false]\n");
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+
+    let assert = cmd
+        .args(["--json-keys", "name,age", "-f", "{1} is {2}"])
+        .write_stdin("{\"age\": 42, \"name\": \"Jane\"}\n")
+        .assert();
+
+    assert.success().stdout("Jane is 42\n");
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn it_selects_json_keys_as_fields() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+
+    let assert = cmd
+        .args(["--json-input", "-f", "user.id=?,msg", "-d", " ", "-j"])
+        .write_stdin("{\"msg\": \"hi\", \"user\": {\"id\": 7}}\n{\"msg\": \"bye\"}\n")
+        .assert();
+
+    assert.success().stdout("7 hi\n? bye\n");
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn it_fails_on_missing_json_keys_without_fallbacks() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+
+    let assert = cmd
+        .env("RUST_BACKTRACE", "0")
+        .args(["--json-input", "-f", "msg,id", "-d", " ", "-j"])
+        .write_stdin("{\"msg\": \"hi\", \"id\": 7}\n{\"msg\": \"bye\"}\n")
+        .assert();
+
+    assert
+        .failure()
+        .code(3)
+        .stdout("hi 7\nbye ")
+        .stderr("Error: line 2: Out of bounds: 2\n");
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn it_selects_json_pointers_as_fields() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+
+    let assert = cmd
+        .args([
+            "--json-input",
+            "-f",
+            "{/user/id}: {/tags/0}",
+            "--fallback-oob",
+            "none",
+        ])
+        .write_stdin("{\"tags\": [\"a\"], \"user\": {\"id\": 7}}\n{\"user\": {\"id\": 8}}\n")
+        .assert();
+
+    assert.success().stdout("7: a\n8: none\n");
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn it_explains_the_options_as_json() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+
+    let assert = cmd
+        .args(["-d", ":", "-f", "{2}x", "--explain=json"])
+        .write_stdin("never read")
+        .assert();
+
+    assert
+        .success()
+        .stdout(predicates::str::contains(
+            r#""bounds":[{"bytes":null,"fallback":null,"left":2,"pad":null,"pipe":null,"right":2,"sub":null,"type":"bound"},{"text":"x","type":"filler"}]"#,
+        ))
+        .stdout(predicates::str::contains(
+            r#""args":["--fields","{2}x","--delimiter",":","--explain=json"]"#,
+        ));
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    let assert = cmd.args(["--explain=yaml"]).assert();
+
+    assert
+        .failure()
+        .stderr("tuc: runtime error. --explain supports only the json format (--explain=json)\n");
+}
+
+#[test]
+fn it_separates_bytes_ranges_with_replace_delimiter() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+
+    let assert = cmd
+        .args(["-b", "1:4,9:", "-r", "|"])
+        .write_stdin("HEAD0001payload\nHEAD0002other")
+        .assert();
+
+    assert.success().stdout("HEAD|payload\nHEAD|other\n");
+}
+
+#[test]
+fn it_deletes_fields_keeping_the_original_delimiters() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+
+    let assert = cmd
+        .args([
+            "-d",
+            " ",
+            "-g",
+            "-f",
+            "2",
+            "-m",
+            "--keep-original-delimiters",
+        ])
+        .write_stdin("a  b\tc   d\ne f")
+        .assert();
+
+    assert.success().stdout("a   d\ne\n");
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    let assert = cmd.args(["-f", "2", "--keep-original-delimiters"]).assert();
+
+    assert
+        .failure()
+        .stderr("tuc: runtime error. --keep-original-delimiters requires --complement\n");
+}
+
+#[test]
+fn it_trims_empty_lines_when_cutting_lines() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+
+    let assert = cmd
+        .args(["-l", "1", "-t", "b"])
+        .write_stdin("\n\nfirst\nsecond\n")
+        .assert();
+
+    assert.success().stdout("first\n");
+}
+
+#[test]
+fn it_skips_empty_lines_when_cutting_lines() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+
+    let assert = cmd
+        .args(["-l", "2:3", "--skip-empty"])
+        .write_stdin("one\n\n\ntwo\n\nthree\n")
+        .assert();
+
+    assert.success().stdout("two\nthree\n");
+}
+
+#[test]
+fn it_reports_timings() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+
+    let assert = cmd
+        .args(["-d", " ", "-f", "2", "--timing"])
+        .write_stdin("a b\nc d\ne")
+        .assert();
+
+    assert
+        .success()
+        .stdout("b\nd\ne\n")
+        .stderr(predicates::str::contains("tuc: timing: read "))
+        .stderr(predicates::str::contains("tuc: timing: 3 records ("));
+}
+
+#[test]
+fn it_strips_ansi_escape_sequences() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+
+    let assert = cmd
+        .args(["-d", " ", "-f", "2", "--strip-ansi"])
+        .write_stdin("\x1b[32mok\x1b[0m \x1b[1;31m12\x1b[0m")
+        .assert();
+
+    assert.success().stdout("12\n");
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+
+    let assert = cmd.args(["-l", "1", "--strip-ansi"]).assert();
+
+    assert.failure().stderr(
+        "tuc: runtime error. --strip-ansi is available only for --fields and --characters\n",
+    );
+}
+
+#[test]
+fn it_shows_nonprinting_characters() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+
+    let assert = cmd
+        .args(["-d", "\t", "-f", "2", "-v"])
+        .write_stdin(b"a\xa0b\tc\x1b".as_slice())
+        .assert();
+
+    assert.success().stdout("c^[\n");
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+
+    let assert = cmd
+        .args(["-d", "\t", "-f", "1", "--show-nonprinting"])
+        .write_stdin(b"a\xa0b c".as_slice())
+        .assert();
+
+    assert.success().stdout("a\\xA0b c\n");
+}
+
+#[test]
+fn it_format_the_whole_line() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+
+    let assert = cmd
+        .args(["-d", " ", "-f", "{2}: {0}"])
+        .write_stdin("GET /index.html 200")
+        .assert();
+
+    assert
+        .success()
+        .stdout("/index.html: GET /index.html 200\n");
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+
+    let assert = cmd.args(["-l", "{0}"]).assert();
+
+    assert
+        .failure()
+        .stderr("tuc: runtime error. The placeholder {0} is not available for --lines\n");
+}
+
+#[test]
+fn it_format_field_1_even_with_no_matching_parameters() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+
+    let assert = cmd.args(["-f", "Say {1}"]).write_stdin("hello").assert();
+
+    assert.success().stdout("Say hello\n");
+}
+
+#[test]
+fn it_cuts_using_a_greedy_delimiter() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+
+    let assert = cmd
+        .args(["-g", "-d", "-", "-f", "1,2"])
+        .write_stdin("a---b")
+        .assert();
+
+    assert.success().stdout("ab\n");
+}
+
+#[cfg(feature = "regex")]
+#[test]
+fn it_prints_the_records_where_a_field_matches() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+
+    let assert = cmd
+        .args(["-d", " ", "-f", "3", "--grep-fields", "^5"])
+        .write_stdin("GET /a 200\nGET /b 503\nPOST /c 500 5")
+        .assert();
+
+    assert.success().stdout("GET /b 503\nPOST /c 500 5\n");
+}
+
+#[cfg(feature = "regex")]
+#[test]
+fn it_cuts_using_a_regex() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+
+    let assert = cmd
+        .args(["-e", "[.,]", "-f", "1,5"])
+        .write_stdin("a..,,b")
+        .assert();
+
+    assert.success().stdout("ab\n");
+}
+
+#[cfg(feature = "regex")]
+#[test]
+fn it_format_named_capture_groups_of_the_regex() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+
+    let assert = cmd
+        .args(["-e", "(?<sep>[=:])", "-f", "{1} is {2} ({sep})"])
+        .write_stdin("a=1\nb:2")
+        .assert();
+
+    assert.success().stdout("a is 1 (=)\nb is 2 (:)\n");
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+
+    let assert = cmd.args(["-e", "=", "-f", "{1}{sep}"]).assert();
+
+    assert.failure().stderr(
+        "tuc: runtime error. The placeholder {sep} requires --regex with a capture group named `sep`\n",
+    );
+}
+
+#[cfg(feature = "regex")]
+#[test]
+fn it_cuts_using_a_greedy_delimiter_and_a_regex() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+
+    let assert = cmd
+        .args(["-g", "-e", "[.,]", "-f", "1,2"])
+        .write_stdin("a..,,b")
+        .assert();
+
+    assert.success().stdout("ab\n");
+}
+
+#[test]
+fn it_accept_any_kind_of_range_as_long_as_its_safe() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+
+    let assert = cmd.args(["-l", "2:-2"]).write_stdin("a\nb\nc\nd").assert();
+
+    assert.success().stdout("b\nc\n");
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+
+    let assert = cmd.args(["-l", "2:-4"]).write_stdin("a\nb\nc\nd").assert();
+
+    assert
+        .code(3)
+        .stderr("Error: Field left value cannot be greater than right value\n");
+}
+
+#[test]
+fn it_exits_with_a_code_per_kind_of_error() {
+    let exit_code = |args: &[&str]| {
+        let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+        cmd.args(args)
+            .write_stdin("a\tb\tc\n")
+            .assert()
+            .get_output()
+            .status
+            .code()
+    };
+
+    assert_eq!(exit_code(&["-f", "2"]), Some(0));
+    assert_eq!(exit_code(&["-f", "1:x"]), Some(2));
+    assert_eq!(exit_code(&["-f", "1", "--no-such-option"]), Some(2));
+    assert_eq!(exit_code(&["-f", "1", "--skip-print"]), Some(2));
+    assert_eq!(exit_code(&["-f", "4"]), Some(3));
+    assert_eq!(exit_code(&["-b", "8"]), Some(3));
+    assert_eq!(exit_code(&["-f", "1", "/does/not/exist"]), Some(4));
+    assert_eq!(
+        exit_code(&["-f", "1", "--paste", "/does/not/exist"]),
+        Some(4)
+    );
+}
+
+#[cfg(not(feature = "gzip"))]
+#[test]
+fn it_exits_with_a_usage_error_for_a_format_not_compiled_in() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+
+    let assert = cmd
+        .env("RUST_BACKTRACE", "0")
+        .args(["-f", "1", "--decompress", "auto"])
+        .write_stdin(b"\x1F\x8B\x08\x00".as_slice())
+        .assert();
+
+    assert
+        .code(2)
+        .stderr("Error: The gzip input requires `tuc` to be compiled with `gzip` support\n");
+}
+
+#[test]
+fn it_reports_the_line_of_an_error() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+
+    let assert = cmd
+        .args(["-f", "3", "--skip", "1"])
+        .env("RUST_BACKTRACE", "0")
+        .write_stdin("h\na\tb\tc\na\tb\n")
+        .assert();
+
+    assert
+        .code(3)
+        .stdout("c\n")
+        .stderr("Error: line 3: Out of bounds: 3\n");
+
+    let path = std::env::temp_dir().join(format!("tuc-test-{}.line", std::process::id()));
+    std::fs::write(&path, "c,d,e\nf,g\n").unwrap();
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+
+    let assert = cmd
+        .args(["-d", ",", "-f", "3", "-"])
+        .arg(&path)
+        .env("RUST_BACKTRACE", "0")
+        .write_stdin("a,b,c\n")
+        .assert();
+
+    std::fs::remove_file(&path).unwrap();
+    assert.code(3).stdout("c\ne\n").stderr(format!(
+        "Error: {}, line 2: Out of bounds: 3\n",
+        path.display()
+    ));
+}
+
+#[test]
+fn it_reads_the_defaults_from_the_config_file() {
+    let config_home = std::env::temp_dir().join(format!("tuc-test-{}.config", std::process::id()));
+    std::fs::create_dir_all(config_home.join("tuc")).unwrap();
+    let path = config_home.join("tuc").join("config.toml");
+    std::fs::write(&path, "# defaults\ndelimiter = \",\"\njoin = true\n").unwrap();
+
+    let tuc = |args: &[&str]| {
+        let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+        cmd.args(args)
+            .env("XDG_CONFIG_HOME", &config_home)
+            .write_stdin("a,b,c\n")
+            .assert()
+    };
+
+    let default_config = tuc(&["-f", "3,1"]);
+    let overridden = tuc(&["-f", "2,1", "-d", "b"]);
+    let ignored = tuc(&["-f", "1", "--no-config"]);
+
+    let other_path = config_home.join("other.toml");
+    std::fs::write(&other_path, "delimiter = 'b'\n").unwrap();
+    let other_config = tuc(&["-f", "2", "--config", other_path.to_str().unwrap()]);
+
+    std::fs::write(&path, "fields = \"1\"\n").unwrap();
+    let invalid = tuc(&["-f", "1"]);
+
+    std::fs::remove_dir_all(&config_home).unwrap();
+    default_config.success().stdout("c,a\n");
+    overridden.success().stdout(",cba,\n");
+    ignored.success().stdout("a,b,c\n");
+    other_config.success().stdout(",c\n");
+    invalid.code(2).stderr(format!(
+        "tuc: runtime error. Config file {}, line 1: unknown key fields\n",
+        path.display()
+    ));
+}
+
+#[cfg(all(feature = "json", feature = "regex"))]
+#[test]
+fn it_applies_the_config_defaults_that_fit_the_mode() {
+    let path = std::env::temp_dir().join(format!("tuc-test-{}.modes", std::process::id()));
+    std::fs::write(
+        &path,
+        "delimiter = ','\ntrim = 'b'\ngreedy_delimiter = true\njson = true\n",
+    )
+    .unwrap();
+
+    let tuc = |args: &[&str]| {
+        let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+        cmd.arg("--config")
+            .arg(&path)
+            .args(args)
+            .write_stdin(",a,,b\n")
+            .assert()
+    };
+
+    let fields = tuc(&["-f", "2"]);
+    let characters = tuc(&["-c", "2:3"]);
+    let bytes = tuc(&["-b", "2:3"]);
+    let lines = tuc(&["-l", "1"]);
+    let width = tuc(&["--width", "2:3"]);
+
+    std::fs::remove_file(&path).unwrap();
+    fields.success().stdout("[\"b\"]\n");
+    characters.success().stdout("[\"a\",\",\"]\n");
+    bytes.success().stdout("a,\n");
+    lines.success().stdout(",a,,b\n");
+    width.success().stdout("a,\n");
+}
+
+#[test]
+fn it_uses_the_presets_of_the_config_file() {
+    let path = std::env::temp_dir().join(format!("tuc-test-{}.presets", std::process::id()));
+    std::fs::write(
+        &path,
+        "delimiter = ','\n[presets.passwd]\ndelimiter = ':'\nfields = '1,7'\njoin = true\n",
+    )
+    .unwrap();
+
+    let tuc = |args: &[&str]| {
+        let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+        cmd.arg("--config")
+            .arg(&path)
+            .args(args)
+            .write_stdin("root:x:0:0:root:/root:/bin/bash\n")
+            .assert()
+    };
+
+    let preset = tuc(&["--preset", "passwd"]);
+    let overridden = tuc(&["--preset", "passwd", "-f", "1,6"]);
+    let missing = tuc(&["--preset", "nginx"]);
+
+    std::fs::remove_file(&path).unwrap();
+    preset.success().stdout("root:/bin/bash\n");
+    overridden.success().stdout("root:/root\n");
+    missing.code(2).stderr(format!(
+        "tuc: runtime error. Preset nginx not found in {}\n",
+        path.display()
+    ));
+}
+
+#[test]
+fn it_reads_the_options_of_tuc_opts() {
+    let tuc = |args: &[&str]| {
+        let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+        cmd.args(args)
+            .env("TUC_OPTS", "-d ',' --replace-delimiter ' | '")
+            .write_stdin("a,b,c\n")
+            .assert()
+    };
+
+    tuc(&["-f", "1:2"]).success().stdout("a | b\n");
+    tuc(&["-f", "1:2", "-d", "b"]).success().stdout("a, | ,c\n");
+}
+
+#[test]
+fn it_uses_only_the_options_of_tuc_opts_that_fit_the_mode() {
+    let tuc = |args: &[&str]| {
+        let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+        cmd.args(args)
+            .env("TUC_OPTS", "-d , -j")
+            .write_stdin("a,b,c\n")
+            .assert()
+    };
+
+    tuc(&["-f", "1,3"]).success().stdout("a,c\n");
+    tuc(&["-f", "1,3", "--no-join"]).success().stdout("ac\n");
+    tuc(&["-b", "1:2"]).success().stdout("a,\n");
+    tuc(&["-l", "1"]).success().stdout("a,b,c\n");
+}
+
+#[test]
+fn it_cuts_on_any_of_several_delimiters() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+
+    let assert = cmd
+        .args(["-d", ",", "-d", ";", "-d", " - ", "-f", "3,1", "-j"])
+        .write_stdin("a,b;c - d\n")
+        .assert();
+
+    assert.success().stdout("c,a\n");
+}
+
+#[test]
+fn it_sniffs_the_delimiter() {
+    let tuc = |args: &[&str], input: &str| {
+        let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+        cmd.args(args).write_stdin(input).assert()
+    };
+
+    tuc(&["--sniff", "-f", "2"], "a;b,c\nd;e\n")
+        .success()
+        .stdout("b,c\ne\n");
+    tuc(&["--sniff", "-f", "2"], "a   b\ncc  d\n")
+        .success()
+        .stdout("b\nd\n");
+    tuc(&["--sniff", "-d", ",", "-f", "2"], "a,b\n")
+        .code(2)
+        .stderr("tuc: runtime error. --sniff cannot be used with --delimiter, --regex, --csv, --json-input or --analyze\n");
+}
+
+#[test]
+fn it_fails_if_there_are_unknown_arguments() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+
+    let assert = cmd.args(["--whatever"]).write_stdin("foobar").assert();
+
+    assert.failure().stderr(
+        "tuc: unexpected arguments [\"--whatever\"]\nTry 'tuc --help' for more information.\n",
+    );
+}
+
+#[test]
+fn it_fails_if_both_join_and_nojoin_are_used_at_once() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+
+    let assert = cmd.args(["-j", "--no-join"]).write_stdin("foobar").assert();
+
+    assert.failure().stderr(
+        "tuc: runtime error. It's not possible to use --join and --no-join simultaneously\n",
+    );
+}
+
+#[test]
+fn it_fails_if_both_replace_and_nojoin_are_used_at_once() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+
+    let assert = cmd
+        .args(["-r", "x", "--no-join"])
+        .write_stdin("foobar")
+        .assert();
+
+    assert.failure().stderr(
+        "tuc: runtime error. You can't pass --no-join when using --replace, which implies --join\n",
+    );
+}
+
+#[cfg(feature = "regex")]
+#[test]
+fn it_fails_when_the_regex_is_malformed() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+
+    let assert = cmd.args(["-e", "["]).write_stdin("foobar").assert();
+
+    assert.failure().stderr(predicates::str::starts_with(
+        "tuc: runtime error. The regular expression is malformed.",
+    ));
+
+    let assert = cmd.args(["-e", "[", "-g"]).write_stdin("foobar").assert();
+
+    assert.failure().stderr(predicates::str::starts_with(
+        "tuc: runtime error. The regular expression is malformed.",
+    ));
+}
+
+#[cfg(not(feature = "regex"))]
+#[test]
+fn does_not_panic_if_attemtping_to_use_regex_arg_with_noregex_build() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+
+    let assert = cmd.args(["-e", "."]).write_stdin("foobar").assert();
+
+    assert.failure().stderr(
+        "tuc: unexpected arguments [\"-e\", \".\"]\nTry 'tuc --help' for more information.\n",
+    );
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn it_emit_output_as_json() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+
+    let assert = cmd
+        .args(["--json", "-d", "/", "-f", "1,2,1:3"])
+        .write_stdin("a/b/c/d")
+        .assert();
+
+    assert.success().stdout(
+        r#"["a","b","a","b","c"]
+"#,
+    );
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn it_emit_output_as_json_object() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+
+    let assert = cmd
+        .args(["--json-object", "-d", "/", "-f", "1,3:"])
+        .write_stdin("a/b/c/d")
+        .assert();
+
+    assert.success().stdout(
+        r#"{"1":"a","3":"c","4":"d"}
+"#,
+    );
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn it_emit_output_as_json_object_keyed_by_the_header() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+
+    let assert = cmd
+        .args([
+            "--json-object",
+            "--keep-header",
+            "-d",
+            ",",
+            "-f",
+            "1,3=none,1",
+        ])
+        .write_stdin("name,qty,price\npen,2,3\ncap\n")
+        .assert();
+
+    assert.success().stdout(
+        r#"{"name":"pen","price":"3"}
+{"name":"cap","price":"none"}
+"#,
+    );
+}
+
+#[cfg(all(feature = "regex", feature = "json"))]
+#[test]
+fn it_emit_output_as_json_even_when_cutting_on_chars() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+
+    let assert = cmd
+        .args(["--json", "-c", "1,2,1:3"])
+        .write_stdin("abcd")
+        .assert();
+
+    assert.success().stdout(
+        r#"["a","b","a","b","c"]
+"#,
+    );
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn it_does_not_allow_to_replace_delimiter_with_json() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+
+    let assert = cmd.args(["--json", "-r", "x"]).assert();
+
+    assert
+        .failure()
+        .stderr("tuc: runtime error. The use of --replace with --json is not supported\n");
+}
+
+#[test]
+fn it_is_not_allowed_to_use_character_with_nojoin() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+
+    let assert = cmd.args(["-c", "1", "--no-join"]).assert();
+
+    assert.failure().stderr(
+        "tuc: runtime error. Since --characters implies --join, you can\'t pass --no-join\n",
+    );
+}
+
+#[cfg(not(feature = "regex"))]
+#[test]
+fn it_cannot_use_characters_without_regex() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+
+    let assert = cmd.args(["-c", "1"]).assert();
+
+    assert.failure().stderr(
+        "tuc: runtime error. The use of --characters requires `tuc` to be compiled with `regex` support\n",
+    );
+}
+
+#[cfg(not(feature = "json"))]
+#[test]
+fn it_cannot_use_json_without_json_support() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+
+    let assert = cmd.args(["-f", "1", "--json"]).assert();
+
+    assert.failure().stderr(
+        "tuc: runtime error. The use of --json requires `tuc` to be compiled with `json` support\n",
+    );
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn it_does_not_support_json_on_lines() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+
+    let assert = cmd.args(["-l", "1", "--json"]).assert();
+
+    assert.failure().stderr(
+        "tuc: runtime error. --json support is available only for --fields and --characters\n",
+    );
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn it_does_not_support_json_on_bytes() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+
+    let assert = cmd.args(["-b", "1", "--json"]).assert();
+
+    assert.failure().stderr(
+        "tuc: runtime error. --json support is available only for --fields and --characters\n",
+    );
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn it_cannot_format_fields_alongside_json() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+
+    let assert = cmd.args(["-f", "a{1}b", "--json"]).assert();
+
+    assert
+        .failure()
+        .stderr("tuc: runtime error. Cannot format fields when using --json\n");
+}
+
+#[test]
+fn it_accepts_excel_columns_as_fields() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+
+    let assert = cmd
+        .args(["-d", ",", "--excel-columns", "-f", "{C}-{A:B}"])
+        .write_stdin("a,b,c,d\n")
+        .assert();
+
+    assert.success().stdout("c-a,b\n");
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+
+    let assert = cmd.args(["--excel-columns", "-c", "1"]).assert();
+
+    assert
+        .failure()
+        .stderr("tuc: runtime error. --excel-columns requires --fields\n");
+}
+
+#[test]
+fn it_adds_fields_to_every_record() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+
+    let assert = cmd
+        .env("TUC_BATCH", "b1")
+        .args(["-d", ",", "-f", "2"])
+        .args(["--add-field", "{env:TUC_BATCH}", "--add-field", "{NR}"])
+        .write_stdin("a,b\nc,d\n")
+        .assert();
+
+    assert.success().stdout("b,b1,1\nd,b1,2\n");
+}
+
+#[test]
+fn it_inserts_a_field() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+
+    let assert = cmd
+        .args(["-d", ",", "--insert", "2", "new"])
+        .write_stdin("a,b,c\nd\n")
+        .assert();
+
+    assert.success().stdout("a,new,b,c\nd,new\n");
+}
+
+#[test]
+fn it_fails_to_insert_a_field_out_of_bounds() {
+    let tuc = |args: &[&str]| {
+        let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+        cmd.args(["-d", ",", "--insert", "4", "new"])
+            .args(args)
+            .env("RUST_BACKTRACE", "0")
+            .write_stdin("a,b\nc,d,e\n")
+            .assert()
+    };
+
+    tuc(&[])
+        .code(3)
+        .stdout("")
+        .stderr("Error: line 1: Out of bounds: 4\n");
+    tuc(&["--lenient"])
+        .success()
+        .stdout("c,d,e,new\n")
+        .stderr("tuc: warning: line 1: Out of bounds: 4 (skipped)\n");
+}
+
+#[test]
+fn it_asserts_the_number_of_fields() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+
+    let assert = cmd
+        .args(["-d", ",", "-f", "1", "--assert-fields", "2"])
+        .write_stdin("a,b\nc\nd,e\n")
+        .assert();
+
+    assert
+        .failure()
+        .stdout("a\n")
+        .stderr("Error: Record 2: expected 2 fields, found 1\n");
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+
+    let assert = cmd
+        .args(["-d", ",", "-f", "1", "--assert-fields", "2:", "--relaxed"])
+        .write_stdin("a,b\nc\nd,e\n")
+        .assert();
+
+    assert
+        .success()
+        .stdout("a\nd\n")
+        .stderr("tuc: warning: Record 2: expected at least 2 fields, found 1 (skipped)\n");
+}
+
+#[cfg(feature = "regex")]
+#[test]
+fn it_validates_fields_against_patterns() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+
+    let assert = cmd
+        .args([
+            "-d",
+            ",",
+            "-f",
+            "1",
+            "--assert",
+            "2~^\\d+$",
+            "--relaxed",
+            "--report",
+        ])
+        .write_stdin("a,1\nb,x\nc,3\n")
+        .assert();
+
+    assert.success().stdout("a\nc\n").stderr(concat!(
+        "tuc: warning: Record 2: field 2 (\"x\") does not match ^\\d+$ (skipped)\n",
+        "tuc: report: 3 records, 1 skipped\n",
+        "tuc: report: 1 failed --assert 2~^\\d+$\n",
+    ));
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+
+    let assert = cmd
+        .args(["-d", ",", "--assert", "2~^\\d+$"])
+        .write_stdin("a,1\nb,x\nc,3\n")
+        .assert();
+
+    assert.failure().stdout("a,1\n");
+}
+
+#[test]
+fn it_skips_the_lines_out_of_bounds_when_lenient() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+
+    let assert = cmd
+        .args(["-d", ",", "-f", "{1}:{2}", "--lenient", "--report"])
+        .write_stdin("a,b\nc\nd,e\n")
+        .assert();
+
+    assert.success().stdout("a:b\nd:e\n").stderr(concat!(
+        "tuc: warning: line 2: Out of bounds: 2 (skipped)\n",
+        "tuc: report: 3 records, 1 skipped\n",
+    ));
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+
+    let assert = cmd
+        .args(["-f", "1", "--lenient", "--errors", "ignore"])
+        .assert();
+
+    assert
+        .failure()
+        .stderr("tuc: runtime error. --lenient cannot be used with --errors collect or ignore\n");
+}
+
+#[test]
+fn it_collects_the_errors_of_every_record() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+
+    let assert = cmd
+        .args(["-d", ",", "-f", "{1}:{2}", "--errors", "collect"])
+        .write_stdin("a,b\nc\nd,e\nf\n")
+        .assert();
+
+    assert.failure().stdout("a:b\nd:e\n").stderr(concat!(
+        "tuc: Record 2: Out of bounds: 2\n",
+        "tuc: Record 4: Out of bounds: 2\n",
+        "Error: 2 records could not be processed\n",
+    ));
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+
+    let assert = cmd
+        .args(["-d", ",", "-f", "{1}:{2}", "--errors", "ignore"])
+        .write_stdin("a,b\nc\nd,e\nf\n")
+        .assert();
+
+    assert.success().stdout("a:b\nd:e\n").stderr("");
+}
+
+#[test]
+fn it_sets_the_value_of_a_field() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+
+    let assert = cmd
+        .args(["-d", " ", "-g", "--set", "2=***", "--set", "-1=[{-1}]"])
+        .write_stdin("alice  secret  ok\n")
+        .assert();
+
+    assert.success().stdout("alice  ***  [ok]\n");
+}
+
+#[test]
+fn it_cuts_sub_fields() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+
+    let assert = cmd
+        .args(["-d", " ", "-D", "@", "-f", "{2.1} on {2.-1}"])
+        .write_stdin("login alice@host1\n")
+        .assert();
+
+    assert.success().stdout("alice on host1\n");
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+
+    let assert = cmd.args(["-d", " ", "-f", "2.1"]).assert();
+
+    assert
+        .failure()
+        .stderr("tuc: runtime error. Sub-fields (e.g. 2.1) require a secondary delimiter (-D)\n");
 }